@@ -2,7 +2,7 @@ use crate::config::file::ConfigFile;
 use crate::config::generator::generate_config;
 use clap::{Parser, Subcommand};
 use hiro_system_kit;
-use ordhook::chainhook_sdk::bitcoincore_rpc::{Auth, Client, RpcApi};
+use ordhook::chainhook_sdk::bitcoincore_rpc::{Client, RpcApi};
 use ordhook::chainhook_sdk::chainhooks::types::{BitcoinChainhookSpecification, HttpHook};
 use ordhook::chainhook_sdk::chainhooks::types::{
     BitcoinPredicateType, ChainhookFullSpecification, HookAction, OrdinalOperations,
@@ -11,7 +11,9 @@ use ordhook::chainhook_sdk::indexer::bitcoin::{
     build_http_client, download_and_parse_block_with_retry, retrieve_block_hash_with_retry,
 };
 use ordhook::chainhook_sdk::observer::BitcoinConfig;
-use ordhook::chainhook_sdk::types::{BitcoinBlockData, TransactionIdentifier};
+use ordhook::chainhook_sdk::types::{
+    BitcoinBlockData, OrdinalInscriptionTransferDestination, TransactionIdentifier,
+};
 use ordhook::chainhook_sdk::utils::BlockHeights;
 use ordhook::chainhook_sdk::utils::Context;
 use ordhook::config::Config;
@@ -22,18 +24,36 @@ use ordhook::core::pipeline::processors::start_inscription_indexing_processor;
 use ordhook::core::protocol::inscription_parsing::parse_inscriptions_and_standardize_block;
 use ordhook::core::protocol::satoshi_numbering::compute_satoshi_number;
 use ordhook::db::{
-    delete_data_in_ordhook_db, find_all_inscription_transfers, find_all_inscriptions_in_block,
-    find_all_transfers_in_block, find_inscription_with_id, find_last_block_inserted,
-    find_latest_inscription_block_height, find_lazy_block_at_block_height,
-    get_default_ordhook_db_file_path, initialize_ordhook_db, open_ordhook_db_conn_rocks_db_loop,
-    open_readonly_ordhook_db_conn, open_readonly_ordhook_db_conn_rocks_db,
-    open_readwrite_ordhook_db_conn,
+    delete_data_in_ordhook_db, find_all_inscription_ids_with_ordinal_number,
+    find_all_inscription_transfers, find_all_inscriptions_in_block, find_all_transfers_in_block,
+    find_inscription_details, find_inscription_id_with_inscription_number,
+    find_inscription_with_id, find_last_block_inserted, find_latest_inscription_block_height,
+    find_latest_transfers_block_height, find_lazy_block_at_block_height,
+    get_default_ordhook_db_file_path, initialize_ordhook_db, migrate_ordhook_db,
+    open_ordhook_db_conn_rocks_db_loop, open_readonly_ordhook_db_conn,
+    open_readonly_ordhook_db_conn_rocks_db, open_readwrite_ordhook_db_conn,
+    open_readwrite_ordhook_db_conn_rocks_db, parse_inscription_id, rewind_ordhook_db,
+    InscriptionDetails,
 };
 use ordhook::download::download_ordinals_dataset_if_required;
-use ordhook::scan::bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate;
+use ordhook::export::{build_export_writer, export_table, ExportFormat, ExportTable};
+use ordhook::ord::sat::Sat;
+use ordhook::scan::bitcoin::{
+    dry_run_bitcoin_predicate, scan_bitcoin_chainstate_via_index_using_predicate,
+    scan_bitcoin_chainstate_via_rpc_using_predicate, DRY_RUN_MAX_BLOCKS,
+};
+use ordhook::service::observers::{
+    get_default_observers_db_file_path, open_readwrite_observers_db_conn, rewind_observers_progress,
+};
+use ordhook::service::outbox::{
+    drain_pending_deliveries, get_outbox_report, open_readwrite_outbox_db_conn,
+    rotate_signing_secret, MAX_DELIVERY_ATTEMPTS,
+};
 use ordhook::service::{start_observer_forwarding, Service};
 use reqwest::Client as HttpClient;
-use std::io::{BufReader, Read};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
 use std::process;
 use std::sync::Arc;
@@ -61,6 +81,12 @@ enum Command {
     /// Perform maintenance operations on local databases
     #[clap(subcommand)]
     Db(OrdhookDbCommand),
+    /// Look up inscriptions in the local database
+    #[clap(subcommand)]
+    Inscription(InscriptionCommand),
+    /// Display the representations, rarity and inscriptions of a sat
+    #[clap(name = "sat", bin_name = "sat")]
+    Sat(SatCommand),
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -119,6 +145,19 @@ struct ScanBlocksCommand {
     /// HTTP Auth token
     #[clap(long = "auth-token")]
     pub auth_token: Option<String>,
+    /// Secret signing the payloads posted to --post-to (HMAC-SHA256, `ordhook-signature` header)
+    #[clap(long = "signing-secret")]
+    pub signing_secret: Option<String>,
+    /// Rebuild blocks from hord.sqlite, without connecting to bitcoind. Blocks indexed before reveal
+    /// details, destinations and values were recorded can only be scanned with bitcoind
+    #[clap(long = "offline")]
+    pub offline: bool,
+    /// Print the payloads the predicate would receive, without posting them
+    #[clap(long = "dry-run")]
+    pub dry_run: bool,
+    /// Seconds spent retrying the payloads whose delivery failed, once the blocks are scanned
+    #[clap(long = "delivery-timeout", default_value = "600")]
+    pub delivery_timeout: u64,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -193,6 +232,118 @@ struct ScanTransactionCommand {
     pub config_path: Option<String>,
 }
 
+#[derive(Subcommand, PartialEq, Clone, Debug)]
+enum InscriptionCommand {
+    /// Display an inscription, its transfers and its current satpoint
+    #[clap(name = "show", bin_name = "show")]
+    Show(InscriptionLookupCommand),
+    /// List the reveal and transfers of an inscription, oldest first
+    #[clap(name = "history", bin_name = "history")]
+    History(InscriptionLookupCommand),
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct InscriptionLookupCommand {
+    /// Inscription Id, inscription number, or ordinal number when --sat is set
+    pub target: String,
+    /// Look up the inscriptions located on the ordinal number passed as target
+    #[clap(long = "sat")]
+    pub sat: bool,
+    /// Output format (json, table, ndjson)
+    #[clap(long = "format", default_value = "table")]
+    pub format: OutputFormat,
+    /// Target Regtest network
+    #[clap(
+        long = "regtest",
+        conflicts_with = "testnet",
+        conflicts_with = "mainnet"
+    )]
+    pub regtest: bool,
+    /// Target Testnet network
+    #[clap(
+        long = "testnet",
+        conflicts_with = "regtest",
+        conflicts_with = "mainnet"
+    )]
+    pub testnet: bool,
+    /// Target Mainnet network
+    #[clap(
+        long = "mainnet",
+        conflicts_with = "testnet",
+        conflicts_with = "regtest"
+    )]
+    pub mainnet: bool,
+    /// Load config file path
+    #[clap(
+        long = "config-path",
+        conflicts_with = "mainnet",
+        conflicts_with = "testnet",
+        conflicts_with = "regtest"
+    )]
+    pub config_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct SatCommand {
+    /// Sat, in integer (2099994106992659), decimal (3891094.16797), degree (3°111094′214″16797‴),
+    /// name (satoshi) or percentile (99.99971949060254%) notation
+    pub notation: String,
+    /// Output format (json, table, ndjson)
+    #[clap(long = "format", default_value = "table")]
+    pub format: OutputFormat,
+    /// Target Regtest network
+    #[clap(
+        long = "regtest",
+        conflicts_with = "testnet",
+        conflicts_with = "mainnet"
+    )]
+    pub regtest: bool,
+    /// Target Testnet network
+    #[clap(
+        long = "testnet",
+        conflicts_with = "regtest",
+        conflicts_with = "mainnet"
+    )]
+    pub testnet: bool,
+    /// Target Mainnet network
+    #[clap(
+        long = "mainnet",
+        conflicts_with = "testnet",
+        conflicts_with = "regtest"
+    )]
+    pub mainnet: bool,
+    /// Load config file path
+    #[clap(
+        long = "config-path",
+        conflicts_with = "mainnet",
+        conflicts_with = "testnet",
+        conflicts_with = "regtest"
+    )]
+    pub config_path: Option<String>,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum OutputFormat {
+    Json,
+    Table,
+    Ndjson,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "table" => Ok(OutputFormat::Table),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(format!(
+                "unsupported format {s} (expected json, table or ndjson)"
+            )),
+        }
+    }
+}
+
 #[derive(Subcommand, PartialEq, Clone, Debug)]
 enum RepairCommand {
     /// Rewrite blocks data in hord.rocksdb
@@ -264,6 +415,16 @@ enum ConfigCommand {
     /// Generate new config
     #[clap(name = "new", bin_name = "new", aliases = &["generate"])]
     New(NewConfig),
+    /// Validate a config file, with the ORDHOOK_* environment overrides applied
+    #[clap(name = "check", bin_name = "check")]
+    Check(CheckConfigCommand),
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct CheckConfigCommand {
+    /// Load config file path
+    #[clap(long = "config-path")]
+    pub config_path: String,
 }
 
 #[derive(Parser, PartialEq, Clone, Debug)]
@@ -338,6 +499,10 @@ struct StartCommand {
     /// HTTP Auth token
     #[clap(long = "auth-token")]
     pub auth_token: Option<String>,
+    /// Secret signing the payloads posted to the --post-to endpoints, for the blocks scanned
+    /// while catching up as well as for the blocks streamed (HMAC-SHA256, `ordhook-signature` header)
+    #[clap(long = "signing-secret")]
+    pub signing_secret: Option<String>,
 }
 
 #[derive(Subcommand, PartialEq, Clone, Debug)]
@@ -348,12 +513,21 @@ enum OrdhookDbCommand {
     /// Catch-up ordhook db
     #[clap(name = "sync", bin_name = "sync")]
     Sync(SyncOrdhookDbCommand),
+    /// Migrate an ordhook db built by an earlier version
+    #[clap(name = "migrate", bin_name = "migrate")]
+    Migrate(SyncOrdhookDbCommand),
     /// Rebuild inscriptions entries for a given block
     #[clap(name = "drop", bin_name = "drop")]
     Drop(DropOrdhookDbCommand),
+    /// Remove all the state above a given block
+    #[clap(name = "rewind", bin_name = "rewind")]
+    Rewind(RewindOrdhookDbCommand),
     /// Check integrity
     #[clap(name = "check", bin_name = "check")]
     Check(CheckDbCommand),
+    /// Export inscriptions, locations or transfers for analytics
+    #[clap(name = "export", bin_name = "export")]
+    Export(ExportOrdhookDbCommand),
     /// Db maintenance related commands
     #[clap(subcommand)]
     Repair(RepairCommand),
@@ -471,6 +645,37 @@ struct DropOrdhookDbCommand {
     pub config_path: Option<String>,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct RewindOrdhookDbCommand {
+    /// Block to rewind to (kept)
+    pub block_height: u64,
+    /// Load config file path
+    #[clap(long = "config-path")]
+    pub config_path: Option<String>,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct ExportOrdhookDbCommand {
+    /// Table to export (inscriptions, locations, transfers)
+    #[clap(long = "table")]
+    pub table: ExportTable,
+    /// Output format (parquet, csv, ndjson)
+    #[clap(long = "format", default_value = "ndjson")]
+    pub format: ExportFormat,
+    /// Starting block
+    #[clap(long = "from", default_value = "0")]
+    pub start_block: u64,
+    /// Ending block (defaults to the latest block indexed)
+    #[clap(long = "to")]
+    pub end_block: Option<u64>,
+    /// Output file path (defaults to stdout)
+    #[clap(long = "output")]
+    pub output: Option<String>,
+    /// Load config file path
+    #[clap(long = "config-path")]
+    pub config_path: Option<String>,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct PatchOrdhookDbCommand {
     /// Load config file path
@@ -512,6 +717,25 @@ pub fn main() {
         }
     };
 
+    let moved_ctx = ctx.clone();
+    let res = ctrlc::set_handler(move || {
+        if ordhook::utils::shutdown::is_shutdown_requested() {
+            // Second signal: stop waiting for the pipelines to drain.
+            process::exit(1);
+        }
+        info!(
+            moved_ctx.expect_logger(),
+            "Received termination signal, shutting down gracefully (send again to force)"
+        );
+        ordhook::utils::shutdown::request_shutdown();
+    });
+    if let Err(e) = res {
+        warn!(
+            ctx.expect_logger(),
+            "Unable to register signal handler: {e}"
+        );
+    }
+
     if let Err(e) = hiro_system_kit::nestable_block_on(handle_command(opts, &ctx)) {
         error!(ctx.expect_logger(), "{e}");
         std::thread::sleep(std::time::Duration::from_millis(500));
@@ -531,7 +755,46 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
             // - Replay that requires connection to bitcoind
             let block_heights = parse_blocks_heights_spec(&cmd.blocks_interval, &cmd.blocks);
             let mut block_range = block_heights.get_sorted_entries();
-            if let Some(ref post_to) = cmd.post_to {
+            if cmd.dry_run {
+                let post_to = cmd.post_to.as_deref().unwrap_or("dry-run");
+                let mut predicate_spec =
+                    build_predicate_from_cli(&config, post_to, Some(&block_heights), None, None)?;
+                let block_range = block_range.into_iter().collect::<Vec<_>>();
+                for blocks in block_range.chunks(DRY_RUN_MAX_BLOCKS) {
+                    predicate_spec.start_block = None;
+                    predicate_spec.end_block = None;
+                    predicate_spec.blocks = Some(blocks.to_vec());
+                    let payloads = dry_run_bitcoin_predicate(
+                        &predicate_spec,
+                        None,
+                        !cmd.offline,
+                        &config,
+                        ctx,
+                    )
+                    .await?;
+                    for payload in payloads.iter() {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(payload).map_err(|e| e.to_string())?
+                        );
+                    }
+                }
+            } else if let (Some(ref post_to), true) = (&cmd.post_to, cmd.offline) {
+                let predicate_spec = build_predicate_from_cli(
+                    &config,
+                    post_to,
+                    Some(&block_heights),
+                    None,
+                    cmd.auth_token,
+                )?;
+                if let Some(ref secret) = cmd.signing_secret {
+                    configure_signing_secret(&config, &predicate_spec.uuid, secret, ctx)?;
+                }
+                scan_bitcoin_chainstate_via_index_using_predicate(&predicate_spec, &config, ctx)
+                    .await?;
+                drain_scan_deliveries(&config, &predicate_spec.uuid, cmd.delivery_timeout, ctx)
+                    .await?;
+            } else if let Some(ref post_to) = cmd.post_to {
                 info!(ctx.expect_logger(), "A fully synchronized bitcoind node is required for retrieving inscriptions content.");
                 info!(
                     ctx.expect_logger(),
@@ -554,6 +817,9 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                     None,
                     cmd.auth_token,
                 )?;
+                if let Some(ref secret) = cmd.signing_secret {
+                    configure_signing_secret(&config, &predicate_spec.uuid, secret, ctx)?;
+                }
                 scan_bitcoin_chainstate_via_rpc_using_predicate(
                     &predicate_spec,
                     &config,
@@ -561,6 +827,8 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                     ctx,
                 )
                 .await?;
+                drain_scan_deliveries(&config, &predicate_spec.uuid, cmd.delivery_timeout, ctx)
+                    .await?;
             } else {
                 let _ = download_ordinals_dataset_if_required(&config, ctx).await;
                 let mut total_inscriptions = 0;
@@ -584,7 +852,7 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                                 total_transfers_in_block += 1;
                                 println!(
                                     "\t→ Transferred in transaction {}",
-                                    t.transaction_identifier_location.hash
+                                    t.satpoint.outpoint.txid
                                 );
                             }
                         }
@@ -595,7 +863,7 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                             total_transfers_in_block += 1;
                             println!(
                                 "\t→ Transferred in transaction {}",
-                                t.transaction_identifier_location.hash
+                                t.satpoint.outpoint.txid
                             );
                         }
                     }
@@ -650,7 +918,7 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
             for (transfer, block_height) in transfers.iter().skip(1) {
                 println!(
                     "\t→ Transferred in transaction {} (block #{block_height})",
-                    transfer.transaction_identifier_location.hash
+                    transfer.satpoint.outpoint.txid
                 );
             }
             println!("Number of transfers: {}", transfers.len() - 1);
@@ -731,6 +999,9 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                         Some(start_block),
                         cmd.auth_token.clone(),
                     )?;
+                    if let Some(ref secret) = cmd.signing_secret {
+                        configure_signing_secret(&config, &predicate.uuid, secret, ctx)?;
+                    }
                     predicates.push(predicate);
                 }
 
@@ -740,8 +1011,6 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
         },
         Command::Config(subcmd) => match subcmd {
             ConfigCommand::New(cmd) => {
-                use std::fs::File;
-                use std::io::Write;
                 let config = ConfigFile::default(cmd.regtest, cmd.testnet, cmd.mainnet, &None)?;
                 let config_content = generate_config(&config.network.bitcoin_network);
                 let mut file_path = PathBuf::new();
@@ -752,6 +1021,21 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                     .map_err(|e| format!("unable to write file {}\n{}", file_path.display(), e))?;
                 println!("Created file Ordhook.toml");
             }
+            ConfigCommand::Check(cmd) => {
+                let loaded = ConfigFile::load_file_path(&cmd.config_path)?;
+                for warning in loaded.warnings.iter() {
+                    println!("Warning: {warning}");
+                }
+                for env_var in loaded.env_overrides.iter() {
+                    println!("Overridden by {env_var}");
+                }
+                println!(
+                    "{} is valid ({:?} network, working dir {})",
+                    cmd.config_path,
+                    loaded.config.network.bitcoin_network,
+                    loaded.config.storage.working_dir
+                );
+            }
         },
         Command::Db(OrdhookDbCommand::New(cmd)) => {
             let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
@@ -764,6 +1048,15 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
             let service = Service::new(config, ctx.clone());
             service.update_state(None).await?;
         }
+        Command::Db(OrdhookDbCommand::Migrate(cmd)) => {
+            let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
+            // Fails while the service holds hord.rocksdb: the migration rewrites hord.sqlite
+            let _blocks_db =
+                open_readwrite_ordhook_db_conn_rocks_db(&config.expected_cache_path(), ctx)
+                    .map_err(|e| format!("{e} (ordhook must be stopped before migrating)"))?;
+            migrate_ordhook_db(&config.expected_cache_path(), ctx)?;
+            info!(ctx.expect_logger(), "Ordhook db migrated");
+        }
         Command::Db(OrdhookDbCommand::Repair(subcmd)) => match subcmd {
             RepairCommand::Blocks(cmd) => {
                 let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
@@ -782,7 +1075,6 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                     blocks,
                     ordhook_config.first_inscription_height,
                     Some(&block_ingestion_processor),
-                    10_000,
                     ctx,
                 )
                 .await?;
@@ -827,7 +1119,6 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                     blocks,
                     ordhook_config.first_inscription_height,
                     Some(&inscription_indexing_processor),
-                    10_000,
                     ctx,
                 )
                 .await?;
@@ -876,6 +1167,63 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                 println!("{:?}", missing_blocks);
             }
         }
+        Command::Db(OrdhookDbCommand::Export(cmd)) => {
+            let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
+            let inscriptions_db_conn =
+                open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
+            let end_block = match cmd.end_block {
+                Some(end_block) => end_block,
+                None => {
+                    let latest_inscription_block_height =
+                        find_latest_inscription_block_height(&inscriptions_db_conn, ctx)?;
+                    let latest_transfers_block_height =
+                        find_latest_transfers_block_height(&inscriptions_db_conn, ctx);
+                    match latest_inscription_block_height.max(latest_transfers_block_height) {
+                        Some(end_block) => end_block,
+                        None => return Err("ordhook db is empty".to_string()),
+                    }
+                }
+            };
+            if cmd.start_block > end_block {
+                return Err(format!(
+                    "nothing to export: --from #{} is past --to #{end_block}",
+                    cmd.start_block
+                ));
+            }
+            let output: Box<dyn Write + Send> = match &cmd.output {
+                Some(path) => Box::new(BufWriter::new(
+                    File::create(path).map_err(|e| format!("unable to create {path}: {e}"))?,
+                )),
+                None if cmd.format == ExportFormat::Parquet => {
+                    return Err("parquet exports require an --output file".to_string())
+                }
+                None => Box::new(BufWriter::new(std::io::stdout())),
+            };
+            let mut writer = build_export_writer(cmd.format, cmd.table.columns(), output)?;
+            let summary = export_table(
+                cmd.table,
+                cmd.start_block,
+                end_block,
+                writer.as_mut(),
+                &inscriptions_db_conn,
+                ctx,
+            )?;
+            writer.finish()?;
+            if summary.rows_without_details > 0 {
+                warn!(
+                    ctx.expect_logger(),
+                    "{} rows were indexed before reveal details, destinations and values were recorded: these columns were exported as null",
+                    summary.rows_without_details
+                );
+            }
+            info!(
+                ctx.expect_logger(),
+                "Exported {} rows from blocks #{} to #{end_block}, resume with --from {}",
+                summary.rows,
+                cmd.start_block,
+                end_block + 1
+            );
+        }
         Command::Db(OrdhookDbCommand::Drop(cmd)) => {
             let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
             let blocks_db =
@@ -896,6 +1244,445 @@ async fn handle_command(opts: Opts, ctx: &Context) -> Result<(), String> {
                 cmd.end_block - cmd.start_block + 1
             );
         }
+        Command::Db(OrdhookDbCommand::Rewind(cmd)) => {
+            let config = ConfigFile::default(false, false, false, &cmd.config_path)?;
+            // Fails while the service holds hord.rocksdb: its caches would outlive the rewind
+            let blocks_db =
+                open_readwrite_ordhook_db_conn_rocks_db(&config.expected_cache_path(), ctx)
+                    .map_err(|e| format!("{e} (ordhook must be stopped before rewinding)"))?;
+            let inscriptions_db_conn_rw =
+                open_readwrite_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
+            let report =
+                rewind_ordhook_db(cmd.block_height, &blocks_db, &inscriptions_db_conn_rw, ctx)?;
+            let observers_rewound =
+                if get_default_observers_db_file_path(&config.expected_cache_path()).exists() {
+                    let observers_db_conn =
+                        open_readwrite_observers_db_conn(&config.expected_cache_path(), ctx)?;
+                    rewind_observers_progress(cmd.block_height, &observers_db_conn, ctx)
+                } else {
+                    0
+                };
+            info!(
+                ctx.expect_logger(),
+                "Ordhook db rewound from block #{} to block #{} ({} blocks removed, {} observers rewound)",
+                report.previous_tip,
+                cmd.block_height,
+                report.blocks_removed,
+                observers_rewound
+            );
+        }
+        Command::Sat(cmd) => {
+            let sat = cmd
+                .notation
+                .parse::<Sat>()
+                .map_err(|e| format!("unable to parse sat {}: {e}", cmd.notation))?;
+            let config: Config =
+                ConfigFile::default(cmd.regtest, cmd.testnet, cmd.mainnet, &cmd.config_path)?;
+            // Sat arithmetic does not require an index: inscriptions are only listed when a local
+            // database is available.
+            let inscriptions =
+                if get_default_ordhook_db_file_path(&config.expected_cache_path()).exists() {
+                    let inscriptions_db_conn =
+                        open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
+                    let mut inscriptions = vec![];
+                    for inscription_id in find_all_inscription_ids_with_ordinal_number(
+                        sat.n(),
+                        &inscriptions_db_conn,
+                        ctx,
+                    ) {
+                        if let Some(details) =
+                            find_inscription_details(&inscription_id, &inscriptions_db_conn, ctx)?
+                        {
+                            inscriptions.push(details);
+                        }
+                    }
+                    Some(inscriptions)
+                } else {
+                    None
+                };
+            print_sat(sat, &inscriptions, cmd.format)?;
+        }
+        Command::Inscription(subcmd) => {
+            let cmd = match &subcmd {
+                InscriptionCommand::Show(cmd) | InscriptionCommand::History(cmd) => cmd,
+            };
+            let config: Config =
+                ConfigFile::default(cmd.regtest, cmd.testnet, cmd.mainnet, &cmd.config_path)?;
+            let inscriptions_db_conn =
+                open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
+
+            let inscription_ids = if cmd.sat {
+                let ordinal_number = cmd
+                    .target
+                    .parse::<u64>()
+                    .map_err(|_| format!("invalid ordinal number {}", cmd.target))?;
+                let inscription_ids = find_all_inscription_ids_with_ordinal_number(
+                    ordinal_number,
+                    &inscriptions_db_conn,
+                    ctx,
+                );
+                if inscription_ids.is_empty() {
+                    return Err(format!("no inscription found on sat {ordinal_number}"));
+                }
+                inscription_ids
+            } else if let Ok(inscription_number) = cmd.target.parse::<i64>() {
+                match find_inscription_id_with_inscription_number(
+                    inscription_number,
+                    &inscriptions_db_conn,
+                    ctx,
+                ) {
+                    Some(inscription_id) => vec![inscription_id],
+                    None => {
+                        return Err(format!(
+                            "unable to retrieve inscription #{inscription_number}"
+                        ))
+                    }
+                }
+            } else {
+                vec![cmd.target.clone()]
+            };
+
+            let mut inscriptions = vec![];
+            for inscription_id in inscription_ids.into_iter() {
+                match find_inscription_details(&inscription_id, &inscriptions_db_conn, ctx)? {
+                    Some(details) => inscriptions.push(details),
+                    None => {
+                        return Err(format!("unable to retrieve inscription {inscription_id}"));
+                    }
+                }
+            }
+            match &subcmd {
+                InscriptionCommand::Show(_) => {
+                    print_inscriptions(&inscriptions, cmd.format, !cmd.sat)?
+                }
+                InscriptionCommand::History(_) => {
+                    print_inscriptions_history(&inscriptions, cmd.format)?
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct InscriptionHistoryEvent {
+    inscription_id: String,
+    event: &'static str,
+    block_height: u64,
+    tx_id: String,
+    satpoint: String,
+    address: Option<String>,
+    value: Option<u64>,
+}
+
+fn build_inscription_history(
+    inscription: &InscriptionDetails,
+) -> Result<Vec<InscriptionHistoryEvent>, String> {
+    let (reveal_tx, _) = parse_inscription_id(&inscription.inscription_id)?;
+    let mut events = vec![InscriptionHistoryEvent {
+        inscription_id: inscription.inscription_id.clone(),
+        event: "reveal",
+        block_height: inscription.block_height,
+        tx_id: reveal_tx.get_hash_bytes_str().to_string(),
+        satpoint: inscription.genesis_satpoint.clone(),
+        address: inscription
+            .reveal
+            .as_ref()
+            .and_then(|reveal| reveal.inscriber_address.clone()),
+        value: inscription
+            .reveal
+            .as_ref()
+            .map(|reveal| reveal.inscription_output_value),
+    }];
+    for transfer in inscription.transfers.iter() {
+        let (event, address) = match &transfer.destination {
+            Some(OrdinalInscriptionTransferDestination::Transferred(address)) => {
+                ("transfer", Some(address.clone()))
+            }
+            Some(OrdinalInscriptionTransferDestination::SpentInFees) => ("spent_in_fees", None),
+            Some(OrdinalInscriptionTransferDestination::Burnt(_)) => ("burn", None),
+            None => ("transfer", None),
+        };
+        events.push(InscriptionHistoryEvent {
+            inscription_id: inscription.inscription_id.clone(),
+            event,
+            block_height: transfer.block_height,
+            tx_id: transfer.tx_id.clone(),
+            satpoint: transfer.satpoint_post_transfer.clone(),
+            address,
+            value: transfer.post_transfer_output_value,
+        });
+    }
+    Ok(events)
+}
+
+fn format_transfer_destination(
+    destination: &Option<OrdinalInscriptionTransferDestination>,
+) -> String {
+    match destination {
+        Some(OrdinalInscriptionTransferDestination::Transferred(address)) => address.clone(),
+        Some(OrdinalInscriptionTransferDestination::SpentInFees) => "spent in fees".into(),
+        Some(OrdinalInscriptionTransferDestination::Burnt(script)) => format!("burnt ({script})"),
+        None => "-".into(),
+    }
+}
+
+fn format_optional<T: std::fmt::Display>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".into(),
+    }
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(headers.to_vec()));
+    for row in rows.iter() {
+        println!(
+            "{}",
+            format_row(row.iter().map(|cell| cell.as_str()).collect())
+        );
+    }
+}
+
+fn print_inscriptions(
+    inscriptions: &Vec<InscriptionDetails>,
+    format: OutputFormat,
+    single: bool,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Json if single => println!(
+            "{}",
+            serde_json::to_string_pretty(&inscriptions[0]).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(inscriptions).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Ndjson => {
+            for inscription in inscriptions.iter() {
+                println!(
+                    "{}",
+                    serde_json::to_string(inscription).map_err(|e| e.to_string())?
+                );
+            }
+        }
+        OutputFormat::Table => {
+            for (i, inscription) in inscriptions.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                let reveal = inscription.reveal.as_ref();
+                let mut rows = vec![
+                    ("Inscription ID", inscription.inscription_id.clone()),
+                    (
+                        "Inscription number",
+                        inscription.inscription_number.to_string(),
+                    ),
+                    ("Ordinal number", inscription.ordinal_number.to_string()),
+                    ("Revealed at block", inscription.block_height.to_string()),
+                    ("Reveal tx index", inscription.tx_index.to_string()),
+                    (
+                        "Content type",
+                        format_optional(&reveal.map(|r| r.content_type.clone())),
+                    ),
+                    (
+                        "Content length",
+                        format_optional(&reveal.map(|r| r.content_length)),
+                    ),
+                    (
+                        "Inscriber address",
+                        format_optional(&reveal.and_then(|r| r.inscriber_address.clone())),
+                    ),
+                    (
+                        "Output value",
+                        format_optional(&reveal.map(|r| r.inscription_output_value)),
+                    ),
+                    ("Fee", format_optional(&reveal.map(|r| r.inscription_fee))),
+                ];
+                if let Some(curse_type) = reveal.and_then(|r| r.curse_type.as_ref()) {
+                    rows.push(("Curse", format!("{:?}", curse_type)));
+                }
+                rows.push(("Genesis satpoint", inscription.genesis_satpoint.clone()));
+                rows.push(("Current satpoint", inscription.current_satpoint.clone()));
+                rows.push(("Transfers", inscription.transfers.len().to_string()));
+                let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+                for (label, value) in rows.iter() {
+                    println!("{:<width$}  {}", label, value, width = label_width);
+                }
+                if !inscription.blocks_without_details.is_empty() {
+                    println!(
+                        "(reveal details, destinations and values of blocks {} unavailable: indexed before they were recorded)",
+                        inscription
+                            .blocks_without_details
+                            .iter()
+                            .map(|block_height| format!("#{block_height}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+                if !inscription.transfers.is_empty() {
+                    println!();
+                    let rows = inscription
+                        .transfers
+                        .iter()
+                        .map(|transfer| {
+                            vec![
+                                transfer.block_height.to_string(),
+                                transfer.tx_id.clone(),
+                                transfer.satpoint_post_transfer.clone(),
+                                format_transfer_destination(&transfer.destination),
+                                format_optional(&transfer.post_transfer_output_value),
+                            ]
+                        })
+                        .collect::<Vec<_>>();
+                    print_table(&["BLOCK", "TX", "SATPOINT", "DESTINATION", "VALUE"], &rows);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_sat(
+    sat: Sat,
+    inscriptions: &Option<Vec<InscriptionDetails>>,
+    format: OutputFormat,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let payload = json!({
+                "number": sat.n(),
+                "decimal": sat.decimal().to_string(),
+                "degree": sat.degree().to_string(),
+                "name": sat.name(),
+                "percentile": sat.percentile(),
+                "rarity": sat.rarity(),
+                "block_height": sat.height().n(),
+                "cycle": sat.cycle(),
+                "epoch": sat.epoch().0,
+                "offset": sat.third(),
+                "inscriptions": inscriptions.as_ref().map(|inscriptions| inscriptions
+                    .iter()
+                    .map(|inscription| json!({
+                        "inscription_id": inscription.inscription_id,
+                        "inscription_number": inscription.inscription_number,
+                        "current_satpoint": inscription.current_satpoint,
+                    }))
+                    .collect::<Vec<_>>()),
+            });
+            let output = if format == OutputFormat::Json {
+                serde_json::to_string_pretty(&payload)
+            } else {
+                serde_json::to_string(&payload)
+            };
+            println!("{}", output.map_err(|e| e.to_string())?);
+        }
+        OutputFormat::Table => {
+            let rows = vec![
+                ("Number", sat.n().to_string()),
+                ("Decimal", sat.decimal().to_string()),
+                ("Degree", sat.degree().to_string()),
+                ("Name", sat.name()),
+                ("Percentile", sat.percentile()),
+                ("Rarity", sat.rarity().to_string()),
+                ("Coinbase block", sat.height().n().to_string()),
+                ("Cycle", sat.cycle().to_string()),
+                ("Epoch", sat.epoch().0.to_string()),
+                ("Offset", sat.third().to_string()),
+            ];
+            for (label, value) in rows.iter() {
+                println!("{:<14}  {}", label, value);
+            }
+            match inscriptions {
+                None => println!("\n(inscriptions unavailable: no local database)"),
+                Some(inscriptions) if inscriptions.is_empty() => {
+                    println!("\nNo inscription on this sat")
+                }
+                Some(inscriptions) => {
+                    println!();
+                    let rows = inscriptions
+                        .iter()
+                        .map(|inscription| {
+                            vec![
+                                inscription.inscription_id.clone(),
+                                inscription.inscription_number.to_string(),
+                                inscription.current_satpoint.clone(),
+                            ]
+                        })
+                        .collect::<Vec<_>>();
+                    print_table(&["INSCRIPTION", "NUMBER", "CURRENT SATPOINT"], &rows);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_inscriptions_history(
+    inscriptions: &Vec<InscriptionDetails>,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let mut events = vec![];
+    for inscription in inscriptions.iter() {
+        events.append(&mut build_inscription_history(inscription)?);
+    }
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&events).map_err(|e| e.to_string())?
+        ),
+        OutputFormat::Ndjson => {
+            for event in events.iter() {
+                println!(
+                    "{}",
+                    serde_json::to_string(event).map_err(|e| e.to_string())?
+                );
+            }
+        }
+        OutputFormat::Table => {
+            let rows = events
+                .iter()
+                .map(|event| {
+                    vec![
+                        event.inscription_id.clone(),
+                        event.event.to_string(),
+                        event.block_height.to_string(),
+                        event.tx_id.clone(),
+                        event.satpoint.clone(),
+                        format_optional(&event.address),
+                        format_optional(&event.value),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            print_table(
+                &[
+                    "INSCRIPTION",
+                    "EVENT",
+                    "BLOCK",
+                    "TX",
+                    "SATPOINT",
+                    "ADDRESS",
+                    "VALUE",
+                ],
+                &rows,
+            );
+        }
     }
     Ok(())
 }
@@ -970,11 +1757,50 @@ pub fn build_predicate_from_cli(
     Ok(predicate)
 }
 
+fn configure_signing_secret(
+    config: &Config,
+    predicate_uuid: &str,
+    secret: &str,
+    ctx: &Context,
+) -> Result<(), String> {
+    let outbox_db_conn = open_readwrite_outbox_db_conn(&config.expected_cache_path(), ctx)?;
+    rotate_signing_secret(predicate_uuid, secret, Duration::ZERO, &outbox_db_conn, ctx);
+    Ok(())
+}
+
+/// Retries the payloads left pending by a scan: no service runloop is around to deliver them once
+/// the command exits.
+async fn drain_scan_deliveries(
+    config: &Config,
+    predicate_uuid: &str,
+    delivery_timeout: u64,
+    ctx: &Context,
+) -> Result<(), String> {
+    let pending = drain_pending_deliveries(
+        predicate_uuid,
+        &config.expected_cache_path(),
+        Duration::from_secs(delivery_timeout),
+        ctx,
+    )
+    .await?;
+    let outbox_db_conn = open_readwrite_outbox_db_conn(&config.expected_cache_path(), ctx)?;
+    let report = get_outbox_report(predicate_uuid, &outbox_db_conn, ctx);
+    if pending > 0 {
+        return Err(format!(
+            "{pending} payloads still pending delivery after {delivery_timeout}s, they will be retried by `ordhook service start`"
+        ));
+    }
+    if report.dead > 0 {
+        return Err(format!(
+            "{} payloads moved to the dead letter queue after {MAX_DELIVERY_ATTEMPTS} delivery attempts",
+            report.dead
+        ));
+    }
+    Ok(())
+}
+
 pub async fn check_bitcoind_connection(config: &Config) -> Result<u64, String> {
-    let auth = Auth::UserPass(
-        config.network.bitcoind_rpc_username.clone(),
-        config.network.bitcoind_rpc_password.clone(),
-    );
+    let auth = config.bitcoind_rpc_auth();
 
     let bitcoin_rpc = match Client::new(&config.network.bitcoind_rpc_url, auth) {
         Ok(con) => con,
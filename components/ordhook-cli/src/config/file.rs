@@ -1,10 +1,11 @@
+use super::schema::{apply_env_overrides, check_config_value};
 use ordhook::chainhook_sdk::indexer::IndexerConfig;
 use ordhook::chainhook_sdk::types::{
     BitcoinBlockSignaling, BitcoinNetwork, StacksNetwork, StacksNodeConfig,
 };
 use ordhook::config::{
-    BootstrapConfig, Config, LimitsConfig, LogConfig, PredicatesApi, PredicatesApiConfig,
-    StorageConfig,
+    read_bitcoind_rpc_cookie_file, BootstrapConfig, Config, LimitsConfig, LogConfig,
+    MonitoringConfig, PredicatesApi, PredicatesApiConfig, StorageConfig, TenantConfig,
 };
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -18,16 +19,115 @@ pub const BITCOIN_MAX_PREDICATE_REGISTRATION: usize = 50;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ConfigFile {
+    #[serde(default)]
     pub storage: StorageConfigFile,
     pub http_api: Option<PredicatesApiConfigFile>,
+    #[serde(default)]
     pub limits: LimitsConfigFile,
     pub network: NetworkConfigFile,
     pub logs: Option<LogConfigFile>,
     pub bootstrap: Option<BootstrapConfigFile>,
+    pub monitoring: Option<MonitoringConfigFile>,
+}
+
+/// Configuration loaded from a file, with the environment overrides applied.
+pub struct LoadedConfig {
+    pub config: Config,
+    /// `ORDHOOK_*` variables applied.
+    pub env_overrides: Vec<String>,
+    /// Sections and keys ignored.
+    pub warnings: Vec<String>,
+}
+
+fn format_config_errors(errors: &[String]) -> String {
+    format!("invalid configuration:\n  - {}", errors.join("\n  - "))
+}
+
+fn read_secret_file(key: &str, file_path: &str) -> Result<String, String> {
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("{key}: unable to read {file_path} ({e})"))?;
+    let secret = content.trim_end_matches(|c| c == '\n' || c == '\r');
+    if secret.is_empty() {
+        return Err(format!("{key}: {file_path} is empty"));
+    }
+    Ok(secret.to_string())
+}
+
+/// Value of the secret `key`, set either inline or through the file `<key>_file`.
+fn resolve_secret(
+    key: &str,
+    value: &Option<String>,
+    file_path: &Option<String>,
+) -> Result<Option<String>, String> {
+    match (value, file_path) {
+        (Some(_), Some(_)) => Err(format!("{key} and {key}_file are mutually exclusive")),
+        (Some(value), None) => Ok(Some(value.clone())),
+        (None, Some(file_path)) => read_secret_file(&format!("{key}_file"), file_path).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Keys listed in a file, one per line.
+fn resolve_api_keys(
+    key: &str,
+    keys: &Option<Vec<String>>,
+    file_path: &Option<String>,
+) -> Result<Option<Vec<String>>, String> {
+    match (keys, file_path) {
+        (Some(_), Some(_)) => Err(format!("{key} and {key}_file are mutually exclusive")),
+        (Some(keys), None) => Ok(Some(keys.clone())),
+        (None, Some(file_path)) => {
+            let content = read_secret_file(&format!("{key}_file"), file_path)?;
+            Ok(Some(
+                content
+                    .lines()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty())
+                    .map(|line| line.to_string())
+                    .collect(),
+            ))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Credentials of the `.cookie` file written by bitcoind when `rpcpassword` is not set. The file is
+/// read on load to fail early, then again by each bitcoind client (see `Config::bitcoind_rpc_auth`).
+fn read_cookie_file(file_path: &str) -> Result<(String, String), String> {
+    read_bitcoind_rpc_cookie_file(file_path)
+        .map_err(|e| format!("network.bitcoind_rpc_cookie_file: {e}"))
+}
+
+fn resolve_bitcoind_rpc_credentials(
+    network: &NetworkConfigFile,
+) -> Result<(String, String), String> {
+    let username = resolve_secret(
+        "network.bitcoind_rpc_username",
+        &network.bitcoind_rpc_username,
+        &network.bitcoind_rpc_username_file,
+    )?;
+    let password = resolve_secret(
+        "network.bitcoind_rpc_password",
+        &network.bitcoind_rpc_password,
+        &network.bitcoind_rpc_password_file,
+    )?;
+    match (&network.bitcoind_rpc_cookie_file, username, password) {
+        (Some(cookie_file), None, None) => read_cookie_file(cookie_file),
+        (Some(_), _, _) => Err("network.bitcoind_rpc_cookie_file cannot be combined with network.bitcoind_rpc_username or network.bitcoind_rpc_password".to_string()),
+        (None, Some(username), Some(password)) => Ok((username, password)),
+        (None, None, _) => Err("network.bitcoind_rpc_username is missing (or set network.bitcoind_rpc_username_file, or network.bitcoind_rpc_cookie_file)".to_string()),
+        (None, _, None) => Err("network.bitcoind_rpc_password is missing (or set network.bitcoind_rpc_password_file, or network.bitcoind_rpc_cookie_file)".to_string()),
+    }
 }
 
 impl ConfigFile {
     pub fn from_file_path(file_path: &str) -> Result<Config, String> {
+        Ok(ConfigFile::load_file_path(file_path)?.config)
+    }
+
+    /// Loads and validates the configuration file `file_path`, overridden by the `ORDHOOK_*`
+    /// environment variables.
+    pub fn load_file_path(file_path: &str) -> Result<LoadedConfig, String> {
         let file = File::open(file_path)
             .map_err(|e| format!("unable to read file {}\n{:?}", file_path, e))?;
         let mut file_reader = BufReader::new(file);
@@ -36,16 +136,158 @@ impl ConfigFile {
             .read_to_end(&mut file_buffer)
             .map_err(|e| format!("unable to read file {}\n{:?}", file_path, e))?;
 
-        let config_file: ConfigFile = match toml::from_slice(&file_buffer) {
+        let mut value: toml::Value = match toml::from_slice(&file_buffer) {
             Ok(s) => s,
             Err(e) => {
                 return Err(format!("Config file malformatted {}", e));
             }
         };
-        ConfigFile::from_config_file(config_file)
+        let env_overrides = apply_env_overrides(&mut value, |name| std::env::var(name).ok())?;
+        let (errors, warnings) = check_config_value(&value);
+        if !errors.is_empty() {
+            return Err(format!("{file_path}: {}", format_config_errors(&errors)));
+        }
+        let config_file: ConfigFile = match value.try_into() {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(format!("Config file malformatted {}", e));
+            }
+        };
+        let config =
+            ConfigFile::from_config_file(config_file).map_err(|e| format!("{file_path}: {e}"))?;
+        Ok(LoadedConfig {
+            config,
+            env_overrides,
+            warnings,
+        })
+    }
+
+    /// Checks the values of the configuration which do not depend on the secrets files.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if !["devnet", "testnet", "mainnet"].contains(&self.network.mode.as_str()) {
+            errors.push(format!(
+                "network.mode: expected devnet, testnet or mainnet, got '{}'",
+                self.network.mode
+            ));
+        }
+        if let Some(working_dir) = &self.storage.working_dir {
+            if working_dir.trim().is_empty() {
+                errors.push("storage.working_dir: expected a path, got an empty string".into());
+            }
+        }
+        let mut ports = vec![];
+        if let Some(http_api) = &self.http_api {
+            ports.push((
+                "http_api.http_port",
+                http_api.http_port.unwrap_or(DEFAULT_CONTROL_PORT),
+            ));
+        }
+        if let Some(port) = self
+            .monitoring
+            .as_ref()
+            .and_then(|m| m.prometheus_monitoring_port)
+        {
+            ports.push(("monitoring.prometheus_monitoring_port", port));
+        }
+        if self.network.bitcoind_zmq_url.is_none() {
+            ports.push((
+                "network.stacks_events_ingestion_port",
+                self.network
+                    .stacks_events_ingestion_port
+                    .unwrap_or(DEFAULT_INGESTION_PORT),
+            ));
+        }
+        for (index, (key, port)) in ports.iter().enumerate() {
+            if let Some((other_key, _)) = ports[..index].iter().find(|(_, p)| p == port) {
+                errors.push(format!("{key}: port {port} is already used by {other_key}"));
+            }
+        }
+        if let Some(tenants) = self.http_api.as_ref().and_then(|h| h.tenants.as_ref()) {
+            for (index, tenant) in tenants.iter().enumerate() {
+                if tenant.owner_uuid.trim().is_empty() {
+                    errors.push(format!(
+                        "http_api.tenants[{index}].owner_uuid: expected a uuid, got an empty string"
+                    ));
+                } else if tenants[..index]
+                    .iter()
+                    .any(|t| t.owner_uuid == tenant.owner_uuid)
+                {
+                    errors.push(format!(
+                        "http_api.tenants[{index}].owner_uuid: {} is already used by another tenant",
+                        tenant.owner_uuid
+                    ));
+                }
+                if tenant.api_keys.is_none() && tenant.api_keys_file.is_none() {
+                    errors.push(format!(
+                        "http_api.tenants[{index}].api_keys is missing (or set http_api.tenants[{index}].api_keys_file)"
+                    ));
+                }
+            }
+        }
+        errors
     }
 
     pub fn from_config_file(config_file: ConfigFile) -> Result<Config, String> {
+        let mut errors = config_file.validate();
+
+        // Secrets
+        let bitcoind_rpc_credentials = resolve_bitcoind_rpc_credentials(&config_file.network)
+            .map_err(|e| errors.push(e))
+            .ok();
+        let mut api_keys_granted: Vec<(String, String)> = vec![];
+        let mut grant_api_keys = |key: String, api_keys: &Vec<String>, errors: &mut Vec<String>| {
+            for api_key in api_keys.iter() {
+                match api_keys_granted.iter().find(|(_, k)| k == api_key) {
+                    Some((other_key, _)) => {
+                        errors.push(format!("{key}: a key is already granted by {other_key}"))
+                    }
+                    None => api_keys_granted.push((key.clone(), api_key.clone())),
+                }
+            }
+        };
+        let http_api = match config_file.http_api {
+            None => None,
+            Some(ref http_api) => {
+                let admin_api_keys = resolve_api_keys(
+                    "http_api.admin_api_keys",
+                    &http_api.admin_api_keys,
+                    &http_api.admin_api_keys_file,
+                )
+                .map_err(|e| errors.push(e))
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+                grant_api_keys(
+                    "http_api.admin_api_keys".into(),
+                    &admin_api_keys,
+                    &mut errors,
+                );
+                let mut tenants = vec![];
+                for (index, tenant) in http_api.tenants.iter().flatten().enumerate() {
+                    let key = format!("http_api.tenants[{index}].api_keys");
+                    let api_keys = resolve_api_keys(&key, &tenant.api_keys, &tenant.api_keys_file)
+                        .map_err(|e| errors.push(e))
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default();
+                    grant_api_keys(key, &api_keys, &mut errors);
+                    tenants.push(TenantConfig {
+                        owner_uuid: tenant.owner_uuid.clone(),
+                        api_keys,
+                        max_number_of_bitcoin_predicates: tenant.max_number_of_bitcoin_predicates,
+                    });
+                }
+                Some((http_api.clone(), admin_api_keys, tenants))
+            }
+        };
+
+        if !errors.is_empty() {
+            return Err(format_config_errors(&errors));
+        }
+        let (bitcoind_rpc_username, bitcoind_rpc_password) =
+            bitcoind_rpc_credentials.unwrap_or_default();
+
         let (stacks_network, bitcoin_network) = match config_file.network.mode.as_str() {
             "devnet" => (StacksNetwork::Devnet, BitcoinNetwork::Regtest),
             "testnet" => (StacksNetwork::Testnet, BitcoinNetwork::Testnet),
@@ -65,13 +307,15 @@ impl ConfigFile {
             storage: StorageConfig {
                 working_dir: config_file.storage.working_dir.unwrap_or("ordhook".into()),
             },
-            http_api: match config_file.http_api {
+            http_api: match http_api {
                 None => PredicatesApi::Off,
-                Some(http_api) => match http_api.disabled {
+                Some((http_api, admin_api_keys, tenants)) => match http_api.disabled {
                     Some(false) => PredicatesApi::Off,
                     _ => PredicatesApi::On(PredicatesApiConfig {
                         http_port: http_api.http_port.unwrap_or(DEFAULT_CONTROL_PORT),
                         display_logs: http_api.display_logs.unwrap_or(true),
+                        admin_api_keys,
+                        tenants,
                     }),
                 },
             },
@@ -105,11 +349,15 @@ impl ConfigFile {
                     .limits
                     .max_caching_memory_size_mb
                     .unwrap_or(2048),
+                max_queued_blocks_memory_size_mb: config_file
+                    .limits
+                    .max_queued_blocks_memory_size_mb
+                    .unwrap_or(1024),
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: config_file.network.bitcoind_rpc_url.to_string(),
-                bitcoind_rpc_username: config_file.network.bitcoind_rpc_username.to_string(),
-                bitcoind_rpc_password: config_file.network.bitcoind_rpc_password.to_string(),
+                bitcoind_rpc_username,
+                bitcoind_rpc_password,
                 bitcoin_block_signaling: match config_file.network.bitcoind_zmq_url {
                     Some(ref zmq_url) => BitcoinBlockSignaling::ZeroMQ(zmq_url.clone()),
                     None => BitcoinBlockSignaling::Stacks(StacksNodeConfig::default_localhost(
@@ -122,6 +370,7 @@ impl ConfigFile {
                 stacks_network,
                 bitcoin_network,
             },
+            bitcoind_rpc_cookie_file: config_file.network.bitcoind_rpc_cookie_file.clone(),
             logs: LogConfig {
                 ordinals_internals: config_file
                     .logs
@@ -134,6 +383,12 @@ impl ConfigFile {
                     .and_then(|l| l.chainhook_internals)
                     .unwrap_or(true),
             },
+            monitoring: MonitoringConfig {
+                prometheus_monitoring_port: config_file
+                    .monitoring
+                    .as_ref()
+                    .and_then(|m| m.prometheus_monitoring_port),
+            },
         };
         Ok(config)
     }
@@ -162,6 +417,11 @@ pub struct LogConfigFile {
 }
 
 #[derive(Deserialize, Debug, Clone)]
+pub struct MonitoringConfigFile {
+    pub prometheus_monitoring_port: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct StorageConfigFile {
     pub working_dir: Option<String>,
 }
@@ -172,6 +432,19 @@ pub struct PredicatesApiConfigFile {
     pub database_uri: Option<String>,
     pub display_logs: Option<bool>,
     pub disabled: Option<bool>,
+    pub admin_api_keys: Option<Vec<String>>,
+    /// Admin keys, one per line
+    pub admin_api_keys_file: Option<String>,
+    pub tenants: Option<Vec<TenantConfigFile>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TenantConfigFile {
+    pub owner_uuid: String,
+    pub api_keys: Option<Vec<String>>,
+    /// Tenant keys, one per line
+    pub api_keys_file: Option<String>,
+    pub max_number_of_bitcoin_predicates: Option<usize>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -179,7 +452,7 @@ pub struct BootstrapConfigFile {
     pub download_url: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct LimitsConfigFile {
     pub max_number_of_bitcoin_predicates: Option<usize>,
     pub max_number_of_concurrent_bitcoin_scans: Option<usize>,
@@ -187,6 +460,7 @@ pub struct LimitsConfigFile {
     pub max_number_of_concurrent_stacks_scans: Option<usize>,
     pub max_number_of_processing_threads: Option<usize>,
     pub max_caching_memory_size_mb: Option<usize>,
+    pub max_queued_blocks_memory_size_mb: Option<usize>,
     pub bitcoin_concurrent_http_requests_max: Option<usize>,
 }
 
@@ -194,8 +468,12 @@ pub struct LimitsConfigFile {
 pub struct NetworkConfigFile {
     pub mode: String,
     pub bitcoind_rpc_url: String,
-    pub bitcoind_rpc_username: String,
-    pub bitcoind_rpc_password: String,
+    pub bitcoind_rpc_username: Option<String>,
+    pub bitcoind_rpc_username_file: Option<String>,
+    pub bitcoind_rpc_password: Option<String>,
+    pub bitcoind_rpc_password_file: Option<String>,
+    /// `.cookie` file written by bitcoind, used instead of a username and a password
+    pub bitcoind_rpc_cookie_file: Option<String>,
     pub bitcoind_zmq_url: Option<String>,
     pub stacks_node_rpc_url: Option<String>,
     pub stacks_events_ingestion_port: Option<u16>,
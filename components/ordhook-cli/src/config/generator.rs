@@ -12,12 +12,27 @@ working_dir = "ordhook"
 #
 # [http_api]
 # http_port = 20456
+# Requests must include one of the keys (`Authorization: Bearer <key>`
+# or `x-api-key: <key>`) when keys are configured.
+# admin_api_keys = ["<operator key>"]
+# or one key per line, from a file:
+# admin_api_keys_file = "/run/secrets/ordhook_admin_api_keys"
+#
+# [[http_api.tenants]]
+# owner_uuid = "<tenant uuid>"
+# api_keys = ["<tenant key>"]
+# max_number_of_bitcoin_predicates = 10
 
 [network]
 mode = "{network}"
 bitcoind_rpc_url = "http://0.0.0.0:8332"
 bitcoind_rpc_username = "devnet"
 bitcoind_rpc_password = "devnet"
+# Credentials can also be read from files (e.g. mounted secrets):
+# bitcoind_rpc_username_file = "/run/secrets/bitcoind_rpc_username"
+# bitcoind_rpc_password_file = "/run/secrets/bitcoind_rpc_password"
+# or from the cookie file of bitcoind, instead of a username and a password:
+# bitcoind_rpc_cookie_file = "/home/bitcoin/.bitcoin/.cookie"
 # Bitcoin block events can be received by Chainhook
 # either through a Bitcoin node's ZeroMQ interface,
 # or through the Stacks node. Zmq is being
@@ -32,6 +47,8 @@ max_number_of_concurrent_bitcoin_scans = 100
 max_number_of_processing_threads = 16
 bitcoin_concurrent_http_requests_max = 16
 max_caching_memory_size_mb = 32000
+# Blocks downloaded ahead of the indexer
+max_queued_blocks_memory_size_mb = 1024
 
 # Disable the following section if the state
 # must be built locally
@@ -41,6 +58,14 @@ download_url = "https://archive.hiro.so/mainnet/ordhook/mainnet-ordhook-sqlite-l
 [logs]
 ordinals_internals = true
 chainhook_internals = true
+
+# Prometheus metrics (tip height and lag, pipeline
+# throughput, traversals, caches, predicates deliveries)
+# can be exposed on a dedicated port.
+# Also served on /metrics when the Http Api is enabled.
+#
+# [monitoring]
+# prometheus_monitoring_port = 20457
 "#,
         network = network.to_lowercase(),
     );
@@ -0,0 +1,374 @@
+use reqwest::Url;
+use toml::value::{Table, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    String,
+    /// Url, with one of the schemes listed
+    Url(&'static [&'static str]),
+    Boolean,
+    Port,
+    /// Strictly positive integer
+    Count,
+    /// List of strings, comma separated when set from the environment
+    Strings,
+    /// `[[http_api.tenants]]` entries, as a TOML inline array when set from the environment
+    Tenants,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConfigField {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+impl ConfigField {
+    pub fn path(&self) -> String {
+        format!("{}.{}", self.section, self.key)
+    }
+
+    /// `ORDHOOK_<SECTION>_<KEY>`, e.g. `ORDHOOK_NETWORK_BITCOIND_RPC_URL`.
+    pub fn env_var(&self) -> String {
+        format!("ORDHOOK_{}_{}", self.section, self.key).to_uppercase()
+    }
+}
+
+const HTTP_SCHEMES: &[&str] = &["http", "https"];
+const ZMQ_SCHEMES: &[&str] = &["tcp", "ipc"];
+
+const fn field(
+    section: &'static str,
+    key: &'static str,
+    kind: FieldKind,
+    required: bool,
+) -> ConfigField {
+    ConfigField {
+        section,
+        key,
+        kind,
+        required,
+    }
+}
+
+pub const CONFIG_FIELDS: &[ConfigField] = &[
+    field("storage", "working_dir", FieldKind::String, false),
+    field("http_api", "http_port", FieldKind::Port, false),
+    field("http_api", "database_uri", FieldKind::String, false),
+    field("http_api", "display_logs", FieldKind::Boolean, false),
+    field("http_api", "disabled", FieldKind::Boolean, false),
+    field("http_api", "admin_api_keys", FieldKind::Strings, false),
+    field("http_api", "admin_api_keys_file", FieldKind::String, false),
+    field("http_api", "tenants", FieldKind::Tenants, false),
+    field(
+        "limits",
+        "max_number_of_bitcoin_predicates",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_number_of_concurrent_bitcoin_scans",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_number_of_stacks_predicates",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_number_of_concurrent_stacks_scans",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_number_of_processing_threads",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_caching_memory_size_mb",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "max_queued_blocks_memory_size_mb",
+        FieldKind::Count,
+        false,
+    ),
+    field(
+        "limits",
+        "bitcoin_concurrent_http_requests_max",
+        FieldKind::Count,
+        false,
+    ),
+    field("network", "mode", FieldKind::String, true),
+    field(
+        "network",
+        "bitcoind_rpc_url",
+        FieldKind::Url(HTTP_SCHEMES),
+        true,
+    ),
+    field("network", "bitcoind_rpc_username", FieldKind::String, false),
+    field(
+        "network",
+        "bitcoind_rpc_username_file",
+        FieldKind::String,
+        false,
+    ),
+    field("network", "bitcoind_rpc_password", FieldKind::String, false),
+    field(
+        "network",
+        "bitcoind_rpc_password_file",
+        FieldKind::String,
+        false,
+    ),
+    field(
+        "network",
+        "bitcoind_rpc_cookie_file",
+        FieldKind::String,
+        false,
+    ),
+    field(
+        "network",
+        "bitcoind_zmq_url",
+        FieldKind::Url(ZMQ_SCHEMES),
+        false,
+    ),
+    field(
+        "network",
+        "stacks_node_rpc_url",
+        FieldKind::Url(HTTP_SCHEMES),
+        false,
+    ),
+    field(
+        "network",
+        "stacks_events_ingestion_port",
+        FieldKind::Port,
+        false,
+    ),
+    field("logs", "ordinals_internals", FieldKind::Boolean, false),
+    field("logs", "chainhook_internals", FieldKind::Boolean, false),
+    field(
+        "bootstrap",
+        "download_url",
+        FieldKind::Url(HTTP_SCHEMES),
+        false,
+    ),
+    field(
+        "monitoring",
+        "prometheus_monitoring_port",
+        FieldKind::Port,
+        false,
+    ),
+];
+
+const TENANT_FIELDS: &[(&str, FieldKind, bool)] = &[
+    ("owner_uuid", FieldKind::String, true),
+    ("api_keys", FieldKind::Strings, false),
+    ("api_keys_file", FieldKind::String, false),
+    ("max_number_of_bitcoin_predicates", FieldKind::Count, false),
+];
+
+fn parse_env_value(kind: FieldKind, raw: &str) -> Result<Value, String> {
+    match kind {
+        FieldKind::String | FieldKind::Url(_) => Ok(Value::String(raw.to_string())),
+        FieldKind::Boolean => raw
+            .trim()
+            .parse::<bool>()
+            .map(Value::Boolean)
+            .map_err(|_| format!("expected true or false, got '{raw}'")),
+        FieldKind::Port | FieldKind::Count => raw
+            .trim()
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| format!("expected an integer, got '{raw}'")),
+        FieldKind::Strings => Ok(Value::Array(
+            raw.split(',')
+                .map(|entry| entry.trim())
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| Value::String(entry.to_string()))
+                .collect(),
+        )),
+        FieldKind::Tenants => {
+            let mut table: Table = toml::from_str(&format!("tenants = {raw}")).map_err(|e| {
+                format!(
+                    "expected an array of tenants, e.g. [{{ owner_uuid = \"...\", api_keys = [\"...\"] }}] ({e})"
+                )
+            })?;
+            Ok(table.remove("tenants").unwrap_or(Value::Array(vec![])))
+        }
+    }
+}
+
+/// Overrides the fields of `value` with the `ORDHOOK_*` variables returned by `lookup`, and
+/// returns the names of the variables applied.
+pub fn apply_env_overrides<F>(value: &mut Value, lookup: F) -> Result<Vec<String>, String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Some(root) = value.as_table_mut() else {
+        return Err("the configuration must be a table".to_string());
+    };
+    let mut applied = vec![];
+    for field in CONFIG_FIELDS.iter() {
+        let env_var = field.env_var();
+        let Some(raw) = lookup(&env_var) else {
+            continue;
+        };
+        let parsed = parse_env_value(field.kind, &raw)
+            .map_err(|e| format!("{env_var} (overriding {}): {e}", field.path()))?;
+        let section = root
+            .entry(field.section.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        let Some(section) = section.as_table_mut() else {
+            return Err(format!(
+                "{env_var}: {} is not a table in the configuration file",
+                field.section
+            ));
+        };
+        section.insert(field.key.to_string(), parsed);
+        applied.push(env_var);
+    }
+    Ok(applied)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Datetime(_) => "a datetime",
+        Value::Array(_) => "an array",
+        Value::Table(_) => "a table",
+    }
+}
+
+fn check_field_value(path: &str, kind: FieldKind, value: &Value, errors: &mut Vec<String>) {
+    match (kind, value) {
+        (FieldKind::String, Value::String(_)) => {}
+        (FieldKind::Url(schemes), Value::String(url)) => match Url::parse(url) {
+            Ok(parsed) if schemes.contains(&parsed.scheme()) => {}
+            Ok(parsed) => errors.push(format!(
+                "{path}: unsupported scheme '{}' in '{url}', expected {}",
+                parsed.scheme(),
+                schemes.join(" or ")
+            )),
+            Err(e) => errors.push(format!("{path}: invalid url '{url}' ({e})")),
+        },
+        (FieldKind::Boolean, Value::Boolean(_)) => {}
+        (FieldKind::Port, Value::Integer(port)) => {
+            if *port < 1 || *port > u16::MAX as i64 {
+                errors.push(format!(
+                    "{path}: {port} is not a valid port, expected 1 to 65535"
+                ));
+            }
+        }
+        (FieldKind::Count, Value::Integer(count)) => {
+            if *count < 1 {
+                errors.push(format!("{path}: expected at least 1, got {count}"));
+            }
+        }
+        (FieldKind::Strings, Value::Array(entries)) => {
+            for (index, entry) in entries.iter().enumerate() {
+                if !entry.is_str() {
+                    errors.push(format!(
+                        "{path}[{index}]: expected a string, got {}",
+                        type_name(entry)
+                    ));
+                }
+            }
+        }
+        (FieldKind::Tenants, Value::Array(tenants)) => {
+            for (index, tenant) in tenants.iter().enumerate() {
+                let tenant_path = format!("{path}[{index}]");
+                let Some(tenant) = tenant.as_table() else {
+                    errors.push(format!(
+                        "{tenant_path}: expected a table, got {}",
+                        type_name(tenant)
+                    ));
+                    continue;
+                };
+                for (key, kind, required) in TENANT_FIELDS.iter() {
+                    match tenant.get(*key) {
+                        Some(value) => {
+                            check_field_value(&format!("{tenant_path}.{key}"), *kind, value, errors)
+                        }
+                        None if *required => errors.push(format!("{tenant_path}.{key} is missing")),
+                        None => {}
+                    }
+                }
+            }
+        }
+        (kind, value) => {
+            let expected = match kind {
+                FieldKind::String | FieldKind::Url(_) => "a string",
+                FieldKind::Boolean => "a boolean",
+                FieldKind::Port | FieldKind::Count => "an integer",
+                FieldKind::Strings | FieldKind::Tenants => "an array",
+            };
+            errors.push(format!(
+                "{path}: expected {expected}, got {}",
+                type_name(value)
+            ));
+        }
+    }
+}
+
+/// Checks the types of the fields of a configuration, and lists the sections and keys unknown.
+/// Returns the errors and the warnings found.
+pub fn check_config_value(value: &Value) -> (Vec<String>, Vec<String>) {
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    let Some(root) = value.as_table() else {
+        return (
+            vec!["the configuration must be a table".to_string()],
+            warnings,
+        );
+    };
+    for (section_name, section) in root.iter() {
+        if !CONFIG_FIELDS.iter().any(|f| f.section == section_name) {
+            warnings.push(format!("unknown section [{section_name}] is ignored"));
+            continue;
+        }
+        let Some(section) = section.as_table() else {
+            errors.push(format!(
+                "{section_name}: expected a table, got {}",
+                type_name(section)
+            ));
+            continue;
+        };
+        for (key, value) in section.iter() {
+            match CONFIG_FIELDS
+                .iter()
+                .find(|f| f.section == section_name && f.key == key)
+            {
+                Some(field) => check_field_value(&field.path(), field.kind, value, &mut errors),
+                None => warnings.push(format!("unknown key {section_name}.{key} is ignored")),
+            }
+        }
+    }
+    for field in CONFIG_FIELDS.iter().filter(|f| f.required) {
+        let present = root
+            .get(field.section)
+            .and_then(|section| section.as_table())
+            .map(|section| section.contains_key(field.key))
+            .unwrap_or(false);
+        if !present {
+            errors.push(format!(
+                "{} is missing (or set {})",
+                field.path(),
+                field.env_var()
+            ));
+        }
+    }
+    (errors, warnings)
+}
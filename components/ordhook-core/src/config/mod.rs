@@ -1,4 +1,5 @@
 use crate::core::OrdhookConfig;
+use chainhook_sdk::bitcoincore_rpc::Auth;
 pub use chainhook_sdk::indexer::IndexerConfig;
 use chainhook_sdk::observer::EventObserverConfig;
 use chainhook_sdk::types::{
@@ -22,8 +23,29 @@ pub struct Config {
     pub http_api: PredicatesApi,
     pub limits: LimitsConfig,
     pub network: IndexerConfig,
+    /// `.cookie` file written by bitcoind, used instead of `network.bitcoind_rpc_username` and
+    /// `network.bitcoind_rpc_password` (see `bitcoind_rpc_auth`).
+    pub bitcoind_rpc_cookie_file: Option<String>,
     pub bootstrap: BootstrapConfig,
     pub logs: LogConfig,
+    pub monitoring: MonitoringConfig,
+}
+
+/// Credentials of the `.cookie` file written by bitcoind when `rpcpassword` is not set.
+pub fn read_bitcoind_rpc_cookie_file(file_path: &str) -> Result<(String, String), String> {
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("unable to read {file_path} ({e})"))?;
+    match content
+        .trim_end_matches(|c| c == '\n' || c == '\r')
+        .split_once(':')
+    {
+        Some((username, password)) if !username.is_empty() && !password.is_empty() => {
+            Ok((username.to_string(), password.to_string()))
+        }
+        _ => Err(format!(
+            "{file_path} is not a bitcoind cookie file (expected <user>:<password>)"
+        )),
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -32,6 +54,11 @@ pub struct LogConfig {
     pub chainhook_internals: bool,
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct MonitoringConfig {
+    pub prometheus_monitoring_port: Option<u16>,
+}
+
 #[derive(Clone, Debug)]
 pub struct StorageConfig {
     pub working_dir: String,
@@ -47,6 +74,19 @@ pub enum PredicatesApi {
 pub struct PredicatesApiConfig {
     pub http_port: u16,
     pub display_logs: bool,
+    /// Keys granting access to the predicates of every tenant. The API is open when neither
+    /// admin nor tenant keys are configured.
+    pub admin_api_keys: Vec<String>,
+    pub tenants: Vec<TenantConfig>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TenantConfig {
+    /// Owner of the predicates registered with the keys of the tenant.
+    pub owner_uuid: String,
+    pub api_keys: Vec<String>,
+    /// Defaults to `limits.max_number_of_bitcoin_predicates`.
+    pub max_number_of_bitcoin_predicates: Option<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -74,6 +114,8 @@ pub struct LimitsConfig {
     pub max_number_of_processing_threads: usize,
     pub bitcoin_concurrent_http_requests_max: usize,
     pub max_caching_memory_size_mb: usize,
+    /// Bytes of blocks downloaded but not yet handed to the post processor.
+    pub max_queued_blocks_memory_size_mb: usize,
 }
 
 impl Config {
@@ -88,8 +130,13 @@ impl Config {
         OrdhookConfig {
             network_thread_max: self.limits.bitcoin_concurrent_http_requests_max,
             ingestion_thread_max: self.limits.max_number_of_processing_threads,
-            ingestion_thread_queue_size: 4,
+            // Large enough for the compression threads to absorb a full round of concurrent downloads
+            ingestion_thread_queue_size: 4.max(
+                self.limits.bitcoin_concurrent_http_requests_max
+                    / self.limits.max_number_of_processing_threads.max(1),
+            ),
             cache_size: self.limits.max_caching_memory_size_mb,
+            queued_blocks_size: self.limits.max_queued_blocks_memory_size_mb,
             db_path: self.expected_cache_path(),
             first_inscription_height: match self.network.bitcoin_network {
                 BitcoinNetwork::Mainnet => 767430,
@@ -101,13 +148,40 @@ impl Config {
         }
     }
 
+    /// Authentication of the bitcoind RPC clients. The cookie file is read by each new client: the
+    /// credentials rotated by a restart of bitcoind are picked up on the next connection.
+    pub fn bitcoind_rpc_auth(&self) -> Auth {
+        match &self.bitcoind_rpc_cookie_file {
+            Some(file_path) => Auth::CookieFile(PathBuf::from(file_path)),
+            None => Auth::UserPass(
+                self.network.bitcoind_rpc_username.clone(),
+                self.network.bitcoind_rpc_password.clone(),
+            ),
+        }
+    }
+
+    /// Username and password of bitcoind, for the clients which do not support cookie files. The
+    /// cookie file is read again on each call, the credentials loaded on start are used when it
+    /// can't be read.
+    pub fn bitcoind_rpc_credentials(&self) -> (String, String) {
+        let credentials = (
+            self.network.bitcoind_rpc_username.clone(),
+            self.network.bitcoind_rpc_password.clone(),
+        );
+        match &self.bitcoind_rpc_cookie_file {
+            Some(file_path) => read_bitcoind_rpc_cookie_file(file_path).unwrap_or(credentials),
+            None => credentials,
+        }
+    }
+
     pub fn get_event_observer_config(&self) -> EventObserverConfig {
+        let (bitcoind_rpc_username, bitcoind_rpc_password) = self.bitcoind_rpc_credentials();
         EventObserverConfig {
             bitcoin_rpc_proxy_enabled: true,
             chainhook_config: None,
             ingestion_port: DEFAULT_INGESTION_PORT,
-            bitcoind_rpc_username: self.network.bitcoind_rpc_username.clone(),
-            bitcoind_rpc_password: self.network.bitcoind_rpc_password.clone(),
+            bitcoind_rpc_username,
+            bitcoind_rpc_password,
             bitcoind_rpc_url: self.network.bitcoind_rpc_url.clone(),
             bitcoin_block_signaling: self.network.bitcoin_block_signaling.clone(),
             display_logs: false,
@@ -168,6 +242,7 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 bitcoin_concurrent_http_requests_max: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_queued_blocks_memory_size_mb: 1024,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:18443".into(),
@@ -179,10 +254,12 @@ impl Config {
                 stacks_network: StacksNetwork::Devnet,
                 bitcoin_network: BitcoinNetwork::Regtest,
             },
+            bitcoind_rpc_cookie_file: None,
             logs: LogConfig {
                 ordinals_internals: true,
                 chainhook_internals: false,
             },
+            monitoring: MonitoringConfig::default(),
         }
     }
 
@@ -201,6 +278,7 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 bitcoin_concurrent_http_requests_max: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_queued_blocks_memory_size_mb: 1024,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:18332".into(),
@@ -212,10 +290,12 @@ impl Config {
                 stacks_network: StacksNetwork::Testnet,
                 bitcoin_network: BitcoinNetwork::Testnet,
             },
+            bitcoind_rpc_cookie_file: None,
             logs: LogConfig {
                 ordinals_internals: true,
                 chainhook_internals: false,
             },
+            monitoring: MonitoringConfig::default(),
         }
     }
 
@@ -236,6 +316,7 @@ impl Config {
                 max_number_of_processing_threads: 1.max(num_cpus::get().saturating_sub(1)),
                 bitcoin_concurrent_http_requests_max: 1.max(num_cpus::get().saturating_sub(1)),
                 max_caching_memory_size_mb: 2048,
+                max_queued_blocks_memory_size_mb: 1024,
             },
             network: IndexerConfig {
                 bitcoind_rpc_url: "http://0.0.0.0:8332".into(),
@@ -247,10 +328,12 @@ impl Config {
                 stacks_network: StacksNetwork::Mainnet,
                 bitcoin_network: BitcoinNetwork::Mainnet,
             },
+            bitcoind_rpc_cookie_file: None,
             logs: LogConfig {
                 ordinals_internals: true,
                 chainhook_internals: false,
             },
+            monitoring: MonitoringConfig::default(),
         }
     }
 }
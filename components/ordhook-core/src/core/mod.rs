@@ -1,40 +1,55 @@
+#[cfg(feature = "indexer")]
 pub mod pipeline;
 pub mod protocol;
+#[cfg(feature = "indexer")]
+pub mod traversals_cache;
 
+#[cfg(feature = "indexer")]
 use dashmap::DashMap;
+#[cfg(feature = "indexer")]
 use fxhash::{FxBuildHasher, FxHasher};
+#[cfg(feature = "indexer")]
 use std::hash::BuildHasherDefault;
-use std::ops::Div;
+#[cfg(feature = "indexer")]
 use std::path::PathBuf;
 
+#[cfg(feature = "indexer")]
 use chainhook_sdk::{
-    bitcoincore_rpc::{Auth, Client, RpcApi},
+    bitcoincore_rpc::{Client, RpcApi},
     utils::Context,
 };
 
+#[cfg(feature = "indexer")]
 use crate::{
     config::{Config, LogConfig},
     db::{find_lazy_block_at_block_height, open_ordhook_db_conn_rocks_db_loop},
+    utils::monitoring::METRICS,
 };
 
+#[cfg(feature = "indexer")]
 use crate::db::{
-    find_last_block_inserted, find_latest_inscription_block_height, initialize_ordhook_db,
-    open_readonly_ordhook_db_conn,
+    find_last_block_inserted, find_last_checkpoint, find_latest_inscription_block_height,
+    find_latest_transfers_block_height, initialize_ordhook_db, is_ordhook_db_migrated,
+    open_readonly_ordhook_db_conn, Checkpoint,
 };
 
-use crate::db::LazyBlockTransaction;
+#[cfg(feature = "indexer")]
+use self::traversals_cache::TraversalsCache;
 
+#[cfg(feature = "indexer")]
 #[derive(Clone, Debug)]
 pub struct OrdhookConfig {
     pub network_thread_max: usize,
     pub ingestion_thread_max: usize,
     pub ingestion_thread_queue_size: usize,
     pub cache_size: usize,
+    pub queued_blocks_size: usize,
     pub db_path: PathBuf,
     pub first_inscription_height: u64,
     pub logs: LogConfig,
 }
 
+#[cfg(feature = "indexer")]
 pub fn new_traversals_cache(
 ) -> DashMap<(u32, [u8; 8]), (Vec<([u8; 8], u32, u16, u64)>, Vec<u64>), BuildHasherDefault<FxHasher>>
 {
@@ -42,15 +57,9 @@ pub fn new_traversals_cache(
     DashMap::with_hasher(hasher)
 }
 
-pub fn new_traversals_lazy_cache(
-    cache_size: usize,
-) -> DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>> {
-    let hasher = FxBuildHasher::default();
-    DashMap::with_capacity_and_hasher(
-        ((cache_size.saturating_sub(500)) * 1000 * 1000)
-            .div(LazyBlockTransaction::get_average_bytes_size()),
-        hasher,
-    )
+#[cfg(feature = "indexer")]
+pub fn new_traversals_lazy_cache(max_caching_memory_size_mb: usize) -> TraversalsCache {
+    TraversalsCache::new(max_caching_memory_size_mb)
 }
 
 #[derive(PartialEq, Debug)]
@@ -94,10 +103,15 @@ pub fn compute_next_satpoint_data(
     SatPosition::Output((output_index, (offset_cross_inputs - offset_intra_outputs)))
 }
 
+#[cfg(feature = "indexer")]
 pub fn should_sync_rocks_db(config: &Config, ctx: &Context) -> Result<Option<(u64, u64)>, String> {
     let blocks_db = open_ordhook_db_conn_rocks_db_loop(true, &config.expected_cache_path(), &ctx);
     let inscriptions_db_conn = open_readonly_ordhook_db_conn(&config.expected_cache_path(), &ctx)?;
-    let last_compressed_block = find_last_block_inserted(&blocks_db) as u64;
+    // Blocks inserted after the last archiving checkpoint may not have been flushed
+    let last_compressed_block = match find_last_checkpoint(Checkpoint::Archived, &blocks_db) {
+        Some(checkpoint) => checkpoint.min(find_last_block_inserted(&blocks_db) as u64),
+        None => find_last_block_inserted(&blocks_db) as u64,
+    };
     let last_indexed_block = match find_latest_inscription_block_height(&inscriptions_db_conn, ctx)?
     {
         Some(last_indexed_block) => last_indexed_block,
@@ -112,14 +126,12 @@ pub fn should_sync_rocks_db(config: &Config, ctx: &Context) -> Result<Option<(u6
     Ok(res)
 }
 
+#[cfg(feature = "indexer")]
 pub fn should_sync_ordhook_db(
     config: &Config,
     ctx: &Context,
-) -> Result<Option<(u64, u64, usize)>, String> {
-    let auth = Auth::UserPass(
-        config.network.bitcoind_rpc_username.clone(),
-        config.network.bitcoind_rpc_password.clone(),
-    );
+) -> Result<Option<(u64, u64)>, String> {
+    let auth = config.bitcoind_rpc_auth();
 
     let bitcoin_rpc = match Client::new(&config.network.bitcoind_rpc_url, auth) {
         Ok(con) => con,
@@ -131,13 +143,29 @@ pub fn should_sync_ordhook_db(
     let blocks_db = open_ordhook_db_conn_rocks_db_loop(true, &config.expected_cache_path(), &ctx);
     let mut start_block = find_last_block_inserted(&blocks_db) as u64;
 
-    if start_block == 0 {
-        let _ = initialize_ordhook_db(&config.expected_cache_path(), &ctx);
+    // Also creates the tables introduced after the database was built or downloaded
+    let inscriptions_db_conn_rw = initialize_ordhook_db(&config.expected_cache_path(), &ctx);
+    if !is_ordhook_db_migrated(&inscriptions_db_conn_rw, &ctx) {
+        return Err(
+            "hord.sqlite stores outpoints as text: stop ordhook and run `ordhook db migrate`"
+                .to_string(),
+        );
     }
 
     let inscriptions_db_conn = open_readonly_ordhook_db_conn(&config.expected_cache_path(), &ctx)?;
 
-    match find_latest_inscription_block_height(&inscriptions_db_conn, ctx)? {
+    // Blocks with transfers only are recorded in locations: resume after the last block indexed.
+    // Blocks without activity leave no row, the indexing checkpoint covers them.
+    let indexed_checkpoint = find_last_checkpoint(Checkpoint::Indexed, &blocks_db)
+        .filter(|checkpoint| *checkpoint <= start_block);
+    let latest_indexed_block_height =
+        find_latest_inscription_block_height(&inscriptions_db_conn, ctx)?
+            .max(find_latest_transfers_block_height(
+                &inscriptions_db_conn,
+                ctx,
+            ))
+            .map(|height| height.max(indexed_checkpoint.unwrap_or(0)));
+    match latest_indexed_block_height {
         Some(height) => {
             if find_lazy_block_at_block_height(height as u32, 3, false, &blocks_db, &ctx).is_none()
             {
@@ -152,8 +180,11 @@ pub fn should_sync_ordhook_db(
         }
     };
 
-    let end_block = match bitcoin_rpc.get_blockchain_info() {
-        Ok(result) => result.blocks,
+    let mut end_block = match bitcoin_rpc.get_blockchain_info() {
+        Ok(result) => {
+            METRICS.set_chain_tip_block_height(result.blocks);
+            result.blocks
+        }
         Err(e) => {
             return Err(format!(
                 "unable to retrieve Bitcoin chain tip ({})",
@@ -163,20 +194,22 @@ pub fn should_sync_ordhook_db(
     };
 
     // TODO: Gracefully handle Regtest, Testnet and Signet
-    let (mut end_block, speed) = if start_block < 200_000 {
-        (end_block.min(200_000), 10_000)
+    // Early blocks are synced in large chunks, recent (heavier) ones up to the tip. Only the
+    // chunks are tiered by height: the number of blocks downloaded ahead adapts to the rpc latency
+    // and to the downstream load (`pipeline::concurrency`), and the blocks queued are bounded by
+    // `limits.max_queued_blocks_memory_size_mb`.
+    if start_block < 200_000 {
+        end_block = end_block.min(200_000);
     } else if start_block < 550_000 {
-        (end_block.min(550_000), 1_000)
-    } else {
-        (end_block, 100)
-    };
+        end_block = end_block.min(550_000);
+    }
 
     if start_block < 767430 && end_block > 767430 {
         end_block = 767430;
     }
 
     if start_block <= end_block {
-        Ok(Some((start_block, end_block, speed)))
+        Ok(Some((start_block, end_block)))
     } else {
         Ok(None)
     }
@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+
+/// Minimum delay between two adjustments of the download concurrency.
+const ADJUSTMENT_INTERVAL: Duration = Duration::from_secs(2);
+/// Above this ratio of failed downloads, concurrency is halved.
+const MAX_ERROR_RATE: f64 = 0.05;
+/// Downstream stages filled above this ratio are considered as keeping up, concurrency is held.
+const DOWNSTREAM_BUSY_RATIO: f64 = 0.5;
+/// Downstream stages filled above this ratio are considered as lagging, concurrency is halved.
+const DOWNSTREAM_SATURATED_RATIO: f64 = 0.9;
+/// RPC latency is considered as degraded once it exceeds this factor of the best latency observed.
+const LATENCY_DEGRADATION_FACTOR: f64 = 2.0;
+/// Smoothing factor of the RPC latency moving average.
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+/// Ratio by which the latency floor drifts towards the moving average on each adjustment,
+/// so that the baseline follows the growth of blocks sizes.
+const LATENCY_FLOOR_DRIFT: f64 = 0.05;
+
+/// Occupancy of the stages consuming the downloaded blocks.
+#[derive(Clone, Debug, Default)]
+pub struct DownstreamLoad {
+    /// Blocks waiting to be parsed and compressed.
+    pub compression_backlog: usize,
+    pub compression_capacity: usize,
+    /// Bytes of blocks downloaded but not yet handed to the post processor.
+    pub queued_bytes: usize,
+    pub max_queued_bytes: usize,
+}
+
+impl DownstreamLoad {
+    pub fn pressure(&self) -> f64 {
+        let compression = ratio(self.compression_backlog, self.compression_capacity);
+        let queued = ratio(self.queued_bytes, self.max_queued_bytes);
+        compression.max(queued)
+    }
+
+    pub fn is_saturated(&self) -> bool {
+        self.queued_bytes >= self.max_queued_bytes
+    }
+}
+
+fn ratio(value: usize, capacity: usize) -> f64 {
+    if capacity == 0 {
+        return 0.0;
+    }
+    value as f64 / capacity as f64
+}
+
+/// Additive increase / multiplicative decrease controller driving the number of concurrent
+/// block downloads.
+///
+/// Concurrency grows by one while bitcoind answers at its usual pace and downstream stages keep up,
+/// and is halved when RPC calls start failing or when compression / indexing lag behind.
+pub struct AdaptiveConcurrency {
+    min: usize,
+    max: usize,
+    target: usize,
+    latency_ewma_ms: Option<f64>,
+    latency_floor_ms: Option<f64>,
+    requests: u64,
+    errors: u64,
+    last_adjustment: Instant,
+}
+
+impl AdaptiveConcurrency {
+    pub fn new(initial: usize, min: usize, max: usize) -> AdaptiveConcurrency {
+        let min = min.max(1);
+        let max = max.max(min);
+        AdaptiveConcurrency {
+            min,
+            max,
+            target: initial.max(min).min(max),
+            latency_ewma_ms: None,
+            latency_floor_ms: None,
+            requests: 0,
+            errors: 0,
+            last_adjustment: Instant::now(),
+        }
+    }
+
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    pub fn latency_ms(&self) -> f64 {
+        self.latency_ewma_ms.unwrap_or(0.0)
+    }
+
+    pub fn record_success(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.requests += 1;
+        self.latency_ewma_ms = Some(match self.latency_ewma_ms {
+            Some(ewma) => ewma + LATENCY_EWMA_ALPHA * (latency_ms - ewma),
+            None => latency_ms,
+        });
+    }
+
+    pub fn record_failure(&mut self) {
+        self.requests += 1;
+        self.errors += 1;
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.requests as f64
+    }
+
+    /// Re-evaluates the target concurrency, at most once per `ADJUSTMENT_INTERVAL`.
+    /// Returns the new target when it changed.
+    pub fn adjust(&mut self, load: &DownstreamLoad) -> Option<usize> {
+        if self.last_adjustment.elapsed() < ADJUSTMENT_INTERVAL {
+            return None;
+        }
+        self.adjust_now(load)
+    }
+
+    fn adjust_now(&mut self, load: &DownstreamLoad) -> Option<usize> {
+        let previous_target = self.target;
+        let pressure = load.pressure();
+
+        let latency_degraded = match (self.latency_ewma_ms, self.latency_floor_ms) {
+            (Some(ewma), Some(floor)) => ewma > floor * LATENCY_DEGRADATION_FACTOR,
+            _ => false,
+        };
+
+        if self.error_rate() > MAX_ERROR_RATE || pressure >= DOWNSTREAM_SATURATED_RATIO {
+            self.target = (self.target / 2).max(self.min);
+        } else if latency_degraded {
+            self.target = self.target.saturating_sub(1).max(self.min);
+        } else if pressure < DOWNSTREAM_BUSY_RATIO && self.requests > 0 {
+            self.target = (self.target + 1).min(self.max);
+        }
+
+        if let Some(ewma) = self.latency_ewma_ms {
+            self.latency_floor_ms = Some(match self.latency_floor_ms {
+                Some(floor) if floor <= ewma => floor + LATENCY_FLOOR_DRIFT * (ewma - floor),
+                _ => ewma,
+            });
+        }
+        self.requests = 0;
+        self.errors = 0;
+        self.last_adjustment = Instant::now();
+
+        if self.target != previous_target {
+            Some(self.target)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_load() -> DownstreamLoad {
+        DownstreamLoad {
+            compression_backlog: 0,
+            compression_capacity: 16,
+            queued_bytes: 0,
+            max_queued_bytes: 1024,
+        }
+    }
+
+    #[test]
+    fn increases_concurrency_while_healthy() {
+        let mut concurrency = AdaptiveConcurrency::new(2, 1, 4);
+        for _ in 0..5 {
+            concurrency.record_success(Duration::from_millis(10));
+            concurrency.adjust_now(&idle_load());
+        }
+        assert_eq!(concurrency.target(), 4);
+    }
+
+    #[test]
+    fn backs_off_on_errors_and_lagging_stages() {
+        let mut concurrency = AdaptiveConcurrency::new(8, 1, 8);
+        concurrency.record_failure();
+        assert_eq!(concurrency.adjust_now(&idle_load()), Some(4));
+
+        concurrency.record_success(Duration::from_millis(10));
+        let mut lagging_load = idle_load();
+        lagging_load.queued_bytes = 1024;
+        assert_eq!(concurrency.adjust_now(&lagging_load), Some(2));
+        assert!(lagging_load.is_saturated());
+    }
+}
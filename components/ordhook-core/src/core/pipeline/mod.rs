@@ -1,3 +1,4 @@
+pub mod concurrency;
 pub mod processors;
 
 use chainhook_sdk::observer::BitcoinConfig;
@@ -5,19 +6,28 @@ use chainhook_sdk::types::BitcoinBlockData;
 use chainhook_sdk::utils::Context;
 use crossbeam_channel::bounded;
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread::{sleep, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::task::JoinSet;
 
 use crate::config::Config;
 use crate::db::LazyBlock;
+use crate::utils::monitoring::METRICS;
+use crate::utils::shutdown::is_shutdown_requested;
 
 use chainhook_sdk::indexer::bitcoin::{
     build_http_client, parse_downloaded_block, try_download_block_bytes_with_retry,
 };
 
+use self::concurrency::{AdaptiveConcurrency, DownstreamLoad};
+
 use super::protocol::inscription_parsing::parse_inscriptions_and_standardize_block;
 
+/// Interval between two throughput reports.
+const THROUGHPUT_REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
 pub enum PostProcessorCommand {
     ProcessBlocks(Vec<(u64, LazyBlock)>, Vec<BitcoinBlockData>),
     Terminate,
@@ -39,7 +49,6 @@ pub async fn download_and_pipeline_blocks(
     blocks: Vec<u64>,
     start_sequencing_blocks_at_height: u64,
     blocks_post_processor: Option<&PostProcessorController>,
-    speed: usize,
     ctx: &Context,
 ) -> Result<(), String> {
     // let guard = pprof::ProfilerGuardBuilder::default()
@@ -48,9 +57,10 @@ pub async fn download_and_pipeline_blocks(
     //     .build()
     //     .unwrap();
 
+    let (username, password) = config.bitcoind_rpc_credentials();
     let bitcoin_config = BitcoinConfig {
-        username: config.network.bitcoind_rpc_username.clone(),
-        password: config.network.bitcoind_rpc_password.clone(),
+        username,
+        password,
         rpc_url: config.network.bitcoind_rpc_url.clone(),
         network: config.network.bitcoin_network.clone(),
         bitcoin_block_signaling: config.network.bitcoin_block_signaling.clone(),
@@ -60,33 +70,24 @@ pub async fn download_and_pipeline_blocks(
 
     let number_of_blocks_to_process = blocks.len() as u64;
 
-    let (block_compressed_tx, block_compressed_rx) = crossbeam_channel::bounded(speed);
+    // Upper bound of the bytes of blocks downloaded but not yet handed to the post processor
+    // (`limits.max_queued_blocks_memory_size_mb`). Downloads are paused once reached, until the
+    // downstream stages catch up: memory usage is bounded by the bytes accounted in
+    // `queued_bytes`, not by the channel.
+    let max_queued_bytes = ordhook_config.queued_blocks_size * 1024 * 1024;
+    let (block_compressed_tx, block_compressed_rx) = crossbeam_channel::unbounded();
     let http_client = build_http_client();
 
-    let moved_config = bitcoin_config.clone();
-    let moved_ctx = ctx.clone();
-    let moved_http_client = http_client.clone();
-
     let mut set = JoinSet::new();
 
     let start_block = *blocks.first().expect("no blocks to pipeline");
     let end_block = *blocks.last().expect("no blocks to pipeline");
     let mut block_heights = VecDeque::from(blocks);
 
-    for _ in 0..ordhook_config.ingestion_thread_queue_size {
-        if let Some(block_height) = block_heights.pop_front() {
-            let config = moved_config.clone();
-            let ctx = moved_ctx.clone();
-            let http_client = moved_http_client.clone();
-            sleep(Duration::from_millis(500));
-            set.spawn(try_download_block_bytes_with_retry(
-                http_client,
-                block_height,
-                config,
-                ctx,
-            ));
-        }
-    }
+    let queued_bytes = Arc::new(AtomicUsize::new(0));
+    let blocks_dispatched = Arc::new(AtomicU64::new(0));
+    let start_sequencing_blocks_at_height = start_sequencing_blocks_at_height.max(start_block);
+    let dispatcher_cursor = Arc::new(AtomicU64::new(start_sequencing_blocks_at_height));
 
     let moved_ctx: Context = ctx.clone();
     let moved_bitcoin_network = bitcoin_config.network.clone();
@@ -109,6 +110,7 @@ pub async fn download_and_pipeline_blocks(
         let handle = hiro_system_kit::thread_named("Block data compression")
             .spawn(move || {
                 while let Ok(Some(block_bytes)) = rx.recv() {
+                    let block_size = block_bytes.len();
                     let raw_block_data =
                         parse_downloaded_block(block_bytes).expect("unable to parse block");
                     let compressed_block = LazyBlock::from_full_block(&raw_block_data)
@@ -125,10 +127,12 @@ pub async fn download_and_pipeline_blocks(
                     } else {
                         None
                     };
+                    METRICS.record_pipeline_blocks("compression", 1);
                     let _ = block_compressed_tx_moved.send(Some((
                         block_height,
                         block_data,
                         compressed_block,
+                        block_size,
                     )));
                 }
                 moved_ctx
@@ -144,10 +148,14 @@ pub async fn download_and_pipeline_blocks(
         .as_ref()
         .and_then(|p| Some(p.commands_tx.clone()));
 
+    let moved_queued_bytes = queued_bytes.clone();
+    let moved_blocks_dispatched = blocks_dispatched.clone();
+    let moved_dispatcher_cursor = dispatcher_cursor.clone();
+
     let storage_thread = hiro_system_kit::thread_named("Block processor dispatcher")
         .spawn(move || {
             let mut inbox = HashMap::new();
-            let mut inbox_cursor = start_sequencing_blocks_at_height.max(start_block);
+            let mut inbox_cursor = start_sequencing_blocks_at_height;
             let mut blocks_processed = 0;
             let mut stop_runloop = false;
 
@@ -169,8 +177,8 @@ pub async fn download_and_pipeline_blocks(
                 let mut new_blocks = vec![];
                 while let Ok(message) = block_compressed_rx.try_recv() {
                     match message {
-                        Some((block_height, block, compacted_block)) => {
-                            new_blocks.push((block_height, block, compacted_block));
+                        Some((block_height, block, compacted_block, block_size)) => {
+                            new_blocks.push((block_height, block, compacted_block, block_size));
                             // Max batch size: 10_000 blocks
                             if new_blocks.len() >= 10_000 {
                                 break;
@@ -193,23 +201,30 @@ pub async fn download_and_pipeline_blocks(
                 }
 
                 let mut ooo_compacted_blocks = vec![];
-                for (block_height, block_opt, compacted_block) in new_blocks.into_iter() {
+                let mut ooo_blocks_size = 0;
+                for (block_height, block_opt, compacted_block, block_size) in new_blocks.into_iter()
+                {
                     if let Some(block) = block_opt {
-                        inbox.insert(block_height, (block, compacted_block));
+                        inbox.insert(block_height, (block, compacted_block, block_size));
                     } else {
                         ooo_compacted_blocks.push((block_height, compacted_block));
+                        ooo_blocks_size += block_size;
                     }
                 }
 
                 // Early "continue"
                 if !ooo_compacted_blocks.is_empty() {
-                    blocks_processed += ooo_compacted_blocks.len() as u64;
+                    let ooo_blocks_count = ooo_compacted_blocks.len() as u64;
+                    blocks_processed += ooo_blocks_count;
                     if let Some(ref blocks_tx) = blocks_post_processor_commands_tx {
                         let _ = blocks_tx.send(PostProcessorCommand::ProcessBlocks(
                             ooo_compacted_blocks,
                             vec![],
                         ));
                     }
+                    moved_queued_bytes.fetch_sub(ooo_blocks_size, Ordering::Relaxed);
+                    moved_blocks_dispatched.fetch_add(ooo_blocks_count, Ordering::Relaxed);
+                    METRICS.record_pipeline_blocks("dispatch", ooo_blocks_count);
                 }
 
                 if inbox.is_empty() {
@@ -219,13 +234,17 @@ pub async fn download_and_pipeline_blocks(
                 // In order processing: construct the longest sequence of known blocks
                 let mut compacted_blocks = vec![];
                 let mut blocks = vec![];
-                while let Some((block, compacted_block)) = inbox.remove(&inbox_cursor) {
+                let mut blocks_size = 0;
+                while let Some((block, compacted_block, block_size)) = inbox.remove(&inbox_cursor) {
                     compacted_blocks.push((inbox_cursor, compacted_block));
                     blocks.push(block);
+                    blocks_size += block_size;
                     inbox_cursor += 1;
                 }
+                moved_dispatcher_cursor.store(inbox_cursor, Ordering::Relaxed);
 
-                blocks_processed += blocks.len() as u64;
+                let blocks_count = blocks.len() as u64;
+                blocks_processed += blocks_count;
 
                 if !blocks.is_empty() {
                     if let Some(ref blocks_tx) = blocks_post_processor_commands_tx {
@@ -234,6 +253,9 @@ pub async fn download_and_pipeline_blocks(
                             blocks,
                         ));
                     }
+                    moved_queued_bytes.fetch_sub(blocks_size, Ordering::Relaxed);
+                    moved_blocks_dispatched.fetch_add(blocks_count, Ordering::Relaxed);
+                    METRICS.record_pipeline_blocks("dispatch", blocks_count);
                 }
 
                 if inbox_cursor > end_block {
@@ -244,23 +266,137 @@ pub async fn download_and_pipeline_blocks(
         })
         .expect("unable to spawn thread");
 
+    let mut concurrency = AdaptiveConcurrency::new(
+        ordhook_config.ingestion_thread_queue_size,
+        1,
+        ordhook_config.network_thread_max,
+    );
+    let compression_capacity =
+        ordhook_config.ingestion_thread_max * ordhook_config.ingestion_thread_queue_size;
+
     let mut thread_index = 0;
-    while let Some(res) = set.join_next().await {
-        let block = res.unwrap().unwrap();
-
-        let _ = tx_thread_pool[thread_index].send(Some(block));
-        if let Some(block_height) = block_heights.pop_front() {
-            let config = moved_config.clone();
-            let ctx = ctx.clone();
-            let http_client = moved_http_client.clone();
-            set.spawn(try_download_block_bytes_with_retry(
-                http_client,
-                block_height,
-                config,
-                ctx,
-            ));
+    let mut blocks_downloaded = 0;
+    let mut bytes_downloaded = 0;
+    let mut last_report = Instant::now();
+    let mut last_report_blocks_dispatched = 0;
+
+    loop {
+        if is_shutdown_requested() && !block_heights.is_empty() {
+            ctx.try_log(|logger| {
+                info!(
+                    logger,
+                    "Shutdown requested, draining {} blocks in flight",
+                    set.len()
+                )
+            });
+            block_heights.clear();
+        }
+
+        let load = DownstreamLoad {
+            compression_backlog: tx_thread_pool.iter().map(|tx| tx.len()).sum(),
+            compression_capacity,
+            queued_bytes: queued_bytes.load(Ordering::Relaxed),
+            max_queued_bytes,
+        };
+
+        if let Some(target) = concurrency.adjust(&load) {
+            ctx.try_log(|logger| {
+                debug!(
+                    logger,
+                    "Adjusting download concurrency to {target} (rpc latency: {:.0}ms, downstream pressure: {:.2})",
+                    concurrency.latency_ms(),
+                    load.pressure(),
+                )
+            });
+        }
+
+        // Fill the download slots, unless downstream stages are saturated. The block expected next by the
+        // dispatcher is always let through, otherwise the blocks pending in its inbox could never be released.
+        while set.len() < concurrency.target() {
+            let next_block_height = if load.is_saturated() {
+                let cursor = dispatcher_cursor.load(Ordering::Relaxed);
+                block_heights
+                    .iter()
+                    .position(|block_height| *block_height == cursor)
+                    .and_then(|index| block_heights.remove(index))
+            } else {
+                block_heights.pop_front()
+            };
+            let Some(block_height) = next_block_height else {
+                break;
+            };
+            let config = bitcoin_config.clone();
+            let moved_ctx = ctx.clone();
+            let http_client = http_client.clone();
+            set.spawn(async move {
+                let started_at = Instant::now();
+                let res = try_download_block_bytes_with_retry(
+                    http_client,
+                    block_height,
+                    config,
+                    moved_ctx,
+                )
+                .await;
+                (block_height, started_at.elapsed(), res)
+            });
+        }
+
+        if set.is_empty() {
+            if block_heights.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            continue;
+        }
+
+        let Some(res) = set.join_next().await else {
+            continue;
+        };
+        match res {
+            Ok((_, latency, Ok(block_bytes))) => {
+                concurrency.record_success(latency);
+                METRICS.record_pipeline_blocks("download", 1);
+                blocks_downloaded += 1;
+                bytes_downloaded += block_bytes.len();
+                queued_bytes.fetch_add(block_bytes.len(), Ordering::Relaxed);
+                let _ = tx_thread_pool[thread_index].send(Some(block_bytes));
+                thread_index = (thread_index + 1) % ordhook_config.ingestion_thread_max;
+            }
+            Ok((block_height, _, Err(e))) => {
+                concurrency.record_failure();
+                ctx.try_log(|logger| {
+                    warn!(
+                        logger,
+                        "Unable to download block #{block_height}: {e}, retrying"
+                    )
+                });
+                block_heights.push_front(block_height);
+            }
+            Err(e) => {
+                return Err(format!("unable to join download task: {}", e.to_string()));
+            }
+        }
+
+        if last_report.elapsed() >= THROUGHPUT_REPORT_INTERVAL {
+            let elapsed = last_report.elapsed().as_secs_f64();
+            let dispatched = blocks_dispatched.load(Ordering::Relaxed);
+            ctx.try_log(|logger| {
+                info!(
+                    logger,
+                    "Pipeline throughput: {:.1} blocks/s downloaded ({:.1} MB/s), {:.1} blocks/s dispatched, {} downloads in flight, rpc latency: {:.0}ms, {} MB queued",
+                    blocks_downloaded as f64 / elapsed,
+                    bytes_downloaded as f64 / elapsed / (1024.0 * 1024.0),
+                    (dispatched - last_report_blocks_dispatched) as f64 / elapsed,
+                    set.len(),
+                    concurrency.latency_ms(),
+                    queued_bytes.load(Ordering::Relaxed) / (1024 * 1024),
+                )
+            });
+            blocks_downloaded = 0;
+            bytes_downloaded = 0;
+            last_report_blocks_dispatched = dispatched;
+            last_report = Instant::now();
         }
-        thread_index = (thread_index + 1) % ordhook_config.ingestion_thread_max;
     }
 
     ctx.try_log(|logger| {
@@ -282,6 +418,10 @@ pub async fn download_and_pipeline_blocks(
 
     ctx.try_log(|logger| debug!(logger, "Pipeline successfully terminated"));
 
+    // All the compressed blocks have been enqueued: once dequeued, the dispatcher
+    // terminates the post processor.
+    let _ = block_compressed_tx.send(None);
+
     if let Some(post_processor) = blocks_post_processor {
         loop {
             if let Ok(signal) = post_processor.events_rx.recv() {
@@ -292,8 +432,6 @@ pub async fn download_and_pipeline_blocks(
         }
     }
 
-    let _ = block_compressed_tx.send(None);
-
     let _ = storage_thread.join();
     let _ = set.shutdown();
 
@@ -321,3 +459,254 @@ pub async fn download_and_pipeline_blocks(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chainhook_sdk::indexer::bitcoin::BitcoinBlockFullBreakdown;
+    use chainhook_sdk::observer::{BitcoinBlockDataCached, HandleBlock};
+    use chainhook_sdk::types::BitcoinBlockData;
+    use chainhook_sdk::utils::Context;
+    use serde_json::Value as JsonValue;
+
+    use super::download_and_pipeline_blocks;
+    use crate::config::Config;
+    use crate::core::pipeline::processors::start_inscription_indexing_processor;
+    use crate::core::protocol::inscription_parsing::{
+        get_inscriptions_revealed_in_block, parse_inscriptions_and_standardize_block,
+    };
+    use crate::core::{new_traversals_lazy_cache, should_sync_ordhook_db};
+    use crate::db::{
+        find_inscription_with_id, find_latest_inscription_transfer_data,
+        open_readonly_ordhook_db_conn, open_readwrite_ordhook_db_conn,
+        open_readwrite_ordhook_db_conn_rocks_db, rewind_ordhook_db,
+    };
+    use crate::ord::height::Height;
+    use crate::service::{chainhook_sidecar_mutate_blocks, chainhook_sidecar_mutate_ordhook_db};
+    use crate::utils::bitcoind_mock::MockBitcoind;
+    use crate::utils::regtest_fixtures::{regtest_corpus, FixtureInscription};
+
+    fn sync_ordhook_db(config: &Config, ctx: &Context) {
+        let (start_block, end_block) = should_sync_ordhook_db(config, ctx)
+            .expect("unable to evaluate sync")
+            .expect("ordhook db already synced");
+        let processor = start_inscription_indexing_processor(config, ctx, None);
+        hiro_system_kit::nestable_block_on(download_and_pipeline_blocks(
+            config,
+            (start_block..=end_block).collect(),
+            start_block,
+            Some(&processor),
+            ctx,
+        ))
+        .expect("unable to pipeline blocks");
+        let _ = processor.thread_handle.join();
+    }
+
+    fn inscription_number_and_sat(
+        inscription_id: &str,
+        config: &Config,
+        ctx: &Context,
+    ) -> Option<(i64, u64)> {
+        let conn = open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx).unwrap();
+        find_inscription_with_id(inscription_id, &conn, ctx)
+            .ok()
+            .flatten()
+            .map(|(traversal, _)| (traversal.inscription_number, traversal.ordinal_number))
+    }
+
+    fn standardize_blocks(
+        blocks: &[JsonValue],
+        config: &Config,
+        ctx: &Context,
+    ) -> Vec<BitcoinBlockData> {
+        blocks
+            .iter()
+            .map(|block| {
+                let block: BitcoinBlockFullBreakdown =
+                    serde_json::from_value(block.clone()).expect("unable to parse fixture block");
+                parse_inscriptions_and_standardize_block(
+                    block,
+                    &config.network.bitcoin_network,
+                    ctx,
+                )
+                .expect("unable to standardize fixture block")
+            })
+            .collect()
+    }
+
+    fn first_sat(block_height: u64) -> u64 {
+        Height(block_height).starting_sat().n()
+    }
+
+    #[test]
+    fn indexes_regtest_corpus() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let config = bitcoind.ordhook_config();
+
+        sync_ordhook_db(&config, &ctx);
+
+        let expectations = [
+            (&corpus.inscription, 0, first_sat(1)),
+            (&corpus.tagged_curse, -1, first_sat(2)),
+            (&corpus.batch_curse, -2, first_sat(4)),
+            (&corpus.reinscription, -3, first_sat(1)),
+            (&corpus.second_inscription, 1, first_sat(5)),
+        ];
+        for (inscription_id, inscription_number, ordinal_number) in expectations {
+            assert_eq!(
+                inscription_number_and_sat(inscription_id, &config, &ctx),
+                Some((inscription_number, ordinal_number)),
+                "unexpected indexing of {inscription_id}"
+            );
+        }
+
+        let conn = open_readonly_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+        let location = find_latest_inscription_transfer_data(&corpus.inscription, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            location.satpoint.outpoint.txid.to_string(),
+            corpus.reinscription_txid
+        );
+
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+
+    #[test]
+    fn reindexes_blocks_after_reorg() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let config = bitcoind.ordhook_config();
+
+        sync_ordhook_db(&config, &ctx);
+
+        // Blocks #6 and #7 are replaced by a fork where the reinscription never happened
+        let mut fork = corpus.chain.fork(5);
+        let fork_inscription = fork.inscribe(&fork.coinbase(5), &FixtureInscription::text("fork"));
+        fork.mine_blocks(3);
+        bitcoind.reorg(fork.blocks().clone());
+
+        // Same sequence as the observer: the sidecar mutates the blocks of the new branch, then
+        // the blocks of the former branch are undone and the new ones applied.
+        let former_blocks = standardize_blocks(&corpus.chain.blocks()[6..], &config, &ctx);
+        let mut blocks_to_mutate = standardize_blocks(&fork.blocks()[6..], &config, &ctx)
+            .into_iter()
+            .map(|block| BitcoinBlockDataCached {
+                block,
+                processed_by_sidecar: false,
+            })
+            .collect::<Vec<_>>();
+        let blocks_ids_to_rollback = former_blocks
+            .iter()
+            .map(|block| block.block_identifier.clone())
+            .collect::<Vec<_>>();
+        let cache_l2 = Arc::new(new_traversals_lazy_cache(16));
+        chainhook_sidecar_mutate_blocks(
+            &mut blocks_to_mutate,
+            &blocks_ids_to_rollback,
+            &cache_l2,
+            &config,
+            &ctx,
+        );
+        let revealed = blocks_to_mutate
+            .iter()
+            .flat_map(|cache| get_inscriptions_revealed_in_block(&cache.block))
+            .map(|reveal| (reveal.inscription_id.clone(), reveal.inscription_number))
+            .collect::<Vec<_>>();
+        assert_eq!(revealed, vec![(format!("{}i0", fork_inscription.txid), 1)]);
+        for block in former_blocks.into_iter().rev() {
+            chainhook_sidecar_mutate_ordhook_db(HandleBlock::UndoBlock(block), &config, &ctx);
+        }
+        for cache in blocks_to_mutate.into_iter() {
+            chainhook_sidecar_mutate_ordhook_db(
+                HandleBlock::ApplyBlock(cache.block),
+                &config,
+                &ctx,
+            );
+        }
+
+        // The fork has been fully applied
+        assert_eq!(should_sync_ordhook_db(&config, &ctx), Ok(None));
+
+        assert_eq!(
+            inscription_number_and_sat(&corpus.reinscription, &config, &ctx),
+            None
+        );
+        assert_eq!(
+            inscription_number_and_sat(&corpus.second_inscription, &config, &ctx),
+            None
+        );
+        assert_eq!(
+            inscription_number_and_sat(&format!("{}i0", fork_inscription.txid), &config, &ctx),
+            Some((1, first_sat(5)))
+        );
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+
+    #[test]
+    fn reindexes_blocks_after_rewind() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let config = bitcoind.ordhook_config();
+
+        sync_ordhook_db(&config, &ctx);
+        let reinscription = inscription_number_and_sat(&corpus.reinscription, &config, &ctx);
+        let second_inscription =
+            inscription_number_and_sat(&corpus.second_inscription, &config, &ctx);
+        assert!(reinscription.is_some() && second_inscription.is_some());
+
+        {
+            let blocks_db =
+                open_readwrite_ordhook_db_conn_rocks_db(&config.expected_cache_path(), &ctx)
+                    .unwrap();
+            let conn = open_readwrite_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+            let report = rewind_ordhook_db(5, &blocks_db, &conn, &ctx).unwrap();
+            assert_eq!(report.previous_tip, 7);
+            assert_eq!(report.blocks_removed, 2);
+        }
+
+        // Blocks #6 and #7 are forgotten, block #5 is kept
+        assert_eq!(
+            inscription_number_and_sat(&corpus.reinscription, &config, &ctx),
+            None
+        );
+        assert_eq!(
+            inscription_number_and_sat(&corpus.batch_curse, &config, &ctx),
+            Some((-2, first_sat(4)))
+        );
+        let conn = open_readonly_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+        let location = find_latest_inscription_transfer_data(&corpus.inscription, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            location.satpoint.outpoint.txid.to_string(),
+            corpus.transfer_txid
+        );
+        assert_eq!(should_sync_ordhook_db(&config, &ctx), Ok(Some((6, 7))));
+
+        // Indexing resumes from block #6 and yields the same numbers
+        sync_ordhook_db(&config, &ctx);
+        assert_eq!(
+            inscription_number_and_sat(&corpus.reinscription, &config, &ctx),
+            reinscription
+        );
+        assert_eq!(
+            inscription_number_and_sat(&corpus.second_inscription, &config, &ctx),
+            second_inscription
+        );
+        let location = find_latest_inscription_transfer_data(&corpus.inscription, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            location.satpoint.outpoint.txid.to_string(),
+            corpus.reinscription_txid
+        );
+        assert_eq!(should_sync_ordhook_db(&config, &ctx), Ok(None));
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+}
@@ -9,7 +9,11 @@ use std::{
 use crate::{
     config::Config,
     core::pipeline::{PostProcessorCommand, PostProcessorController, PostProcessorEvent},
-    db::{insert_entry_in_blocks, open_ordhook_db_conn_rocks_db_loop, LazyBlock},
+    db::{
+        find_last_block_inserted, flush_ordhook_db_conn_rocks_db, insert_checkpoint_in_blocks,
+        insert_entry_in_blocks, open_ordhook_db_conn_rocks_db_loop, Checkpoint, LazyBlock,
+    },
+    utils::monitoring::METRICS,
 };
 
 pub fn start_block_archiving_processor(
@@ -35,6 +39,14 @@ pub fn start_block_archiving_processor(
                         (compacted_blocks, blocks)
                     }
                     Ok(PostProcessorCommand::Terminate) => {
+                        flush_ordhook_db_conn_rocks_db(&blocks_db_rw, &ctx);
+                        let last_block_inserted = find_last_block_inserted(&blocks_db_rw);
+                        insert_checkpoint_in_blocks(
+                            Checkpoint::Archived,
+                            last_block_inserted as u64,
+                            &blocks_db_rw,
+                            &ctx,
+                        );
                         let _ = events_tx.send(PostProcessorEvent::Terminated);
                         break;
                     }
@@ -90,6 +102,7 @@ pub fn store_compacted_blocks(
         ctx.try_log(|logger| {
             info!(logger, "Block #{block_height} saved to disk");
         });
+        METRICS.record_pipeline_blocks("archiving", 1);
     }
 
     if let Err(e) = blocks_db_rw.flush() {
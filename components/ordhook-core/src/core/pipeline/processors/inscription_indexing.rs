@@ -12,10 +12,7 @@ use chainhook_sdk::{
 use crossbeam_channel::{Sender, TryRecvError};
 use rusqlite::Transaction;
 
-use dashmap::DashMap;
-use fxhash::FxHasher;
 use rusqlite::Connection;
-use std::hash::BuildHasherDefault;
 
 use crate::{
     core::{
@@ -30,15 +27,18 @@ use crate::{
             },
             inscription_tracking::augment_block_with_ordinals_transfer_data,
         },
+        traversals_cache::TraversalsCache,
         OrdhookConfig,
     },
     db::{
-        get_any_entry_in_ordinal_activities, open_ordhook_db_conn_rocks_db_loop,
-        open_readonly_ordhook_db_conn,
+        checkpoint_ordhook_db_conn_wal, flush_ordhook_db_conn_rocks_db,
+        get_any_entry_in_ordinal_activities, insert_checkpoint_in_blocks,
+        open_ordhook_db_conn_rocks_db_loop, open_readonly_ordhook_db_conn,
+        update_sequence_metadata_with_block, Checkpoint,
     },
 };
 
-use crate::db::{LazyBlockTransaction, TraversalResult};
+use crate::db::TraversalResult;
 
 use crate::{
     config::Config,
@@ -47,6 +47,7 @@ use crate::{
         pipeline::{PostProcessorCommand, PostProcessorController, PostProcessorEvent},
     },
     db::open_readwrite_ordhook_db_conn,
+    utils::{monitoring::METRICS, shutdown::is_shutdown_requested},
 };
 
 pub fn start_inscription_indexing_processor(
@@ -61,13 +62,13 @@ pub fn start_inscription_indexing_processor(
     let ctx = ctx.clone();
     let handle: JoinHandle<()> = hiro_system_kit::thread_named("Inscription indexing runloop")
         .spawn(move || {
-            let cache_l2 = Arc::new(new_traversals_lazy_cache(2048));
+            let ordhook_config = config.get_ordhook_config();
+            let cache_l2 = Arc::new(new_traversals_lazy_cache(ordhook_config.cache_size));
             let garbage_collect_every_n_blocks = 100;
             let mut garbage_collect_nth_block = 0;
 
             let mut inscriptions_db_conn_rw =
                 open_readwrite_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
-            let ordhook_config = config.get_ordhook_config();
             let blocks_db_rw =
                 open_ordhook_db_conn_rocks_db_loop(true, &config.expected_cache_path(), &ctx);
             let mut empty_cycles = 0;
@@ -75,6 +76,7 @@ pub fn start_inscription_indexing_processor(
             let inscriptions_db_conn =
                 open_readonly_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
             let mut sequence_cursor = SequenceCursor::new(&inscriptions_db_conn);
+            let mut last_block_indexed = None;
 
             loop {
                 let (compacted_blocks, mut blocks) = match commands_rx.try_recv() {
@@ -83,6 +85,19 @@ pub fn start_inscription_indexing_processor(
                         (compacted_blocks, blocks)
                     }
                     Ok(PostProcessorCommand::Terminate) => {
+                        flush_ordhook_db_conn_rocks_db(&blocks_db_rw, &ctx);
+                        checkpoint_ordhook_db_conn_wal(&inscriptions_db_conn_rw, &ctx);
+                        if let Some(block_height) = last_block_indexed {
+                            insert_checkpoint_in_blocks(
+                                Checkpoint::Indexed,
+                                block_height,
+                                &blocks_db_rw,
+                                &ctx,
+                            );
+                            ctx.try_log(|logger| {
+                                info!(logger, "Checkpoint recorded at block #{block_height}")
+                            });
+                        }
                         let _ = events_tx.send(PostProcessorEvent::Terminated);
                         break;
                     }
@@ -130,16 +145,28 @@ pub fn start_inscription_indexing_processor(
                     &ctx,
                 );
 
+                if let Some(block) = blocks.last() {
+                    last_block_indexed = Some(block.block_identifier.index);
+                }
+
                 garbage_collect_nth_block += blocks.len();
 
                 if garbage_collect_nth_block > garbage_collect_every_n_blocks {
                     ctx.try_log(|logger| info!(logger, "Performing garbage collecting"));
 
-                    // Clear L2 cache on a regular basis
+                    // L2 cache is bounded by its memory budget, only report its usage
+                    let stats = cache_l2.stats();
                     ctx.try_log(|logger| {
-                        info!(logger, "Clearing cache L2 ({} entries)", cache_l2.len())
+                        info!(
+                            logger,
+                            "Cache L2: {} entries, {} / {} MB, hit rate: {:.2}%, evictions: {}",
+                            stats.entries,
+                            stats.used_bytes / (1024 * 1024),
+                            stats.max_bytes / (1024 * 1024),
+                            stats.hit_rate() * 100.0,
+                            stats.evictions,
+                        )
                     });
-                    cache_l2.clear();
 
                     // Recreate sqlite db connection on a regular basis
                     inscriptions_db_conn_rw =
@@ -162,7 +189,7 @@ pub fn start_inscription_indexing_processor(
 pub fn process_blocks(
     next_blocks: &mut Vec<BitcoinBlockData>,
     sequence_cursor: &mut SequenceCursor,
-    cache_l2: &Arc<DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>>>,
+    cache_l2: &Arc<TraversalsCache>,
     inscriptions_db_conn_rw: &mut Connection,
     ordhook_config: &OrdhookConfig,
     post_processor: &Option<Sender<BitcoinBlockData>>,
@@ -173,6 +200,18 @@ pub fn process_blocks(
     let mut updated_blocks = vec![];
 
     for _cursor in 0..next_blocks.len() {
+        // Blocks are committed one by one: on shutdown, stop in between two blocks
+        if is_shutdown_requested() {
+            ctx.try_log(|logger| {
+                info!(
+                    logger,
+                    "Shutdown requested, {} blocks left unprocessed",
+                    next_blocks.len()
+                )
+            });
+            break;
+        }
+
         let inscriptions_db_tx: rusqlite::Transaction<'_> =
             inscriptions_db_conn_rw.transaction().unwrap();
 
@@ -226,6 +265,8 @@ pub fn process_blocks(
         } else {
             match inscriptions_db_tx.commit() {
                 Ok(_) => {
+                    METRICS.record_pipeline_blocks("indexing", 1);
+                    METRICS.set_indexed_block_height(block.block_identifier.index);
                     // ctx.try_log(|logger| {
                     //     info!(
                     //         logger,
@@ -259,7 +300,7 @@ pub fn process_block(
     next_blocks: &Vec<BitcoinBlockData>,
     sequence_cursor: &mut SequenceCursor,
     cache_l1: &mut BTreeMap<(TransactionIdentifier, usize), TraversalResult>,
-    cache_l2: &Arc<DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>>>,
+    cache_l2: &Arc<TraversalsCache>,
     inscriptions_db_tx: &Transaction,
     ordhook_config: &OrdhookConfig,
     ctx: &Context,
@@ -292,7 +333,14 @@ pub fn process_block(
     }
 
     // Handle transfers
-    let _ = augment_block_with_ordinals_transfer_data(block, inscriptions_db_tx, true, &inner_ctx);
+    let any_transfers =
+        augment_block_with_ordinals_transfer_data(block, inscriptions_db_tx, true, &inner_ctx);
+
+    // Blocks with inscriptions got their header recorded with their sequence metadata, which is
+    // needed to rebuild the inscription feed of blocks with transfers only as well
+    if !any_processable_transactions && any_transfers {
+        update_sequence_metadata_with_block(block, inscriptions_db_tx, &inner_ctx);
+    }
 
     Ok(())
 }
@@ -16,9 +16,10 @@ use crate::{
         },
     },
     db::{
-        insert_new_inscriptions_from_block_in_locations, open_readwrite_ordhook_db_conn,
-        remove_entries_from_locations_at_block_height,
+        checkpoint_ordhook_db_conn_wal, insert_new_inscriptions_from_block_in_locations,
+        open_readwrite_ordhook_db_conn, remove_entries_from_locations_at_block_height,
     },
+    utils::{monitoring::METRICS, shutdown::is_shutdown_requested},
 };
 
 pub fn start_transfers_recomputing_processor(
@@ -44,6 +45,7 @@ pub fn start_transfers_recomputing_processor(
                         blocks
                     }
                     Ok(PostProcessorCommand::Terminate) => {
+                        checkpoint_ordhook_db_conn_wal(&inscriptions_db_conn_rw, &ctx);
                         let _ = events_tx.send(PostProcessorEvent::Terminated);
                         break;
                     }
@@ -70,6 +72,11 @@ pub fn start_transfers_recomputing_processor(
                 let inscriptions_db_tx = inscriptions_db_conn_rw.transaction().unwrap();
 
                 for block in blocks.iter_mut() {
+                    // Commit the blocks already processed and skip the others
+                    if is_shutdown_requested() {
+                        break;
+                    }
+
                     consolidate_block_with_pre_computed_ordinals_data(
                         block,
                         &inscriptions_db_tx,
@@ -99,6 +106,7 @@ pub fn start_transfers_recomputing_processor(
                     if let Some(ref post_processor) = post_processor {
                         let _ = post_processor.send(block.clone());
                     }
+                    METRICS.record_pipeline_blocks("transfers", 1);
                 }
                 let _ = inscriptions_db_tx.commit();
             }
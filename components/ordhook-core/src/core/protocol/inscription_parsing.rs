@@ -1,27 +1,31 @@
 use std::collections::BTreeMap;
 use std::str::FromStr;
 
-use chainhook_sdk::bitcoincore_rpc_json::bitcoin::hashes::hex::FromHex;
-use chainhook_sdk::bitcoincore_rpc_json::bitcoin::Txid;
-use chainhook_sdk::indexer::bitcoin::{standardize_bitcoin_block, BitcoinBlockFullBreakdown};
-use chainhook_sdk::types::{
-    BitcoinBlockData, BitcoinNetwork, BitcoinTransactionData, OrdinalInscriptionCurseType,
-    OrdinalInscriptionRevealData, OrdinalInscriptionTransferData, OrdinalOperation,
-};
-use chainhook_sdk::utils::Context;
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::hashes::Hash;
+use bitcoin::Txid;
+#[cfg(feature = "indexer")]
 use chainhook_sdk::{
-    bitcoincore_rpc::bitcoin::Transaction, indexer::bitcoin::BitcoinTransactionFullBreakdown,
+    indexer::bitcoin::{
+        standardize_bitcoin_block, BitcoinBlockFullBreakdown, BitcoinTransactionFullBreakdown,
+    },
+    types::BitcoinNetwork,
+    utils::Context,
+};
+use chainhook_types::{
+    BitcoinBlockData, BitcoinTransactionData, OrdinalInscriptionCurseType,
+    OrdinalInscriptionRevealData, OrdinalInscriptionTransferData, OrdinalOperation,
 };
 
 use crate::ord::inscription_id::InscriptionId;
 use {
-    chainhook_sdk::bitcoincore_rpc::bitcoin::{
+    bitcoin::{
         blockdata::{
             opcodes,
             script::{self, Instruction, Instructions},
         },
         util::taproot::TAPROOT_ANNEX_PREFIX,
-        Script, Witness,
+        Script, Transaction, Witness,
     },
     std::{iter::Peekable, str},
 };
@@ -30,12 +34,14 @@ const PROTOCOL_ID: &[u8] = b"ord";
 
 const BODY_TAG: &[u8] = &[];
 const CONTENT_TYPE_TAG: &[u8] = &[1];
+const PARENT_TAG: &[u8] = &[3];
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Inscription {
     pub body: Option<Vec<u8>>,
     pub content_type: Option<Vec<u8>>,
     pub curse: Option<OrdinalInscriptionCurseType>,
+    pub parent: Option<InscriptionId>,
 }
 
 impl Inscription {
@@ -50,6 +56,21 @@ impl Inscription {
     pub(crate) fn content_type(&self) -> Option<&str> {
         str::from_utf8(self.content_type.as_ref()?).ok()
     }
+
+    /// Parent inscription id, encoded as the txid bytes followed by the index
+    /// (little endian, trailing zeros omitted).
+    fn parse_parent(value: &[u8]) -> Option<InscriptionId> {
+        if value.len() < 32 || value.len() > 36 {
+            return None;
+        }
+        let txid = Txid::from_slice(&value[0..32]).ok()?;
+        let mut index = [0u8; 4];
+        index[..value.len() - 32].copy_from_slice(&value[32..]);
+        Some(InscriptionId {
+            txid,
+            index: u32::from_le_bytes(index),
+        })
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -152,6 +173,9 @@ impl<'a> InscriptionParser<'a> {
 
             let body = fields.remove(BODY_TAG);
             let content_type = fields.remove(CONTENT_TYPE_TAG);
+            let parent = fields
+                .remove(PARENT_TAG)
+                .and_then(|value| Inscription::parse_parent(&value));
 
             for tag in fields.keys() {
                 if let Some(lsb) = tag.first() {
@@ -160,6 +184,7 @@ impl<'a> InscriptionParser<'a> {
                             body,
                             content_type,
                             curse: Some(OrdinalInscriptionCurseType::Tag(*lsb)),
+                            parent,
                         }));
                     }
                 }
@@ -169,6 +194,7 @@ impl<'a> InscriptionParser<'a> {
                 body,
                 content_type,
                 curse: None,
+                parent,
             }));
         }
 
@@ -198,8 +224,9 @@ impl<'a> InscriptionParser<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-pub(crate) enum Media {
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Media {
     Audio,
     Iframe,
     Image,
@@ -247,16 +274,17 @@ impl FromStr for Media {
     }
 }
 
-pub fn parse_inscriptions_from_witness(
-    input_index: usize,
-    witness_bytes: Vec<Vec<u8>>,
-    txid: &str,
-) -> Option<OrdinalOperation> {
+impl Media {
+    pub fn from_content_type(content_type: &str) -> Media {
+        content_type.parse().unwrap_or(Media::Unknown)
+    }
+}
+
+fn parse_inscription_in_witness(witness_bytes: &Vec<Vec<u8>>) -> Option<Inscription> {
     let witness = Witness::from_vec(witness_bytes.clone());
-    let mut inscription = match InscriptionParser::parse(&witness) {
-        Ok(inscription) => inscription,
+    match InscriptionParser::parse(&witness) {
+        Ok(inscription) => Some(inscription),
         Err(_e) => {
-            let mut cursed_inscription = None;
             for bytes in witness_bytes.iter() {
                 let script = Script::from(bytes.to_vec());
                 let parser = InscriptionParser {
@@ -268,15 +296,35 @@ pub fn parse_inscriptions_from_witness(
                     Err(_) => continue,
                 };
                 inscription.curse = Some(OrdinalInscriptionCurseType::P2wsh);
-                cursed_inscription = Some(inscription);
-                break;
-            }
-            match cursed_inscription {
-                Some(inscription) => inscription,
-                None => return None,
+                return Some(inscription);
             }
+            None
         }
-    };
+    }
+}
+
+/// Parent of the inscription revealed in the input `input_index` of `tx`, if any.
+/// Parents are not part of `OrdinalInscriptionRevealData`, and are retrieved from the witness.
+pub fn get_inscription_parent_in_standardized_tx(
+    tx: &BitcoinTransactionData,
+    input_index: usize,
+) -> Option<String> {
+    let input = tx.metadata.inputs.get(input_index)?;
+    let witness_bytes = input
+        .witness
+        .iter()
+        .map(|w| hex::decode(w.trim_start_matches("0x")).ok())
+        .collect::<Option<Vec<Vec<u8>>>>()?;
+    let inscription = parse_inscription_in_witness(&witness_bytes)?;
+    inscription.parent.map(|parent| parent.to_string())
+}
+
+pub fn parse_inscriptions_from_witness(
+    input_index: usize,
+    witness_bytes: Vec<Vec<u8>>,
+    txid: &str,
+) -> Option<OrdinalOperation> {
+    let mut inscription = parse_inscription_in_witness(&witness_bytes)?;
 
     let inscription_id = InscriptionId {
         txid: Txid::from_hex(txid).unwrap(),
@@ -314,6 +362,7 @@ pub fn parse_inscriptions_from_witness(
     Some(OrdinalOperation::InscriptionRevealed(payload))
 }
 
+#[cfg(feature = "indexer")]
 pub fn parse_inscriptions_from_standardized_tx(
     tx: &BitcoinTransactionData,
     _ctx: &Context,
@@ -337,6 +386,7 @@ pub fn parse_inscriptions_from_standardized_tx(
     operations
 }
 
+#[cfg(feature = "indexer")]
 pub fn parse_inscriptions_in_raw_tx(
     tx: &BitcoinTransactionFullBreakdown,
     _ctx: &Context,
@@ -376,6 +426,7 @@ fn test_ordinal_inscription_parsing() {
     println!("{:?}", inscription);
 }
 
+#[cfg(feature = "indexer")]
 pub fn parse_inscriptions_and_standardize_block(
     raw_block: BitcoinBlockFullBreakdown,
     network: &BitcoinNetwork,
@@ -399,6 +450,7 @@ pub fn parse_inscriptions_and_standardize_block(
     Ok(block)
 }
 
+#[cfg(feature = "indexer")]
 pub fn parse_inscriptions_in_standardized_block(block: &mut BitcoinBlockData, ctx: &Context) {
     for tx in block.transactions.iter_mut() {
         tx.metadata.ordinal_operations = parse_inscriptions_from_standardized_tx(tx, ctx);
@@ -1,6 +1,5 @@
 use std::{
     collections::{BTreeMap, HashMap, VecDeque},
-    hash::BuildHasherDefault,
     sync::Arc,
 };
 
@@ -13,20 +12,18 @@ use chainhook_sdk::{
     utils::Context,
 };
 use crossbeam_channel::unbounded;
-use dashmap::DashMap;
-use fxhash::FxHasher;
 use rusqlite::{Connection, Transaction};
 
 use crate::{
-    core::OrdhookConfig,
+    core::{traversals_cache::TraversalsCache, OrdhookConfig},
     db::{
         find_blessed_inscription_with_ordinal_number,
         find_latest_cursed_inscription_number_at_block_height,
-        find_latest_inscription_number_at_block_height, format_satpoint_to_watch,
-        update_inscriptions_with_block, update_sequence_metadata_with_block, LazyBlockTransaction,
-        TraversalResult,
+        find_latest_inscription_number_at_block_height, update_inscriptions_with_block,
+        update_sequence_metadata_with_block, TraversalResult,
     },
     ord::height::Height,
+    utils::monitoring::METRICS,
 };
 
 use rand::seq::SliceRandom;
@@ -68,7 +65,7 @@ pub fn parallelize_inscription_data_computations(
     block: &BitcoinBlockData,
     next_blocks: &Vec<BitcoinBlockData>,
     cache_l1: &mut BTreeMap<(TransactionIdentifier, usize), TraversalResult>,
-    cache_l2: &Arc<DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>>>,
+    cache_l2: &Arc<TraversalsCache>,
     inscriptions_db_tx: &Transaction,
     ordhook_config: &OrdhookConfig,
     ctx: &Context,
@@ -89,6 +86,12 @@ pub fn parallelize_inscription_data_computations(
         Context::empty()
     };
 
+    METRICS.record_cache_lookups(
+        "l1",
+        l1_cache_hits.len() as u64,
+        transactions_ids.len() as u64,
+    );
+
     let has_transactions_to_process = !transactions_ids.is_empty() || !l1_cache_hits.is_empty();
 
     let thread_max = ordhook_config.ingestion_thread_max;
@@ -127,6 +130,7 @@ pub fn parallelize_inscription_data_computations(
                         &local_cache,
                         &moved_ctx,
                     );
+                    METRICS.record_traversal(traversal.as_ref().ok().map(|t| t.transfers));
                     let _ = moved_traversal_tx.send((traversal, prioritary, thread_index));
                 }
             })
@@ -151,13 +155,14 @@ pub fn parallelize_inscription_data_computations(
     ctx.try_log(|logger| {
         info!(
             logger,
-            "Number of inscriptions in block #{} to process: {} (L1 cache hits: {}, queue: [{}], L1 cache len: {}, L2 cache len: {})",
+            "Number of inscriptions in block #{} to process: {} (L1 cache hits: {}, queue: [{}], L1 cache len: {}, L2 cache len: {}, L2 cache hit rate: {:.2}%)",
             block.block_identifier.index,
             transactions_ids.len(),
             l1_cache_hits.len(),
             next_block_heights.join(", "),
             cache_l1.len(),
             cache_l2.len(),
+            cache_l2.stats().hit_rate() * 100.0,
         )
     });
 
@@ -657,12 +662,8 @@ fn augment_transaction_with_ordinals_inscriptions_data(
             Some(curse_type) => Some(curse_type),
             None => inscription.curse_type.take(),
         };
-        inscription.satpoint_post_inscription = format_satpoint_to_watch(
-            &traversal.transfer_data.transaction_identifier_location,
-            traversal.transfer_data.output_index,
-            traversal.transfer_data.inscription_offset_intra_output,
-        );
-        if let Some(output) = outputs.get(traversal.transfer_data.output_index) {
+        inscription.satpoint_post_inscription = traversal.transfer_data.satpoint.to_string();
+        if let Some(output) = outputs.get(traversal.transfer_data.satpoint.outpoint.vout as usize) {
             inscription.inscription_output_value = output.value;
             inscription.inscriber_address = {
                 let script_pub_key = output.get_script_pubkey_hex();
@@ -749,20 +750,15 @@ fn consolidate_transaction_with_pre_computed_inscription_data(
         inscription.transfers_pre_inscription = traversal.transfers;
         inscription.inscription_fee = tx.metadata.fee;
         inscription.tx_index = tx_index;
-        inscription.satpoint_post_inscription = format_satpoint_to_watch(
-            &traversal.transfer_data.transaction_identifier_location,
-            traversal.transfer_data.output_index,
-            traversal.transfer_data.inscription_offset_intra_output,
-        );
+        inscription.satpoint_post_inscription = traversal.transfer_data.satpoint.to_string();
 
-        if inscription.inscription_number < 0 {
+        // Keep the curse detected while parsing the envelope, the tables only record the number
+        if inscription.inscription_number < 0 && inscription.curse_type.is_none() {
             inscription.curse_type = Some(OrdinalInscriptionCurseType::Unknown);
         }
 
-        if traversal
-            .transfer_data
-            .transaction_identifier_location
-            .eq(coinbase_txid)
+        if traversal.transfer_data.satpoint.outpoint.txid.to_string()
+            == coinbase_txid.get_hash_bytes_str()
         {
             continue;
         }
@@ -770,7 +766,7 @@ fn consolidate_transaction_with_pre_computed_inscription_data(
         if let Some(output) = tx
             .metadata
             .outputs
-            .get(traversal.transfer_data.output_index)
+            .get(traversal.transfer_data.satpoint.outpoint.vout as usize)
         {
             inscription.inscription_output_value = output.value;
             inscription.inscriber_address = {
@@ -788,7 +784,7 @@ fn consolidate_transaction_with_pre_computed_inscription_data(
 }
 
 /// Best effort to re-augment a `BitcoinBlockData` with data coming from `inscriptions` and `locations` tables.
-/// Some informations are being lost (curse_type).
+/// Curses that are not detected while parsing (e.g. reinscriptions) are reported as `Unknown`.
 pub fn consolidate_block_with_pre_computed_ordinals_data(
     block: &mut BitcoinBlockData,
     inscriptions_db_tx: &Transaction,
@@ -10,11 +10,11 @@ use chainhook_sdk::{
 
 use crate::{
     core::{compute_next_satpoint_data, SatPosition},
-    db::{
-        find_inscriptions_at_wached_outpoint, format_outpoint_to_watch,
-        insert_transfer_in_locations_tx,
+    db::{find_inscriptions_at_wached_outpoint, insert_transfer_in_locations_tx},
+    ord::{
+        height::Height,
+        sat_point::{outpoint_from_transaction_identifier, SatPoint},
     },
-    ord::height::Height,
 };
 use rusqlite::Transaction;
 
@@ -55,6 +55,7 @@ pub fn augment_block_with_ordinals_transfer_data(
             for transfer_data in transfers.into_iter() {
                 insert_transfer_in_locations_tx(
                     &transfer_data,
+                    &tx.transaction_identifier,
                     &block.block_identifier,
                     &inscriptions_db_tx,
                     &ctx,
@@ -80,10 +81,22 @@ pub fn augment_transaction_with_ordinals_transfers_data(
     let mut transfers = vec![];
 
     for (input_index, input) in tx.metadata.inputs.iter().enumerate() {
-        let outpoint_pre_transfer = format_outpoint_to_watch(
+        let outpoint_pre_transfer = match outpoint_from_transaction_identifier(
             &input.previous_output.txid,
             input.previous_output.vout as usize,
-        );
+        ) {
+            Ok(outpoint) => outpoint,
+            Err(e) => {
+                ctx.try_log(|logger| {
+                    error!(
+                        logger,
+                        "unable to track input {input_index} of {}: {e}",
+                        tx.transaction_identifier.hash
+                    )
+                });
+                continue;
+            }
+        };
 
         let entries =
             find_inscriptions_at_wached_outpoint(&outpoint_pre_transfer, &inscriptions_db_tx, ctx);
@@ -91,7 +104,7 @@ pub fn augment_transaction_with_ordinals_transfers_data(
         // outpoint to watch
         for watched_satpoint in entries.into_iter() {
             let satpoint_pre_transfer =
-                format!("{}:{}", outpoint_pre_transfer, watched_satpoint.offset);
+                SatPoint::new(outpoint_pre_transfer, watched_satpoint.offset);
 
             // Question is: are inscriptions moving to a new output,
             // burnt or lost in fees and transfered to the miner?
@@ -113,8 +126,21 @@ pub fn augment_transaction_with_ordinals_transfers_data(
                 post_transfer_output_value,
             ) = match post_transfer_data {
                 SatPosition::Output((output_index, offset)) => {
-                    let outpoint =
-                        format_outpoint_to_watch(&tx.transaction_identifier, output_index);
+                    let outpoint = match outpoint_from_transaction_identifier(
+                        &tx.transaction_identifier,
+                        output_index,
+                    ) {
+                        Ok(outpoint) => outpoint,
+                        Err(e) => {
+                            ctx.try_log(|logger| {
+                                error!(
+                                    logger,
+                                    "unable to track {}: {e}", watched_satpoint.inscription_id
+                                )
+                            });
+                            continue;
+                        }
+                    };
                     let script_pub_key_hex =
                         tx.metadata.outputs[output_index].get_script_pubkey_hex();
                     let updated_address = match Script::from_hex(&script_pub_key_hex) {
@@ -169,7 +195,18 @@ pub fn augment_transaction_with_ordinals_transfers_data(
                 SatPosition::Fee(offset) => {
                     // Get Coinbase TX
                     let total_offset = coinbase_subsidy + *cumulated_fees + offset;
-                    let outpoint = format_outpoint_to_watch(&coinbase_txid, 0);
+                    let outpoint = match outpoint_from_transaction_identifier(coinbase_txid, 0) {
+                        Ok(outpoint) => outpoint,
+                        Err(e) => {
+                            ctx.try_log(|logger| {
+                                error!(
+                                    logger,
+                                    "unable to track {}: {e}", watched_satpoint.inscription_id
+                                )
+                            });
+                            continue;
+                        }
+                    };
                     ctx.try_log(|logger| {
                         info!(
                             logger,
@@ -190,14 +227,14 @@ pub fn augment_transaction_with_ordinals_transfers_data(
             };
 
             let satpoint_post_transfer =
-                format!("{}:{}", outpoint_post_transfer, offset_post_transfer);
+                SatPoint::new(outpoint_post_transfer, offset_post_transfer);
 
             let transfer_data = OrdinalInscriptionTransferData {
                 inscription_id: watched_satpoint.inscription_id.clone(),
                 destination,
                 tx_index,
-                satpoint_pre_transfer,
-                satpoint_post_transfer,
+                satpoint_pre_transfer: satpoint_pre_transfer.to_string(),
+                satpoint_post_transfer: satpoint_post_transfer.to_string(),
                 post_transfer_output_value,
             };
 
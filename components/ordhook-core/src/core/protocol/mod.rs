@@ -1,4 +1,7 @@
 pub mod inscription_parsing;
+#[cfg(feature = "indexer")]
 pub mod inscription_sequencing;
+#[cfg(feature = "indexer")]
 pub mod inscription_tracking;
+#[cfg(feature = "indexer")]
 pub mod satoshi_numbering;
@@ -1,8 +1,5 @@
 use chainhook_sdk::types::{BlockIdentifier, TransactionIdentifier};
 use chainhook_sdk::utils::Context;
-use dashmap::DashMap;
-use fxhash::FxHasher;
-use std::hash::BuildHasherDefault;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -10,8 +7,10 @@ use crate::db::{
     find_lazy_block_at_block_height, open_ordhook_db_conn_rocks_db_loop, TransferData,
 };
 
-use crate::db::{LazyBlockTransaction, TraversalResult};
+use crate::core::traversals_cache::TraversalsCache;
+use crate::db::TraversalResult;
 use crate::ord::height::Height;
+use crate::ord::sat_point::{outpoint_from_transaction_identifier, SatPoint};
 
 pub fn compute_satoshi_number(
     blocks_db_dir: &PathBuf,
@@ -19,9 +18,7 @@ pub fn compute_satoshi_number(
     transaction_identifier: &TransactionIdentifier,
     inscription_input_index: usize,
     inscription_number: i64,
-    traversals_cache: &Arc<
-        DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>>,
-    >,
+    traversals_cache: &Arc<TraversalsCache>,
     ctx: &Context,
 ) -> Result<TraversalResult, String> {
     let mut inscription_offset_intra_output = 0;
@@ -35,13 +32,10 @@ pub fn compute_satoshi_number(
     let (sats_ranges, inscription_offset_cross_outputs) = match traversals_cache
         .get(&(block_identifier.index as u32, txid.clone()))
     {
-        Some(entry) => {
-            let tx = entry.value();
-            (
-                tx.get_sat_ranges(),
-                tx.get_cumulated_sats_in_until_input_index(inscription_input_index),
-            )
-        }
+        Some(tx) => (
+            tx.get_sat_ranges(),
+            tx.get_cumulated_sats_in_until_input_index(inscription_input_index),
+        ),
         None => {
             let mut attempt = 0;
             loop {
@@ -104,9 +98,7 @@ pub fn compute_satoshi_number(
             ));
         }
 
-        if let Some(cached_tx) = traversals_cache.get(&(ordinal_block_number, tx_cursor.0)) {
-            let tx = cached_tx.value();
-
+        if let Some(tx) = traversals_cache.get(&(ordinal_block_number, tx_cursor.0)) {
             let mut next_found_in_cache = false;
             let mut sats_out = 0;
             for (index, output_value) in tx.outputs.iter().enumerate() {
@@ -149,9 +141,13 @@ pub fn compute_satoshi_number(
                     inscription_input_index,
                     transaction_identifier_inscription: transaction_identifier.clone(),
                     transfer_data: TransferData {
-                        inscription_offset_intra_output,
-                        transaction_identifier_location: transaction_identifier.clone(),
-                        output_index: inscription_output_index,
+                        satpoint: SatPoint::new(
+                            outpoint_from_transaction_identifier(
+                                transaction_identifier,
+                                inscription_output_index,
+                            )?,
+                            inscription_offset_intra_output,
+                        ),
                         tx_index: 0,
                     },
                 });
@@ -284,9 +280,13 @@ pub fn compute_satoshi_number(
                     inscription_input_index,
                     transaction_identifier_inscription: transaction_identifier.clone(),
                     transfer_data: TransferData {
-                        inscription_offset_intra_output,
-                        transaction_identifier_location: transaction_identifier.clone(),
-                        output_index: inscription_output_index,
+                        satpoint: SatPoint::new(
+                            outpoint_from_transaction_identifier(
+                                transaction_identifier,
+                                inscription_output_index,
+                            )?,
+                            inscription_offset_intra_output,
+                        ),
                         tx_index: 0,
                     },
                 });
@@ -304,9 +304,13 @@ pub fn compute_satoshi_number(
         inscription_input_index,
         transaction_identifier_inscription: transaction_identifier.clone(),
         transfer_data: TransferData {
-            inscription_offset_intra_output,
-            transaction_identifier_location: transaction_identifier.clone(),
-            output_index: inscription_output_index,
+            satpoint: SatPoint::new(
+                outpoint_from_transaction_identifier(
+                    transaction_identifier,
+                    inscription_output_index,
+                )?,
+                inscription_offset_intra_output,
+            ),
             tx_index: 0,
         },
     })
@@ -0,0 +1,289 @@
+use std::hash::BuildHasherDefault;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use dashmap::mapref::one::MappedRef;
+use dashmap::DashMap;
+use fxhash::{FxBuildHasher, FxHasher};
+use prometheus::IntCounter;
+use rand::{thread_rng, Rng};
+
+use crate::db::{LazyBlockTransaction, LazyBlockTransactionInput};
+use crate::utils::monitoring::METRICS;
+
+type TraversalsCacheKey = (u32, [u8; 8]);
+
+/// Read guard over a cached transaction: the shard stays locked for reads until it is dropped.
+pub type CachedTransactionRef<'a> = MappedRef<
+    'a,
+    TraversalsCacheKey,
+    CachedTransaction,
+    LazyBlockTransaction,
+    BuildHasherDefault<FxHasher>,
+>;
+
+/// Number of entries being sampled when looking for the access tick below which entries get evicted.
+const EVICTION_SAMPLE_SIZE: usize = 1024;
+/// Once the memory budget is exceeded, entries are evicted until the cache is back to this ratio of its budget.
+const EVICTION_LOW_WATERMARK: f64 = 0.8;
+/// Estimated overhead of a DashMap slot (hash, bucket metadata, padding).
+const ENTRY_OVERHEAD_BYTES: usize = 32;
+
+pub struct CachedTransaction {
+    tx: LazyBlockTransaction,
+    bytes: usize,
+    last_access: AtomicU64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TraversalsCacheStats {
+    pub entries: usize,
+    pub used_bytes: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl TraversalsCacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+}
+
+/// Cache L2, shared by the workers computing satoshi numbers.
+///
+/// Transactions traversed while walking back to a coinbase are being kept in memory, so that sats moving
+/// through the same (old) transactions don't require the block to be fetched and deserialized again.
+/// The cache is bounded by a memory budget: when an insertion pushes the estimated footprint above the
+/// budget, the least recently accessed entries are evicted (approximated by sampling access ticks) until
+/// the footprint is back under the low watermark.
+pub struct TraversalsCache {
+    entries: DashMap<TraversalsCacheKey, CachedTransaction, BuildHasherDefault<FxHasher>>,
+    max_bytes: usize,
+    used_bytes: AtomicUsize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    eviction_lock: Mutex<()>,
+    hits_counter: IntCounter,
+    misses_counter: IntCounter,
+}
+
+impl TraversalsCache {
+    pub fn new(max_memory_size_mb: usize) -> TraversalsCache {
+        let hasher = FxBuildHasher::default();
+        TraversalsCache {
+            entries: DashMap::with_hasher(hasher),
+            max_bytes: max_memory_size_mb * 1024 * 1024,
+            used_bytes: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            eviction_lock: Mutex::new(()),
+            hits_counter: METRICS.cache_lookups.with_label_values(&["l2", "hit"]),
+            misses_counter: METRICS.cache_lookups.with_label_values(&["l2", "miss"]),
+        }
+    }
+
+    /// The transaction is borrowed, not copied: the guard must be dropped before inserting into the
+    /// cache from the same thread.
+    pub fn get(&self, key: &TraversalsCacheKey) -> Option<CachedTransactionRef<'_>> {
+        match self.entries.get(key) {
+            Some(entry) => {
+                let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+                entry.last_access.store(tick, Ordering::Relaxed);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                self.hits_counter.inc();
+                Some(entry.map(|entry| &entry.tx))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                self.misses_counter.inc();
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: TraversalsCacheKey, tx: LazyBlockTransaction) {
+        let bytes = estimate_entry_size(&tx);
+        if bytes > self.max_bytes {
+            return;
+        }
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let entry = CachedTransaction {
+            tx,
+            bytes,
+            last_access: AtomicU64::new(tick),
+        };
+        match self.entries.insert(key, entry) {
+            Some(previous) => {
+                self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+                self.used_bytes.fetch_sub(previous.bytes, Ordering::Relaxed);
+            }
+            None => {
+                self.used_bytes.fetch_add(bytes, Ordering::Relaxed);
+            }
+        }
+        if self.used_bytes.load(Ordering::Relaxed) > self.max_bytes {
+            self.evict();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&self) {
+        let _guard = self.eviction_lock.lock();
+        self.entries.clear();
+        self.used_bytes.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> TraversalsCacheStats {
+        TraversalsCacheStats {
+            entries: self.entries.len(),
+            used_bytes: self.used_bytes.load(Ordering::Relaxed),
+            max_bytes: self.max_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn evict(&self) {
+        // Only one thread evicts at a time, the others keep on inserting.
+        let Ok(_guard) = self.eviction_lock.try_lock() else {
+            return;
+        };
+        let used_bytes = self.used_bytes.load(Ordering::Relaxed);
+        if used_bytes <= self.max_bytes {
+            return;
+        }
+        let target_bytes = (self.max_bytes as f64 * EVICTION_LOW_WATERMARK) as usize;
+        let ratio_to_evict = (used_bytes - target_bytes) as f64 / used_bytes as f64;
+
+        // The sample starts at a random entry and wraps around: always starting with the first
+        // shards would only ever compare the entries of these shards.
+        let entries_count = self.entries.len();
+        if entries_count == 0 {
+            return;
+        }
+        let sample_start = thread_rng().gen_range(0..entries_count);
+        let mut sampled_ticks = self
+            .entries
+            .iter()
+            .skip(sample_start)
+            .take(EVICTION_SAMPLE_SIZE)
+            .map(|entry| entry.last_access.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        if sampled_ticks.len() < EVICTION_SAMPLE_SIZE {
+            let remaining = (EVICTION_SAMPLE_SIZE - sampled_ticks.len()).min(sample_start);
+            sampled_ticks.extend(
+                self.entries
+                    .iter()
+                    .take(remaining)
+                    .map(|entry| entry.last_access.load(Ordering::Relaxed)),
+            );
+        }
+        if sampled_ticks.is_empty() {
+            return;
+        }
+        sampled_ticks.sort_unstable();
+        let cutoff_index = ((sampled_ticks.len() as f64 * ratio_to_evict).ceil() as usize)
+            .min(sampled_ticks.len() - 1);
+        let cutoff_tick = sampled_ticks[cutoff_index];
+
+        let mut evicted = 0;
+        self.entries.retain(|_, entry| {
+            if entry.last_access.load(Ordering::Relaxed) <= cutoff_tick {
+                self.used_bytes.fetch_sub(entry.bytes, Ordering::Relaxed);
+                evicted += 1;
+                false
+            } else {
+                true
+            }
+        });
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+    }
+}
+
+fn estimate_entry_size(tx: &LazyBlockTransaction) -> usize {
+    size_of::<TraversalsCacheKey>()
+        + size_of::<CachedTransaction>()
+        + tx.inputs.capacity() * size_of::<LazyBlockTransactionInput>()
+        + tx.outputs.capacity() * size_of::<u64>()
+        + ENTRY_OVERHEAD_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy_transaction(seed: u8, inputs: usize) -> LazyBlockTransaction {
+        LazyBlockTransaction {
+            txid: [seed; 8],
+            inputs: (0..inputs)
+                .map(|i| LazyBlockTransactionInput {
+                    txin: [seed; 8],
+                    block_height: i as u32,
+                    vout: 0,
+                    txin_value: 1_000,
+                })
+                .collect(),
+            outputs: vec![1_000],
+        }
+    }
+
+    #[test]
+    fn tracks_hits_and_misses() {
+        let cache = TraversalsCache::new(1);
+        cache.insert((1, [1; 8]), lazy_transaction(1, 1));
+        assert!(cache.get(&(1, [1; 8])).is_some());
+        assert!(cache.get(&(2, [2; 8])).is_none());
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn stays_within_memory_budget() {
+        let cache = TraversalsCache::new(1);
+        for i in 0..50_000u32 {
+            cache.insert(
+                (i, [(i % 255) as u8; 8]),
+                lazy_transaction((i % 255) as u8, 4),
+            );
+        }
+        let stats = cache.stats();
+        assert!(stats.used_bytes <= stats.max_bytes);
+        assert!(stats.evictions > 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entries_first() {
+        let cache = TraversalsCache::new(1);
+        let hot_key = (0, [0; 8]);
+        cache.insert(hot_key, lazy_transaction(0, 4));
+        for i in 1..50_000u32 {
+            cache.insert(
+                (i, [(i % 255) as u8; 8]),
+                lazy_transaction((i % 255) as u8, 4),
+            );
+            let _ = cache.get(&hot_key);
+        }
+        assert!(cache.get(&hot_key).is_some());
+    }
+}
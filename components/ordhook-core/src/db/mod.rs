@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     io::{Read, Write},
     path::PathBuf,
     thread::sleep,
@@ -9,23 +9,27 @@ use std::{
 use rand::{thread_rng, Rng};
 
 use rocksdb::DB;
-use rusqlite::{Connection, OpenFlags, ToSql, Transaction};
+use rusqlite::{types::ValueRef, Connection, OpenFlags, Row, ToSql, Transaction};
 use std::io::Cursor;
 
 use chainhook_sdk::{
+    bitcoincore_rpc::bitcoin::OutPoint,
     indexer::bitcoin::BitcoinBlockFullBreakdown,
     types::{
-        BitcoinBlockData, BlockIdentifier, OrdinalInscriptionRevealData,
-        OrdinalInscriptionTransferData, TransactionIdentifier,
+        BitcoinBlockData, BitcoinNetwork, BlockIdentifier, OrdinalInscriptionRevealData,
+        OrdinalInscriptionTransferData, OrdinalInscriptionTransferDestination, OrdinalOperation,
+        TransactionIdentifier,
     },
     utils::Context,
 };
+use serde_json::json;
 
 use crate::{
-    core::protocol::inscription_parsing::{
-        get_inscriptions_revealed_in_block, get_inscriptions_transferred_in_block,
+    core::protocol::inscription_parsing::get_inscriptions_revealed_in_block,
+    ord::{
+        sat::Sat,
+        sat_point::{decode_outpoint, encode_outpoint, SatPoint},
     },
-    ord::sat::Sat,
 };
 
 pub fn get_default_ordhook_db_file_path(base_dir: &PathBuf) -> PathBuf {
@@ -98,7 +102,7 @@ pub fn initialize_ordhook_db(base_dir: &PathBuf, ctx: &Context) -> Connection {
             inscription_id TEXT NOT NULL,
             block_height INTEGER NOT NULL,
             tx_index INTEGER NOT NULL,
-            outpoint_to_watch TEXT NOT NULL,
+            outpoint_to_watch BLOB NOT NULL,
             offset INTEGER NOT NULL
         )",
         [],
@@ -155,9 +159,212 @@ pub fn initialize_ordhook_db(base_dir: &PathBuf, ctx: &Context) -> Connection {
         }
     }
 
+    add_feed_details_columns(&conn, ctx);
+
+    if !is_ordhook_db_migrated(&conn, ctx) {
+        // Databases without locations have nothing to migrate
+        if perform_query_exists("SELECT 1 FROM locations LIMIT 1", &[], &conn, ctx) {
+            ctx.try_log(|logger| {
+                warn!(
+                    logger,
+                    "hord.sqlite stores outpoints as text: stop ordhook and run `ordhook db migrate`"
+                )
+            });
+        } else {
+            set_ordhook_db_schema_version(&conn, ctx);
+        }
+    }
+
     conn
 }
 
+/// Columns recording what the inscription feed of a block needs on top of numbers and locations:
+/// reveal details, transfer destinations and values, and block headers. They are added to databases
+/// created before they existed, the rows indexed earlier keeping null values.
+const FEED_DETAILS_COLUMNS: &[(&str, &str, &str)] = &[
+    ("inscriptions", "content_type", "TEXT"),
+    ("inscriptions", "content_length", "INTEGER"),
+    ("inscriptions", "inscriber_address", "TEXT"),
+    ("inscriptions", "output_value", "INTEGER"),
+    ("inscriptions", "fee", "INTEGER"),
+    ("inscriptions", "curse_type", "TEXT"),
+    ("inscriptions", "transfers_pre_inscription", "INTEGER"),
+    ("locations", "destination", "TEXT"),
+    ("locations", "output_value", "INTEGER"),
+    // Only recorded for inscriptions spent in fees, their location being on the coinbase
+    ("locations", "tx_id", "TEXT"),
+    ("sequence_metadata", "block_hash", "TEXT"),
+    ("sequence_metadata", "parent_block_hash", "TEXT"),
+    ("sequence_metadata", "timestamp", "INTEGER"),
+];
+
+fn is_column_available(table: &str, column: &str, db_conn: &Connection, ctx: &Context) -> bool {
+    let args: &[&dyn ToSql] = &[&table.to_sql().unwrap(), &column.to_sql().unwrap()];
+    let query = "SELECT name FROM pragma_table_info(?1) WHERE name = ?2";
+    perform_query_exists(query, args, db_conn, ctx)
+}
+
+fn add_feed_details_columns(conn: &Connection, ctx: &Context) {
+    for (table, column, column_type) in FEED_DETAILS_COLUMNS.iter() {
+        if is_column_available(table, column, conn, ctx) {
+            continue;
+        }
+        if let Err(e) = conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {column_type}"),
+            [],
+        ) {
+            ctx.try_log(|logger| {
+                warn!(
+                    logger,
+                    "Unable to add column {column} to table {table}: {}",
+                    e.to_string()
+                )
+            });
+        }
+    }
+}
+
+/// Returns `true` once the columns of `FEED_DETAILS_COLUMNS` exist. Databases opened read-only may
+/// predate them, until the next `initialize_ordhook_db`.
+pub fn are_feed_details_available(db_conn: &Connection, ctx: &Context) -> bool {
+    FEED_DETAILS_COLUMNS
+        .iter()
+        .all(|(table, column, _)| is_column_available(table, column, db_conn, ctx))
+}
+
+/// `PRAGMA user_version` of databases storing outpoints in their binary form.
+const BINARY_OUTPOINTS_SCHEMA_VERSION: i64 = 1;
+
+/// Returns `true` once `locations` only stores binary outpoints: lookups by outpoint miss the
+/// `txid:vout` strings of databases that `migrate_ordhook_db` did not migrate yet.
+pub fn is_ordhook_db_migrated(db_conn: &Connection, _ctx: &Context) -> bool {
+    let schema_version: i64 = db_conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap_or(0);
+    schema_version >= BINARY_OUTPOINTS_SCHEMA_VERSION
+}
+
+fn set_ordhook_db_schema_version(conn: &Connection, ctx: &Context) {
+    if let Err(e) = conn.execute_batch(&format!(
+        "PRAGMA user_version = {BINARY_OUTPOINTS_SCHEMA_VERSION}"
+    )) {
+        ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
+    }
+}
+
+/// Brings a `hord.sqlite` built by an earlier version to the current schema: `txid:vout` strings of
+/// `locations.outpoint_to_watch` are rewritten in their binary form, in batches, and the tables of
+/// the former replay store are dropped. The database is then vacuumed and reindexed, to give back
+/// the space freed. The schema version is only bumped once every outpoint is migrated: outpoints
+/// that can't be parsed are logged and reported in the error returned.
+///
+/// Rewrites most of the database: ordhook must not be running.
+pub fn migrate_ordhook_db(base_dir: &PathBuf, ctx: &Context) -> Result<(), String> {
+    let conn = initialize_ordhook_db(base_dir, ctx);
+    if !is_ordhook_db_migrated(&conn, ctx) {
+        migrate_locations_to_binary_outpoints(&conn, ctx)?;
+    }
+    for table in ["replay_blocks", "inscription_operations"] {
+        conn.execute_batch(&format!("DROP TABLE IF EXISTS {table}"))
+            .map_err(|e| format!("unable to drop {table}: {e}"))?;
+    }
+    let unmigrated = perform_query_set(
+        "SELECT rowid FROM locations WHERE typeof(outpoint_to_watch) = 'text'",
+        &[],
+        &conn,
+        ctx,
+        |row| {
+            let rowid: i64 = row.get(0).unwrap();
+            rowid
+        },
+    );
+    if unmigrated.is_empty() {
+        set_ordhook_db_schema_version(&conn, ctx);
+    }
+    ctx.try_log(|logger| info!(logger, "Vacuuming and reindexing hord.sqlite"));
+    conn.execute_batch("VACUUM; REINDEX;")
+        .map_err(|e| format!("unable to vacuum hord.sqlite: {e}"))?;
+    if !unmigrated.is_empty() {
+        return Err(format!(
+            "{} locations could not be migrated to binary outpoints, starting with rowid {} (see the errors logged): fix or drop them, and run the migration again",
+            unmigrated.len(),
+            unmigrated[0]
+        ));
+    }
+    Ok(())
+}
+
+fn migrate_locations_to_binary_outpoints(conn: &Connection, ctx: &Context) -> Result<(), String> {
+    let mut last_rowid: i64 = 0;
+    let mut migrated = 0;
+    loop {
+        let args: &[&dyn ToSql] = &[&last_rowid.to_sql().unwrap()];
+        let query = "SELECT rowid, outpoint_to_watch FROM locations WHERE rowid > ? AND typeof(outpoint_to_watch) = 'text' ORDER BY rowid ASC LIMIT 100000";
+        let entries = perform_query_set(query, args, conn, ctx, |row| {
+            let rowid: i64 = row.get(0).unwrap();
+            let outpoint_to_watch: String = row.get(1).unwrap();
+            (rowid, outpoint_to_watch)
+        });
+        let Some((rowid, _)) = entries.last() else {
+            break;
+        };
+        last_rowid = *rowid;
+        let db_tx = loop {
+            match conn.unchecked_transaction() {
+                Ok(db_tx) => break db_tx,
+                Err(e) => {
+                    ctx.try_log(|logger| {
+                        warn!(logger, "unable to begin hord.sqlite tx: {}", e.to_string())
+                    });
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+            }
+        };
+        for (rowid, outpoint_to_watch) in entries.iter() {
+            let outpoint = match outpoint_to_watch.parse::<OutPoint>() {
+                Ok(outpoint) => outpoint,
+                Err(e) => {
+                    ctx.try_log(|logger| {
+                        error!(
+                            logger,
+                            "unable to migrate location {rowid} ({outpoint_to_watch}): {e}"
+                        )
+                    });
+                    continue;
+                }
+            };
+            while let Err(e) = db_tx.execute(
+                "UPDATE locations SET outpoint_to_watch = ?1 WHERE rowid = ?2",
+                rusqlite::params![&encode_outpoint(&outpoint), rowid],
+            ) {
+                ctx.try_log(|logger| {
+                    warn!(logger, "unable to query hord.sqlite: {}", e.to_string())
+                });
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+        db_tx
+            .commit()
+            .map_err(|e| format!("unable to migrate locations: {e}"))?;
+        migrated += entries.len();
+        ctx.try_log(|logger| info!(logger, "{migrated} locations migrated to binary outpoints"));
+    }
+    Ok(())
+}
+
+/// Reads an outpoint from the `locations` table. Databases opened read-only may not have been
+/// migrated yet, in which case outpoints are still stored as `txid:vout` strings.
+fn get_outpoint_from_row(row: &Row, index: usize) -> Result<OutPoint, String> {
+    match row.get_ref(index).map_err(|e| e.to_string())? {
+        ValueRef::Blob(bytes) => decode_outpoint(bytes),
+        ValueRef::Text(text) => std::str::from_utf8(text)
+            .map_err(|e| e.to_string())?
+            .parse::<OutPoint>()
+            .map_err(|e| format!("invalid outpoint: {e}")),
+        _ => Err(format!("unexpected outpoint type")),
+    }
+}
+
 pub fn create_or_open_readwrite_db(db_path: &PathBuf, ctx: &Context) -> Connection {
     let open_flags = match std::fs::metadata(&db_path) {
         Err(e) => {
@@ -306,7 +513,7 @@ pub fn open_readwrite_ordhook_dbs(
     Ok((blocks_db, inscriptions_db))
 }
 
-fn open_readwrite_ordhook_db_conn_rocks_db(
+pub fn open_readwrite_ordhook_db_conn_rocks_db(
     base_dir: &PathBuf,
     _ctx: &Context,
 ) -> Result<DB, String> {
@@ -360,6 +567,84 @@ pub fn find_last_block_inserted(blocks_db: &DB) -> u32 {
     }
 }
 
+/// Progress recorded in hord.rocksdb when a processor terminates, for the next run to resume from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checkpoint {
+    /// Last block stored in hord.rocksdb by the block archiving processor.
+    Archived,
+    /// Last block committed in hord.sqlite by the inscription indexing processor.
+    Indexed,
+}
+
+impl Checkpoint {
+    fn key(&self) -> &'static [u8] {
+        match self {
+            Checkpoint::Archived => b"metadata::archived_checkpoint",
+            Checkpoint::Indexed => b"metadata::indexed_checkpoint",
+        }
+    }
+}
+
+pub fn insert_checkpoint_in_blocks(
+    checkpoint: Checkpoint,
+    block_height: u64,
+    blocks_db_rw: &DB,
+    ctx: &Context,
+) {
+    if let Err(e) = blocks_db_rw.put(checkpoint.key(), block_height.to_be_bytes()) {
+        ctx.try_log(|logger| {
+            error!(
+                logger,
+                "unable to record {:?} checkpoint at block #{block_height}: {}",
+                checkpoint,
+                e.to_string()
+            )
+        });
+    }
+}
+
+pub fn find_last_checkpoint(checkpoint: Checkpoint, blocks_db: &DB) -> Option<u64> {
+    match blocks_db.get(checkpoint.key()) {
+        Ok(Some(bytes)) if bytes.len() == 8 => {
+            let mut block_height_bytes = [0u8; 8];
+            block_height_bytes.copy_from_slice(&bytes);
+            Some(u64::from_be_bytes(block_height_bytes))
+        }
+        _ => None,
+    }
+}
+
+/// Persists the memtables and the write-ahead log of hord.rocksdb.
+pub fn flush_ordhook_db_conn_rocks_db(blocks_db_rw: &DB, ctx: &Context) {
+    if let Err(e) = blocks_db_rw.flush_wal(true) {
+        ctx.try_log(|logger| {
+            error!(
+                logger,
+                "unable to flush hord.rocksdb wal: {}",
+                e.to_string()
+            )
+        });
+    }
+    if let Err(e) = blocks_db_rw.flush() {
+        ctx.try_log(|logger| error!(logger, "unable to flush hord.rocksdb: {}", e.to_string()));
+    }
+}
+
+/// Moves the content of the hord.sqlite write-ahead log into the database file.
+pub fn checkpoint_ordhook_db_conn_wal(inscriptions_db_conn: &Connection, ctx: &Context) {
+    if let Err(e) =
+        inscriptions_db_conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    {
+        ctx.try_log(|logger| {
+            error!(
+                logger,
+                "unable to checkpoint hord.sqlite wal: {}",
+                e.to_string()
+            )
+        });
+    }
+}
+
 pub fn find_lazy_block_at_block_height(
     block_height: u32,
     retry: u8,
@@ -438,9 +723,13 @@ pub fn insert_entry_in_inscriptions(
     inscriptions_db_conn_rw: &Connection,
     ctx: &Context,
 ) {
+    let curse_type = inscription_data
+        .curse_type
+        .as_ref()
+        .and_then(|curse_type| serde_json::to_string(curse_type).ok());
     while let Err(e) = inscriptions_db_conn_rw.execute(
-        "INSERT INTO inscriptions (inscription_id, ordinal_number, inscription_number, block_height) VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![&inscription_data.inscription_id, &inscription_data.ordinal_number, &inscription_data.inscription_number, &block_identifier.index],
+        "INSERT INTO inscriptions (inscription_id, ordinal_number, inscription_number, block_height, content_type, content_length, inscriber_address, output_value, fee, curse_type, transfers_pre_inscription) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![&inscription_data.inscription_id, &inscription_data.ordinal_number, &inscription_data.inscription_number, &block_identifier.index, &inscription_data.content_type, &inscription_data.content_length, &inscription_data.inscriber_address, &inscription_data.inscription_output_value, &inscription_data.inscription_fee, &curse_type, &inscription_data.transfers_pre_inscription],
     ) {
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -453,12 +742,24 @@ pub fn insert_inscription_in_locations(
     inscriptions_db_conn_rw: &Connection,
     ctx: &Context,
 ) {
-    let (tx, output_index, offset) =
-        parse_satpoint_to_watch(&inscription_data.satpoint_post_inscription);
-    let outpoint_to_watch = format_outpoint_to_watch(&tx, output_index);
+    let satpoint = match inscription_data
+        .satpoint_post_inscription
+        .parse::<SatPoint>()
+    {
+        Ok(satpoint) => satpoint,
+        Err(e) => {
+            ctx.try_log(|logger| {
+                error!(
+                    logger,
+                    "unable to store location of {}: {e}", inscription_data.inscription_id
+                )
+            });
+            return;
+        }
+    };
     while let Err(e) = inscriptions_db_conn_rw.execute(
         "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&inscription_data.inscription_id, &outpoint_to_watch, offset, &block_identifier.index, &inscription_data.tx_index],
+        rusqlite::params![&inscription_data.inscription_id, &encode_outpoint(&satpoint.outpoint), &satpoint.offset, &block_identifier.index, &inscription_data.tx_index],
     ) {
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -491,13 +792,18 @@ pub fn update_locations_with_block(
     inscriptions_db_conn_rw: &Connection,
     ctx: &Context,
 ) {
-    for transfer_data in get_inscriptions_transferred_in_block(&block).iter() {
-        insert_transfer_in_locations(
-            &transfer_data,
-            &block.block_identifier,
-            &inscriptions_db_conn_rw,
-            ctx,
-        );
+    for tx in block.transactions.iter() {
+        for operation in tx.metadata.ordinal_operations.iter() {
+            if let OrdinalOperation::InscriptionTransferred(transfer_data) = operation {
+                insert_transfer_in_locations(
+                    &transfer_data,
+                    &tx.transaction_identifier,
+                    &block.block_identifier,
+                    &inscriptions_db_conn_rw,
+                    ctx,
+                );
+            }
+        }
     }
 }
 
@@ -523,8 +829,8 @@ pub fn update_sequence_metadata_with_block(
         latest_cursed = latest_cursed.min(inscription_data.inscription_number);
     }
     while let Err(e) = inscriptions_db_conn_rw.execute(
-        "INSERT INTO sequence_metadata (block_height, latest_inscription_number, latest_cursed_inscription_number) VALUES (?1, ?2, ?3)",
-        rusqlite::params![&block.block_identifier.index, latest_blessed, latest_cursed],
+        "INSERT INTO sequence_metadata (block_height, latest_inscription_number, latest_cursed_inscription_number, block_hash, parent_block_hash, timestamp) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![&block.block_identifier.index, latest_blessed, latest_cursed, &block.block_identifier.hash, &block.parent_block_identifier.hash, &block.timestamp],
     ) {
         ctx.try_log(|logger| warn!(logger, "unable to update sequence_metadata: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -548,32 +854,51 @@ pub fn insert_new_inscriptions_from_block_in_locations(
 
 pub fn insert_transfer_in_locations_tx(
     transfer_data: &OrdinalInscriptionTransferData,
+    transaction_identifier: &TransactionIdentifier,
     block_identifier: &BlockIdentifier,
     inscriptions_db_conn_rw: &Transaction,
     ctx: &Context,
 ) {
-    let (tx, output_index, offset) = parse_satpoint_to_watch(&transfer_data.satpoint_post_transfer);
-    let outpoint_to_watch = format_outpoint_to_watch(&tx, output_index);
-    while let Err(e) = inscriptions_db_conn_rw.execute(
-        "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&transfer_data.inscription_id, &outpoint_to_watch, offset, &block_identifier.index, &transfer_data.tx_index],
-    ) {
-        ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
-        std::thread::sleep(std::time::Duration::from_secs(1));
-    }
+    insert_transfer_in_locations(
+        transfer_data,
+        transaction_identifier,
+        block_identifier,
+        inscriptions_db_conn_rw,
+        ctx,
+    )
 }
 
+/// Records the location of a transfer along with its destination and value. The transaction is only
+/// recorded for inscriptions spent in fees: other locations are outputs of the transaction.
 pub fn insert_transfer_in_locations(
     transfer_data: &OrdinalInscriptionTransferData,
+    transaction_identifier: &TransactionIdentifier,
     block_identifier: &BlockIdentifier,
     inscriptions_db_conn_rw: &Connection,
     ctx: &Context,
 ) {
-    let (tx, output_index, offset) = parse_satpoint_to_watch(&transfer_data.satpoint_post_transfer);
-    let outpoint_to_watch = format_outpoint_to_watch(&tx, output_index);
+    let satpoint = match transfer_data.satpoint_post_transfer.parse::<SatPoint>() {
+        Ok(satpoint) => satpoint,
+        Err(e) => {
+            ctx.try_log(|logger| {
+                error!(
+                    logger,
+                    "unable to store location of {}: {e}", transfer_data.inscription_id
+                )
+            });
+            return;
+        }
+    };
+    let destination = serde_json::to_string(&transfer_data.destination).ok();
+    let tx_id = match transfer_data.destination {
+        OrdinalInscriptionTransferDestination::SpentInFees => {
+            Some(transaction_identifier.get_hash_bytes_str().to_string())
+        }
+        _ => None,
+    };
     while let Err(e) = inscriptions_db_conn_rw.execute(
-        "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&transfer_data.inscription_id, &outpoint_to_watch, offset, &block_identifier.index, &transfer_data.tx_index],
+        "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index, destination, output_value, tx_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![&transfer_data.inscription_id, &encode_outpoint(&satpoint.outpoint), &satpoint.offset, &block_identifier.index, &transfer_data.tx_index, &destination, &transfer_data.post_transfer_output_value, &tx_id],
     ) {
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -716,20 +1041,16 @@ pub fn find_initial_inscription_transfer_data(
 ) -> Result<Option<TransferData>, String> {
     let args: &[&dyn ToSql] = &[&inscription_id.to_sql().unwrap()];
     let query = "SELECT outpoint_to_watch, offset, tx_index FROM locations WHERE inscription_id = ? ORDER BY block_height ASC, tx_index ASC LIMIT 1";
-    let entry = perform_query_one(query, args, db_conn, ctx, |row| {
-        let outpoint_to_watch: String = row.get(0).unwrap();
-        let (transaction_identifier_location, output_index) =
-            parse_outpoint_to_watch(&outpoint_to_watch);
-        let inscription_offset_intra_output: u64 = row.get(1).unwrap();
+    let entry = perform_query_one(query, args, db_conn, ctx, |row| -> Result<_, String> {
+        let outpoint = get_outpoint_from_row(row, 0)?;
+        let offset: u64 = row.get(1).unwrap();
         let tx_index: u64 = row.get(2).unwrap();
-        TransferData {
-            transaction_identifier_location,
-            output_index,
-            inscription_offset_intra_output,
+        Ok(TransferData {
+            satpoint: SatPoint::new(outpoint, offset),
             tx_index,
-        }
+        })
     });
-    Ok(entry)
+    entry.transpose()
 }
 
 pub fn find_latest_inscription_transfer_data(
@@ -739,20 +1060,40 @@ pub fn find_latest_inscription_transfer_data(
 ) -> Result<Option<TransferData>, String> {
     let args: &[&dyn ToSql] = &[&inscription_id.to_sql().unwrap()];
     let query = "SELECT outpoint_to_watch, offset, tx_index FROM locations WHERE inscription_id = ? ORDER BY block_height DESC, tx_index DESC LIMIT 1";
-    let entry = perform_query_one(query, args, db_conn, ctx, |row| {
-        let outpoint_to_watch: String = row.get(0).unwrap();
-        let (transaction_identifier_location, output_index) =
-            parse_outpoint_to_watch(&outpoint_to_watch);
-        let inscription_offset_intra_output: u64 = row.get(1).unwrap();
+    let entry = perform_query_one(query, args, db_conn, ctx, |row| -> Result<_, String> {
+        let outpoint = get_outpoint_from_row(row, 0)?;
+        let offset: u64 = row.get(1).unwrap();
         let tx_index: u64 = row.get(2).unwrap();
-        TransferData {
-            transaction_identifier_location,
-            output_index,
-            inscription_offset_intra_output,
+        Ok(TransferData {
+            satpoint: SatPoint::new(outpoint, offset),
             tx_index,
-        }
+        })
     });
-    Ok(entry)
+    entry.transpose()
+}
+
+/// Returns the last location of `inscription_id` recorded before `block_height`.
+pub fn find_inscription_transfer_data_before_block(
+    inscription_id: &str,
+    block_height: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Option<TransferData>, String> {
+    let args: &[&dyn ToSql] = &[
+        &inscription_id.to_sql().unwrap(),
+        &block_height.to_sql().unwrap(),
+    ];
+    let query = "SELECT outpoint_to_watch, offset, tx_index FROM locations WHERE inscription_id = ? AND block_height < ? ORDER BY block_height DESC, tx_index DESC LIMIT 1";
+    let entry = perform_query_one(query, args, db_conn, ctx, |row| -> Result<_, String> {
+        let outpoint = get_outpoint_from_row(row, 0)?;
+        let offset: u64 = row.get(1).unwrap();
+        let tx_index: u64 = row.get(2).unwrap();
+        Ok(TransferData {
+            satpoint: SatPoint::new(outpoint, offset),
+            tx_index,
+        })
+    });
+    entry.transpose()
 }
 
 pub fn find_latest_transfers_block_height(db_conn: &Connection, ctx: &Context) -> Option<u64> {
@@ -767,9 +1108,7 @@ pub fn find_latest_transfers_block_height(db_conn: &Connection, ctx: &Context) -
 
 #[derive(Debug, Clone)]
 pub struct TransferData {
-    pub inscription_offset_intra_output: u64,
-    pub transaction_identifier_location: TransactionIdentifier,
-    pub output_index: usize,
+    pub satpoint: SatPoint,
     pub tx_index: u64,
 }
 
@@ -809,15 +1148,19 @@ pub fn find_all_transfers_in_block(
         match rows.next() {
             Ok(Some(row)) => {
                 let inscription_id: String = row.get(0).unwrap();
-                let inscription_offset_intra_output: u64 = row.get(1).unwrap();
-                let outpoint_to_watch: String = row.get(2).unwrap();
+                let offset: u64 = row.get(1).unwrap();
                 let tx_index: u64 = row.get(3).unwrap();
-                let (transaction_identifier_location, output_index) =
-                    parse_outpoint_to_watch(&outpoint_to_watch);
+                let outpoint = match get_outpoint_from_row(row, 2) {
+                    Ok(outpoint) => outpoint,
+                    Err(e) => {
+                        ctx.try_log(|logger| {
+                            error!(logger, "unable to read location of {inscription_id}: {e}")
+                        });
+                        continue;
+                    }
+                };
                 let transfer = TransferData {
-                    inscription_offset_intra_output,
-                    transaction_identifier_location,
-                    output_index,
+                    satpoint: SatPoint::new(outpoint, offset),
                     tx_index,
                 };
                 results
@@ -844,22 +1187,28 @@ pub fn find_all_inscription_transfers(
 ) -> Vec<(TransferData, u64)> {
     let args: &[&dyn ToSql] = &[&inscription_id.to_sql().unwrap()];
     let query = "SELECT offset, outpoint_to_watch, tx_index, block_height FROM locations WHERE inscription_id = ? ORDER BY block_height ASC, tx_index ASC";
-    perform_query_set(query, args, db_conn, ctx, |row| {
-        let inscription_offset_intra_output: u64 = row.get(0).unwrap();
-        let outpoint_to_watch: String = row.get(1).unwrap();
+    let entries = perform_query_set(query, args, db_conn, ctx, |row| {
+        let offset: u64 = row.get(0).unwrap();
         let tx_index: u64 = row.get(2).unwrap();
         let block_height: u64 = row.get(3).unwrap();
-
-        let (transaction_identifier_location, output_index) =
-            parse_outpoint_to_watch(&outpoint_to_watch);
-        let transfer = TransferData {
-            inscription_offset_intra_output,
-            transaction_identifier_location,
-            output_index,
-            tx_index,
-        };
-        (transfer, block_height)
-    })
+        get_outpoint_from_row(row, 1).map(|outpoint| {
+            let transfer = TransferData {
+                satpoint: SatPoint::new(outpoint, offset),
+                tx_index,
+            };
+            (transfer, block_height)
+        })
+    });
+    let mut transfers = vec![];
+    for entry in entries.into_iter() {
+        match entry {
+            Ok(transfer) => transfers.push(transfer),
+            Err(e) => ctx.try_log(|logger| {
+                error!(logger, "unable to read location of {inscription_id}: {e}")
+            }),
+        }
+    }
+    transfers
 }
 
 pub fn find_latest_inscription_number_at_block_height(
@@ -943,52 +1292,601 @@ pub fn find_blessed_inscription_with_ordinal_number(
     })
 }
 
+/// Returns `None` when `inscription_id` is not indexed, and fails when it is malformed.
 pub fn find_inscription_with_id(
     inscription_id: &str,
     db_conn: &Connection,
     ctx: &Context,
 ) -> Result<Option<(TraversalResult, u64)>, String> {
-    let Some(transfer_data) = find_initial_inscription_transfer_data(inscription_id, db_conn, ctx)?
-    else {
-        return Err(format!("unable to retrieve location for {inscription_id}"));
-    };
+    let (transaction_identifier_inscription, inscription_input_index) =
+        parse_inscription_id(inscription_id)?;
     let args: &[&dyn ToSql] = &[&inscription_id.to_sql().unwrap()];
     let query = "SELECT inscription_number, ordinal_number, block_height FROM inscriptions WHERE inscription_id = ?";
-    let entry = perform_query_one(query, args, db_conn, ctx, move |row| {
+    let entry = perform_query_one(query, args, db_conn, ctx, |row| {
         let inscription_number: i64 = row.get(0).unwrap();
         let ordinal_number: u64 = row.get(1).unwrap();
         let block_height: u64 = row.get(2).unwrap();
-        let (transaction_identifier_inscription, inscription_input_index) =
-            parse_inscription_id(inscription_id);
-        (
+        (inscription_number, ordinal_number, block_height)
+    });
+    let Some((inscription_number, ordinal_number, block_height)) = entry else {
+        return Ok(None);
+    };
+    let Some(transfer_data) = find_initial_inscription_transfer_data(inscription_id, db_conn, ctx)?
+    else {
+        return Err(format!("unable to retrieve location for {inscription_id}"));
+    };
+    Ok(Some((
+        TraversalResult {
             inscription_number,
             ordinal_number,
             inscription_input_index,
             transaction_identifier_inscription,
+            transfers: 0,
+            transfer_data,
+        },
+        block_height,
+    )))
+}
+
+pub fn find_inscription_id_with_inscription_number(
+    inscription_number: i64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Option<String> {
+    let args: &[&dyn ToSql] = &[&inscription_number.to_sql().unwrap()];
+    let query = "SELECT inscription_id FROM inscriptions WHERE inscription_number = ?";
+    perform_query_one(query, args, db_conn, ctx, |row| {
+        let inscription_id: String = row.get(0).unwrap();
+        inscription_id
+    })
+}
+
+pub fn find_all_inscription_ids_with_ordinal_number(
+    ordinal_number: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<String> {
+    let args: &[&dyn ToSql] = &[&ordinal_number.to_sql().unwrap()];
+    let query = "SELECT inscription_id FROM inscriptions WHERE ordinal_number = ? ORDER BY block_height ASC, rowid ASC";
+    perform_query_set(query, args, db_conn, ctx, |row| {
+        let inscription_id: String = row.get(0).unwrap();
+        inscription_id
+    })
+}
+
+pub fn find_all_inscriptions_in_block_range(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<(u64, String, i64, u64)> {
+    let args: &[&dyn ToSql] = &[&start_block.to_sql().unwrap(), &end_block.to_sql().unwrap()];
+    let query = "SELECT block_height, inscription_id, inscription_number, ordinal_number FROM inscriptions WHERE block_height >= ? AND block_height <= ? ORDER BY block_height ASC, rowid ASC";
+    perform_query_set(query, args, db_conn, ctx, |row| {
+        let block_height: u64 = row.get(0).unwrap();
+        let inscription_id: String = row.get(1).unwrap();
+        let inscription_number: i64 = row.get(2).unwrap();
+        let ordinal_number: u64 = row.get(3).unwrap();
+        (
             block_height,
+            inscription_id,
+            inscription_number,
+            ordinal_number,
         )
+    })
+}
+
+pub fn find_all_locations_in_block_range(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<(u64, String, TransferData)> {
+    let args: &[&dyn ToSql] = &[&start_block.to_sql().unwrap(), &end_block.to_sql().unwrap()];
+    let query = "SELECT block_height, inscription_id, outpoint_to_watch, offset, tx_index FROM locations WHERE block_height >= ? AND block_height <= ? ORDER BY block_height ASC, tx_index ASC, rowid ASC";
+    let entries = perform_query_set(query, args, db_conn, ctx, |row| {
+        let block_height: u64 = row.get(0).unwrap();
+        let inscription_id: String = row.get(1).unwrap();
+        let offset: u64 = row.get(3).unwrap();
+        let tx_index: u64 = row.get(4).unwrap();
+        let outpoint = get_outpoint_from_row(row, 2);
+        (block_height, inscription_id, outpoint, offset, tx_index)
+    });
+    let mut locations = vec![];
+    for (block_height, inscription_id, outpoint, offset, tx_index) in entries.into_iter() {
+        match outpoint {
+            Ok(outpoint) => locations.push((
+                block_height,
+                inscription_id,
+                TransferData {
+                    satpoint: SatPoint::new(outpoint, offset),
+                    tx_index,
+                },
+            )),
+            Err(e) => ctx.try_log(|logger| {
+                error!(logger, "unable to read location of {inscription_id}: {e}")
+            }),
+        }
+    }
+    locations
+}
+
+/// Location of an inscription, along with the destination, value and transaction recorded for
+/// transfers (null for genesis locations, and for transfers indexed before they were recorded).
+struct LocationDetails {
+    block_height: u64,
+    inscription_id: String,
+    transfer_data: TransferData,
+    destination: Option<String>,
+    output_value: Option<u64>,
+    tx_id: Option<String>,
+}
+
+fn find_locations_with_details(
+    condition: &str,
+    args: &[&dyn ToSql],
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<LocationDetails> {
+    let columns = match are_feed_details_available(db_conn, ctx) {
+        true => "destination, output_value, tx_id",
+        false => "NULL, NULL, NULL",
+    };
+    let query = format!("SELECT block_height, inscription_id, outpoint_to_watch, offset, tx_index, {columns} FROM locations WHERE {condition} ORDER BY block_height ASC, tx_index ASC, rowid ASC");
+    let entries = perform_query_set(&query, args, db_conn, ctx, |row| {
+        let block_height: u64 = row.get(0).unwrap();
+        let inscription_id: String = row.get(1).unwrap();
+        let offset: u64 = row.get(3).unwrap();
+        let tx_index: u64 = row.get(4).unwrap();
+        let destination: Option<String> = row.get(5).unwrap();
+        let output_value: Option<u64> = row.get(6).unwrap();
+        let tx_id: Option<String> = row.get(7).unwrap();
+        let location = get_outpoint_from_row(row, 2).map(|outpoint| LocationDetails {
+            block_height,
+            inscription_id: inscription_id.clone(),
+            transfer_data: TransferData {
+                satpoint: SatPoint::new(outpoint, offset),
+                tx_index,
+            },
+            destination,
+            output_value,
+            tx_id,
+        });
+        (inscription_id, location)
     });
-    Ok(entry.map(
-        |(
+    let mut locations = vec![];
+    for (inscription_id, location) in entries.into_iter() {
+        match location {
+            Ok(location) => locations.push(location),
+            Err(e) => ctx.try_log(|logger| {
+                error!(logger, "unable to read location of {inscription_id}: {e}")
+            }),
+        }
+    }
+    locations
+}
+
+fn build_transfer_record(
+    location: LocationDetails,
+    satpoint_pre_transfer: String,
+) -> Result<InscriptionTransferRecord, String> {
+    let destination = match location.destination {
+        Some(destination) => Some(serde_json::from_str(&destination).map_err(|e| {
+            format!(
+                "unable to parse destination of {} (block #{}): {e}",
+                location.inscription_id, location.block_height
+            )
+        })?),
+        None => None,
+    };
+    let satpoint = location.transfer_data.satpoint;
+    Ok(InscriptionTransferRecord {
+        block_height: location.block_height,
+        tx_id: location
+            .tx_id
+            .unwrap_or_else(|| satpoint.outpoint.txid.to_string()),
+        tx_index: location.transfer_data.tx_index,
+        satpoint_pre_transfer,
+        satpoint_post_transfer: satpoint.to_string(),
+        destination,
+        post_transfer_output_value: location.output_value,
+    })
+}
+
+/// Inscription revealed, as recorded in the inscriptions and locations tables.
+#[derive(Clone, Debug)]
+pub struct IndexedReveal {
+    pub block_height: u64,
+    pub inscription_id: String,
+    pub inscription_number: i64,
+    pub ordinal_number: u64,
+    pub genesis: TransferData,
+    /// `None` for inscriptions indexed before reveal details were recorded. Contents are not part
+    /// of the index: `content_bytes` is always empty.
+    pub data: Option<OrdinalInscriptionRevealData>,
+}
+
+/// Returns the inscriptions revealed between `start_block` and `end_block`, ordered by block height
+/// and position in their block.
+pub fn find_indexed_reveals_in_block_range(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Vec<IndexedReveal>, String> {
+    let mut genesis_locations = BTreeMap::new();
+    for (_, inscription_id, transfer_data) in
+        find_all_locations_in_block_range(start_block, end_block, db_conn, ctx)
+    {
+        genesis_locations
+            .entry(inscription_id)
+            .or_insert(transfer_data);
+    }
+    let columns = match are_feed_details_available(db_conn, ctx) {
+        true => "content_type, content_length, inscriber_address, output_value, fee, curse_type, transfers_pre_inscription",
+        false => "NULL, NULL, NULL, NULL, NULL, NULL, NULL",
+    };
+    let args: &[&dyn ToSql] = &[&start_block.to_sql().unwrap(), &end_block.to_sql().unwrap()];
+    let query = format!("SELECT block_height, inscription_id, inscription_number, ordinal_number, {columns} FROM inscriptions WHERE block_height >= ? AND block_height <= ? ORDER BY block_height ASC, rowid ASC");
+    let entries = perform_query_set(&query, args, db_conn, ctx, |row| {
+        let block_height: u64 = row.get(0).unwrap();
+        let inscription_id: String = row.get(1).unwrap();
+        let inscription_number: i64 = row.get(2).unwrap();
+        let ordinal_number: u64 = row.get(3).unwrap();
+        let content_type: Option<String> = row.get(4).unwrap();
+        let content_length: Option<usize> = row.get(5).unwrap();
+        let inscriber_address: Option<String> = row.get(6).unwrap();
+        let output_value: Option<u64> = row.get(7).unwrap();
+        let fee: Option<u64> = row.get(8).unwrap();
+        let curse_type: Option<String> = row.get(9).unwrap();
+        let transfers_pre_inscription: Option<u32> = row.get(10).unwrap();
+        let details = match (content_type, content_length, output_value, fee) {
+            (Some(content_type), Some(content_length), Some(output_value), Some(fee)) => Some((
+                content_type,
+                content_length,
+                inscriber_address,
+                output_value,
+                fee,
+                curse_type,
+                transfers_pre_inscription.unwrap_or(0),
+            )),
+            _ => None,
+        };
+        (
+            block_height,
+            inscription_id,
             inscription_number,
             ordinal_number,
-            inscription_input_index,
-            transaction_identifier_inscription,
-            block_height,
-        )| {
-            (
-                TraversalResult {
+            details,
+        )
+    });
+    let mut reveals = vec![];
+    for (block_height, inscription_id, inscription_number, ordinal_number, details) in
+        entries.into_iter()
+    {
+        let Some(genesis) = genesis_locations.remove(&inscription_id) else {
+            return Err(format!("unable to retrieve location for {inscription_id}"));
+        };
+        let data = match details {
+            Some((
+                content_type,
+                content_length,
+                inscriber_address,
+                inscription_output_value,
+                inscription_fee,
+                curse_type,
+                transfers_pre_inscription,
+            )) => {
+                let (_, inscription_input_index) = parse_inscription_id(&inscription_id)?;
+                let curse_type = match curse_type {
+                    Some(curse_type) => Some(serde_json::from_str(&curse_type).map_err(|e| {
+                        format!("unable to parse curse type of {inscription_id}: {e}")
+                    })?),
+                    None => None,
+                };
+                let sat = Sat(ordinal_number);
+                Some(OrdinalInscriptionRevealData {
+                    content_type,
+                    content_bytes: String::new(),
+                    content_length,
+                    inscription_id: inscription_id.clone(),
+                    inscription_input_index,
+                    tx_index: genesis.tx_index as usize,
+                    inscription_output_value,
+                    inscription_fee,
                     inscription_number,
+                    inscriber_address,
                     ordinal_number,
-                    inscription_input_index,
-                    transaction_identifier_inscription,
-                    transfers: 0,
-                    transfer_data,
+                    ordinal_block_height: sat.height().n(),
+                    ordinal_offset: ordinal_number - sat.height().starting_sat().n(),
+                    transfers_pre_inscription,
+                    satpoint_post_inscription: genesis.satpoint.to_string(),
+                    curse_type,
+                })
+            }
+            None => None,
+        };
+        reveals.push(IndexedReveal {
+            block_height,
+            inscription_id,
+            inscription_number,
+            ordinal_number,
+            genesis,
+            data,
+        });
+    }
+    Ok(reveals)
+}
+
+/// Returns the transfers of inscriptions between `start_block` and `end_block`, ordered by block
+/// height and position in their block, along with the id of the inscription transferred.
+pub fn find_indexed_transfers_in_block_range(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Vec<(String, InscriptionTransferRecord)>, String> {
+    let revealed = find_all_inscriptions_in_block_range(start_block, end_block, db_conn, ctx)
+        .into_iter()
+        .map(|(_, inscription_id, _, _)| inscription_id)
+        .collect::<HashSet<_>>();
+    let args: &[&dyn ToSql] = &[&start_block.to_sql().unwrap(), &end_block.to_sql().unwrap()];
+    let locations = find_locations_with_details(
+        "block_height >= ? AND block_height <= ?",
+        args,
+        db_conn,
+        ctx,
+    );
+    let mut satpoints = BTreeMap::new();
+    let mut transfers = vec![];
+    for location in locations.into_iter() {
+        let inscription_id = location.inscription_id.clone();
+        let satpoint_post_transfer = location.transfer_data.satpoint.to_string();
+        let satpoint_pre_transfer =
+            match satpoints.insert(inscription_id.clone(), satpoint_post_transfer) {
+                Some(satpoint) => satpoint,
+                // The first location of an inscription revealed in the range is its genesis
+                None if revealed.contains(&inscription_id) => continue,
+                None => match find_inscription_transfer_data_before_block(
+                    &inscription_id,
+                    start_block,
+                    db_conn,
+                    ctx,
+                )? {
+                    Some(previous) => previous.satpoint.to_string(),
+                    None => {
+                        return Err(format!(
+                            "unable to retrieve location of {inscription_id} before block #{}",
+                            location.block_height
+                        ))
+                    }
                 },
-                block_height,
-            )
+            };
+        transfers.push((
+            inscription_id,
+            build_transfer_record(location, satpoint_pre_transfer)?,
+        ));
+    }
+    Ok(transfers)
+}
+
+/// Blocks between `start_block` and `end_block` with ordinal activity, indexed before their header,
+/// reveal details, destinations and values were recorded: their inscription feed can not be rebuilt
+/// from the index tables.
+pub fn find_blocks_without_feed_details(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<u64> {
+    let args: &[&dyn ToSql] = &[&start_block.to_sql().unwrap(), &end_block.to_sql().unwrap()];
+    let query = match are_feed_details_available(db_conn, ctx) {
+        true => {
+            "SELECT block_height FROM inscriptions WHERE block_height BETWEEN ?1 AND ?2
+            UNION SELECT block_height FROM locations WHERE block_height BETWEEN ?1 AND ?2
+            EXCEPT SELECT block_height FROM sequence_metadata WHERE block_hash IS NOT NULL
+            ORDER BY block_height ASC"
+        }
+        false => {
+            "SELECT block_height FROM inscriptions WHERE block_height BETWEEN ?1 AND ?2
+            UNION SELECT block_height FROM locations WHERE block_height BETWEEN ?1 AND ?2
+            ORDER BY block_height ASC"
+        }
+    };
+    perform_query_set(query, args, db_conn, ctx, |row| {
+        let block_height: u64 = row.get(0).unwrap();
+        block_height
+    })
+}
+
+/// Rebuilds the inscription feed of the block at `block_height` from the index tables: the
+/// transactions revealing or transferring inscriptions, with their ordinal operations. Inputs,
+/// outputs and inscription contents are not indexed and are left empty, and fees are only known for
+/// reveal transactions. Returns `None` for blocks without ordinal activity, and fails for blocks
+/// listed by `find_blocks_without_feed_details`.
+pub fn find_inscription_feed_at_block_height(
+    block_height: u64,
+    network: &BitcoinNetwork,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Option<BitcoinBlockData>, String> {
+    let reveals = find_indexed_reveals_in_block_range(block_height, block_height, db_conn, ctx)?;
+    let transfers =
+        find_indexed_transfers_in_block_range(block_height, block_height, db_conn, ctx)?;
+    if reveals.is_empty() && transfers.is_empty() {
+        return Ok(None);
+    }
+    let missing_details = || {
+        format!("block #{block_height} was indexed before reveal details, destinations and values were recorded")
+    };
+    let header = match are_feed_details_available(db_conn, ctx) {
+        true => {
+            let args: &[&dyn ToSql] = &[&block_height.to_sql().unwrap()];
+            let query = "SELECT block_hash, parent_block_hash, timestamp FROM sequence_metadata WHERE block_height = ? AND block_hash IS NOT NULL LIMIT 1";
+            perform_query_one(query, args, db_conn, ctx, |row| {
+                let block_hash: String = row.get(0).unwrap();
+                let parent_block_hash: String = row.get(1).unwrap();
+                let timestamp: u32 = row.get(2).unwrap();
+                (block_hash, parent_block_hash, timestamp)
+            })
+        }
+        false => None,
+    };
+    let Some((block_hash, parent_block_hash, timestamp)) = header else {
+        return Err(missing_details());
+    };
+
+    // Operations grouped by transaction: reveals first, then transfers, as while indexing
+    let mut transactions: BTreeMap<u64, (String, u64, Vec<OrdinalOperation>)> = BTreeMap::new();
+    for reveal in reveals.into_iter() {
+        let Some(data) = reveal.data else {
+            return Err(missing_details());
+        };
+        let (transaction_identifier, _) = parse_inscription_id(&data.inscription_id)?;
+        let tx = transactions
+            .entry(data.tx_index as u64)
+            .or_insert_with(|| (transaction_identifier.hash, 0, vec![]));
+        tx.1 = data.inscription_fee;
+        tx.2.push(OrdinalOperation::InscriptionRevealed(data));
+    }
+    for (inscription_id, transfer) in transfers.into_iter() {
+        let Some(destination) = transfer.destination else {
+            return Err(missing_details());
+        };
+        let tx = transactions
+            .entry(transfer.tx_index)
+            .or_insert_with(|| (TransactionIdentifier::new(&transfer.tx_id).hash, 0, vec![]));
+        tx.2.push(OrdinalOperation::InscriptionTransferred(
+            OrdinalInscriptionTransferData {
+                inscription_id,
+                destination,
+                tx_index: transfer.tx_index as usize,
+                satpoint_pre_transfer: transfer.satpoint_pre_transfer,
+                satpoint_post_transfer: transfer.satpoint_post_transfer,
+                post_transfer_output_value: transfer.post_transfer_output_value,
+            },
+        ));
+    }
+    let transactions = transactions
+        .into_iter()
+        .map(|(tx_index, (hash, fee, ordinal_operations))| {
+            json!({
+                "transaction_identifier": { "hash": hash },
+                "operations": [],
+                "metadata": {
+                    "inputs": [],
+                    "outputs": [],
+                    "stacks_operations": [],
+                    "ordinal_operations": ordinal_operations,
+                    "proof": null,
+                    "fee": fee,
+                    "index": tx_index,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let block = json!({
+        "block_identifier": { "index": block_height, "hash": block_hash },
+        "parent_block_identifier": {
+            "index": block_height.saturating_sub(1),
+            "hash": parent_block_hash,
         },
-    ))
+        "timestamp": timestamp,
+        "transactions": transactions,
+        "metadata": { "network": network },
+    });
+    serde_json::from_value(block)
+        .map(Some)
+        .map_err(|e| format!("unable to rebuild block #{block_height}: {e}"))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InscriptionTransferRecord {
+    pub block_height: u64,
+    pub tx_id: String,
+    pub tx_index: u64,
+    pub satpoint_pre_transfer: String,
+    pub satpoint_post_transfer: String,
+    /// `None` for transfers indexed before destinations and values were recorded.
+    pub destination: Option<OrdinalInscriptionTransferDestination>,
+    pub post_transfer_output_value: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InscriptionDetails {
+    pub inscription_id: String,
+    pub inscription_number: i64,
+    pub ordinal_number: u64,
+    pub block_height: u64,
+    pub tx_index: u64,
+    /// `None` when the inscription was indexed before reveal details were recorded.
+    pub reveal: Option<OrdinalInscriptionRevealData>,
+    pub genesis_satpoint: String,
+    pub transfers: Vec<InscriptionTransferRecord>,
+    pub current_satpoint: String,
+    /// Blocks of the reveal and transfers indexed before reveal details, destinations and values
+    /// were recorded: they are only available once these blocks are indexed again.
+    pub blocks_without_details: Vec<u64>,
+}
+
+/// Assembles everything the local database knows about `inscription_id`, from the inscriptions and
+/// locations tables.
+pub fn find_inscription_details(
+    inscription_id: &str,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Option<InscriptionDetails>, String> {
+    let Some((inscription, block_height)) = find_inscription_with_id(inscription_id, db_conn, ctx)?
+    else {
+        return Ok(None);
+    };
+    let reveal = find_indexed_reveals_in_block_range(block_height, block_height, db_conn, ctx)?
+        .into_iter()
+        .find(|reveal| reveal.inscription_id == inscription_id)
+        .and_then(|reveal| reveal.data);
+    let mut blocks_without_details = vec![];
+    if reveal.is_none() {
+        blocks_without_details.push(block_height);
+    }
+
+    let args: &[&dyn ToSql] = &[&inscription_id.to_sql().unwrap()];
+    let mut locations =
+        find_locations_with_details("inscription_id = ?", args, db_conn, ctx).into_iter();
+    // The first location is the reveal
+    let Some(genesis) = locations.next() else {
+        return Err(format!("unable to retrieve location for {inscription_id}"));
+    };
+    let genesis_satpoint = genesis.transfer_data.satpoint.to_string();
+    let tx_index = genesis.transfer_data.tx_index;
+    let mut transfers = vec![];
+    let mut satpoint_pre_transfer = genesis_satpoint.clone();
+    for location in locations {
+        let satpoint_post_transfer = location.transfer_data.satpoint.to_string();
+        let transfer = build_transfer_record(
+            location,
+            std::mem::replace(&mut satpoint_pre_transfer, satpoint_post_transfer),
+        )?;
+        if transfer.destination.is_none()
+            && !blocks_without_details.contains(&transfer.block_height)
+        {
+            blocks_without_details.push(transfer.block_height);
+        }
+        transfers.push(transfer);
+    }
+    let current_satpoint = satpoint_pre_transfer;
+
+    Ok(Some(InscriptionDetails {
+        inscription_id: inscription.get_inscription_id(),
+        inscription_number: inscription.inscription_number,
+        ordinal_number: inscription.ordinal_number,
+        block_height,
+        tx_index,
+        reveal,
+        genesis_satpoint,
+        transfers,
+        current_satpoint,
+        blocks_without_details,
+    }))
 }
 
 pub fn find_all_inscriptions_in_block(
@@ -1032,7 +1930,13 @@ pub fn find_all_inscriptions_in_block(
                 let ordinal_number: u64 = row.get(1).unwrap();
                 let inscription_id: String = row.get(2).unwrap();
                 let (transaction_identifier_inscription, inscription_input_index) =
-                    { parse_inscription_id(&inscription_id) };
+                    match parse_inscription_id(&inscription_id) {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            ctx.try_log(|logger| error!(logger, "{e}"));
+                            continue;
+                        }
+                    };
                 let Some(transfer_data) = transfers_data
                     .get(&inscription_id)
                     .and_then(|entries| entries.first())
@@ -1078,9 +1982,9 @@ pub struct WatchedSatpoint {
 }
 
 impl WatchedSatpoint {
-    pub fn get_genesis_satpoint(&self) -> String {
-        let (transaction_id, input) = parse_inscription_id(&self.inscription_id);
-        format!("{}:{}", transaction_id.hash, input)
+    pub fn get_genesis_satpoint(&self) -> Result<String, String> {
+        let (transaction_id, input) = parse_inscription_id(&self.inscription_id)?;
+        Ok(format!("{}:{}", transaction_id.hash, input))
     }
 }
 
@@ -1106,12 +2010,18 @@ pub fn find_watched_satpoint_for_inscription(
 }
 
 pub fn find_inscriptions_at_wached_outpoint(
-    outpoint: &str,
+    outpoint: &OutPoint,
     db_conn: &Connection,
     ctx: &Context,
 ) -> Vec<WatchedSatpoint> {
-    let args: &[&dyn ToSql] = &[&outpoint.to_sql().unwrap()];
-    let query = "SELECT inscription_id, offset FROM locations WHERE outpoint_to_watch = ? ORDER BY offset ASC";
+    // Read-only connections can't migrate the text outpoints of legacy databases: match both encodings
+    let encoded_outpoint = encode_outpoint(outpoint);
+    let legacy_outpoint = outpoint.to_string();
+    let args: &[&dyn ToSql] = &[
+        &encoded_outpoint.to_sql().unwrap(),
+        &legacy_outpoint.to_sql().unwrap(),
+    ];
+    let query = "SELECT inscription_id, offset FROM locations WHERE outpoint_to_watch IN (?, ?) ORDER BY offset ASC";
     perform_query_set(query, args, db_conn, ctx, |row| {
         let inscription_id: String = row.get(0).unwrap();
         let offset: u64 = row.get(1).unwrap();
@@ -1193,13 +2103,9 @@ pub fn insert_entry_in_locations(
     inscriptions_db_rw_conn: &Transaction,
     ctx: &Context,
 ) {
-    let outpoint_to_watch = format_outpoint_to_watch(
-        &transfer_data.transaction_identifier_location,
-        transfer_data.output_index,
-    );
     while let Err(e) = inscriptions_db_rw_conn.execute(
         "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&inscription_id, &outpoint_to_watch, &transfer_data.inscription_offset_intra_output, &block_height, &transfer_data.tx_index],
+        rusqlite::params![&inscription_id, &encode_outpoint(&transfer_data.satpoint.outpoint), &transfer_data.satpoint.offset, &block_height, &transfer_data.tx_index],
     ) {
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -1235,6 +2141,127 @@ pub fn delete_data_in_ordhook_db(
     Ok(())
 }
 
+/// Block heights stored in hord.rocksdb above `block_height`. Block entries are keyed on their height
+/// (4 bytes, big endian) and sorted before the `metadata::` entries.
+fn find_block_heights_inserted_above(block_height: u32, blocks_db: &DB) -> Vec<u32> {
+    let start_key = (block_height as u64 + 1).min(u32::MAX as u64) as u32;
+    let mut block_heights = vec![];
+    let iter = blocks_db.iterator(rocksdb::IteratorMode::From(
+        &start_key.to_be_bytes(),
+        rocksdb::Direction::Forward,
+    ));
+    for entry in iter {
+        let Ok((key, _)) = entry else {
+            break;
+        };
+        if key.len() != 4 {
+            break;
+        }
+        let entry_height = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+        if entry_height > block_height {
+            block_heights.push(entry_height);
+        }
+    }
+    block_heights
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RewindReport {
+    /// Highest block height found in hord.rocksdb or hord.sqlite before the rewind.
+    pub previous_tip: u64,
+    /// Blocks removed from hord.rocksdb.
+    pub blocks_removed: u64,
+}
+
+/// Removes all the state recorded above `block_height` from hord.rocksdb and hord.sqlite: blocks,
+/// inscriptions, locations and sequence metadata. The tip and checkpoint metadata are moved back to
+/// `block_height`, so that indexing resumes from the following block.
+pub fn rewind_ordhook_db(
+    block_height: u64,
+    blocks_db_rw: &DB,
+    inscriptions_db_conn_rw: &Connection,
+    ctx: &Context,
+) -> Result<RewindReport, String> {
+    if block_height >= u32::MAX as u64 {
+        return Err(format!("block #{block_height} is out of range"));
+    }
+    let target = block_height as u32;
+
+    let block_heights = find_block_heights_inserted_above(target, blocks_db_rw);
+    let previous_tip = [
+        block_heights.last().map(|h| *h as u64),
+        Some(find_last_block_inserted(blocks_db_rw) as u64),
+        find_latest_inscription_block_height(inscriptions_db_conn_rw, ctx)?,
+        find_latest_transfers_block_height(inscriptions_db_conn_rw, ctx),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(0);
+
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Rewinding ordhook db from block #{previous_tip} to block #{block_height}"
+        )
+    });
+    // hord.sqlite is rewound atomically, before hord.rocksdb: an interrupted rewind leaves blocks
+    // above the target that the next rewind (or the indexer) removes, never a partially deleted index.
+    let inscriptions_db_tx = loop {
+        match inscriptions_db_conn_rw.unchecked_transaction() {
+            Ok(db_tx) => break db_tx,
+            Err(e) => {
+                ctx.try_log(|logger| {
+                    warn!(logger, "unable to begin hord.sqlite tx: {}", e.to_string())
+                });
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+    };
+    delete_inscriptions_in_block_range(target + 1, u32::MAX, &inscriptions_db_tx, ctx);
+    inscriptions_db_tx
+        .commit()
+        .map_err(|e| format!("unable to rewind hord.sqlite: {}", e.to_string()))?;
+    for entry_height in block_heights.iter() {
+        remove_entry_from_blocks(*entry_height, blocks_db_rw, ctx);
+    }
+
+    if find_last_block_inserted(blocks_db_rw) > target {
+        blocks_db_rw
+            .put(b"metadata::last_insert", target.to_be_bytes())
+            .map_err(|e| format!("unable to update tip metadata: {}", e.to_string()))?;
+    }
+    for checkpoint in [Checkpoint::Archived, Checkpoint::Indexed] {
+        if matches!(find_last_checkpoint(checkpoint, blocks_db_rw), Some(height) if height > block_height)
+        {
+            insert_checkpoint_in_blocks(checkpoint, block_height, blocks_db_rw, ctx);
+        }
+    }
+    flush_ordhook_db_conn_rocks_db(blocks_db_rw, ctx);
+    checkpoint_ordhook_db_conn_wal(inscriptions_db_conn_rw, ctx);
+
+    // Checks that no state is left above the target height
+    if let Some(entry_height) = find_block_heights_inserted_above(target, blocks_db_rw).first() {
+        return Err(format!(
+            "block #{entry_height} is still present in hord.rocksdb"
+        ));
+    }
+    let args: &[&dyn ToSql] = &[&block_height.to_sql().unwrap()];
+    for table in ["inscriptions", "locations", "sequence_metadata"] {
+        let query = format!("SELECT 1 FROM {table} WHERE block_height > ? LIMIT 1");
+        if perform_query_exists(&query, args, inscriptions_db_conn_rw, ctx) {
+            return Err(format!(
+                "entries above block #{block_height} are still present in table {table}"
+            ));
+        }
+    }
+
+    Ok(RewindReport {
+        previous_tip,
+        blocks_removed: block_heights.len() as u64,
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct TraversalResult {
     pub inscription_number: i64,
@@ -1265,62 +2292,20 @@ impl TraversalResult {
     }
 }
 
-pub fn format_satpoint_to_watch(
-    transaction_identifier: &TransactionIdentifier,
-    output_index: usize,
-    offset: u64,
-) -> String {
-    format!(
-        "{}:{}:{}",
-        transaction_identifier.get_hash_bytes_str(),
-        output_index,
-        offset
-    )
-}
-
-pub fn parse_satpoint_to_watch(outpoint_to_watch: &str) -> (TransactionIdentifier, usize, u64) {
-    let comps: Vec<&str> = outpoint_to_watch.split(":").collect();
-    let tx = TransactionIdentifier::new(comps[0]);
-    let output_index = comps[1].to_string().parse::<usize>().expect(&format!(
-        "fatal: unable to extract output_index from outpoint {}",
-        outpoint_to_watch
-    ));
-    let offset = comps[2].to_string().parse::<u64>().expect(&format!(
-        "fatal: unable to extract offset from outpoint {}",
-        outpoint_to_watch
-    ));
-    (tx, output_index, offset)
-}
-
-pub fn format_outpoint_to_watch(
-    transaction_identifier: &TransactionIdentifier,
-    output_index: usize,
-) -> String {
-    format!(
-        "{}:{}",
-        transaction_identifier.get_hash_bytes_str(),
-        output_index
-    )
-}
-
-pub fn parse_inscription_id(inscription_id: &str) -> (TransactionIdentifier, usize) {
-    let comps: Vec<&str> = inscription_id.split("i").collect();
-    let tx = TransactionIdentifier::new(&comps[0]);
-    let output_index = comps[1].to_string().parse::<usize>().expect(&format!(
-        "fatal: unable to extract output_index from inscription_id {}",
-        inscription_id
-    ));
-    (tx, output_index)
-}
-
-pub fn parse_outpoint_to_watch(outpoint_to_watch: &str) -> (TransactionIdentifier, usize) {
-    let comps: Vec<&str> = outpoint_to_watch.split(":").collect();
-    let tx = TransactionIdentifier::new(&comps[0]);
-    let output_index = comps[1].to_string().parse::<usize>().expect(&format!(
-        "fatal: unable to extract output_index from outpoint {}",
-        outpoint_to_watch
-    ));
-    (tx, output_index)
+/// Splits `<txid>i<index>` into the reveal transaction and the index of the inscription.
+pub fn parse_inscription_id(
+    inscription_id: &str,
+) -> Result<(TransactionIdentifier, usize), String> {
+    let Some((txid, index)) = inscription_id.split_once('i') else {
+        return Err(format!("invalid inscription id {inscription_id}"));
+    };
+    if txid.len() != 64 || !txid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid inscription id {inscription_id}"));
+    }
+    let index = index
+        .parse::<usize>()
+        .map_err(|_| format!("invalid inscription id {inscription_id}"))?;
+    Ok((TransactionIdentifier::new(txid), index))
 }
 
 #[derive(Debug)]
@@ -1694,3 +2679,59 @@ impl<'a> Iterator for LazyBlockTransactionIterator<'a> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::regtest_fixtures::TempDir;
+
+    const TXID: &str = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799";
+
+    fn insert_text_location(outpoint_to_watch: &str, conn: &Connection) {
+        conn.execute(
+            "INSERT INTO locations (inscription_id, outpoint_to_watch, offset, block_height, tx_index) VALUES (?1, ?2, 0, 767430, 1)",
+            rusqlite::params![format!("{TXID}i0"), outpoint_to_watch],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn migrates_text_outpoints_explicitly() {
+        let ctx = Context::empty();
+        let temp_dir = TempDir::new("ordhook-db-migrate");
+        let conn = initialize_ordhook_db(temp_dir.path(), &ctx);
+        assert!(is_ordhook_db_migrated(&conn, &ctx));
+
+        // Legacy database, with a location that can't be parsed
+        conn.execute_batch("PRAGMA user_version = 0; CREATE TABLE replay_blocks (block_height INTEGER NOT NULL PRIMARY KEY);")
+            .unwrap();
+        insert_text_location(&format!("{TXID}:0"), &conn);
+        insert_text_location("not-an-outpoint", &conn);
+        let conn = initialize_ordhook_db(temp_dir.path(), &ctx);
+        assert!(!is_ordhook_db_migrated(&conn, &ctx));
+
+        let res = migrate_ordhook_db(temp_dir.path(), &ctx);
+        assert!(res
+            .unwrap_err()
+            .contains("1 locations could not be migrated"));
+        assert!(!is_ordhook_db_migrated(&conn, &ctx));
+        assert!(!is_column_available(
+            "replay_blocks",
+            "block_height",
+            &conn,
+            &ctx
+        ));
+
+        conn.execute(
+            "DELETE FROM locations WHERE outpoint_to_watch = 'not-an-outpoint'",
+            [],
+        )
+        .unwrap();
+        migrate_ordhook_db(temp_dir.path(), &ctx).unwrap();
+        assert!(is_ordhook_db_migrated(&conn, &ctx));
+        let outpoint = format!("{TXID}:0").parse::<OutPoint>().unwrap();
+        let args: &[&dyn ToSql] = &[&encode_outpoint(&outpoint)];
+        let query = "SELECT 1 FROM locations WHERE outpoint_to_watch = ?";
+        assert!(perform_query_exists(query, args, &conn, &ctx));
+    }
+}
@@ -0,0 +1,637 @@
+mod parquet;
+
+use std::{io::Write, str::FromStr};
+
+use chainhook_sdk::{types::OrdinalInscriptionTransferDestination, utils::Context};
+use rusqlite::Connection;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::db::{
+    find_all_locations_in_block_range, find_indexed_reveals_in_block_range,
+    find_indexed_transfers_in_block_range,
+};
+
+/// Number of blocks read from `hord.sqlite` and written out at once.
+pub const EXPORT_BATCH_BLOCKS: u64 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportTable {
+    Inscriptions,
+    Locations,
+    Transfers,
+}
+
+impl FromStr for ExportTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inscriptions" => Ok(ExportTable::Inscriptions),
+            "locations" => Ok(ExportTable::Locations),
+            "transfers" => Ok(ExportTable::Transfers),
+            _ => Err(format!(
+                "unsupported table {s} (expected inscriptions, locations or transfers)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "parquet" => Ok(ExportFormat::Parquet),
+            "csv" => Ok(ExportFormat::Csv),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(format!(
+                "unsupported format {s} (expected parquet, csv or ndjson)"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Int64,
+    Utf8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportColumn {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+const fn column(name: &'static str, column_type: ColumnType, nullable: bool) -> ExportColumn {
+    ExportColumn {
+        name,
+        column_type,
+        nullable,
+    }
+}
+
+// The column sets below are documented in docs/how-to-guides/how-to-scan-ordinal-activities.md:
+// columns can be appended, but existing columns must not be renamed, reordered or retyped.
+
+const INSCRIPTIONS_COLUMNS: &[ExportColumn] = &[
+    column("block_height", ColumnType::Int64, false),
+    column("inscription_id", ColumnType::Utf8, false),
+    column("inscription_number", ColumnType::Int64, false),
+    column("ordinal_number", ColumnType::Int64, false),
+    column("tx_id", ColumnType::Utf8, false),
+    column("tx_index", ColumnType::Int64, true),
+    column("satpoint", ColumnType::Utf8, true),
+    column("inscriber_address", ColumnType::Utf8, true),
+    column("output_value", ColumnType::Int64, true),
+    column("fee", ColumnType::Int64, true),
+    column("content_type", ColumnType::Utf8, true),
+    column("content_length", ColumnType::Int64, true),
+    column("curse_type", ColumnType::Utf8, true),
+];
+
+const LOCATIONS_COLUMNS: &[ExportColumn] = &[
+    column("block_height", ColumnType::Int64, false),
+    column("tx_index", ColumnType::Int64, false),
+    column("inscription_id", ColumnType::Utf8, false),
+    column("tx_id", ColumnType::Utf8, false),
+    column("vout", ColumnType::Int64, false),
+    column("offset", ColumnType::Int64, false),
+    column("satpoint", ColumnType::Utf8, false),
+];
+
+const TRANSFERS_COLUMNS: &[ExportColumn] = &[
+    column("block_height", ColumnType::Int64, false),
+    column("tx_index", ColumnType::Int64, false),
+    column("tx_id", ColumnType::Utf8, false),
+    column("inscription_id", ColumnType::Utf8, false),
+    column("satpoint_pre_transfer", ColumnType::Utf8, false),
+    column("satpoint_post_transfer", ColumnType::Utf8, false),
+    column("destination_type", ColumnType::Utf8, true),
+    column("address", ColumnType::Utf8, true),
+    column("script", ColumnType::Utf8, true),
+    column("output_value", ColumnType::Int64, true),
+];
+
+impl ExportTable {
+    pub fn columns(&self) -> &'static [ExportColumn] {
+        match self {
+            ExportTable::Inscriptions => INSCRIPTIONS_COLUMNS,
+            ExportTable::Locations => LOCATIONS_COLUMNS,
+            ExportTable::Transfers => TRANSFERS_COLUMNS,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportValue {
+    Int64(i64),
+    Utf8(String),
+    Null,
+}
+
+impl From<u64> for ExportValue {
+    fn from(value: u64) -> Self {
+        ExportValue::Int64(value as i64)
+    }
+}
+
+impl From<i64> for ExportValue {
+    fn from(value: i64) -> Self {
+        ExportValue::Int64(value)
+    }
+}
+
+impl From<usize> for ExportValue {
+    fn from(value: usize) -> Self {
+        ExportValue::Int64(value as i64)
+    }
+}
+
+impl From<String> for ExportValue {
+    fn from(value: String) -> Self {
+        ExportValue::Utf8(value)
+    }
+}
+
+impl<T: Into<ExportValue>> From<Option<T>> for ExportValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => ExportValue::Null,
+        }
+    }
+}
+
+/// One value per column of the table exported.
+pub type ExportRow = Vec<ExportValue>;
+
+pub trait ExportWriter {
+    fn write_batch(&mut self, rows: &[ExportRow]) -> Result<(), String>;
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+pub fn build_export_writer(
+    format: ExportFormat,
+    columns: &'static [ExportColumn],
+    output: Box<dyn Write + Send>,
+) -> Result<Box<dyn ExportWriter>, String> {
+    match format {
+        ExportFormat::Csv => Ok(Box::new(CsvWriter::new(columns, output)?)),
+        ExportFormat::Ndjson => Ok(Box::new(NdjsonWriter { columns, output })),
+        ExportFormat::Parquet => parquet::build_writer(columns, output),
+    }
+}
+
+struct CsvWriter {
+    output: Box<dyn Write + Send>,
+}
+
+impl CsvWriter {
+    fn new(
+        columns: &'static [ExportColumn],
+        mut output: Box<dyn Write + Send>,
+    ) -> Result<Self, String> {
+        let header = columns
+            .iter()
+            .map(|column| column.name)
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(output, "{header}").map_err(|e| e.to_string())?;
+        Ok(CsvWriter { output })
+    }
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl ExportWriter for CsvWriter {
+    fn write_batch(&mut self, rows: &[ExportRow]) -> Result<(), String> {
+        for row in rows.iter() {
+            let line = row
+                .iter()
+                .map(|value| match value {
+                    ExportValue::Int64(value) => value.to_string(),
+                    ExportValue::Utf8(value) => escape_csv_field(value),
+                    ExportValue::Null => String::new(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            writeln!(self.output, "{line}").map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.output.flush().map_err(|e| e.to_string())
+    }
+}
+
+struct NdjsonWriter {
+    columns: &'static [ExportColumn],
+    output: Box<dyn Write + Send>,
+}
+
+impl ExportWriter for NdjsonWriter {
+    fn write_batch(&mut self, rows: &[ExportRow]) -> Result<(), String> {
+        for row in rows.iter() {
+            let mut entry = Map::new();
+            for (column, value) in self.columns.iter().zip(row.iter()) {
+                let value = match value {
+                    ExportValue::Int64(value) => JsonValue::from(*value),
+                    ExportValue::Utf8(value) => JsonValue::from(value.clone()),
+                    ExportValue::Null => JsonValue::Null,
+                };
+                entry.insert(column.name.to_string(), value);
+            }
+            writeln!(self.output, "{}", JsonValue::Object(entry)).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        self.output.flush().map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportSummary {
+    pub rows: u64,
+    /// Height of the last block containing exported rows.
+    pub last_block_height: Option<u64>,
+    /// Rows of blocks indexed before reveal details, destinations and values were recorded,
+    /// exported with these columns null.
+    pub rows_without_details: u64,
+}
+
+/// Streams the rows of `table` between `start_block` and `end_block` (inclusive) into `writer`,
+/// `EXPORT_BATCH_BLOCKS` blocks at a time. Rows are ordered by block height: an export can be
+/// resumed from the block following the last block exported. Rows of blocks indexed before their
+/// details were recorded are exported with nulls, and counted in the summary.
+pub fn export_table(
+    table: ExportTable,
+    start_block: u64,
+    end_block: u64,
+    writer: &mut dyn ExportWriter,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<ExportSummary, String> {
+    let mut summary = ExportSummary::default();
+    let mut batch_start = start_block;
+    while batch_start <= end_block {
+        let batch_end = end_block.min(batch_start.saturating_add(EXPORT_BATCH_BLOCKS - 1));
+        let rows = match table {
+            ExportTable::Inscriptions => {
+                build_inscriptions_rows(batch_start, batch_end, &mut summary, db_conn, ctx)?
+            }
+            ExportTable::Locations => build_locations_rows(batch_start, batch_end, db_conn, ctx),
+            ExportTable::Transfers => {
+                build_transfers_rows(batch_start, batch_end, &mut summary, db_conn, ctx)?
+            }
+        };
+        if let Some(ExportValue::Int64(block_height)) = rows.last().and_then(|row| row.first()) {
+            summary.last_block_height = Some(*block_height as u64);
+        }
+        if !rows.is_empty() {
+            writer.write_batch(&rows)?;
+            summary.rows += rows.len() as u64;
+        }
+        ctx.try_log(|logger| {
+            info!(
+                logger,
+                "Exported blocks #{batch_start} to #{batch_end} ({} rows)", summary.rows
+            )
+        });
+        if batch_end == u64::MAX {
+            break;
+        }
+        batch_start = batch_end + 1;
+    }
+    Ok(summary)
+}
+
+fn build_inscriptions_rows(
+    start_block: u64,
+    end_block: u64,
+    summary: &mut ExportSummary,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Vec<ExportRow>, String> {
+    let mut rows = vec![];
+    for reveal in find_indexed_reveals_in_block_range(start_block, end_block, db_conn, ctx)? {
+        let tx_id = match reveal.inscription_id.rsplit_once('i') {
+            Some((tx_id, _)) => tx_id.to_string(),
+            None => return Err(format!("invalid inscription id {}", reveal.inscription_id)),
+        };
+        let mut row: ExportRow = vec![
+            reveal.block_height.into(),
+            reveal.inscription_id.into(),
+            reveal.inscription_number.into(),
+            reveal.ordinal_number.into(),
+            tx_id.into(),
+            reveal.genesis.tx_index.into(),
+            reveal.genesis.satpoint.to_string().into(),
+        ];
+        match reveal.data {
+            Some(data) => {
+                let curse_type = match data.curse_type.as_ref() {
+                    Some(curse_type) => {
+                        Some(serde_json::to_string(curse_type).map_err(|e| e.to_string())?)
+                    }
+                    None => None,
+                };
+                let details: [ExportValue; 6] = [
+                    data.inscriber_address.into(),
+                    data.inscription_output_value.into(),
+                    data.inscription_fee.into(),
+                    data.content_type.into(),
+                    data.content_length.into(),
+                    curse_type.into(),
+                ];
+                row.extend(details);
+            }
+            None => {
+                row.resize(INSCRIPTIONS_COLUMNS.len(), ExportValue::Null);
+                summary.rows_without_details += 1;
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn build_locations_rows(
+    start_block: u64,
+    end_block: u64,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<ExportRow> {
+    find_all_locations_in_block_range(start_block, end_block, db_conn, ctx)
+        .into_iter()
+        .map(|(block_height, inscription_id, transfer_data)| {
+            let satpoint = transfer_data.satpoint;
+            vec![
+                block_height.into(),
+                transfer_data.tx_index.into(),
+                inscription_id.into(),
+                satpoint.outpoint.txid.to_string().into(),
+                (satpoint.outpoint.vout as u64).into(),
+                satpoint.offset.into(),
+                satpoint.to_string().into(),
+            ]
+        })
+        .collect()
+}
+
+fn build_transfers_rows(
+    start_block: u64,
+    end_block: u64,
+    summary: &mut ExportSummary,
+    db_conn: &Connection,
+    ctx: &Context,
+) -> Result<Vec<ExportRow>, String> {
+    let mut rows = vec![];
+    for (inscription_id, transfer) in
+        find_indexed_transfers_in_block_range(start_block, end_block, db_conn, ctx)?
+    {
+        let (destination_type, address, script) = match transfer.destination {
+            Some(OrdinalInscriptionTransferDestination::Transferred(address)) => {
+                (Some("transferred"), Some(address), None)
+            }
+            Some(OrdinalInscriptionTransferDestination::SpentInFees) => {
+                (Some("spent_in_fees"), None, None)
+            }
+            Some(OrdinalInscriptionTransferDestination::Burnt(script)) => {
+                (Some("burnt"), None, Some(script))
+            }
+            None => {
+                summary.rows_without_details += 1;
+                (None, None, None)
+            }
+        };
+        rows.push(vec![
+            transfer.block_height.into(),
+            transfer.tx_index.into(),
+            transfer.tx_id.into(),
+            inscription_id.into(),
+            transfer.satpoint_pre_transfer.into(),
+            transfer.satpoint_post_transfer.into(),
+            destination_type.map(|d| d.to_string()).into(),
+            address.into(),
+            script.into(),
+            transfer.post_transfer_output_value.into(),
+        ]);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pipeline::download_and_pipeline_blocks;
+    use crate::core::pipeline::processors::start_inscription_indexing_processor;
+    use crate::core::should_sync_ordhook_db;
+    use crate::db::{find_initial_inscription_transfer_data, open_readwrite_ordhook_db_conn};
+    use crate::utils::bitcoind_mock::MockBitcoind;
+    use crate::utils::regtest_fixtures::regtest_corpus;
+
+    #[test]
+    fn parses_tables_and_formats() {
+        assert_eq!(
+            "transfers".parse::<ExportTable>(),
+            Ok(ExportTable::Transfers)
+        );
+        assert!("transactions".parse::<ExportTable>().is_err());
+        assert_eq!("csv".parse::<ExportFormat>(), Ok(ExportFormat::Csv));
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(escape_csv_field("text/plain"), "text/plain");
+        assert_eq!(
+            escape_csv_field("text/html;charset=utf-8,foo"),
+            "\"text/html;charset=utf-8,foo\""
+        );
+        assert_eq!(escape_csv_field("say \"gm\""), "\"say \"\"gm\"\"\"");
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_csv_and_ndjson() {
+        let rows = vec![vec![
+            ExportValue::Int64(767430),
+            ExportValue::Utf8("a,b".into()),
+            ExportValue::Null,
+        ]];
+        const COLUMNS: &[ExportColumn] = &[
+            column("block_height", ColumnType::Int64, false),
+            column("name", ColumnType::Utf8, false),
+            column("value", ColumnType::Int64, true),
+        ];
+
+        let buffer = SharedBuffer::default();
+        let mut writer =
+            build_export_writer(ExportFormat::Csv, COLUMNS, Box::new(buffer.clone())).unwrap();
+        writer.write_batch(&rows).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "block_height,name,value\n767430,\"a,b\",\n"
+        );
+
+        let buffer = SharedBuffer::default();
+        let mut writer =
+            build_export_writer(ExportFormat::Ndjson, COLUMNS, Box::new(buffer.clone())).unwrap();
+        writer.write_batch(&rows).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap(),
+            "{\"block_height\":767430,\"name\":\"a,b\",\"value\":null}\n"
+        );
+    }
+
+    fn export_ndjson(
+        table: ExportTable,
+        end_block: u64,
+        db_conn: &Connection,
+        ctx: &Context,
+    ) -> Result<Vec<JsonValue>, String> {
+        let buffer = SharedBuffer::default();
+        let mut writer = build_export_writer(
+            ExportFormat::Ndjson,
+            table.columns(),
+            Box::new(buffer.clone()),
+        )?;
+        export_table(table, 0, end_block, writer.as_mut(), db_conn, ctx)?;
+        writer.finish()?;
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        Ok(output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn exports_rows_from_the_index_tables() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let config = bitcoind.ordhook_config();
+        let (start_block, end_block) = should_sync_ordhook_db(&config, &ctx).unwrap().unwrap();
+        let processor = start_inscription_indexing_processor(&config, &ctx, None);
+        hiro_system_kit::nestable_block_on(download_and_pipeline_blocks(
+            &config,
+            (start_block..=end_block).collect(),
+            start_block,
+            Some(&processor),
+            &ctx,
+        ))
+        .unwrap();
+        let _ = processor.thread_handle.join();
+        let conn = open_readwrite_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+
+        let inscriptions =
+            export_ndjson(ExportTable::Inscriptions, end_block, &conn, &ctx).unwrap();
+        let inscription = inscriptions
+            .iter()
+            .find(|row| row["inscription_id"] == corpus.inscription.as_str())
+            .unwrap();
+        let genesis = find_initial_inscription_transfer_data(&corpus.inscription, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            inscription["satpoint"],
+            genesis.satpoint.to_string().as_str()
+        );
+        assert_eq!(inscription["content_type"], "text/plain;charset=utf-8");
+
+        let transfers = export_ndjson(ExportTable::Transfers, end_block, &conn, &ctx).unwrap();
+        let transfer = transfers
+            .iter()
+            .find(|row| row["inscription_id"] == corpus.inscription.as_str())
+            .unwrap();
+        assert_eq!(transfer["block_height"], 5);
+        assert_eq!(transfer["tx_id"], corpus.transfer_txid.as_str());
+        assert_eq!(
+            transfer["satpoint_pre_transfer"],
+            genesis.satpoint.to_string().as_str()
+        );
+        assert_eq!(transfer["destination_type"], "transferred");
+
+        // Blocks indexed before details were recorded are exported with nulls
+        conn.execute(
+            "UPDATE inscriptions SET content_type = NULL, content_length = NULL, inscriber_address = NULL, output_value = NULL, fee = NULL, curse_type = NULL, transfers_pre_inscription = NULL",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE locations SET destination = NULL, output_value = NULL",
+            [],
+        )
+        .unwrap();
+        conn.execute("UPDATE sequence_metadata SET block_hash = NULL", [])
+            .unwrap();
+        let old_inscriptions =
+            export_ndjson(ExportTable::Inscriptions, end_block, &conn, &ctx).unwrap();
+        assert_eq!(old_inscriptions.len(), inscriptions.len());
+        let old_inscription = old_inscriptions
+            .iter()
+            .find(|row| row["inscription_id"] == corpus.inscription.as_str())
+            .unwrap();
+        assert_eq!(old_inscription["satpoint"], inscription["satpoint"]);
+        assert!(old_inscription["content_type"].is_null());
+        let old_transfers = export_ndjson(ExportTable::Transfers, end_block, &conn, &ctx).unwrap();
+        assert_eq!(old_transfers.len(), transfers.len());
+        assert!(old_transfers
+            .iter()
+            .all(|row| row["destination_type"].is_null() && row["output_value"].is_null()));
+
+        let buffer = SharedBuffer::default();
+        let mut writer = build_export_writer(
+            ExportFormat::Ndjson,
+            ExportTable::Transfers.columns(),
+            Box::new(buffer),
+        )
+        .unwrap();
+        let summary = export_table(
+            ExportTable::Transfers,
+            0,
+            end_block,
+            writer.as_mut(),
+            &conn,
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(summary.rows_without_details, transfers.len() as u64);
+
+        let locations = export_ndjson(ExportTable::Locations, end_block, &conn, &ctx).unwrap();
+        assert!(locations.len() > transfers.len());
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::Write;
+
+use super::{ExportColumn, ExportWriter};
+
+#[cfg(feature = "parquet")]
+mod writer {
+    use std::{io::Write, sync::Arc};
+
+    use ::parquet::{
+        basic::Compression,
+        data_type::{ByteArray, ByteArrayType, Int64Type},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+
+    use super::super::{ColumnType, ExportColumn, ExportRow, ExportValue, ExportWriter};
+
+    pub struct ParquetWriter {
+        columns: &'static [ExportColumn],
+        writer: SerializedFileWriter<Box<dyn Write + Send>>,
+    }
+
+    fn build_message_type(columns: &[ExportColumn]) -> String {
+        let fields = columns
+            .iter()
+            .map(|column| {
+                let repetition = if column.nullable {
+                    "OPTIONAL"
+                } else {
+                    "REQUIRED"
+                };
+                match column.column_type {
+                    ColumnType::Int64 => format!("{repetition} INT64 {};", column.name),
+                    ColumnType::Utf8 => format!("{repetition} BYTE_ARRAY {} (UTF8);", column.name),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("message ordhook_export {{ {fields} }}")
+    }
+
+    impl ExportWriter for ParquetWriter {
+        // Each batch is written as its own row group.
+        fn write_batch(&mut self, rows: &[ExportRow]) -> Result<(), String> {
+            let mut row_group = self.writer.next_row_group().map_err(|e| e.to_string())?;
+            let mut index = 0;
+            while let Some(mut column_writer) =
+                row_group.next_column().map_err(|e| e.to_string())?
+            {
+                let column = &self.columns[index];
+                let def_levels = rows
+                    .iter()
+                    .map(|row| match row[index] {
+                        ExportValue::Null => 0,
+                        _ => 1,
+                    })
+                    .collect::<Vec<i16>>();
+                let def_levels = if column.nullable {
+                    Some(&def_levels[..])
+                } else {
+                    None
+                };
+                match column.column_type {
+                    ColumnType::Int64 => {
+                        let values = rows
+                            .iter()
+                            .filter_map(|row| match &row[index] {
+                                ExportValue::Int64(value) => Some(*value),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>();
+                        column_writer
+                            .typed::<Int64Type>()
+                            .write_batch(&values, def_levels, None)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    ColumnType::Utf8 => {
+                        let values = rows
+                            .iter()
+                            .filter_map(|row| match &row[index] {
+                                ExportValue::Utf8(value) => Some(ByteArray::from(value.as_str())),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>();
+                        column_writer
+                            .typed::<ByteArrayType>()
+                            .write_batch(&values, def_levels, None)
+                            .map_err(|e| e.to_string())?;
+                    }
+                }
+                column_writer.close().map_err(|e| e.to_string())?;
+                index += 1;
+            }
+            row_group.close().map_err(|e| e.to_string())?;
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> Result<(), String> {
+            self.writer.close().map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+
+    pub fn build_writer(
+        columns: &'static [ExportColumn],
+        output: Box<dyn Write + Send>,
+    ) -> Result<Box<dyn ExportWriter>, String> {
+        let schema = parse_message_type(&build_message_type(columns))
+            .map_err(|e| format!("unable to build parquet schema: {}", e.to_string()))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = SerializedFileWriter::new(output, Arc::new(schema), Arc::new(properties))
+            .map_err(|e| e.to_string())?;
+        Ok(Box::new(ParquetWriter { columns, writer }))
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub fn build_writer(
+    columns: &'static [ExportColumn],
+    output: Box<dyn Write + Send>,
+) -> Result<Box<dyn ExportWriter>, String> {
+    writer::build_writer(columns, output)
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn build_writer(
+    _columns: &'static [ExportColumn],
+    _output: Box<dyn Write + Send>,
+) -> Result<Box<dyn ExportWriter>, String> {
+    Err(format!("ordhook was built without the `parquet` feature"))
+}
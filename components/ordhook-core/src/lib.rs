@@ -1,6 +1,8 @@
+#[cfg(feature = "indexer")]
 #[macro_use]
 extern crate rocket;
 
+#[cfg(feature = "indexer")]
 #[macro_use]
 extern crate hiro_system_kit;
 
@@ -9,14 +11,29 @@ extern crate serde_derive;
 
 extern crate serde;
 
+pub extern crate bitcoin;
+#[cfg(feature = "indexer")]
 pub extern crate chainhook_sdk;
+pub extern crate chainhook_types;
 pub extern crate hex;
 
+// Without the `indexer` feature, only the inscription parser, the sat math and the `ord` types
+// are built: they compile to wasm32-unknown-unknown.
+#[cfg(feature = "indexer")]
 pub mod config;
 pub mod core;
+#[cfg(feature = "indexer")]
 pub mod db;
+#[cfg(feature = "indexer")]
 pub mod download;
+#[cfg(feature = "indexer")]
+pub mod export;
 pub mod ord;
+#[cfg(feature = "indexer")]
 pub mod scan;
+#[cfg(feature = "indexer")]
 pub mod service;
+#[cfg(feature = "indexer")]
+pub mod sinks;
+#[cfg(feature = "indexer")]
 pub mod utils;
@@ -3,7 +3,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use chainhook_sdk::bitcoincore_rpc::bitcoin::{self, Address, Block, Network, Script};
+use bitcoin::{Address, Block, Network, Script};
 
 use super::*;
 
@@ -0,0 +1,55 @@
+use std::fmt::{Display, Formatter};
+
+use super::{height::Height, sat::Sat};
+
+#[derive(PartialEq, Debug)]
+pub struct Decimal {
+    pub height: Height,
+    pub offset: u64,
+}
+
+impl From<Sat> for Decimal {
+    fn from(sat: Sat) -> Self {
+        Self {
+            height: sat.height(),
+            offset: sat.third(),
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}", self.height.n(), self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal() {
+        assert_eq!(
+            Sat(0).decimal(),
+            Decimal {
+                height: Height(0),
+                offset: 0
+            }
+        );
+        assert_eq!(
+            Sat(1).decimal(),
+            Decimal {
+                height: Height(0),
+                offset: 1
+            }
+        );
+        assert_eq!(
+            Sat(2099999997689999).decimal(),
+            Decimal {
+                height: Height(6929999),
+                offset: 0
+            }
+        );
+        assert_eq!(Sat(2099999997689999).decimal().to_string(), "6929999.0");
+    }
+}
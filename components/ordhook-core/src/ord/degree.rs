@@ -0,0 +1,83 @@
+use std::fmt::{Display, Formatter};
+
+use super::{sat::Sat, *};
+
+#[derive(PartialEq, Debug)]
+pub struct Degree {
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+    pub third: u64,
+}
+
+impl Display for Degree {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}°{}′{}″{}‴",
+            self.hour, self.minute, self.second, self.third
+        )
+    }
+}
+
+impl From<Sat> for Degree {
+    fn from(sat: Sat) -> Self {
+        let height = sat.height().n();
+        Degree {
+            hour: height / (CYCLE_EPOCHS * SUBSIDY_HALVING_INTERVAL),
+            minute: height % SUBSIDY_HALVING_INTERVAL,
+            second: height % DIFFCHANGE_INTERVAL,
+            third: sat.third(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(sat: u64, hour: u64, minute: u64, second: u64, third: u64) {
+        assert_eq!(
+            Degree::from(Sat(sat)),
+            Degree {
+                hour,
+                minute,
+                second,
+                third,
+            }
+        );
+    }
+
+    #[test]
+    fn from() {
+        case(0, 0, 0, 0, 0);
+        case(1, 0, 0, 0, 1);
+        case(5_000_000_000, 0, 1, 1, 0);
+        case(
+            5_000_000_000 * DIFFCHANGE_INTERVAL,
+            0,
+            DIFFCHANGE_INTERVAL,
+            0,
+            0,
+        );
+        case(5_000_000_000 * SUBSIDY_HALVING_INTERVAL, 0, 0, 336, 0);
+        case(
+            (5_000_000_000
+                + 2_500_000_000
+                + 1_250_000_000
+                + 625_000_000
+                + 312_500_000
+                + 156_250_000)
+                * SUBSIDY_HALVING_INTERVAL,
+            1,
+            0,
+            0,
+            0,
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Degree::from(Sat(1)).to_string(), "0°0′0″1‴");
+    }
+}
@@ -1,12 +1,12 @@
-use chainhook_sdk::bitcoincore_rpc::bitcoin::blockdata::constants::COIN_VALUE;
+use bitcoin::blockdata::constants::COIN_VALUE;
 
 use super::{height::Height, sat::Sat, SUBSIDY_HALVING_INTERVAL};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, PartialOrd)]
-pub(crate) struct Epoch(pub(crate) u64);
+pub struct Epoch(pub u64);
 
 impl Epoch {
-    pub(crate) const STARTING_SATS: [Sat; 34] = [
+    pub const STARTING_SATS: [Sat; 34] = [
         Sat(0),
         Sat(1050000000000000),
         Sat(1575000000000000),
@@ -42,9 +42,9 @@ impl Epoch {
         Sat(2099999997480000),
         Sat(Sat::SUPPLY),
     ];
-    pub(crate) const FIRST_POST_SUBSIDY: Epoch = Self(33);
+    pub const FIRST_POST_SUBSIDY: Epoch = Self(33);
 
-    pub(crate) fn subsidy(self) -> u64 {
+    pub fn subsidy(self) -> u64 {
         if self < Self::FIRST_POST_SUBSIDY {
             (50 * COIN_VALUE) >> self.0
         } else {
@@ -52,13 +52,13 @@ impl Epoch {
         }
     }
 
-    pub(crate) fn starting_sat(self) -> Sat {
+    pub fn starting_sat(self) -> Sat {
         *Self::STARTING_SATS
             .get(usize::try_from(self.0).unwrap())
             .unwrap_or_else(|| Self::STARTING_SATS.last().unwrap())
     }
 
-    pub(crate) fn starting_height(self) -> Height {
+    pub fn starting_height(self) -> Height {
         Height(self.0 * SUBSIDY_HALVING_INTERVAL)
     }
 }
@@ -3,15 +3,15 @@ use std::{
     str::FromStr,
 };
 
-use chainhook_sdk::bitcoincore_rpc::bitcoin::Txid;
+use bitcoin::Txid;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::deserialize_from_str::DeserializeFromStr;
 
 #[derive(Debug, PartialEq, Copy, Clone, Hash, Eq)]
 pub struct InscriptionId {
-    pub(crate) txid: Txid,
-    pub(crate) index: u32,
+    pub txid: Txid,
+    pub index: u32,
 }
 
 impl<'de> Deserialize<'de> for InscriptionId {
@@ -43,7 +43,7 @@ pub enum ParseError {
     Character(char),
     Length(usize),
     Separator(char),
-    Txid(chainhook_sdk::bitcoincore_rpc::bitcoin::hashes::hex::Error),
+    Txid(bitcoin::hashes::hex::Error),
     Index(std::num::ParseIntError),
 }
 
@@ -3,18 +3,20 @@
 
 type Result<T = (), E = anyhow::Error> = std::result::Result<T, E>;
 
-use chainhook_sdk::types::BitcoinNetwork;
+use chainhook_types::BitcoinNetwork;
 
 pub mod chain;
+pub mod decimal;
+pub mod degree;
 pub mod deserialize_from_str;
 pub mod epoch;
 pub mod height;
 pub mod inscription_id;
+pub mod rarity;
 pub mod sat;
 pub mod sat_point;
 
-const DIFFCHANGE_INTERVAL: u64 =
-    chainhook_sdk::bitcoincore_rpc::bitcoin::blockdata::constants::DIFFCHANGE_INTERVAL as u64;
+const DIFFCHANGE_INTERVAL: u64 = bitcoin::blockdata::constants::DIFFCHANGE_INTERVAL as u64;
 const SUBSIDY_HALVING_INTERVAL: u64 =
-    chainhook_sdk::bitcoincore_rpc::bitcoin::blockdata::constants::SUBSIDY_HALVING_INTERVAL as u64;
+    bitcoin::blockdata::constants::SUBSIDY_HALVING_INTERVAL as u64;
 const CYCLE_EPOCHS: u64 = 6;
@@ -0,0 +1,126 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use super::{sat::Sat, *};
+
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+    Mythic,
+}
+
+impl Display for Rarity {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Common => "common",
+                Self::Uncommon => "uncommon",
+                Self::Rare => "rare",
+                Self::Epic => "epic",
+                Self::Legendary => "legendary",
+                Self::Mythic => "mythic",
+            }
+        )
+    }
+}
+
+impl From<Sat> for Rarity {
+    fn from(sat: Sat) -> Self {
+        if sat.is_common() {
+            return Self::Common;
+        }
+
+        let height = sat.height().n();
+        let hour = height / (CYCLE_EPOCHS * SUBSIDY_HALVING_INTERVAL);
+        let minute = height % SUBSIDY_HALVING_INTERVAL;
+        let second = height % DIFFCHANGE_INTERVAL;
+
+        if hour == 0 && minute == 0 && second == 0 {
+            Self::Mythic
+        } else if minute == 0 && second == 0 {
+            Self::Legendary
+        } else if minute == 0 {
+            Self::Epic
+        } else if second == 0 {
+            Self::Rare
+        } else {
+            Self::Uncommon
+        }
+    }
+}
+
+impl FromStr for Rarity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "common" => Ok(Self::Common),
+            "uncommon" => Ok(Self::Uncommon),
+            "rare" => Ok(Self::Rare),
+            "epic" => Ok(Self::Epic),
+            "legendary" => Ok(Self::Legendary),
+            "mythic" => Ok(Self::Mythic),
+            _ => Err(format!("invalid rarity: {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::blockdata::constants::COIN_VALUE;
+
+    use super::*;
+
+    #[test]
+    fn rarity() {
+        assert_eq!(Sat(0).rarity(), Rarity::Mythic);
+        assert_eq!(Sat(1).rarity(), Rarity::Common);
+
+        assert_eq!(Sat(50 * COIN_VALUE - 1).rarity(), Rarity::Common);
+        assert_eq!(Sat(50 * COIN_VALUE).rarity(), Rarity::Uncommon);
+        assert_eq!(Sat(50 * COIN_VALUE + 1).rarity(), Rarity::Common);
+
+        assert_eq!(
+            Sat(50 * COIN_VALUE * DIFFCHANGE_INTERVAL - 1).rarity(),
+            Rarity::Common
+        );
+        assert_eq!(
+            Sat(50 * COIN_VALUE * DIFFCHANGE_INTERVAL).rarity(),
+            Rarity::Rare
+        );
+
+        assert_eq!(Sat(2067187500000000 - 1).rarity(), Rarity::Common);
+        assert_eq!(Sat(2067187500000000).rarity(), Rarity::Legendary);
+        assert_eq!(Sat(2067187500000000 + 1).rarity(), Rarity::Common);
+
+        assert_eq!(Sat(1050000000000000).rarity(), Rarity::Epic);
+    }
+
+    #[test]
+    fn from_str_and_deserialize_ok() {
+        for rarity in [
+            Rarity::Common,
+            Rarity::Uncommon,
+            Rarity::Rare,
+            Rarity::Epic,
+            Rarity::Legendary,
+            Rarity::Mythic,
+        ] {
+            assert_eq!(rarity.to_string().parse::<Rarity>().unwrap(), rarity);
+            assert_eq!(
+                serde_json::from_str::<Rarity>(&format!("\"{rarity}\"")).unwrap(),
+                rarity
+            );
+        }
+        assert!("foo".parse::<Rarity>().is_err());
+    }
+}
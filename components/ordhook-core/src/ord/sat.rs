@@ -1,43 +1,53 @@
-use std::ops::{Add, AddAssign};
+use std::{
+    fmt::{Display, Formatter},
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
 
-use super::{epoch::Epoch, height::Height, *};
+use anyhow::{anyhow, bail};
+
+use super::{decimal::Decimal, degree::Degree, epoch::Epoch, height::Height, rarity::Rarity, *};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Ord, PartialOrd, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct Sat(pub u64);
 
 impl Sat {
-    pub(crate) const LAST: Self = Self(Self::SUPPLY - 1);
-    pub(crate) const SUPPLY: u64 = 2099999997690000;
+    pub const LAST: Self = Self(Self::SUPPLY - 1);
+    pub const SUPPLY: u64 = 2099999997690000;
 
-    pub(crate) fn n(self) -> u64 {
+    pub fn n(self) -> u64 {
         self.0
     }
 
-    pub(crate) fn height(self) -> Height {
+    pub fn height(self) -> Height {
         self.epoch().starting_height() + self.epoch_position() / self.epoch().subsidy()
     }
 
-    pub(crate) fn cycle(self) -> u64 {
+    pub fn cycle(self) -> u64 {
         Epoch::from(self).0 / CYCLE_EPOCHS
     }
 
-    pub(crate) fn percentile(self) -> String {
+    pub fn percentile(self) -> String {
         format!("{}%", (self.0 as f64 / Self::LAST.0 as f64) * 100.0)
     }
 
-    pub(crate) fn epoch(self) -> Epoch {
+    pub fn epoch(self) -> Epoch {
         self.into()
     }
 
-    pub(crate) fn third(self) -> u64 {
+    pub fn third(self) -> u64 {
         self.epoch_position() % self.epoch().subsidy()
     }
 
-    pub(crate) fn epoch_position(self) -> u64 {
+    pub fn epoch_position(self) -> u64 {
         self.0 - self.epoch().starting_sat().0
     }
 
+    pub fn rarity(self) -> Rarity {
+        self.into()
+    }
+
     /// `Sat::rarity` is expensive and is called frequently when indexing.
     /// Sat::is_common only checks if self is `Rarity::Common` but is
     /// much faster.
@@ -46,7 +56,7 @@ impl Sat {
         (self.0 - epoch.starting_sat().0) % epoch.subsidy() != 0
     }
 
-    pub(crate) fn name(self) -> String {
+    pub fn name(self) -> String {
         let mut x = Self::SUPPLY - self.0;
         let mut name = String::new();
         while x > 0 {
@@ -60,6 +70,159 @@ impl Sat {
         }
         name.chars().rev().collect()
     }
+
+    pub fn degree(self) -> Degree {
+        self.into()
+    }
+
+    pub fn decimal(self) -> Decimal {
+        self.into()
+    }
+
+    fn from_name(s: &str) -> Result<Self> {
+        let mut x = 0;
+        for c in s.chars() {
+            match c {
+                'a'..='z' => {
+                    x = x * 26 + c as u64 - 'a' as u64 + 1;
+                    if x > Self::SUPPLY {
+                        bail!("sat name out of range");
+                    }
+                }
+                _ => bail!("invalid character in sat name: {c}"),
+            }
+        }
+        Ok(Sat(Self::SUPPLY - x))
+    }
+
+    fn from_degree(degree: &str) -> Result<Self> {
+        let (cycle_number, rest) = degree
+            .split_once('°')
+            .ok_or_else(|| anyhow!("missing degree symbol"))?;
+        let cycle_number = cycle_number.parse::<u64>()?;
+
+        let (epoch_offset, rest) = rest
+            .split_once('′')
+            .ok_or_else(|| anyhow!("missing minute symbol"))?;
+        let epoch_offset = epoch_offset.parse::<u64>()?;
+        if epoch_offset >= SUBSIDY_HALVING_INTERVAL {
+            bail!("invalid epoch offset");
+        }
+
+        let (period_offset, rest) = rest
+            .split_once('″')
+            .ok_or_else(|| anyhow!("missing second symbol"))?;
+        let period_offset = period_offset.parse::<u64>()?;
+        if period_offset >= DIFFCHANGE_INTERVAL {
+            bail!("invalid period offset");
+        }
+
+        let cycle_start_epoch = cycle_number
+            .checked_mul(CYCLE_EPOCHS)
+            .ok_or_else(|| anyhow!("invalid cycle number"))?;
+
+        const HALVING_INCREMENT: u64 = SUBSIDY_HALVING_INTERVAL % DIFFCHANGE_INTERVAL;
+
+        // For valid degrees the relationship between epoch_offset and period_offset
+        // will increment by 336 every halving.
+        let relationship = period_offset + SUBSIDY_HALVING_INTERVAL * CYCLE_EPOCHS - epoch_offset;
+
+        if relationship % HALVING_INCREMENT != 0 {
+            bail!("relationship between epoch offset and period offset must be multiple of 336");
+        }
+
+        let epochs_since_cycle_start = relationship % DIFFCHANGE_INTERVAL / HALVING_INCREMENT;
+
+        let height = cycle_start_epoch
+            .checked_add(epochs_since_cycle_start)
+            .and_then(|epoch| epoch.checked_mul(SUBSIDY_HALVING_INTERVAL))
+            .and_then(|height| height.checked_add(epoch_offset))
+            .map(Height)
+            .ok_or_else(|| anyhow!("invalid cycle number"))?;
+
+        let (block_offset, rest) = match rest.split_once('‴') {
+            Some((block_offset, rest)) => (block_offset.parse::<u64>()?, rest),
+            None => (0, rest),
+        };
+
+        if !rest.is_empty() {
+            bail!("trailing characters");
+        }
+
+        if block_offset >= height.subsidy() {
+            bail!("invalid block offset");
+        }
+
+        Ok(height.starting_sat() + block_offset)
+    }
+
+    fn from_decimal(decimal: &str) -> Result<Self> {
+        let (height, offset) = decimal
+            .split_once('.')
+            .ok_or_else(|| anyhow!("missing period"))?;
+        let height = Height(height.parse()?);
+        let offset = offset.parse::<u64>()?;
+
+        if offset >= height.subsidy() {
+            bail!("invalid block offset");
+        }
+
+        Ok(height.starting_sat() + offset)
+    }
+
+    fn from_percentile(percentile: &str) -> Result<Self> {
+        let Some(percentile) = percentile.strip_suffix('%') else {
+            bail!("invalid percentile: {percentile}");
+        };
+
+        let percentile = percentile.parse::<f64>()?;
+
+        if percentile < 0.0 {
+            bail!("invalid percentile: {percentile}");
+        }
+
+        let last = Sat::LAST.n() as f64;
+
+        let n = (percentile / 100.0 * last).round();
+
+        if n > last {
+            bail!("invalid percentile: {percentile}");
+        }
+
+        Ok(Sat(n as u64))
+    }
+}
+
+impl Display for Sat {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses the notations of ordinal theory: integer (`2099994106992659`), decimal
+/// (`3891094.16797`), degree (`3°111094′214″16797‴`), name (`satoshi`) and percentile
+/// (`99.99971949060254%`).
+impl FromStr for Sat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.chars().any(|c| c.is_ascii_lowercase()) {
+            Self::from_name(s)
+        } else if s.contains('°') {
+            Self::from_degree(s)
+        } else if s.contains('%') {
+            Self::from_percentile(s)
+        } else if s.contains('.') {
+            Self::from_decimal(s)
+        } else {
+            let sat = Self(s.parse()?);
+            if sat > Self::LAST {
+                Err(anyhow!("invalid sat"))
+            } else {
+                Ok(sat)
+            }
+        }
+    }
 }
 
 impl PartialEq<u64> for Sat {
@@ -90,7 +253,7 @@ impl AddAssign<u64> for Sat {
 
 #[cfg(test)]
 mod tests {
-    use chainhook_sdk::bitcoincore_rpc::bitcoin::blockdata::constants::COIN_VALUE;
+    use bitcoin::blockdata::constants::COIN_VALUE;
 
     use super::*;
 
@@ -205,4 +368,61 @@ mod tests {
         assert_eq!(Sat(Sat::LAST.n() / 2).percentile(), "49.99999999999998%");
         assert_eq!(Sat::LAST.percentile(), "100%");
     }
+
+    #[test]
+    fn from_str_decimal() {
+        assert_eq!("0".parse::<Sat>().unwrap(), 0);
+        assert_eq!("2099999997689999".parse::<Sat>().unwrap(), 2099999997689999);
+        assert!("2099999997690000".parse::<Sat>().is_err());
+        assert_eq!("0.0".parse::<Sat>().unwrap(), 0);
+        assert_eq!("0.1".parse::<Sat>().unwrap(), 1);
+        assert_eq!("1.0".parse::<Sat>().unwrap(), 50 * COIN_VALUE);
+        assert_eq!("6929999.0".parse::<Sat>().unwrap(), 2099999997689999);
+        assert!("0.5000000000".parse::<Sat>().is_err());
+        assert!("6930000.0".parse::<Sat>().is_err());
+    }
+
+    #[test]
+    fn from_str_degree() {
+        assert_eq!("0°0′0″0‴".parse::<Sat>().unwrap(), 0);
+        assert_eq!("0°0′0″".parse::<Sat>().unwrap(), 0);
+        assert_eq!("0°0′0″1‴".parse::<Sat>().unwrap(), 1);
+        assert_eq!("0°2015′2015″0‴".parse::<Sat>().unwrap(), 10075000000000);
+        assert_eq!("0°2016′0″0‴".parse::<Sat>().unwrap(), 10080000000000);
+        assert_eq!("0°0′336″0‴".parse::<Sat>().unwrap(), 1050000000000000);
+        assert_eq!("1°0′0″0‴".parse::<Sat>().unwrap(), 2067187500000000);
+        assert_eq!("5°0′0″0‴".parse::<Sat>().unwrap(), 2099999996220000);
+        assert!("0°0′0″5000000000‴".parse::<Sat>().is_err());
+        assert!("0°1′0″0‴".parse::<Sat>().is_err());
+        assert!("0°0′0″0‴foo".parse::<Sat>().is_err());
+        assert!("18446744073709551615°0′0″0‴".parse::<Sat>().is_err());
+        assert!("3074457345618258602°0′0″0‴".parse::<Sat>().is_err());
+    }
+
+    #[test]
+    fn from_str_name() {
+        assert_eq!("nvtdijuwxlp".parse::<Sat>().unwrap(), 0);
+        assert_eq!("a".parse::<Sat>().unwrap(), 2099999997689999);
+        assert!("nvtdijuwxlr".parse::<Sat>().is_err());
+        assert!("A".parse::<Sat>().is_err());
+    }
+
+    #[test]
+    fn from_str_percentile() {
+        assert_eq!("0%".parse::<Sat>().unwrap(), 0);
+        assert_eq!("100%".parse::<Sat>().unwrap(), Sat::LAST);
+        assert_eq!(Sat::LAST.percentile().parse::<Sat>().unwrap(), Sat::LAST);
+        assert!("101%".parse::<Sat>().is_err());
+        assert!("-1%".parse::<Sat>().is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        for sat in [0, 1, 50 * COIN_VALUE, 1050000000000000, Sat::LAST.n()] {
+            let sat = Sat(sat);
+            assert_eq!(sat.name().parse::<Sat>().unwrap(), sat);
+            assert_eq!(sat.degree().to_string().parse::<Sat>().unwrap(), sat);
+            assert_eq!(sat.decimal().to_string().parse::<Sat>().unwrap(), sat);
+        }
+    }
 }
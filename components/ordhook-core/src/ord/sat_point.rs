@@ -4,19 +4,63 @@ use std::{
     str::FromStr,
 };
 
-use chainhook_sdk::bitcoincore_rpc::bitcoin::{
-    self,
-    consensus::{Decodable, Encodable},
-    OutPoint,
+use bitcoin::{
+    consensus::{
+        encode::{deserialize, serialize},
+        Decodable, Encodable,
+    },
+    hashes::hex::FromHex,
+    OutPoint, Txid,
 };
+use chainhook_types::TransactionIdentifier;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{deserialize_from_str::DeserializeFromStr, Result};
 
 #[derive(Debug, PartialEq, Copy, Clone, Eq, PartialOrd, Ord)]
 pub struct SatPoint {
-    pub(crate) outpoint: OutPoint,
-    pub(crate) offset: u64,
+    pub outpoint: OutPoint,
+    pub offset: u64,
+}
+
+impl SatPoint {
+    pub fn new(outpoint: OutPoint, offset: u64) -> Self {
+        SatPoint { outpoint, offset }
+    }
+
+    /// Consensus encoding of the satpoint (44 bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        deserialize(bytes).map_err(|e| format!("invalid satpoint encoding: {e}"))
+    }
+}
+
+pub fn txid_from_transaction_identifier(
+    transaction_identifier: &TransactionIdentifier,
+) -> Result<Txid, String> {
+    Txid::from_hex(transaction_identifier.get_hash_bytes_str())
+        .map_err(|e| format!("invalid txid {}: {e}", transaction_identifier.hash))
+}
+
+pub fn outpoint_from_transaction_identifier(
+    transaction_identifier: &TransactionIdentifier,
+    vout: usize,
+) -> Result<OutPoint, String> {
+    let txid = txid_from_transaction_identifier(transaction_identifier)?;
+    let vout = u32::try_from(vout).map_err(|_| format!("invalid output index {vout}"))?;
+    Ok(OutPoint::new(txid, vout))
+}
+
+/// Consensus encoding of the outpoint (36 bytes), as stored in the `locations` table.
+pub fn encode_outpoint(outpoint: &OutPoint) -> Vec<u8> {
+    serialize(outpoint)
+}
+
+pub fn decode_outpoint(bytes: &[u8]) -> Result<OutPoint, String> {
+    deserialize(bytes).map_err(|e| format!("invalid outpoint encoding: {e}"))
 }
 
 impl Display for SatPoint {
@@ -110,6 +154,37 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn binary_round_trip() {
+        let satpoint = "1111111111111111111111111111111111111111111111111111111111111111:1:1"
+            .parse::<SatPoint>()
+            .unwrap();
+        let bytes = satpoint.to_bytes();
+        assert_eq!(bytes.len(), 44);
+        assert_eq!(SatPoint::from_bytes(&bytes).unwrap(), satpoint);
+        assert!(SatPoint::from_bytes(&bytes[..43]).is_err());
+
+        let bytes = encode_outpoint(&satpoint.outpoint);
+        assert_eq!(bytes.len(), 36);
+        assert_eq!(decode_outpoint(&bytes).unwrap(), satpoint.outpoint);
+        assert!(decode_outpoint(&[0; 35]).is_err());
+    }
+
+    #[test]
+    fn from_transaction_identifier() {
+        let transaction_identifier = TransactionIdentifier::new(
+            "0x1111111111111111111111111111111111111111111111111111111111111112",
+        );
+        let outpoint = outpoint_from_transaction_identifier(&transaction_identifier, 3).unwrap();
+        assert_eq!(
+            outpoint.to_string(),
+            "1111111111111111111111111111111111111111111111111111111111111112:3"
+        );
+        assert!(
+            outpoint_from_transaction_identifier(&TransactionIdentifier::new("0x11"), 0).is_err()
+        );
+    }
+
     #[test]
     fn deserialize_ok() {
         assert_eq!(
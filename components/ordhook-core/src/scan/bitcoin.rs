@@ -4,16 +4,32 @@ use crate::core::protocol::inscription_parsing::{
     parse_inscriptions_and_standardize_block,
 };
 use crate::core::protocol::inscription_sequencing::consolidate_block_with_pre_computed_ordinals_data;
-use crate::db::{get_any_entry_in_ordinal_activities, open_readonly_ordhook_db_conn};
+use crate::db::{
+    find_blocks_without_feed_details, find_inscription_feed_at_block_height,
+    find_latest_inscription_block_height, find_latest_transfers_block_height,
+    get_any_entry_in_ordinal_activities, get_default_ordhook_db_file_path,
+    open_readonly_ordhook_db_conn,
+};
 use crate::download::download_ordinals_dataset_if_required;
+use crate::scan::filters::InscriptionFilter;
 use crate::service::observers::{
-    open_readwrite_observers_db_conn_or_panic, update_observer_progress,
+    find_predicate_filters, find_predicate_sinks, initialize_observers_db,
+    update_observer_scan_progress,
+};
+use crate::service::outbox::{
+    count_pending_deliveries, deliver_pending_deliveries, enqueue_http_delivery,
+    open_readwrite_outbox_db_conn, spawn_sink_events_publishing,
+    MAX_PENDING_DELIVERIES_PER_PREDICATE,
 };
+use crate::service::scan_queue::SCAN_QUEUE;
+use crate::sinks::{build_trigger_sink_events, SinkConfig};
+use crate::utils::monitoring::METRICS;
+use crate::utils::shutdown::is_shutdown_requested;
+use chainhook_sdk::bitcoincore_rpc::Client;
 use chainhook_sdk::bitcoincore_rpc::RpcApi;
-use chainhook_sdk::bitcoincore_rpc::{Auth, Client};
 use chainhook_sdk::chainhooks::bitcoin::{
     evaluate_bitcoin_chainhooks_on_chain_event, handle_bitcoin_hook_action,
-    BitcoinChainhookOccurrence, BitcoinTriggerChainhook,
+    serialize_bitcoin_payload_to_json, BitcoinChainhookOccurrence, BitcoinTriggerChainhook,
 };
 use chainhook_sdk::chainhooks::types::BitcoinChainhookSpecification;
 use chainhook_sdk::indexer::bitcoin::{
@@ -22,9 +38,81 @@ use chainhook_sdk::indexer::bitcoin::{
 use chainhook_sdk::observer::{gather_proofs, DataHandlerEvent, EventObserverConfig};
 use chainhook_sdk::types::{
     BitcoinBlockData, BitcoinChainEvent, BitcoinChainUpdatedWithBlocksData,
+    BitcoinChainUpdatedWithReorgData,
 };
-use chainhook_sdk::utils::{file_append, send_request, BlockHeights, Context};
-use std::collections::HashMap;
+use chainhook_sdk::utils::{file_append, BlockHeights, Context};
+use lazy_static::lazy_static;
+use rusqlite::Connection;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Maximum number of blocks evaluated by `dry_run_bitcoin_predicate`.
+pub const DRY_RUN_MAX_BLOCKS: usize = 100;
+
+/// Error returned by scans interrupted with `interrupt_bitcoin_scan`.
+pub const SCAN_INTERRUPTED: &str = "Scan interrupted";
+
+lazy_static! {
+    static ref SCANS_IN_PROGRESS: Mutex<HashMap<String, Arc<AtomicBool>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Registration of a scan in progress, released when the scan returns.
+struct ScanRegistration {
+    uuid: String,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl ScanRegistration {
+    /// Registers a scan of the predicate `uuid`, interrupting the previous scan of this predicate, if any.
+    fn new(uuid: &str) -> ScanRegistration {
+        let interrupted = Arc::new(AtomicBool::new(false));
+        {
+            let mut scans = SCANS_IN_PROGRESS.lock().unwrap();
+            if let Some(previous) = scans.insert(uuid.to_string(), interrupted.clone()) {
+                previous.store(true, Ordering::SeqCst);
+            }
+        }
+        // Cancelled by the service after being dequeued, before being registered
+        if SCAN_QUEUE.is_cancelled(uuid) {
+            interrupted.store(true, Ordering::SeqCst);
+        }
+        ScanRegistration {
+            uuid: uuid.to_string(),
+            interrupted,
+        }
+    }
+
+    fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ScanRegistration {
+    fn drop(&mut self) {
+        let mut scans = SCANS_IN_PROGRESS.lock().unwrap();
+        if let Some(current) = scans.get(&self.uuid) {
+            if Arc::ptr_eq(current, &self.interrupted) {
+                scans.remove(&self.uuid);
+            }
+        }
+    }
+}
+
+/// Stops the scan of the predicate `uuid` before its next block. Returns `false` if no scan was in progress.
+pub fn interrupt_bitcoin_scan(uuid: &str) -> bool {
+    match SCANS_IN_PROGRESS.lock().unwrap().get(uuid) {
+        Some(interrupted) => {
+            interrupted.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
 
 pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     predicate_spec: &BitcoinChainhookSpecification,
@@ -32,19 +120,99 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
     event_observer_config_override: Option<&EventObserverConfig>,
     ctx: &Context,
 ) -> Result<(), String> {
-    let _ = download_ordinals_dataset_if_required(config, ctx).await;
+    scan_bitcoin_chainstate_using_predicate(
+        predicate_spec,
+        config,
+        event_observer_config_override,
+        true,
+        ctx,
+    )
+    .await
+}
 
-    let auth = Auth::UserPass(
-        config.network.bitcoind_rpc_username.clone(),
-        config.network.bitcoind_rpc_password.clone(),
-    );
+/// Evaluates `predicate_spec` on the inscription feed rebuilt from hord.sqlite, without connecting to
+/// bitcoind. Fails on blocks with ordinal activity indexed before the feed could be rebuilt.
+pub async fn scan_bitcoin_chainstate_via_index_using_predicate(
+    predicate_spec: &BitcoinChainhookSpecification,
+    config: &Config,
+    ctx: &Context,
+) -> Result<(), String> {
+    scan_bitcoin_chainstate_using_predicate(predicate_spec, config, None, false, ctx).await
+}
+
+/// Chain tip, from bitcoind when available, or the latest block with ordinal activity indexed.
+fn retrieve_scan_chain_tip(
+    bitcoin_rpc: &Option<Client>,
+    inscriptions_db_conn: &Connection,
+    ctx: &Context,
+) -> Result<u64, String> {
+    match bitcoin_rpc {
+        Some(bitcoin_rpc) => match bitcoin_rpc.get_blockchain_info() {
+            Ok(result) => Ok(result.blocks),
+            Err(e) => Err(format!(
+                "unable to retrieve Bitcoin chain tip ({})",
+                e.to_string()
+            )),
+        },
+        None => find_latest_inscription_block_height(inscriptions_db_conn, ctx)?
+            .max(find_latest_transfers_block_height(
+                inscriptions_db_conn,
+                ctx,
+            ))
+            .ok_or(format!("no ordinal activity indexed")),
+    }
+}
+
+fn without_feed_details_error(blocks: &[u64]) -> String {
+    let (first, last) = (blocks[0], blocks[blocks.len() - 1]);
+    format!(
+        "{} blocks with ordinal activity between #{first} and #{last} were indexed before reveal details, destinations and values were recorded: scan them with bitcoind",
+        blocks.len()
+    )
+}
+
+/// Fails if blocks of `block_heights` (sorted) with ordinal activity can not be rebuilt from hord.sqlite.
+fn check_feed_details_coverage(
+    block_heights: &VecDeque<u64>,
+    inscriptions_db_conn: &Connection,
+    ctx: &Context,
+) -> Result<(), String> {
+    let (Some(first), Some(last)) = (block_heights.front(), block_heights.back()) else {
+        return Ok(());
+    };
+    let blocks = find_blocks_without_feed_details(*first, *last, inscriptions_db_conn, ctx)
+        .into_iter()
+        .filter(|block_height| block_heights.binary_search(block_height).is_ok())
+        .collect::<Vec<_>>();
+    match blocks.is_empty() {
+        true => Ok(()),
+        false => Err(without_feed_details_error(&blocks)),
+    }
+}
+
+async fn scan_bitcoin_chainstate_using_predicate(
+    predicate_spec: &BitcoinChainhookSpecification,
+    config: &Config,
+    event_observer_config_override: Option<&EventObserverConfig>,
+    rpc_enabled: bool,
+    ctx: &Context,
+) -> Result<(), String> {
+    let _ = download_ordinals_dataset_if_required(config, ctx).await;
 
-    let bitcoin_rpc = match Client::new(&config.network.bitcoind_rpc_url, auth) {
-        Ok(con) => con,
-        Err(message) => {
-            return Err(format!("Bitcoin RPC error: {}", message.to_string()));
+    let bitcoin_rpc = match rpc_enabled {
+        true => {
+            let auth = config.bitcoind_rpc_auth();
+            match Client::new(&config.network.bitcoind_rpc_url, auth) {
+                Ok(con) => Some(con),
+                Err(message) => {
+                    return Err(format!("Bitcoin RPC error: {}", message.to_string()));
+                }
+            }
         }
+        false => None,
     };
+    let mut inscriptions_db_conn =
+        open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
     let mut floating_end_block = false;
 
     let mut block_heights_to_scan = if let Some(ref blocks) = predicate_spec.blocks {
@@ -61,20 +229,21 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
         };
         let (end_block, update_end_block) = match predicate_spec.end_block {
             Some(end_block) => (end_block, false),
-            None => match bitcoin_rpc.get_blockchain_info() {
-                Ok(result) => (result.blocks, true),
-                Err(e) => {
-                    return Err(format!(
-                        "unable to retrieve Bitcoin chain tip ({})",
-                        e.to_string()
-                    ));
-                }
-            },
+            None => (
+                retrieve_scan_chain_tip(&bitcoin_rpc, &inscriptions_db_conn, ctx)?,
+                true,
+            ),
         };
         floating_end_block = update_end_block;
         BlockHeights::BlockRange(start_block, end_block).get_sorted_entries()
     };
 
+    // Without bitcoind, hord.sqlite is the only source: fail before evaluating anything rather than
+    // on the first block indexed before the inscription feed could be rebuilt.
+    if !rpc_enabled {
+        check_feed_details_coverage(&block_heights_to_scan, &inscriptions_db_conn, ctx)?;
+    }
+
     info!(
         ctx.expect_logger(),
         "Starting predicate evaluation on {} Bitcoin blocks",
@@ -87,14 +256,20 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
         Some(config_override) => config_override.clone(),
         None => config.get_event_observer_config(),
     };
-    let bitcoin_config = event_observer_config.get_bitcoin_config();
     let mut number_of_blocks_scanned = 0;
     let http_client = build_http_client();
+    let registration = ScanRegistration::new(&predicate_spec.uuid);
+    let observers_db_conn = initialize_observers_db(&config.expected_cache_path(), ctx);
 
     while let Some(current_block_height) = block_heights_to_scan.pop_front() {
-        let mut inscriptions_db_conn =
-            open_readonly_ordhook_db_conn(&config.expected_cache_path(), ctx)?;
-
+        if registration.is_interrupted() {
+            info!(
+                ctx.expect_logger(),
+                "Scan of predicate {} interrupted at block #{current_block_height}",
+                predicate_spec.uuid
+            );
+            return Err(SCAN_INTERRUPTED.to_string());
+        }
         number_of_blocks_scanned += 1;
 
         if !get_any_entry_in_ordinal_activities(&current_block_height, &inscriptions_db_conn, &ctx)
@@ -102,41 +277,19 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
             continue;
         }
 
-        let block_hash = retrieve_block_hash_with_retry(
+        let Some(block) = retrieve_block_to_scan(
+            current_block_height,
+            &mut inscriptions_db_conn,
+            rpc_enabled,
             &http_client,
-            &current_block_height,
-            &bitcoin_config,
+            &event_observer_config,
             ctx,
         )
-        .await?;
-        let block_breakdown =
-            download_and_parse_block_with_retry(&http_client, &block_hash, &bitcoin_config, ctx)
-                .await?;
-        let mut block = match parse_inscriptions_and_standardize_block(
-            block_breakdown,
-            &event_observer_config.bitcoin_network,
-            ctx,
-        ) {
-            Ok(data) => data,
-            Err((e, _)) => {
-                warn!(
-                    ctx.expect_logger(),
-                    "Unable to standardize block#{} {}: {}", current_block_height, block_hash, e
-                );
-                continue;
-            }
+        .await?
+        else {
+            continue;
         };
 
-        {
-            let inscriptions_db_tx = inscriptions_db_conn.transaction().unwrap();
-            consolidate_block_with_pre_computed_ordinals_data(
-                &mut block,
-                &inscriptions_db_tx,
-                true,
-                &Context::empty(),
-            );
-        }
-
         let inscriptions_revealed = get_inscriptions_revealed_in_block(&block)
             .iter()
             .map(|d| d.inscription_number.to_string())
@@ -152,6 +305,16 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
             inscriptions_revealed.join(", ")
         );
 
+        // Payloads are persisted before delivery: instead of aborting when the
+        // endpoint is unavailable, pause the scan until the outbox drains.
+        wait_for_outbox_capacity(
+            &predicate_spec.uuid,
+            &registration,
+            &event_observer_config,
+            ctx,
+        )
+        .await?;
+
         match process_block_with_predicates(
             block,
             &vec![&predicate_spec],
@@ -167,20 +330,17 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
         if err_count >= 3 {
             return Err(format!("Scan aborted (consecutive action errors >= 3)"));
         }
-        {
-            let observers_db_conn =
-                open_readwrite_observers_db_conn_or_panic(&config.expected_cache_path(), &ctx);
-            update_observer_progress(
-                &predicate_spec.uuid,
-                current_block_height,
-                &observers_db_conn,
-                &ctx,
-            )
-        }
+        update_observer_scan_progress(
+            &predicate_spec.uuid,
+            current_block_height,
+            block_heights_to_scan.len() as u64,
+            &observers_db_conn,
+            &ctx,
+        );
         if block_heights_to_scan.is_empty() && floating_end_block {
-            match bitcoin_rpc.get_blockchain_info() {
-                Ok(result) => {
-                    for entry in (current_block_height + 1)..=result.blocks {
+            match retrieve_scan_chain_tip(&bitcoin_rpc, &inscriptions_db_conn, ctx) {
+                Ok(tip) => {
+                    for entry in (current_block_height + 1)..=tip {
                         block_heights_to_scan.push_back(entry);
                     }
                 }
@@ -194,10 +354,87 @@ pub async fn scan_bitcoin_chainstate_via_rpc_using_predicate(
         ctx.expect_logger(),
         "{number_of_blocks_scanned} blocks scanned, {actions_triggered} actions triggered"
     );
+    let outbox_db_conn =
+        open_readwrite_outbox_db_conn(&PathBuf::from(&event_observer_config.cache_path), ctx)?;
+    let pending_deliveries = count_pending_deliveries(&predicate_spec.uuid, &outbox_db_conn, ctx);
+    if pending_deliveries > 0 {
+        warn!(
+            ctx.expect_logger(),
+            "{pending_deliveries} payloads of predicate {} still pending delivery in the outbox",
+            predicate_spec.uuid
+        );
+    }
 
     Ok(())
 }
 
+/// Block at `block_height` augmented with its ordinal operations: downloaded from bitcoind when
+/// `rpc_enabled`, or rebuilt from hord.sqlite otherwise.
+async fn retrieve_block_to_scan(
+    block_height: u64,
+    inscriptions_db_conn: &mut Connection,
+    rpc_enabled: bool,
+    http_client: &reqwest::Client,
+    event_observer_config: &EventObserverConfig,
+    ctx: &Context,
+) -> Result<Option<BitcoinBlockData>, String> {
+    if !rpc_enabled {
+        return find_inscription_feed_at_block_height(
+            block_height,
+            &event_observer_config.bitcoin_network,
+            inscriptions_db_conn,
+            ctx,
+        );
+    }
+    download_and_consolidate_block(
+        block_height,
+        inscriptions_db_conn,
+        http_client,
+        event_observer_config,
+        ctx,
+    )
+    .await
+}
+
+/// Downloads the block at `block_height` from bitcoind, and attaches the ordinal operations indexed.
+async fn download_and_consolidate_block(
+    block_height: u64,
+    inscriptions_db_conn: &mut Connection,
+    http_client: &reqwest::Client,
+    event_observer_config: &EventObserverConfig,
+    ctx: &Context,
+) -> Result<Option<BitcoinBlockData>, String> {
+    let bitcoin_config = event_observer_config.get_bitcoin_config();
+    let block_hash =
+        retrieve_block_hash_with_retry(http_client, &block_height, &bitcoin_config, ctx).await?;
+    let block_breakdown =
+        download_and_parse_block_with_retry(http_client, &block_hash, &bitcoin_config, ctx).await?;
+    let mut block = match parse_inscriptions_and_standardize_block(
+        block_breakdown,
+        &event_observer_config.bitcoin_network,
+        ctx,
+    ) {
+        Ok(data) => data,
+        Err((e, _)) => {
+            warn!(
+                ctx.expect_logger(),
+                "Unable to standardize block#{} {}: {}", block_height, block_hash, e
+            );
+            return Ok(None);
+        }
+    };
+    {
+        let inscriptions_db_tx = inscriptions_db_conn.transaction().unwrap();
+        consolidate_block_with_pre_computed_ordinals_data(
+            &mut block,
+            &inscriptions_db_tx,
+            true,
+            &Context::empty(),
+        );
+    }
+    Ok(Some(block))
+}
+
 pub async fn process_block_with_predicates(
     block: BitcoinBlockData,
     predicates: &Vec<&BitcoinChainhookSpecification>,
@@ -209,46 +446,590 @@ pub async fn process_block_with_predicates(
             new_blocks: vec![block],
             confirmed_blocks: vec![],
         });
+    process_chain_event_with_predicates(
+        &chain_event,
+        predicates,
+        "scan",
+        event_observer_config,
+        ctx,
+    )
+    .await
+}
 
-    let (predicates_triggered, _predicates_evaluated, _) =
-        evaluate_bitcoin_chainhooks_on_chain_event(&chain_event, predicates, ctx);
+/// Evaluates `predicates` on the blocks applied and rolled back by `chain_event`, then triggers
+/// their actions. Used by scans, and by the service for the blocks streamed by the observer.
+pub async fn process_chain_event_with_predicates(
+    chain_event: &BitcoinChainEvent,
+    predicates: &Vec<&BitcoinChainhookSpecification>,
+    delivery_source: &str,
+    event_observer_config: &EventObserverConfig,
+    ctx: &Context,
+) -> Result<u32, String> {
+    let base_dir = PathBuf::from(&event_observer_config.cache_path);
+    let uuids = predicates
+        .iter()
+        .map(|p| p.uuid.as_str())
+        .collect::<Vec<_>>();
+    let filters = find_predicate_filters(&uuids, &base_dir, ctx);
+    let sinks = find_predicate_sinks(&uuids, &base_dir, ctx);
+
+    let chain_events =
+        build_predicates_chain_events(chain_event, predicates, &filters, &base_dir, ctx);
 
-    execute_predicates_action(predicates_triggered, &event_observer_config, &ctx).await
+    let mut predicates_triggered = vec![];
+    for (predicates, chain_event) in chain_events.iter() {
+        let (mut triggered, _predicates_evaluated, _) =
+            evaluate_bitcoin_chainhooks_on_chain_event(chain_event, predicates, ctx);
+        predicates_triggered.append(&mut triggered);
+    }
+
+    execute_predicates_action(
+        predicates_triggered,
+        &sinks,
+        delivery_source,
+        &event_observer_config,
+        &ctx,
+    )
+    .await
+}
+
+/// Predicates without ordhook filters are evaluated together, on the full chain event, while
+/// each of the filtered predicates is evaluated on the subset of the blocks it matched.
+fn build_predicates_chain_events<'a>(
+    chain_event: &BitcoinChainEvent,
+    predicates: &Vec<&'a BitcoinChainhookSpecification>,
+    filters: &HashMap<String, InscriptionFilter>,
+    base_dir: &PathBuf,
+    ctx: &Context,
+) -> Vec<(Vec<&'a BitcoinChainhookSpecification>, BitcoinChainEvent)> {
+    let mut unfiltered_predicates = vec![];
+    let mut chain_events = vec![];
+    {
+        let inscriptions_db_conn =
+            match filters.is_empty() || !get_default_ordhook_db_file_path(base_dir).exists() {
+                true => None,
+                false => open_readonly_ordhook_db_conn(base_dir, ctx).ok(),
+            };
+        for predicate in predicates.iter() {
+            match filters.get(&predicate.uuid) {
+                Some(filter) => {
+                    if let Some(filtered_chain_event) =
+                        filter_chain_event(chain_event, filter, inscriptions_db_conn.as_ref(), ctx)
+                    {
+                        chain_events.push((vec![*predicate], filtered_chain_event));
+                    }
+                }
+                None => unfiltered_predicates.push(*predicate),
+            }
+        }
+    }
+    if !unfiltered_predicates.is_empty() {
+        chain_events.insert(0, (unfiltered_predicates, chain_event.clone()));
+    }
+    chain_events
+}
+
+/// Blocks of `chain_event` narrowed by `filter`, or `None` when none of them matched.
+fn filter_chain_event(
+    chain_event: &BitcoinChainEvent,
+    filter: &InscriptionFilter,
+    inscriptions_db_conn: Option<&Connection>,
+    ctx: &Context,
+) -> Option<BitcoinChainEvent> {
+    let filter_blocks = |blocks: &Vec<BitcoinBlockData>| {
+        blocks
+            .iter()
+            .filter_map(|block| filter.apply(block, inscriptions_db_conn, ctx))
+            .collect::<Vec<_>>()
+    };
+    match chain_event {
+        BitcoinChainEvent::ChainUpdatedWithBlocks(data) => {
+            let new_blocks = filter_blocks(&data.new_blocks);
+            if new_blocks.is_empty() {
+                return None;
+            }
+            Some(BitcoinChainEvent::ChainUpdatedWithBlocks(
+                BitcoinChainUpdatedWithBlocksData {
+                    new_blocks,
+                    confirmed_blocks: vec![],
+                },
+            ))
+        }
+        BitcoinChainEvent::ChainUpdatedWithReorg(data) => {
+            let blocks_to_rollback = filter_blocks(&data.blocks_to_rollback);
+            let blocks_to_apply = filter_blocks(&data.blocks_to_apply);
+            if blocks_to_rollback.is_empty() && blocks_to_apply.is_empty() {
+                return None;
+            }
+            Some(BitcoinChainEvent::ChainUpdatedWithReorg(
+                BitcoinChainUpdatedWithReorgData {
+                    blocks_to_rollback,
+                    blocks_to_apply,
+                    confirmed_blocks: vec![],
+                },
+            ))
+        }
+    }
+}
+
+/// Payloads that `predicate_spec` would receive on its blocks, evaluated synchronously. Actions are not
+/// triggered, and nothing is persisted (outbox, observers, sinks). Proofs are not included.
+/// Without `rpc_enabled`, blocks are rebuilt from hord.sqlite.
+pub async fn dry_run_bitcoin_predicate(
+    predicate_spec: &BitcoinChainhookSpecification,
+    filter: Option<&InscriptionFilter>,
+    rpc_enabled: bool,
+    config: &Config,
+    ctx: &Context,
+) -> Result<Vec<JsonValue>, String> {
+    let block_heights = match (
+        &predicate_spec.blocks,
+        predicate_spec.start_block,
+        predicate_spec.end_block,
+    ) {
+        (Some(blocks), _, _) => BlockHeights::Blocks(blocks.clone()).get_sorted_entries(),
+        (None, Some(start_block), Some(end_block)) if start_block <= end_block => {
+            if end_block - start_block >= DRY_RUN_MAX_BLOCKS as u64 {
+                return Err(format!(
+                    "dry-runs are limited to {DRY_RUN_MAX_BLOCKS} blocks"
+                ));
+            }
+            BlockHeights::BlockRange(start_block, end_block).get_sorted_entries()
+        }
+        _ => {
+            return Err(format!(
+                "dry-runs require either blocks, or a start_block and an end_block"
+            ))
+        }
+    };
+    if block_heights.len() > DRY_RUN_MAX_BLOCKS {
+        return Err(format!(
+            "dry-runs are limited to {DRY_RUN_MAX_BLOCKS} blocks"
+        ));
+    }
+
+    let base_dir = config.expected_cache_path();
+    let event_observer_config = config.get_event_observer_config();
+    let http_client = build_http_client();
+    let mut inscriptions_db_conn = open_readonly_ordhook_db_conn(&base_dir, ctx)?;
+    if !rpc_enabled {
+        check_feed_details_coverage(&block_heights, &inscriptions_db_conn, ctx)?;
+    }
+    let mut filters = HashMap::new();
+    if let Some(filter) = filter {
+        filters.insert(predicate_spec.uuid.clone(), filter.clone());
+    }
+
+    let mut payloads = vec![];
+    for block_height in block_heights.into_iter() {
+        if !get_any_entry_in_ordinal_activities(&block_height, &inscriptions_db_conn, ctx) {
+            continue;
+        }
+        let Some(block) = retrieve_block_to_scan(
+            block_height,
+            &mut inscriptions_db_conn,
+            rpc_enabled,
+            &http_client,
+            &event_observer_config,
+            ctx,
+        )
+        .await?
+        else {
+            continue;
+        };
+        let chain_event =
+            BitcoinChainEvent::ChainUpdatedWithBlocks(BitcoinChainUpdatedWithBlocksData {
+                new_blocks: vec![block],
+                confirmed_blocks: vec![],
+            });
+        let chain_events = build_predicates_chain_events(
+            &chain_event,
+            &vec![predicate_spec],
+            &filters,
+            &base_dir,
+            ctx,
+        );
+        let proofs = HashMap::new();
+        for (predicates, chain_event) in chain_events.iter() {
+            let (triggered, _, _) =
+                evaluate_bitcoin_chainhooks_on_chain_event(chain_event, predicates, ctx);
+            for trigger in triggered.into_iter() {
+                payloads.push(serialize_bitcoin_payload_to_json(trigger, &proofs));
+            }
+        }
+    }
+    Ok(payloads)
 }
 
 pub async fn execute_predicates_action<'a>(
     hits: Vec<BitcoinTriggerChainhook<'a>>,
+    sinks: &HashMap<String, SinkConfig>,
+    delivery_source: &str,
     config: &EventObserverConfig,
     ctx: &Context,
 ) -> Result<u32, String> {
     let mut actions_triggered = 0;
     let mut proofs = HashMap::new();
+    let outbox_db_conn = open_readwrite_outbox_db_conn(&PathBuf::from(&config.cache_path), ctx)?;
+    let http_client = reqwest::Client::new();
+    let mut predicates_to_flush = vec![];
+    // A failing predicate does not hold back the others: errors are reported once all of them ran.
+    let mut failures = vec![];
     for trigger in hits.into_iter() {
         if trigger.chainhook.include_proof {
             gather_proofs(&trigger, &mut proofs, &config, &ctx);
         }
-        match handle_bitcoin_hook_action(trigger, &proofs) {
-            Err(e) => {
-                error!(ctx.expect_logger(), "unable to handle action {}", e);
+        let predicate_uuid = trigger.chainhook.uuid.clone();
+        let block_height = trigger
+            .apply
+            .last()
+            .or(trigger.rollback.last())
+            .map(|(_, block)| block.block_identifier.index)
+            .unwrap_or_default();
+        let sink_res = match sinks.get(&predicate_uuid) {
+            Some(sink) => {
+                let events = build_trigger_sink_events(&predicate_uuid, &trigger);
+                spawn_sink_events_publishing(
+                    predicate_uuid.clone(),
+                    block_height,
+                    sink.clone(),
+                    events,
+                    PathBuf::from(&config.cache_path),
+                    ctx,
+                )
+                .await
             }
+            None => Ok(()),
+        };
+        if let Err(ref e) = sink_res {
+            ctx.try_log(|logger| {
+                warn!(
+                    logger,
+                    "Sink events of predicate {predicate_uuid} (block #{block_height}) queued for retry: {e}"
+                )
+            });
+        }
+        let action_res = match handle_bitcoin_hook_action(trigger, &proofs) {
+            Err(e) => Err(format!("unable to handle action: {e}")),
             Ok(action) => {
                 actions_triggered += 1;
                 match action {
-                    BitcoinChainhookOccurrence::Http(request) => {
-                        send_request(request, 60, 3, &ctx).await?
-                    }
-                    BitcoinChainhookOccurrence::File(path, bytes) => {
-                        file_append(path, bytes, &ctx)?
-                    }
-                    BitcoinChainhookOccurrence::Data(payload) => {
-                        if let Some(ref tx) = config.data_handler_tx {
-                            let _ = tx.send(DataHandlerEvent::Process(payload));
+                    BitcoinChainhookOccurrence::Http(request) => match request.build() {
+                        Ok(request) => {
+                            if !predicates_to_flush.contains(&predicate_uuid) {
+                                predicates_to_flush.push(predicate_uuid.clone());
+                            }
+                            enqueue_http_delivery(
+                                &predicate_uuid,
+                                block_height,
+                                &request,
+                                &outbox_db_conn,
+                                ctx,
+                            )
                         }
-                    }
-                };
+                        Err(e) => Err(format!("unable to build request: {}", e.to_string())),
+                    },
+                    BitcoinChainhookOccurrence::File(path, bytes) => file_append(path, bytes, &ctx),
+                    BitcoinChainhookOccurrence::Data(payload) => match config.data_handler_tx {
+                        // Fails once the consumer stopped, for the scan to be aborted
+                        Some(ref tx) => tx
+                            .send(DataHandlerEvent::Process(payload))
+                            .map_err(|_| "the consumer of the blocks has stopped".to_string()),
+                        None => Ok(()),
+                    },
+                }
             }
+        };
+        METRICS.record_predicate_delivery(delivery_source, sink_res.is_ok() && action_res.is_ok());
+        if let Err(e) = action_res {
+            ctx.try_log(|logger| {
+                error!(logger, "Action of predicate {predicate_uuid} failed: {e}")
+            });
+            failures.push(format!("predicate {predicate_uuid}: {e}"));
         }
     }
 
+    // Failed deliveries stay in the outbox, and are retried by the outbox runloop.
+    for predicate_uuid in predicates_to_flush.iter() {
+        deliver_pending_deliveries(predicate_uuid, &outbox_db_conn, &http_client, ctx).await;
+    }
+
+    if !failures.is_empty() {
+        return Err(failures.join("; "));
+    }
     Ok(actions_triggered)
 }
+
+async fn wait_for_outbox_capacity(
+    predicate_uuid: &str,
+    registration: &ScanRegistration,
+    config: &EventObserverConfig,
+    ctx: &Context,
+) -> Result<(), String> {
+    let outbox_db_conn = open_readwrite_outbox_db_conn(&PathBuf::from(&config.cache_path), ctx)?;
+    let http_client = reqwest::Client::new();
+    loop {
+        let pending_deliveries = count_pending_deliveries(predicate_uuid, &outbox_db_conn, ctx);
+        if pending_deliveries < MAX_PENDING_DELIVERIES_PER_PREDICATE {
+            return Ok(());
+        }
+        if is_shutdown_requested() {
+            return Err(format!(
+                "Scan interrupted (outbox of predicate {predicate_uuid} full)"
+            ));
+        }
+        if registration.is_interrupted() {
+            return Err(SCAN_INTERRUPTED.to_string());
+        }
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "{pending_deliveries} payloads of predicate {predicate_uuid} pending delivery, pausing scan"
+            )
+        });
+        deliver_pending_deliveries(predicate_uuid, &outbox_db_conn, &http_client, ctx).await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use chainhook_sdk::chainhooks::types::{HookAction, HttpHook};
+    use chainhook_sdk::indexer::bitcoin::BitcoinBlockFullBreakdown;
+    use chainhook_sdk::types::{OrdinalInscriptionCurseType, OrdinalOperation};
+
+    use super::*;
+    use crate::core::pipeline::download_and_pipeline_blocks;
+    use crate::core::pipeline::processors::start_inscription_indexing_processor;
+    use crate::core::should_sync_ordhook_db;
+    use crate::db::open_readwrite_ordhook_db_conn;
+    use crate::service::observers::insert_predicate_filter;
+    use crate::service::outbox::{get_outbox_report, rotate_signing_secret};
+    use crate::utils::bitcoind_mock::MockBitcoind;
+    use crate::utils::regtest_fixtures::{inscription_feed_predicate, regtest_corpus};
+    use crate::utils::webhooks::SIGNATURE_HEADER;
+
+    /// Answers the first HTTP request received with a 200, and returns it.
+    fn receive_request(listener: TcpListener) -> JoinHandle<String> {
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(headers_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..headers_end]
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= headers_end + 4 + content_length || read == 0 {
+                        break;
+                    }
+                }
+            }
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+            String::from_utf8_lossy(&request).to_string()
+        })
+    }
+
+    fn test_config() -> Config {
+        let mut config = Config::devnet_default();
+        config.storage.working_dir = format!(
+            "{}",
+            std::env::temp_dir()
+                .join(format!("ordhook-stream-{}", uuid::Uuid::new_v4()))
+                .display()
+        );
+        config
+    }
+
+    /// Block #1 of the regtest corpus, revealing a `text/plain` inscription.
+    fn inscription_block(config: &Config, ctx: &Context) -> BitcoinBlockData {
+        let corpus = regtest_corpus();
+        let block: BitcoinBlockFullBreakdown =
+            serde_json::from_value(corpus.chain.blocks()[1].clone()).unwrap();
+        parse_inscriptions_and_standardize_block(block, &config.network.bitcoin_network, ctx)
+            .unwrap()
+    }
+
+    fn http_predicate(uuid: &str, url: &str, config: &Config) -> BitcoinChainhookSpecification {
+        BitcoinChainhookSpecification {
+            network: config.network.bitcoin_network.clone(),
+            action: HookAction::HttpPost(HttpHook {
+                url: url.to_string(),
+                authorization_header: String::new(),
+            }),
+            ..inscription_feed_predicate(uuid)
+        }
+    }
+
+    #[test]
+    fn streamed_blocks_are_delivered_through_the_signed_outbox() {
+        let ctx = Context::empty();
+        let config = test_config();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let server = receive_request(listener);
+
+        let predicate = http_predicate("streamed", &url, &config);
+        let outbox_db_conn =
+            open_readwrite_outbox_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+        rotate_signing_secret(
+            &predicate.uuid,
+            "secret",
+            Duration::ZERO,
+            &outbox_db_conn,
+            &ctx,
+        );
+
+        let chain_event =
+            BitcoinChainEvent::ChainUpdatedWithBlocks(BitcoinChainUpdatedWithBlocksData {
+                new_blocks: vec![inscription_block(&config, &ctx)],
+                confirmed_blocks: vec![],
+            });
+        let actions = hiro_system_kit::nestable_block_on(process_chain_event_with_predicates(
+            &chain_event,
+            &vec![&predicate],
+            "stream",
+            &config.get_event_observer_config(),
+            &ctx,
+        ))
+        .unwrap();
+        assert_eq!(actions, 1);
+
+        let request = server.join().unwrap().to_lowercase();
+        assert!(request.contains(&format!("{SIGNATURE_HEADER}: v1=")));
+        assert_eq!(
+            get_outbox_report(&predicate.uuid, &outbox_db_conn, &ctx).delivered,
+            1
+        );
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+
+    #[test]
+    fn filters_apply_to_streamed_blocks() {
+        let ctx = Context::empty();
+        let config = test_config();
+        let block = inscription_block(&config, &ctx);
+        let reorg = BitcoinChainEvent::ChainUpdatedWithReorg(BitcoinChainUpdatedWithReorgData {
+            blocks_to_rollback: vec![block.clone()],
+            blocks_to_apply: vec![block.clone()],
+            confirmed_blocks: vec![],
+        });
+        let text_filter: InscriptionFilter =
+            serde_json::from_str(r#"{"content_types": ["text/*"]}"#).unwrap();
+        let Some(BitcoinChainEvent::ChainUpdatedWithReorg(filtered)) =
+            filter_chain_event(&reorg, &text_filter, None, &ctx)
+        else {
+            panic!("reorg filtered out");
+        };
+        assert_eq!(filtered.blocks_to_rollback.len(), 1);
+        assert_eq!(filtered.blocks_to_apply.len(), 1);
+
+        // Predicates whose filter does not match the block streamed are not triggered
+        let image_filter: InscriptionFilter =
+            serde_json::from_str(r#"{"content_types": ["image/*"]}"#).unwrap();
+        assert!(filter_chain_event(&reorg, &image_filter, None, &ctx).is_none());
+        let predicate = http_predicate("filtered", "http://127.0.0.1:1/events", &config);
+        let observers_db_conn = initialize_observers_db(&config.expected_cache_path(), &ctx);
+        insert_predicate_filter(&predicate.uuid, &image_filter, &observers_db_conn, &ctx);
+        let chain_event =
+            BitcoinChainEvent::ChainUpdatedWithBlocks(BitcoinChainUpdatedWithBlocksData {
+                new_blocks: vec![block],
+                confirmed_blocks: vec![],
+            });
+        let actions = hiro_system_kit::nestable_block_on(process_chain_event_with_predicates(
+            &chain_event,
+            &vec![&predicate],
+            "stream",
+            &config.get_event_observer_config(),
+            &ctx,
+        ))
+        .unwrap();
+        assert_eq!(actions, 0);
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+
+    #[test]
+    fn rebuilds_the_inscription_feed_from_the_index() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let config = bitcoind.ordhook_config();
+        let (start_block, end_block) = should_sync_ordhook_db(&config, &ctx).unwrap().unwrap();
+        let processor = start_inscription_indexing_processor(&config, &ctx, None);
+        hiro_system_kit::nestable_block_on(download_and_pipeline_blocks(
+            &config,
+            (start_block..=end_block).collect(),
+            start_block,
+            Some(&processor),
+            &ctx,
+        ))
+        .unwrap();
+        let _ = processor.thread_handle.join();
+        let conn = open_readwrite_ordhook_db_conn(&config.expected_cache_path(), &ctx).unwrap();
+        assert!(find_blocks_without_feed_details(0, end_block, &conn, &ctx).is_empty());
+
+        // Curses detected while parsing are kept
+        let network = &config.network.bitcoin_network;
+        let block = find_inscription_feed_at_block_height(4, network, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        let curse_type = get_inscriptions_revealed_in_block(&block)
+            .into_iter()
+            .find(|reveal| reveal.inscription_id == corpus.tagged_curse)
+            .and_then(|reveal| reveal.curse_type.clone());
+        assert_eq!(curse_type, Some(OrdinalInscriptionCurseType::Tag(2)));
+
+        // Transfers are attached to their transaction
+        let block = find_inscription_feed_at_block_height(5, network, &conn, &ctx)
+            .unwrap()
+            .unwrap();
+        let transfer_tx = block
+            .transactions
+            .iter()
+            .find(|tx| tx.transaction_identifier.get_hash_bytes_str() == corpus.transfer_txid)
+            .unwrap();
+        assert!(transfer_tx
+            .metadata
+            .ordinal_operations
+            .iter()
+            .any(|operation| matches!(
+                operation,
+                OrdinalOperation::InscriptionTransferred(transfer)
+                    if transfer.inscription_id == corpus.inscription
+            )));
+
+        let mut predicate = http_predicate("offline", "http://127.0.0.1:1/", &config);
+        predicate.start_block = Some(0);
+        predicate.end_block = Some(end_block);
+        let res = hiro_system_kit::nestable_block_on(
+            scan_bitcoin_chainstate_via_index_using_predicate(&predicate, &config, &ctx),
+        );
+        assert_eq!(res, Ok(()));
+
+        // Blocks indexed before block headers were recorded can only be scanned with bitcoind
+        conn.execute(
+            "UPDATE sequence_metadata SET block_hash = NULL WHERE block_height = 4",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            find_blocks_without_feed_details(0, end_block, &conn, &ctx),
+            vec![4]
+        );
+        let res = hiro_system_kit::nestable_block_on(
+            scan_bitcoin_chainstate_via_index_using_predicate(&predicate, &config, &ctx),
+        );
+        assert!(res.unwrap_err().contains("scan them with bitcoind"));
+        let _ = std::fs::remove_dir_all(config.expected_cache_path());
+    }
+}
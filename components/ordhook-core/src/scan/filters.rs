@@ -0,0 +1,263 @@
+use chainhook_sdk::{
+    types::{
+        BitcoinBlockData, BitcoinTransactionData, OrdinalInscriptionRevealData,
+        OrdinalInscriptionTransferData, OrdinalInscriptionTransferDestination, OrdinalOperation,
+    },
+    utils::Context,
+};
+use rusqlite::Connection;
+
+use crate::{
+    core::protocol::inscription_parsing::{get_inscription_parent_in_standardized_tx, Media},
+    db::find_inscription_with_id,
+    ord::{rarity::Rarity, sat::Sat},
+};
+
+/// Inclusive range of inscription numbers. Cursed inscriptions have negative numbers.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InscriptionNumberRange {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+/// Ordhook-specific filter, narrowing down the operations of the `InscriptionFeed` delivered to a predicate.
+/// Criteria are combined (an operation must satisfy all of them), and lists match any of their entries.
+///
+/// Content type, media and parent are only known when an inscription is revealed: filters using these
+/// criteria do not match transfers.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InscriptionFilter {
+    /// Inscriber address (reveals) or recipient address (transfers).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+    /// Content types, such as `image/png`, or `image/*`. Parameters (`;charset=utf-8`) are ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<Media>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inscription_number: Option<InscriptionNumberRange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rarities: Vec<Rarity>,
+    /// Parent inscription ids.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+}
+
+impl InscriptionFilter {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(InscriptionNumberRange {
+            from: Some(from),
+            to: Some(to),
+        }) = self.inscription_number
+        {
+            if from > to {
+                return Err(format!("invalid inscription_number range: {from} > {to}"));
+            }
+        }
+        for content_type in self.content_types.iter() {
+            if !content_type.contains('/') {
+                return Err(format!("invalid content type: {content_type}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn requires_reveal_data(&self) -> bool {
+        !self.content_types.is_empty() || !self.media.is_empty() || !self.parents.is_empty()
+    }
+
+    fn requires_inscription_data(&self) -> bool {
+        self.inscription_number.is_some() || self.cursed.is_some() || !self.rarities.is_empty()
+    }
+
+    fn matches_inscription(&self, inscription_number: i64, ordinal_number: u64) -> bool {
+        if let Some(ref range) = self.inscription_number {
+            if range
+                .from
+                .map(|from| inscription_number < from)
+                .unwrap_or(false)
+                || range.to.map(|to| inscription_number > to).unwrap_or(false)
+            {
+                return false;
+            }
+        }
+        if let Some(cursed) = self.cursed {
+            if cursed != (inscription_number < 0) {
+                return false;
+            }
+        }
+        if !self.rarities.is_empty() && !self.rarities.contains(&Sat(ordinal_number).rarity()) {
+            return false;
+        }
+        true
+    }
+
+    fn matches_content_type(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        if !self.content_types.is_empty() {
+            let matched = self.content_types.iter().any(|expected| {
+                let expected = expected.trim().to_lowercase();
+                match expected.strip_suffix("/*") {
+                    Some(top_level_type) => essence.split('/').next() == Some(top_level_type),
+                    None => expected.split(';').next().map(|e| e.trim()) == Some(essence.as_str()),
+                }
+            });
+            if !matched {
+                return false;
+            }
+        }
+        if !self.media.is_empty() && !self.media.contains(&Media::from_content_type(content_type)) {
+            return false;
+        }
+        true
+    }
+
+    pub fn matches_reveal(
+        &self,
+        reveal: &OrdinalInscriptionRevealData,
+        tx: &BitcoinTransactionData,
+    ) -> bool {
+        if !self.addresses.is_empty() {
+            match reveal.inscriber_address {
+                Some(ref address) if self.addresses.contains(address) => {}
+                _ => return false,
+            }
+        }
+        if !self.matches_content_type(&reveal.content_type) {
+            return false;
+        }
+        if !self.matches_inscription(reveal.inscription_number, reveal.ordinal_number) {
+            return false;
+        }
+        if !self.parents.is_empty() {
+            match get_inscription_parent_in_standardized_tx(tx, reveal.inscription_input_index) {
+                Some(ref parent) if self.parents.contains(parent) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    pub fn matches_transfer(
+        &self,
+        transfer: &OrdinalInscriptionTransferData,
+        inscriptions_db_conn: Option<&Connection>,
+        ctx: &Context,
+    ) -> bool {
+        if self.requires_reveal_data() {
+            return false;
+        }
+        if !self.addresses.is_empty() {
+            match transfer.destination {
+                OrdinalInscriptionTransferDestination::Transferred(ref address)
+                    if self.addresses.contains(address) => {}
+                _ => return false,
+            }
+        }
+        if self.requires_inscription_data() {
+            let Some(inscriptions_db_conn) = inscriptions_db_conn else {
+                return false;
+            };
+            match find_inscription_with_id(&transfer.inscription_id, inscriptions_db_conn, ctx) {
+                Ok(Some((inscription, _))) => {
+                    if !self.matches_inscription(
+                        inscription.inscription_number,
+                        inscription.ordinal_number,
+                    ) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Copy of `block` trimmed down to the operations matching the filter (and to the transactions
+    /// including these operations), or `None` when no operation matched.
+    pub fn apply(
+        &self,
+        block: &BitcoinBlockData,
+        inscriptions_db_conn: Option<&Connection>,
+        ctx: &Context,
+    ) -> Option<BitcoinBlockData> {
+        let mut transactions = vec![];
+        for tx in block.transactions.iter() {
+            let operations = tx
+                .metadata
+                .ordinal_operations
+                .iter()
+                .filter(|operation| match operation {
+                    OrdinalOperation::InscriptionRevealed(reveal) => {
+                        self.matches_reveal(reveal, tx)
+                    }
+                    OrdinalOperation::InscriptionTransferred(transfer) => {
+                        self.matches_transfer(transfer, inscriptions_db_conn, ctx)
+                    }
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if operations.is_empty() {
+                continue;
+            }
+            let mut tx = tx.clone();
+            tx.metadata.ordinal_operations = operations;
+            transactions.push(tx);
+        }
+        if transactions.is_empty() {
+            return None;
+        }
+        // Only the header is copied: cloning `block` would copy every transaction
+        Some(BitcoinBlockData {
+            block_identifier: block.block_identifier.clone(),
+            parent_block_identifier: block.parent_block_identifier.clone(),
+            timestamp: block.timestamp,
+            transactions,
+            metadata: block.metadata.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_content_types_and_media() {
+        let filter: InscriptionFilter =
+            serde_json::from_str(r#"{"content_types": ["image/*", "text/plain"]}"#).unwrap();
+        assert!(filter.matches_content_type("image/png"));
+        assert!(filter.matches_content_type("text/plain;charset=utf-8"));
+        assert!(!filter.matches_content_type("text/html;charset=utf-8"));
+
+        let filter: InscriptionFilter = serde_json::from_str(r#"{"media": ["iframe"]}"#).unwrap();
+        assert!(filter.matches_content_type("image/svg+xml"));
+        assert!(!filter.matches_content_type("image/png"));
+    }
+
+    #[test]
+    fn matches_number_range_curse_and_rarity() {
+        let filter: InscriptionFilter = serde_json::from_str(
+            r#"{"inscription_number": {"from": -10, "to": 100}, "cursed": true}"#,
+        )
+        .unwrap();
+        assert!(filter.matches_inscription(-5, 1));
+        assert!(!filter.matches_inscription(-11, 1));
+        assert!(!filter.matches_inscription(5, 1));
+
+        let filter: InscriptionFilter =
+            serde_json::from_str(r#"{"rarities": ["uncommon", "rare"]}"#).unwrap();
+        assert!(filter.matches_inscription(0, 50 * 100_000_000));
+        assert!(!filter.matches_inscription(0, 50 * 100_000_000 + 1));
+        assert!(serde_json::from_str::<InscriptionFilter>(r#"{"rarity": ["rare"]}"#).is_err());
+    }
+}
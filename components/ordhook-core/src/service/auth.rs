@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::PredicatesApiConfig;
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Caller of the predicates API, identified by its API key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiCaller {
+    /// Operators, managing the predicates of every tenant.
+    Admin,
+    /// Tenants only see and manage the predicates they own.
+    Tenant {
+        owner_uuid: String,
+        max_number_of_bitcoin_predicates: usize,
+    },
+}
+
+impl ApiCaller {
+    pub fn can_access(&self, owner_uuid: Option<&String>) -> bool {
+        match self {
+            ApiCaller::Admin => true,
+            ApiCaller::Tenant {
+                owner_uuid: tenant, ..
+            } => owner_uuid == Some(tenant),
+        }
+    }
+}
+
+/// API keys accepted by the predicates API. Keys are only kept hashed, and the API is left open
+/// (every caller being an admin) when none are configured.
+pub struct ApiKeys {
+    callers: HashMap<[u8; 32], ApiCaller>,
+}
+
+fn hash_api_key(key: &str) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(key.as_bytes()));
+    hash
+}
+
+impl ApiKeys {
+    pub fn new(
+        config: &PredicatesApiConfig,
+        default_max_number_of_bitcoin_predicates: usize,
+    ) -> ApiKeys {
+        let mut callers = HashMap::new();
+        for key in config.admin_api_keys.iter() {
+            callers.insert(hash_api_key(key), ApiCaller::Admin);
+        }
+        for tenant in config.tenants.iter() {
+            for key in tenant.api_keys.iter() {
+                callers.insert(
+                    hash_api_key(key),
+                    ApiCaller::Tenant {
+                        owner_uuid: tenant.owner_uuid.clone(),
+                        max_number_of_bitcoin_predicates: tenant
+                            .max_number_of_bitcoin_predicates
+                            .unwrap_or(default_max_number_of_bitcoin_predicates),
+                    },
+                );
+            }
+        }
+        ApiKeys { callers }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.callers.is_empty()
+    }
+
+    pub fn authenticate(&self, api_key: Option<&str>) -> Option<ApiCaller> {
+        if !self.is_enabled() {
+            return Some(ApiCaller::Admin);
+        }
+        self.callers.get(&hash_api_key(api_key?)).cloned()
+    }
+
+    /// Tenants with at least one API key, along with their predicates limit.
+    pub fn tenants(&self) -> Vec<(String, usize)> {
+        let mut tenants = self
+            .callers
+            .values()
+            .filter_map(|caller| match caller {
+                ApiCaller::Admin => None,
+                ApiCaller::Tenant {
+                    owner_uuid,
+                    max_number_of_bitcoin_predicates,
+                } => Some((owner_uuid.clone(), *max_number_of_bitcoin_predicates)),
+            })
+            .collect::<Vec<_>>();
+        tenants.sort();
+        tenants.dedup();
+        tenants
+    }
+}
+
+/// API keys are sent either as a bearer token (`Authorization: Bearer <key>`), or with the `x-api-key` header.
+fn extract_api_key<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    if let Some(authorization) = request.headers().get_one("authorization") {
+        if let Some(token) = authorization.strip_prefix("Bearer ") {
+            return Some(token.trim());
+        }
+    }
+    request.headers().get_one(API_KEY_HEADER)
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiCaller {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(api_keys) = request.rocket().state::<ApiKeys>() else {
+            return Outcome::Failure((Status::Unauthorized, ()));
+        };
+        match api_keys.authenticate(extract_api_key(request)) {
+            Some(caller) => Outcome::Success(caller),
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::TenantConfig;
+
+    use super::*;
+
+    #[test]
+    fn authenticates_admins_and_tenants() {
+        let mut config = PredicatesApiConfig {
+            http_port: 20456,
+            display_logs: false,
+            admin_api_keys: vec![],
+            tenants: vec![],
+        };
+        assert_eq!(
+            ApiKeys::new(&config, 10).authenticate(None),
+            Some(ApiCaller::Admin)
+        );
+
+        config.admin_api_keys = vec!["operator".into()];
+        config.tenants = vec![TenantConfig {
+            owner_uuid: "tenant".into(),
+            api_keys: vec!["tenant-key".into()],
+            max_number_of_bitcoin_predicates: None,
+        }];
+        let api_keys = ApiKeys::new(&config, 10);
+        assert_eq!(api_keys.authenticate(None), None);
+        assert_eq!(api_keys.authenticate(Some("unknown")), None);
+        assert_eq!(
+            api_keys.authenticate(Some("operator")),
+            Some(ApiCaller::Admin)
+        );
+        let tenant = api_keys.authenticate(Some("tenant-key")).unwrap();
+        assert_eq!(
+            tenant,
+            ApiCaller::Tenant {
+                owner_uuid: "tenant".into(),
+                max_number_of_bitcoin_predicates: 10
+            }
+        );
+        assert!(tenant.can_access(Some(&"tenant".to_string())));
+        assert!(!tenant.can_access(Some(&"other".to_string())));
+        assert!(!tenant.can_access(None));
+    }
+}
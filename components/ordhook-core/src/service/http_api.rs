@@ -1,7 +1,9 @@
 use std::{
+    collections::VecDeque,
     net::{IpAddr, Ipv4Addr},
     path::PathBuf,
     sync::{mpsc::Sender, Arc, Mutex},
+    time::Duration,
 };
 
 use chainhook_sdk::{
@@ -10,16 +12,41 @@ use chainhook_sdk::{
     utils::Context,
 };
 use rocket::config::{self, Config, LogLevel};
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::{json, Json, Value as JsonValue};
-use rocket::State;
+use rocket::{Shutdown, State};
 use std::error::Error;
+use tokio::sync::broadcast::error::RecvError;
 
+use crate::db::open_readonly_ordhook_db_conn;
+use crate::scan::bitcoin::dry_run_bitcoin_predicate;
+use crate::scan::filters::InscriptionFilter;
+use crate::sinks::SinkConfig;
+use crate::utils::monitoring::encode_metrics;
+use crate::utils::webhooks::generate_signing_secret;
+
+use super::auth::{ApiCaller, ApiKeys};
+use super::live_stream::{
+    event_type_name, LastEventId, LiveSubscription, StreamCursor, StreamPosition, LIVE_EVENTS,
+};
 use super::observers::{
-    find_all_observers, find_observer_with_uuid, open_readonly_observers_db_conn, ObserverReport,
+    build_bitcoin_full_specification, find_all_observers, find_observer_with_uuid,
+    find_predicate_filter, find_predicate_sink, initialize_observers_db, insert_predicate_filter,
+    insert_predicate_sink, open_readonly_observers_db_conn, remove_predicate_data,
+    update_observer_phase, update_observer_streaming_enabled, ObserverPhase, ObserverReport,
+};
+use super::outbox::{
+    acknowledge_delivery, find_deliveries_with_status, get_outbox_report,
+    open_readwrite_outbox_db_conn, remove_signing_secrets_for_predicate, replay_deliveries,
+    rotate_signing_secret, DeliveryStatus, OutboxReport,
 };
+use super::scan_queue::{ScanPriority, SCAN_QUEUE};
 
 pub async fn start_predicate_api_server(
     port: u16,
+    api_keys: ApiKeys,
+    config: crate::config::Config,
     observers_db_dir_path: PathBuf,
     observer_commands_tx: Sender<ObserverCommand>,
     ctx: Context,
@@ -48,7 +75,22 @@ pub async fn start_predicate_api_server(
         handle_get_predicates,
         handle_get_predicate,
         handle_create_predicate,
+        handle_evaluate_predicate,
         handle_delete_bitcoin_predicate,
+        handle_pause_predicate,
+        handle_resume_predicate,
+        handle_rescan_predicate,
+        handle_get_scans,
+        handle_get_tenants,
+        handle_stream_inscription_events,
+        handle_set_predicate_scan_priority,
+        handle_get_predicate_outbox,
+        handle_get_predicate_dead_letters,
+        handle_replay_predicate_deliveries,
+        handle_acknowledge_predicate_delivery,
+        handle_rotate_predicate_signing_secret,
+        handle_delete_predicate_signing_secrets,
+        handle_get_api_metrics,
     ];
 
     let background_job_tx_mutex = Arc::new(Mutex::new(observer_commands_tx.clone()));
@@ -59,7 +101,10 @@ pub async fn start_predicate_api_server(
         .manage(background_job_tx_mutex)
         .manage(observers_db_dir_path)
         .manage(ctx_cloned)
+        .manage(api_keys)
+        .manage(config)
         .mount("/", routes)
+        .register("/", catchers![handle_unauthorized])
         .ignite()
         .await?;
 
@@ -69,6 +114,43 @@ pub async fn start_predicate_api_server(
     Ok(())
 }
 
+#[catch(401)]
+fn handle_unauthorized() -> Json<JsonValue> {
+    Json(json!({
+        "status": 401,
+        "error": "Missing or invalid API key",
+    }))
+}
+
+/// Tenants can only access their own predicates. Predicates of other tenants are reported as missing.
+fn authorize_predicate_access(
+    caller: &ApiCaller,
+    predicate_uuid: &str,
+    observers_db_dir_path: &PathBuf,
+    ctx: &Context,
+) -> Result<(), Json<JsonValue>> {
+    if *caller == ApiCaller::Admin {
+        return Ok(());
+    }
+    let owner_uuid = open_readonly_observers_db_conn(observers_db_dir_path, ctx)
+        .ok()
+        .and_then(|db_conn| find_observer_with_uuid(predicate_uuid, &db_conn, ctx))
+        .and_then(|(spec, _)| predicate_owner(&spec).cloned());
+    match caller.can_access(owner_uuid.as_ref()) {
+        true => Ok(()),
+        false => Err(Json(json!({
+            "status": 404,
+        }))),
+    }
+}
+
+fn predicate_owner(predicate: &ChainhookSpecification) -> Option<&String> {
+    match predicate {
+        ChainhookSpecification::Bitcoin(spec) => spec.owner_uuid.as_ref(),
+        ChainhookSpecification::Stacks(spec) => spec.owner_uuid.as_ref(),
+    }
+}
+
 #[get("/ping")]
 fn handle_ping(ctx: &State<Context>) -> Json<JsonValue> {
     ctx.try_log(|logger| info!(logger, "Handling HTTP GET /ping"));
@@ -80,6 +162,7 @@ fn handle_ping(ctx: &State<Context>) -> Json<JsonValue> {
 
 #[get("/v1/observers", format = "application/json")]
 fn handle_get_predicates(
+    caller: ApiCaller,
     observers_db_dir_path: &State<PathBuf>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
@@ -89,7 +172,11 @@ fn handle_get_predicates(
             let observers = find_all_observers(&mut db_conn, &ctx);
             let serialized_predicates = observers
                 .iter()
-                .map(|(p, s)| serialized_predicate_with_status(p, s))
+                .filter(|(p, _)| caller.can_access(predicate_owner(p)))
+                .map(|(p, s)| {
+                    let deliveries = find_delivery_counters(&p.uuid(), observers_db_dir_path, &ctx);
+                    serialized_predicate_with_status(p, s, deliveries.as_ref())
+                })
                 .collect::<Vec<_>>();
 
             Json(json!({
@@ -106,26 +193,122 @@ fn handle_get_predicates(
 
 #[post("/v1/observers", format = "application/json", data = "<predicate>")]
 fn handle_create_predicate(
-    predicate: Json<ChainhookFullSpecification>,
+    predicate: Json<JsonValue>,
+    caller: ApiCaller,
     observers_db_dir_path: &State<PathBuf>,
     background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
     ctx.try_log(|logger| info!(logger, "Handling HTTP POST /v1/observers"));
-    let predicate = predicate.into_inner();
+    let mut predicate = predicate.into_inner();
+    // Ordhook filters are not part of the chainhook specification
+    let filter = match predicate.as_object_mut().and_then(|p| p.remove("filter")) {
+        Some(filter) => match serde_json::from_value::<InscriptionFilter>(filter) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                return Json(json!({
+                    "status": 422,
+                    "error": format!("invalid filter: {}", e.to_string()),
+                }))
+            }
+        },
+        None => None,
+    };
+    let sink = match predicate.as_object_mut().and_then(|p| p.remove("sink")) {
+        Some(sink) => match serde_json::from_value::<SinkConfig>(sink) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                return Json(json!({
+                    "status": 422,
+                    "error": format!("invalid sink: {}", e.to_string()),
+                }))
+            }
+        },
+        None => None,
+    };
+    let scan_priority = match predicate
+        .as_object_mut()
+        .and_then(|p| p.remove("scan_priority"))
+    {
+        Some(priority) => match serde_json::from_value::<ScanPriority>(priority) {
+            Ok(priority) => Some(priority),
+            Err(e) => {
+                return Json(json!({
+                    "status": 422,
+                    "error": format!("invalid scan_priority: {}", e.to_string()),
+                }))
+            }
+        },
+        None => None,
+    };
+    let mut predicate = match serde_json::from_value::<ChainhookFullSpecification>(predicate) {
+        Ok(predicate) => predicate,
+        Err(e) => {
+            return Json(json!({
+                "status": 422,
+                "error": format!("invalid predicate: {}", e.to_string()),
+            }))
+        }
+    };
+    if let ApiCaller::Tenant {
+        ref owner_uuid,
+        max_number_of_bitcoin_predicates,
+    } = caller
+    {
+        let ChainhookFullSpecification::Bitcoin(ref mut spec) = predicate else {
+            return Json(json!({
+                "status": 422,
+                "error": "Only Bitcoin predicates can be registered",
+            }));
+        };
+        match spec.owner_uuid {
+            Some(ref owner) if owner != owner_uuid => {
+                return Json(json!({
+                    "status": 403,
+                    "error": "Predicates can only be registered for their own tenant",
+                }))
+            }
+            _ => spec.owner_uuid = Some(owner_uuid.clone()),
+        }
+        let number_of_predicates = match open_readonly_observers_db_conn(observers_db_dir_path, ctx)
+        {
+            Ok(db_conn) => find_all_observers(&db_conn, ctx)
+                .iter()
+                .filter(|(p, _)| caller.can_access(predicate_owner(p)))
+                .count(),
+            Err(_) => 0,
+        };
+        if number_of_predicates >= max_number_of_bitcoin_predicates {
+            return Json(json!({
+                "status": 429,
+                "error": format!("Limit of {max_number_of_bitcoin_predicates} predicates reached"),
+            }));
+        }
+    }
     if let Err(e) = predicate.validate() {
         return Json(json!({
             "status": 422,
             "error": e,
         }));
     }
+    if let Some(Err(e)) = filter.as_ref().map(|f| f.validate()) {
+        return Json(json!({
+            "status": 422,
+            "error": e,
+        }));
+    }
+    if let Some(Err(e)) = sink.as_ref().map(|s| s.validate()) {
+        return Json(json!({
+            "status": 422,
+            "error": e,
+        }));
+    }
 
     let predicate_uuid = predicate.get_uuid().to_string();
 
     if let Ok(mut predicates_db_conn) = open_readonly_observers_db_conn(observers_db_dir_path, ctx)
     {
-        let key: String = format!("{}", ChainhookSpecification::bitcoin_key(&predicate_uuid));
-        match find_observer_with_uuid(&key, &mut predicates_db_conn, &ctx) {
+        match find_observer_with_uuid(&predicate_uuid, &mut predicates_db_conn, &ctx) {
             Some(_) => {
                 return Json(json!({
                     "status": 409,
@@ -136,6 +319,18 @@ fn handle_create_predicate(
         }
     }
 
+    if let Some(ref filter) = filter {
+        let observers_db_conn = initialize_observers_db(observers_db_dir_path, ctx);
+        insert_predicate_filter(&predicate_uuid, filter, &observers_db_conn, ctx);
+    }
+    if let Some(ref sink) = sink {
+        let observers_db_conn = initialize_observers_db(observers_db_dir_path, ctx);
+        insert_predicate_sink(&predicate_uuid, sink, &observers_db_conn, ctx);
+    }
+    if let Some(priority) = scan_priority {
+        SCAN_QUEUE.set_priority(&predicate_uuid, priority);
+    }
+
     let background_job_tx = background_job_tx.inner();
     match background_job_tx.lock() {
         Ok(tx) => {
@@ -150,24 +345,127 @@ fn handle_create_predicate(
     }))
 }
 
+/// Evaluates a predicate on a few blocks, and returns the payloads it would receive. Nothing is
+/// registered nor persisted, and the action of the predicate is not triggered.
+#[post(
+    "/v1/observers/evaluate",
+    format = "application/json",
+    data = "<predicate>"
+)]
+async fn handle_evaluate_predicate(
+    predicate: Json<JsonValue>,
+    _caller: ApiCaller,
+    config: &State<crate::config::Config>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| info!(logger, "Handling HTTP POST /v1/observers/evaluate"));
+    let mut predicate = predicate.into_inner();
+    // Same body as a registration: settings only relevant to registered predicates are ignored
+    if let Some(predicate) = predicate.as_object_mut() {
+        predicate.remove("sink");
+        predicate.remove("scan_priority");
+    }
+    let filter = match predicate.as_object_mut().and_then(|p| p.remove("filter")) {
+        Some(filter) => match serde_json::from_value::<InscriptionFilter>(filter) {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                return Json(json!({
+                    "status": 422,
+                    "error": format!("invalid filter: {}", e.to_string()),
+                }))
+            }
+        },
+        None => None,
+    };
+    if let Some(Err(e)) = filter.as_ref().map(|f| f.validate()) {
+        return Json(json!({
+            "status": 422,
+            "error": e,
+        }));
+    }
+    let predicate = match serde_json::from_value::<ChainhookFullSpecification>(predicate) {
+        Ok(ChainhookFullSpecification::Bitcoin(predicate)) => predicate,
+        Ok(_) => {
+            return Json(json!({
+                "status": 422,
+                "error": "Only Bitcoin predicates can be evaluated",
+            }))
+        }
+        Err(e) => {
+            return Json(json!({
+                "status": 422,
+                "error": format!("invalid predicate: {}", e.to_string()),
+            }))
+        }
+    };
+    let predicate_spec =
+        match predicate.into_selected_network_specification(&config.network.bitcoin_network) {
+            Ok(spec) => spec,
+            Err(e) => {
+                return Json(json!({
+                    "status": 422,
+                    "error": e,
+                }))
+            }
+        };
+
+    let config = config.inner().clone();
+    let ctx = ctx.inner().clone();
+    let res = rocket::tokio::task::spawn_blocking(move || {
+        hiro_system_kit::nestable_block_on(dry_run_bitcoin_predicate(
+            &predicate_spec,
+            filter.as_ref(),
+            true,
+            &config,
+            &ctx,
+        ))
+    })
+    .await;
+    match res {
+        Ok(Ok(payloads)) => Json(json!({
+            "status": 200,
+            "result": payloads,
+        })),
+        Ok(Err(e)) => Json(json!({
+            "status": 422,
+            "error": e,
+        })),
+        Err(e) => Json(json!({
+            "status": 500,
+            "error": format!("Unable to evaluate predicate: {e}"),
+        })),
+    }
+}
+
 #[get("/v1/observers/<predicate_uuid>", format = "application/json")]
 fn handle_get_predicate(
     predicate_uuid: String,
+    caller: ApiCaller,
     observers_db_dir_path: &State<PathBuf>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
     ctx.try_log(|logger| info!(logger, "Handling HTTP GET /v1/observers/{}", predicate_uuid));
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
 
     match open_readonly_observers_db_conn(observers_db_dir_path, ctx) {
         Ok(mut predicates_db_conn) => {
-            let key: String = format!("{}", ChainhookSpecification::bitcoin_key(&predicate_uuid));
-            let entry = match find_observer_with_uuid(&key, &mut predicates_db_conn, &ctx) {
+            let entry = match find_observer_with_uuid(
+                &predicate_uuid,
+                &mut predicates_db_conn,
+                &ctx,
+            ) {
                 Some((ChainhookSpecification::Bitcoin(spec), report)) => json!({
                     "chain": "bitcoin",
                     "uuid": spec.uuid,
                     "network": spec.network,
                     "predicate": spec.predicate,
+                    "filter": find_predicate_filter(&spec.uuid, &predicates_db_conn, &ctx),
+                    "sink": find_predicate_sink(&spec.uuid, &predicates_db_conn, &ctx),
                     "status": report,
+                    "deliveries": find_delivery_counters(&spec.uuid, observers_db_dir_path, &ctx),
                     "enabled": spec.enabled,
                 }),
                 _ => {
@@ -191,6 +489,8 @@ fn handle_get_predicate(
 #[delete("/v1/observers/<predicate_uuid>", format = "application/json")]
 fn handle_delete_bitcoin_predicate(
     predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
     background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
     ctx: &State<Context>,
 ) -> Json<JsonValue> {
@@ -200,6 +500,24 @@ fn handle_delete_bitcoin_predicate(
             "Handling HTTP DELETE /v1/observers/{}", predicate_uuid
         )
     });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+
+    // Paused predicates are no longer registered with the observer
+    if let Ok(observers_db_conn) = open_readonly_observers_db_conn(observers_db_dir_path, ctx) {
+        if let Some((_, report)) = find_observer_with_uuid(&predicate_uuid, &observers_db_conn, ctx)
+        {
+            if report.phase == ObserverPhase::Paused {
+                remove_predicate_data(&predicate_uuid, observers_db_dir_path, ctx);
+                return Json(json!({
+                    "status": 200,
+                    "result": "Ok",
+                }));
+            }
+        }
+    }
 
     let background_job_tx = background_job_tx.inner();
     match background_job_tx.lock() {
@@ -215,9 +533,636 @@ fn handle_delete_bitcoin_predicate(
     }))
 }
 
+/// Stops the evaluation of a predicate (scan and streaming), keeping its progress and deliveries.
+fn pause_predicate(
+    predicate_uuid: &str,
+    observers_db_dir_path: &PathBuf,
+    background_job_tx: &Arc<Mutex<Sender<ObserverCommand>>>,
+    ctx: &Context,
+) -> Result<(ChainhookSpecification, ObserverReport), JsonValue> {
+    let observers_db_conn = initialize_observers_db(observers_db_dir_path, ctx);
+    let Some((spec, report)) = find_observer_with_uuid(predicate_uuid, &observers_db_conn, ctx)
+    else {
+        return Err(json!({
+            "status": 404,
+        }));
+    };
+    update_observer_phase(
+        predicate_uuid,
+        ObserverPhase::Paused,
+        None,
+        &observers_db_conn,
+        ctx,
+    );
+    update_observer_streaming_enabled(predicate_uuid, false, &observers_db_conn, ctx);
+    SCAN_QUEUE.cancel(predicate_uuid);
+    if report.phase != ObserverPhase::Paused {
+        if let Ok(tx) = background_job_tx.lock() {
+            let _ = tx.send(ObserverCommand::DeregisterBitcoinPredicate(
+                predicate_uuid.to_string(),
+            ));
+        }
+    }
+    Ok((spec, report))
+}
+
+/// Registers a paused predicate again, to be evaluated from `start_block`.
+fn register_paused_predicate(
+    spec: ChainhookSpecification,
+    start_block: u64,
+    background_job_tx: &Arc<Mutex<Sender<ObserverCommand>>>,
+) -> Result<(), JsonValue> {
+    let ChainhookSpecification::Bitcoin(spec) = spec else {
+        return Err(json!({
+            "status": 422,
+            "error": "Only Bitcoin predicates can be resumed",
+        }));
+    };
+    let full_spec = build_bitcoin_full_specification(spec, start_block);
+    if let Ok(tx) = background_job_tx.lock() {
+        let _ = tx.send(ObserverCommand::RegisterPredicate(
+            ChainhookFullSpecification::Bitcoin(full_spec),
+        ));
+    }
+    Ok(())
+}
+
+#[post("/v1/observers/<predicate_uuid>/pause")]
+fn handle_pause_predicate(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/pause", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match pause_predicate(
+        &predicate_uuid,
+        observers_db_dir_path,
+        background_job_tx,
+        ctx,
+    ) {
+        Ok(_) => Json(json!({
+            "status": 200,
+            "result": "Ok",
+        })),
+        Err(e) => Json(e),
+    }
+}
+
+#[post("/v1/observers/<predicate_uuid>/resume")]
+fn handle_resume_predicate(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/resume", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    let observers_db_conn = initialize_observers_db(observers_db_dir_path, ctx);
+    let (spec, report) = match find_observer_with_uuid(&predicate_uuid, &observers_db_conn, ctx) {
+        Some((_, report)) if report.phase != ObserverPhase::Paused => {
+            return Json(json!({
+                "status": 409,
+                "error": "Predicate not paused",
+            }))
+        }
+        Some(entry) => entry,
+        None => {
+            return Json(json!({
+                "status": 404,
+            }))
+        }
+    };
+    let start_block = report.last_block_height_update + 1;
+    match register_paused_predicate(spec, start_block, background_job_tx) {
+        Ok(()) => Json(json!({
+            "status": 200,
+            "result": { "start_block": start_block },
+        })),
+        Err(e) => Json(e),
+    }
+}
+
+#[post("/v1/observers/<predicate_uuid>/rescan?<from>")]
+fn handle_rescan_predicate(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    from: u64,
+    observers_db_dir_path: &State<PathBuf>,
+    background_job_tx: &State<Arc<Mutex<Sender<ObserverCommand>>>>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/rescan?from={}", predicate_uuid, from
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    let (spec, _) = match pause_predicate(
+        &predicate_uuid,
+        observers_db_dir_path,
+        background_job_tx,
+        ctx,
+    ) {
+        Ok(entry) => entry,
+        Err(e) => return Json(e),
+    };
+    match register_paused_predicate(spec, from, background_job_tx) {
+        Ok(()) => Json(json!({
+            "status": 200,
+            "result": { "start_block": from },
+        })),
+        Err(e) => Json(e),
+    }
+}
+
+#[get("/v1/scans", format = "application/json")]
+fn handle_get_scans(
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| info!(logger, "Handling HTTP GET /v1/scans"));
+    let entries = SCAN_QUEUE
+        .entries()
+        .into_iter()
+        .filter(|entry| {
+            authorize_predicate_access(&caller, &entry.uuid, observers_db_dir_path, ctx).is_ok()
+        })
+        .collect::<Vec<_>>();
+    Json(json!({
+        "status": 200,
+        "result": entries,
+    }))
+}
+
+/// Metrics are labelled with the predicates: only admins can read them here, the
+/// `prometheus_monitoring_port` serves them without authentication.
+#[get("/metrics")]
+fn handle_get_api_metrics(caller: ApiCaller) -> (Status, (ContentType, String)) {
+    if caller != ApiCaller::Admin {
+        return (
+            Status::Forbidden,
+            (ContentType::Plain, "Reserved to admins".to_string()),
+        );
+    }
+    encode_metrics()
+}
+
+#[get("/v1/tenants", format = "application/json")]
+fn handle_get_tenants(
+    caller: ApiCaller,
+    api_keys: &State<ApiKeys>,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| info!(logger, "Handling HTTP GET /v1/tenants"));
+    if caller != ApiCaller::Admin {
+        return Json(json!({
+            "status": 403,
+            "error": "Reserved to admins",
+        }));
+    }
+    let observers = match open_readonly_observers_db_conn(observers_db_dir_path, ctx) {
+        Ok(db_conn) => find_all_observers(&db_conn, ctx),
+        Err(_) => vec![],
+    };
+    let tenants = api_keys
+        .tenants()
+        .into_iter()
+        .map(|(owner_uuid, max_number_of_bitcoin_predicates)| {
+            let number_of_predicates = observers
+                .iter()
+                .filter(|(p, _)| predicate_owner(p) == Some(&owner_uuid))
+                .count();
+            json!({
+                "owner_uuid": owner_uuid,
+                "number_of_predicates": number_of_predicates,
+                "max_number_of_bitcoin_predicates": max_number_of_bitcoin_predicates,
+            })
+        })
+        .collect::<Vec<_>>();
+    Json(json!({
+        "status": 200,
+        "result": tenants,
+    }))
+}
+
+/// Streams the apply and rollback events of the inscriptions matching `filter` (JSON encoded), as
+/// Server-Sent Events. Streams start at the next block, unless resumed `from_block` or after a `cursor`
+/// (or the `Last-Event-ID` sent by reconnecting `EventSource` clients).
+#[get("/v1/stream?<from_block>&<cursor>&<filter>")]
+fn handle_stream_inscription_events(
+    from_block: Option<u64>,
+    cursor: Option<String>,
+    filter: Option<String>,
+    last_event_id: LastEventId,
+    _caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], Json<JsonValue>> {
+    ctx.try_log(|logger| info!(logger, "Handling HTTP GET /v1/stream"));
+    let filter = match filter {
+        Some(filter) => match serde_json::from_str::<InscriptionFilter>(&filter) {
+            Ok(filter) => match filter.validate() {
+                Ok(_) => Some(filter),
+                Err(e) => {
+                    return Err(Json(json!({
+                        "status": 422,
+                        "error": format!("Unable to handle request: {e}"),
+                    })))
+                }
+            },
+            Err(e) => {
+                return Err(Json(json!({
+                    "status": 422,
+                    "error": format!("Unable to parse filter: {e}"),
+                })))
+            }
+        },
+        None => None,
+    };
+    let position = match (cursor.or(last_event_id.0), from_block) {
+        (Some(cursor), _) => match cursor.parse::<StreamCursor>() {
+            Ok(cursor) => StreamPosition::Cursor(cursor),
+            Err(e) => {
+                return Err(Json(json!({
+                    "status": 422,
+                    "error": format!("Unable to handle request: {e}"),
+                })))
+            }
+        },
+        (None, Some(block_height)) => StreamPosition::FromBlock(block_height),
+        (None, None) => StreamPosition::Tip,
+    };
+    let (replay, last_entry, mut receiver) = match LIVE_EVENTS.subscribe(&position) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            return Err(Json(json!({
+                "status": 410,
+                "error": format!("{e}: register a predicate to scan older blocks"),
+            })))
+        }
+    };
+
+    let mut subscription =
+        LiveSubscription::new(uuid::Uuid::new_v4().to_string(), filter, &position);
+    subscription.last_entry = last_entry;
+    let inscriptions_db_conn = match subscription.requires_inscriptions_db() {
+        true => open_readonly_ordhook_db_conn(observers_db_dir_path, ctx).ok(),
+        false => None,
+    };
+    let ctx = ctx.inner().clone();
+    ctx.try_log(|logger| info!(logger, "Live stream {} opened", subscription.id));
+
+    Ok(EventStream! {
+        let mut pending = VecDeque::from(replay);
+        loop {
+            let entry = match pending.pop_front() {
+                Some(entry) => entry,
+                None => tokio::select! {
+                    received = receiver.recv() => match received {
+                        Ok(entry) => entry,
+                        Err(RecvError::Lagged(_)) => {
+                            // Slow consumer: catch up from the history, or give up with the cursor to resume from
+                            match LIVE_EVENTS.resubscribe(&subscription.last_entry) {
+                                Ok((replay, last_entry, resubscribed)) => {
+                                    pending.extend(replay);
+                                    receiver = resubscribed;
+                                    if pending.is_empty() {
+                                        subscription.last_entry = last_entry;
+                                    }
+                                    continue;
+                                }
+                                Err(e) => {
+                                    yield Event::json(&json!({
+                                        "error": e,
+                                        "cursor": subscription.last_cursor.as_ref().map(|c| c.to_string()),
+                                    }))
+                                    .event("overflow");
+                                    break;
+                                }
+                            }
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = &mut shutdown => break,
+                },
+            };
+            let events = subscription.process(&entry, inscriptions_db_conn.as_ref(), &ctx);
+            for event in events.into_iter() {
+                let cursor = StreamCursor::from_event(&event);
+                yield Event::json(&event)
+                    .event(event_type_name(event.event_type))
+                    .id(cursor.to_string());
+            }
+        }
+        ctx.try_log(|logger| info!(logger, "Live stream {} closed", subscription.id));
+    })
+}
+
+#[post("/v1/observers/<predicate_uuid>/scan_priority?<priority>")]
+fn handle_set_predicate_scan_priority(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    priority: String,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/scan_priority?priority={}",
+            predicate_uuid,
+            priority
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match priority.parse::<ScanPriority>() {
+        Ok(priority) => {
+            SCAN_QUEUE.set_priority(&predicate_uuid, priority);
+            Json(json!({
+                "status": 200,
+                "result": priority,
+            }))
+        }
+        Err(e) => Json(json!({
+            "status": 422,
+            "error": e,
+        })),
+    }
+}
+
+#[get("/v1/observers/<predicate_uuid>/outbox", format = "application/json")]
+fn handle_get_predicate_outbox(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP GET /v1/observers/{}/outbox", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => Json(json!({
+            "status": 200,
+            "result": get_outbox_report(&predicate_uuid, &outbox_db_conn, ctx),
+        })),
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+#[get(
+    "/v1/observers/<predicate_uuid>/outbox/dead",
+    format = "application/json"
+)]
+fn handle_get_predicate_dead_letters(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP GET /v1/observers/{}/outbox/dead", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => {
+            let deliveries = find_deliveries_with_status(
+                &predicate_uuid,
+                DeliveryStatus::Dead,
+                1000,
+                &outbox_db_conn,
+                ctx,
+            );
+            Json(json!({
+                "status": 200,
+                "result": deliveries,
+            }))
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+#[post("/v1/observers/<predicate_uuid>/outbox/replay?<from_block>")]
+fn handle_replay_predicate_deliveries(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    from_block: Option<u64>,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/outbox/replay", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => {
+            let replayed = replay_deliveries(&predicate_uuid, from_block, &outbox_db_conn, ctx);
+            Json(json!({
+                "status": 200,
+                "result": { "replayed": replayed },
+            }))
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+#[post("/v1/observers/<predicate_uuid>/outbox/<delivery_id>/ack")]
+fn handle_acknowledge_predicate_delivery(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    delivery_id: u64,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/outbox/{}/ack", predicate_uuid, delivery_id
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => {
+            if acknowledge_delivery(&predicate_uuid, delivery_id, &outbox_db_conn, ctx) {
+                Json(json!({
+                    "status": 200,
+                    "result": "Ok",
+                }))
+            } else {
+                Json(json!({
+                    "status": 404,
+                }))
+            }
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+#[derive(Deserialize)]
+struct SigningSecretRotation {
+    /// Generated when not specified.
+    secret: Option<String>,
+    /// Period during which the deliveries keep being signed with the previous secrets too.
+    grace_period_seconds: Option<u64>,
+}
+
+#[post(
+    "/v1/observers/<predicate_uuid>/signing_secret",
+    format = "application/json",
+    data = "<rotation>"
+)]
+fn handle_rotate_predicate_signing_secret(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    rotation: Json<SigningSecretRotation>,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP POST /v1/observers/{}/signing_secret", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    let rotation = rotation.into_inner();
+    let secret = match rotation.secret {
+        Some(secret) if secret.len() < 16 => {
+            return Json(json!({
+                "status": 422,
+                "error": "Signing secret must be at least 16 characters long",
+            }))
+        }
+        Some(secret) => secret,
+        None => generate_signing_secret(),
+    };
+    let grace_period = Duration::from_secs(rotation.grace_period_seconds.unwrap_or(86400));
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => {
+            rotate_signing_secret(&predicate_uuid, &secret, grace_period, &outbox_db_conn, ctx);
+            Json(json!({
+                "status": 200,
+                "result": { "secret": secret },
+            }))
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+#[delete("/v1/observers/<predicate_uuid>/signing_secret")]
+fn handle_delete_predicate_signing_secrets(
+    predicate_uuid: String,
+    caller: ApiCaller,
+    observers_db_dir_path: &State<PathBuf>,
+    ctx: &State<Context>,
+) -> Json<JsonValue> {
+    ctx.try_log(|logger| {
+        info!(
+            logger,
+            "Handling HTTP DELETE /v1/observers/{}/signing_secret", predicate_uuid
+        )
+    });
+    if let Err(e) = authorize_predicate_access(&caller, &predicate_uuid, observers_db_dir_path, ctx)
+    {
+        return e;
+    }
+    match open_readwrite_outbox_db_conn(observers_db_dir_path, ctx) {
+        Ok(outbox_db_conn) => {
+            remove_signing_secrets_for_predicate(&predicate_uuid, &outbox_db_conn, ctx);
+            Json(json!({
+                "status": 200,
+                "result": "Ok",
+            }))
+        }
+        Err(e) => Json(json!({
+            "status": 500,
+            "message": e,
+        })),
+    }
+}
+
+fn find_delivery_counters(
+    predicate_uuid: &str,
+    observers_db_dir_path: &PathBuf,
+    ctx: &Context,
+) -> Option<OutboxReport> {
+    let outbox_db_conn = open_readwrite_outbox_db_conn(observers_db_dir_path, ctx).ok()?;
+    Some(get_outbox_report(predicate_uuid, &outbox_db_conn, ctx))
+}
+
 fn serialized_predicate_with_status(
     predicate: &ChainhookSpecification,
     report: &ObserverReport,
+    deliveries: Option<&OutboxReport>,
 ) -> JsonValue {
     match (predicate, report) {
         (ChainhookSpecification::Stacks(spec), report) => json!({
@@ -234,6 +1179,7 @@ fn serialized_predicate_with_status(
             "network": spec.network,
             "predicate": spec.predicate,
             "status": report,
+            "deliveries": deliveries,
             "enabled": spec.enabled,
         }),
     }
@@ -0,0 +1,528 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use chainhook_sdk::{
+    types::{BitcoinBlockData, BlockIdentifier, OrdinalOperation},
+    utils::Context,
+};
+use lazy_static::lazy_static;
+use rocket::request::{FromRequest, Outcome, Request};
+use rusqlite::Connection;
+use tokio::sync::broadcast;
+
+use crate::{
+    scan::filters::InscriptionFilter,
+    sinks::{build_sink_events, SinkEvent, SinkEventType},
+};
+
+/// Number of blocks (applied or rolled back) kept in memory while the stream has subscribers, that
+/// subscribers can resume from (~1 day).
+pub const LIVE_HISTORY_BLOCKS: usize = 144;
+/// Number of blocks buffered for each subscriber. Subscribers falling further behind catch up from the history.
+const LIVE_CHANNEL_CAPACITY: usize = 16;
+
+lazy_static! {
+    /// Blocks applied and rolled back by the service, streamed to the subscribers of `/v1/stream`.
+    pub static ref LIVE_EVENTS: LiveEventHub = LiveEventHub::new(LIVE_HISTORY_BLOCKS);
+}
+
+#[derive(Clone, Debug)]
+pub struct LiveBlock {
+    pub event_type: SinkEventType,
+    pub block: Arc<BitcoinBlockData>,
+}
+
+/// Position of an event in the stream: `<apply|rollback>:<block_height>:<block_hash>:<tx_index>:<operation_index>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamCursor {
+    pub event_type: SinkEventType,
+    pub block_height: u64,
+    pub block_hash: String,
+    pub tx_index: usize,
+    pub operation_index: usize,
+}
+
+impl StreamCursor {
+    pub fn from_event(event: &SinkEvent) -> StreamCursor {
+        StreamCursor {
+            event_type: event.event_type,
+            block_height: event.block_height,
+            block_hash: event.block_hash.clone(),
+            tx_index: event.tx_index,
+            operation_index: event.operation_index,
+        }
+    }
+}
+
+impl fmt::Display for StreamCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}",
+            event_type_name(self.event_type),
+            self.block_height,
+            self.block_hash,
+            self.tx_index,
+            self.operation_index
+        )
+    }
+}
+
+impl FromStr for StreamCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split(':').collect::<Vec<_>>();
+        let invalid = || format!("invalid cursor: {s}");
+        let [event_type, block_height, block_hash, tx_index, operation_index] = parts[..] else {
+            return Err(invalid());
+        };
+        let event_type = match event_type {
+            "apply" => SinkEventType::Apply,
+            "rollback" => SinkEventType::Rollback,
+            _ => return Err(invalid()),
+        };
+        if block_hash.is_empty() {
+            return Err(invalid());
+        }
+        Ok(StreamCursor {
+            event_type,
+            block_height: block_height.parse().map_err(|_| invalid())?,
+            block_hash: block_hash.to_string(),
+            tx_index: tx_index.parse().map_err(|_| invalid())?,
+            operation_index: operation_index.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+pub fn event_type_name(event_type: SinkEventType) -> &'static str {
+    match event_type {
+        SinkEventType::Apply => "apply",
+        SinkEventType::Rollback => "rollback",
+    }
+}
+
+/// Where a subscription starts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamPosition {
+    /// Next block.
+    Tip,
+    /// First block applied at or above this height.
+    FromBlock(u64),
+    /// Event following this cursor.
+    Cursor(StreamCursor),
+}
+
+/// Index of the first history entry to replay for `position`, history entries being described by
+/// their `(event_type, block_height, block_hash)`.
+fn replay_start(
+    history: &[(SinkEventType, u64, &str)],
+    position: &StreamPosition,
+) -> Result<usize, String> {
+    match position {
+        StreamPosition::Tip => Ok(history.len()),
+        StreamPosition::FromBlock(block_height) => {
+            let Some(oldest) = history
+                .iter()
+                .find(|(event_type, _, _)| *event_type == SinkEventType::Apply)
+            else {
+                return Err(format!(
+                    "block #{block_height} is not available for streaming yet"
+                ));
+            };
+            if oldest.1 > *block_height {
+                return Err(format!(
+                    "block #{block_height} is no longer available for streaming (oldest block: #{})",
+                    oldest.1
+                ));
+            }
+            Ok(history
+                .iter()
+                .position(|(event_type, height, _)| {
+                    *event_type == SinkEventType::Apply && height >= block_height
+                })
+                .unwrap_or(history.len()))
+        }
+        StreamPosition::Cursor(cursor) => history
+            .iter()
+            .rposition(|(event_type, _, hash)| {
+                *event_type == cursor.event_type && *hash == cursor.block_hash
+            })
+            .ok_or(format!(
+                "cursor {cursor} is no longer available for streaming"
+            )),
+    }
+}
+
+/// Recent blocks, broadcasted to the live stream subscribers.
+pub struct LiveEventHub {
+    sender: broadcast::Sender<LiveBlock>,
+    history: Mutex<VecDeque<LiveBlock>>,
+    history_capacity: usize,
+}
+
+impl LiveEventHub {
+    pub fn new(history_capacity: usize) -> LiveEventHub {
+        let (sender, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        LiveEventHub {
+            sender,
+            history: Mutex::new(VecDeque::new()),
+            history_capacity,
+        }
+    }
+
+    fn is_applied(history: &VecDeque<LiveBlock>, block_hash: &str) -> bool {
+        history
+            .iter()
+            .rev()
+            .find(|entry| entry.block.block_identifier.hash == block_hash)
+            .map(|entry| entry.event_type == SinkEventType::Apply)
+            .unwrap_or(false)
+    }
+
+    fn publish(&self, history: &mut VecDeque<LiveBlock>, entry: LiveBlock) {
+        history.push_back(entry.clone());
+        while history.len() > self.history_capacity {
+            history.pop_front();
+        }
+        // Sent while holding the history lock, so that subscribers don't miss or duplicate blocks
+        let _ = self.sender.send(entry);
+    }
+
+    pub fn apply_block(&self, block: &BitcoinBlockData) {
+        let mut history = self.history.lock().unwrap();
+        if self.sender.receiver_count() == 0 {
+            // Nobody to stream to: a history with gaps could not be resumed from anyway
+            history.clear();
+            return;
+        }
+        if Self::is_applied(&history, &block.block_identifier.hash) {
+            return;
+        }
+        // Only the transactions with operations are kept, the other ones are never streamed
+        let block = BitcoinBlockData {
+            block_identifier: block.block_identifier.clone(),
+            parent_block_identifier: block.parent_block_identifier.clone(),
+            timestamp: block.timestamp,
+            transactions: block
+                .transactions
+                .iter()
+                .filter(|tx| !tx.metadata.ordinal_operations.is_empty())
+                .cloned()
+                .collect(),
+            metadata: block.metadata.clone(),
+        };
+        let entry = LiveBlock {
+            event_type: SinkEventType::Apply,
+            block: Arc::new(block),
+        };
+        self.publish(&mut history, entry);
+    }
+
+    /// Blocks applied before the service started were never streamed, and are not rolled back.
+    pub fn rollback_block(&self, block_identifier: &BlockIdentifier) {
+        let mut history = self.history.lock().unwrap();
+        if !Self::is_applied(&history, &block_identifier.hash) {
+            return;
+        }
+        let Some(block) = history
+            .iter()
+            .rev()
+            .find(|entry| entry.block.block_identifier.hash == block_identifier.hash)
+            .map(|entry| entry.block.clone())
+        else {
+            return;
+        };
+        let entry = LiveBlock {
+            event_type: SinkEventType::Rollback,
+            block,
+        };
+        self.publish(&mut history, entry);
+    }
+
+    /// Entries of the history to replay from `position`, followed by the blocks received by the returned
+    /// receiver. Also returns the last entry of the history, as the last entry processed once replayed.
+    pub fn subscribe(
+        &self,
+        position: &StreamPosition,
+    ) -> Result<
+        (
+            Vec<LiveBlock>,
+            Option<(SinkEventType, String)>,
+            broadcast::Receiver<LiveBlock>,
+        ),
+        String,
+    > {
+        let history = self.history.lock().unwrap();
+        let keys = history
+            .iter()
+            .map(|entry| {
+                (
+                    entry.event_type,
+                    entry.block.block_identifier.index,
+                    entry.block.block_identifier.hash.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let start = replay_start(&keys, position)?;
+        let replay = history.iter().skip(start).cloned().collect::<Vec<_>>();
+        let last_entry = history
+            .back()
+            .map(|entry| (entry.event_type, entry.block.block_identifier.hash.clone()));
+        Ok((replay, last_entry, self.sender.subscribe()))
+    }
+
+    /// Entries following the last entry processed by a subscriber that fell behind.
+    pub fn resubscribe(
+        &self,
+        last_entry: &Option<(SinkEventType, String)>,
+    ) -> Result<
+        (
+            Vec<LiveBlock>,
+            Option<(SinkEventType, String)>,
+            broadcast::Receiver<LiveBlock>,
+        ),
+        String,
+    > {
+        let history = self.history.lock().unwrap();
+        let start = match last_entry {
+            None => 0,
+            Some((event_type, block_hash)) => history
+                .iter()
+                .rposition(|entry| {
+                    entry.event_type == *event_type
+                        && entry.block.block_identifier.hash == *block_hash
+                })
+                .map(|index| index + 1)
+                .ok_or(format!("subscriber fell too far behind"))?,
+        };
+        let replay = history.iter().skip(start).cloned().collect::<Vec<_>>();
+        let last_entry = history
+            .back()
+            .map(|entry| (entry.event_type, entry.block.block_identifier.hash.clone()))
+            .or(last_entry.clone());
+        Ok((replay, last_entry, self.sender.subscribe()))
+    }
+}
+
+/// State of a live stream subscriber.
+pub struct LiveSubscription {
+    pub id: String,
+    filter: Option<InscriptionFilter>,
+    /// Events up to this cursor were already delivered before the client reconnected.
+    resume_after: Option<StreamCursor>,
+    /// Apply events delivered, by block hash, to be compensated if the block is rolled back.
+    delivered: HashMap<String, (u64, Vec<SinkEvent>)>,
+    pub last_entry: Option<(SinkEventType, String)>,
+    pub last_cursor: Option<StreamCursor>,
+}
+
+impl LiveSubscription {
+    pub fn new(
+        id: String,
+        filter: Option<InscriptionFilter>,
+        position: &StreamPosition,
+    ) -> LiveSubscription {
+        let resume_after = match position {
+            StreamPosition::Cursor(cursor) => Some(cursor.clone()),
+            _ => None,
+        };
+        LiveSubscription {
+            id,
+            filter,
+            resume_after: resume_after.clone(),
+            delivered: HashMap::new(),
+            last_entry: None,
+            last_cursor: resume_after,
+        }
+    }
+
+    pub fn requires_inscriptions_db(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    fn matches(
+        &self,
+        event: &SinkEvent,
+        block: &BitcoinBlockData,
+        inscriptions_db_conn: Option<&Connection>,
+        ctx: &Context,
+    ) -> bool {
+        let Some(ref filter) = self.filter else {
+            return true;
+        };
+        match event.operation {
+            OrdinalOperation::InscriptionRevealed(ref reveal) => block
+                .transactions
+                .iter()
+                .find(|tx| tx.transaction_identifier.hash == event.tx_id)
+                .map(|tx| filter.matches_reveal(reveal, tx))
+                .unwrap_or(false),
+            OrdinalOperation::InscriptionTransferred(ref transfer) => {
+                filter.matches_transfer(transfer, inscriptions_db_conn, ctx)
+            }
+        }
+    }
+
+    /// Events of `entry` to deliver to the subscriber.
+    pub fn process(
+        &mut self,
+        entry: &LiveBlock,
+        inscriptions_db_conn: Option<&Connection>,
+        ctx: &Context,
+    ) -> Vec<SinkEvent> {
+        let block = &entry.block;
+        let block_hash = block.block_identifier.hash.clone();
+        self.last_entry = Some((entry.event_type, block_hash.clone()));
+
+        let mut events = match (entry.event_type, self.delivered.remove(&block_hash)) {
+            // Compensate exactly what was delivered: the inscriptions revealed by this block may already
+            // be gone from the inscriptions db.
+            (SinkEventType::Rollback, Some((_, mut delivered))) => {
+                delivered.reverse();
+                for event in delivered.iter_mut() {
+                    event.event_type = SinkEventType::Rollback;
+                }
+                delivered
+            }
+            (event_type, _) => {
+                let transactions = block
+                    .transactions
+                    .iter()
+                    .filter(|tx| !tx.metadata.ordinal_operations.is_empty())
+                    .collect::<Vec<_>>();
+                build_sink_events(&self.id, event_type, block, &transactions)
+                    .into_iter()
+                    .filter(|event| self.matches(event, block, inscriptions_db_conn, ctx))
+                    .collect()
+            }
+        };
+
+        if entry.event_type == SinkEventType::Apply {
+            self.delivered.insert(
+                block_hash.clone(),
+                (block.block_identifier.index, events.clone()),
+            );
+            if self.delivered.len() > LIVE_HISTORY_BLOCKS {
+                if let Some(oldest) = self
+                    .delivered
+                    .iter()
+                    .min_by_key(|(_, (block_height, _))| *block_height)
+                    .map(|(hash, _)| hash.clone())
+                {
+                    self.delivered.remove(&oldest);
+                }
+            }
+        }
+
+        if let Some(cursor) = self.resume_after.take() {
+            if cursor.event_type == entry.event_type && cursor.block_hash == block_hash {
+                if let Some(index) = events.iter().position(|event| {
+                    event.tx_index == cursor.tx_index
+                        && event.operation_index == cursor.operation_index
+                }) {
+                    events.drain(..=index);
+                }
+            }
+        }
+        if let Some(event) = events.last() {
+            self.last_cursor = Some(StreamCursor::from_event(event));
+        }
+        events
+    }
+}
+
+/// Cursor sent back by `EventSource` clients when reconnecting.
+pub struct LastEventId(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(LastEventId(
+            request
+                .headers()
+                .get_one("last-event-id")
+                .map(|id| id.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chainhook_sdk::indexer::bitcoin::BitcoinBlockFullBreakdown;
+
+    use super::*;
+    use crate::{
+        config::Config,
+        core::protocol::inscription_parsing::parse_inscriptions_and_standardize_block,
+        utils::regtest_fixtures::regtest_corpus,
+    };
+
+    #[test]
+    fn keeps_the_operations_of_blocks_while_subscribed() {
+        let ctx = Context::empty();
+        let config = Config::devnet_default();
+        let corpus = regtest_corpus();
+        let block: BitcoinBlockFullBreakdown =
+            serde_json::from_value(corpus.chain.blocks()[1].clone()).unwrap();
+        let block =
+            parse_inscriptions_and_standardize_block(block, &config.network.bitcoin_network, &ctx)
+                .unwrap();
+
+        let hub = LiveEventHub::new(LIVE_HISTORY_BLOCKS);
+        hub.apply_block(&block);
+        assert!(hub.subscribe(&StreamPosition::FromBlock(0)).is_err());
+
+        let (_, _, _receiver) = hub.subscribe(&StreamPosition::Tip).unwrap();
+        hub.apply_block(&block);
+        let (replay, _, _) = hub.subscribe(&StreamPosition::FromBlock(0)).unwrap();
+        assert_eq!(replay.len(), 1);
+        let transactions = &replay[0].block.transactions;
+        assert!(!transactions.is_empty() && transactions.len() < block.transactions.len());
+        assert!(transactions
+            .iter()
+            .all(|tx| !tx.metadata.ordinal_operations.is_empty()));
+    }
+
+    #[test]
+    fn resumes_from_block_height_or_cursor() {
+        let cursor: StreamCursor = "rollback:840001:00000000b:12:0".parse().unwrap();
+        assert_eq!(cursor.to_string(), "rollback:840001:00000000b:12:0");
+        assert!("apply:840001:00000000b:12".parse::<StreamCursor>().is_err());
+
+        let history = vec![
+            (SinkEventType::Apply, 840000, "00000000a"),
+            (SinkEventType::Apply, 840001, "00000000b"),
+            (SinkEventType::Rollback, 840001, "00000000b"),
+            (SinkEventType::Apply, 840001, "00000000c"),
+        ];
+        assert_eq!(replay_start(&history, &StreamPosition::Tip), Ok(4));
+        assert_eq!(
+            replay_start(&history, &StreamPosition::FromBlock(840001)),
+            Ok(1)
+        );
+        assert_eq!(
+            replay_start(&history, &StreamPosition::FromBlock(840002)),
+            Ok(4)
+        );
+        assert!(replay_start(&history, &StreamPosition::FromBlock(839999)).is_err());
+        assert_eq!(
+            replay_start(&history, &StreamPosition::Cursor(cursor.clone())),
+            Ok(2)
+        );
+        let mut cursor = cursor;
+        cursor.event_type = SinkEventType::Apply;
+        assert_eq!(
+            replay_start(&history, &StreamPosition::Cursor(cursor.clone())),
+            Ok(1)
+        );
+        cursor.block_hash = "00000000d".into();
+        assert!(replay_start(&history, &StreamPosition::Cursor(cursor)).is_err());
+    }
+}
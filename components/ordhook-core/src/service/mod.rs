@@ -1,6 +1,10 @@
+mod auth;
 mod http_api;
+mod live_stream;
 pub mod observers;
+pub mod outbox;
 mod runloops;
+pub(crate) mod scan_queue;
 
 use crate::config::{Config, PredicatesApi};
 use crate::core::pipeline::download_and_pipeline_blocks;
@@ -13,22 +17,31 @@ use crate::core::protocol::inscription_parsing::{
     parse_inscriptions_in_standardized_block,
 };
 use crate::core::protocol::inscription_sequencing::SequenceCursor;
+use crate::core::traversals_cache::TraversalsCache;
 use crate::core::{new_traversals_lazy_cache, should_sync_ordhook_db, should_sync_rocks_db};
 use crate::db::{
-    delete_data_in_ordhook_db, insert_entry_in_blocks, open_ordhook_db_conn_rocks_db_loop,
-    open_readwrite_ordhook_db_conn, open_readwrite_ordhook_dbs, update_inscriptions_with_block,
-    update_locations_with_block, update_sequence_metadata_with_block, LazyBlock,
-    LazyBlockTransaction,
+    delete_data_in_ordhook_db, find_last_checkpoint, insert_checkpoint_in_blocks,
+    insert_entry_in_blocks, open_ordhook_db_conn_rocks_db_loop, open_readwrite_ordhook_db_conn,
+    open_readwrite_ordhook_dbs, update_inscriptions_with_block, update_locations_with_block,
+    update_sequence_metadata_with_block, Checkpoint, LazyBlock,
 };
-use crate::scan::bitcoin::process_block_with_predicates;
+use crate::scan::bitcoin::{process_block_with_predicates, process_chain_event_with_predicates};
+use crate::service::auth::ApiKeys;
 use crate::service::http_api::start_predicate_api_server;
+use crate::service::live_stream::LIVE_EVENTS;
 use crate::service::observers::{
-    create_and_consolidate_chainhook_config_with_predicates, insert_entry_in_observers,
-    open_readwrite_observers_db_conn, remove_entry_from_observers, update_observer_progress,
-    update_observer_streaming_enabled, ObserverReport,
+    create_and_consolidate_chainhook_config_with_predicates, find_all_observers,
+    find_observer_with_uuid, insert_entry_in_observers, open_readwrite_observers_db_conn,
+    remove_predicate_data, update_observer_phase, update_observer_progress,
+    update_observer_streaming_enabled, ObserverPhase, ObserverReport,
 };
+use crate::service::outbox::start_outbox_delivery_runloop;
 use crate::service::runloops::start_bitcoin_scan_runloop;
+use crate::service::scan_queue::SCAN_QUEUE;
+use crate::utils::monitoring::{start_serving_prometheus_metrics, METRICS};
+use crate::utils::shutdown::is_shutdown_requested;
 
+use chainhook_sdk::bitcoincore_rpc::{Client, RpcApi};
 use chainhook_sdk::chainhooks::bitcoin::BitcoinChainhookOccurrencePayload;
 use chainhook_sdk::chainhooks::types::{
     BitcoinChainhookSpecification, ChainhookFullSpecification, ChainhookSpecification,
@@ -37,17 +50,18 @@ use chainhook_sdk::observer::{
     start_event_observer, BitcoinBlockDataCached, DataHandlerEvent, EventObserverConfig,
     HandleBlock, ObserverCommand, ObserverEvent, ObserverSidecar,
 };
-use chainhook_sdk::types::{BitcoinBlockData, BlockIdentifier};
+use chainhook_sdk::types::{BitcoinBlockData, BitcoinChainEvent, BlockIdentifier};
 use chainhook_sdk::utils::{BlockHeights, Context};
 use crossbeam_channel::unbounded;
 use crossbeam_channel::{select, Sender};
-use dashmap::DashMap;
-use fxhash::FxHasher;
 
 use std::collections::BTreeMap;
-use std::hash::BuildHasherDefault;
 use std::sync::mpsc::channel;
 use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+const CHAIN_TIP_MONITORING_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct Service {
     pub config: Config,
@@ -68,8 +82,59 @@ impl Service {
     ) -> Result<(), String> {
         let mut event_observer_config = self.config.get_event_observer_config();
 
+        if let Some(port) = self.config.monitoring.prometheus_monitoring_port {
+            info!(
+                self.ctx.expect_logger(),
+                "Serving Prometheus metrics on port {port}"
+            );
+            let ctx = self.ctx.clone();
+            let _ = hiro_system_kit::thread_named("Prometheus monitoring").spawn(move || {
+                let future = start_serving_prometheus_metrics(port);
+                if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+                    ctx.try_log(|logger| {
+                        error!(
+                            logger,
+                            "Unable to serve Prometheus metrics: {}",
+                            e.to_string()
+                        )
+                    });
+                }
+            });
+        }
+        // Tip height and lag gauges, read on the Prometheus port or through the predicates API
+        if self.config.monitoring.prometheus_monitoring_port.is_some()
+            || self.config.is_http_api_enabled()
+        {
+            start_chain_tip_monitoring(&self.config, &self.ctx);
+        }
+
+        // Retry the webhook payloads left pending by previous runs and scans
+        start_outbox_delivery_runloop(&self.config, &self.ctx);
+
+        let blocks_db = open_ordhook_db_conn_rocks_db_loop(
+            false,
+            &self.config.expected_cache_path(),
+            &self.ctx,
+        );
+        for checkpoint in [Checkpoint::Archived, Checkpoint::Indexed] {
+            if let Some(block_height) = find_last_checkpoint(checkpoint, &blocks_db) {
+                info!(
+                    self.ctx.expect_logger(),
+                    "Resuming from {:?} checkpoint recorded at block #{block_height}", checkpoint
+                );
+            }
+        }
+        drop(blocks_db);
+
         // Catch-up with chain tip
         let chain_tip_height = self.catch_up_with_chain_tip(false).await?;
+        if is_shutdown_requested() {
+            info!(
+                self.ctx.expect_logger(),
+                "Service stopped before reaching chain tip"
+            );
+            return Ok(());
+        }
         info!(
             self.ctx.expect_logger(),
             "Database up to date, service will start streaming blocks"
@@ -116,6 +181,15 @@ impl Service {
             inner_ctx,
         );
 
+        // On shutdown, the observer terminates once the block being applied is committed
+        let moved_observer_command_tx = observer_command_tx.clone();
+        let _ = hiro_system_kit::thread_named("Shutdown watcher").spawn(move || {
+            while !is_shutdown_requested() {
+                sleep(Duration::from_millis(500));
+            }
+            let _ = moved_observer_command_tx.send(ObserverCommand::Terminate);
+        });
+
         // If HTTP Predicates API is on, we start:
         // - Thread pool in charge of performing replays
         // - API server
@@ -219,15 +293,9 @@ impl Service {
         let (bitcoin_scan_op_tx, bitcoin_scan_op_rx) = crossbeam_channel::unbounded();
         let ctx = self.ctx.clone();
         let config = self.config.clone();
-        let observer_command_tx_moved = observer_command_tx.clone();
         let _ = hiro_system_kit::thread_named("Bitcoin scan runloop")
             .spawn(move || {
-                start_bitcoin_scan_runloop(
-                    &config,
-                    bitcoin_scan_op_rx,
-                    observer_command_tx_moved,
-                    &ctx,
-                );
+                start_bitcoin_scan_runloop(&config, bitcoin_scan_op_rx, &ctx);
             })
             .expect("unable to spawn thread");
 
@@ -236,14 +304,27 @@ impl Service {
                 self.ctx.expect_logger(),
                 "Listening on port {} for chainhook predicate registrations", api_config.http_port
             );
+            let api_keys = ApiKeys::new(
+                api_config,
+                self.config.limits.max_number_of_bitcoin_predicates,
+            );
+            if !api_keys.is_enabled() {
+                warn!(
+                    self.ctx.expect_logger(),
+                    "No API keys configured in [http_api], predicates API open to any client"
+                );
+            }
             let ctx = self.ctx.clone();
             let api_config = api_config.clone();
             let moved_observer_command_tx = observer_command_tx.clone();
             let db_dir_path = self.config.expected_cache_path();
+            let config = self.config.clone();
             // Test and initialize a database connection
             let _ = hiro_system_kit::thread_named("HTTP Predicate API").spawn(move || {
                 let future = start_predicate_api_server(
                     api_config.http_port,
+                    api_keys,
+                    config,
                     db_dir_path,
                     moved_observer_command_tx,
                     ctx,
@@ -283,7 +364,22 @@ impl Service {
                             continue;
                         }
                     };
-                    let report = ObserverReport::default();
+                    let report = match (
+                        find_observer_with_uuid(&spec.uuid(), &observers_db_conn, &self.ctx),
+                        &spec,
+                    ) {
+                        // Resumed or re-scanned predicate: keep reporting its progress
+                        (Some(_), ChainhookSpecification::Bitcoin(predicate_spec)) => {
+                            ObserverReport {
+                                last_block_height_update: predicate_spec
+                                    .start_block
+                                    .unwrap_or(1)
+                                    .saturating_sub(1),
+                                ..Default::default()
+                            }
+                        }
+                        _ => ObserverReport::default(),
+                    };
                     insert_entry_in_observers(&spec, &report, &observers_db_conn, &self.ctx);
                     match spec {
                         ChainhookSpecification::Stacks(_predicate_spec) => {}
@@ -313,6 +409,13 @@ impl Service {
                         &observers_db_conn,
                         &self.ctx,
                     );
+                    update_observer_phase(
+                        &spec.uuid(),
+                        ObserverPhase::Streaming,
+                        None,
+                        &observers_db_conn,
+                        &self.ctx,
+                    );
                 }
                 ObserverEvent::PredicateDeregistered(spec) => {
                     let observers_db_conn = match open_readwrite_observers_db_conn(
@@ -329,32 +432,34 @@ impl Service {
                             continue;
                         }
                     };
-                    remove_entry_from_observers(&spec.uuid(), &observers_db_conn, &self.ctx);
+                    // Paused predicates are only deregistered from the observer, and keep their state
+                    if let Some((_, report)) =
+                        find_observer_with_uuid(&spec.uuid(), &observers_db_conn, &self.ctx)
+                    {
+                        if report.phase == ObserverPhase::Paused {
+                            SCAN_QUEUE.cancel(&spec.uuid());
+                            update_observer_streaming_enabled(
+                                &spec.uuid(),
+                                false,
+                                &observers_db_conn,
+                                &self.ctx,
+                            );
+                            continue;
+                        }
+                    }
+                    // Stop any replay still posting to the predicate
+                    SCAN_QUEUE.forget(&spec.uuid());
+                    remove_predicate_data(
+                        &spec.uuid(),
+                        &self.config.expected_cache_path(),
+                        &self.ctx,
+                    );
                 }
+                ObserverEvent::BitcoinChainEvent((chain_event, _)) => {
+                    self.process_streamed_chain_event(&chain_event, observer_command_tx);
+                }
+                // Only triggered by the internal trigger, user predicates are disabled in the observer
                 ObserverEvent::BitcoinPredicateTriggered(data) => {
-                    if let Some(ref tip) = data.apply.last() {
-                        let observers_db_conn = match open_readwrite_observers_db_conn(
-                            &self.config.expected_cache_path(),
-                            &self.ctx,
-                        ) {
-                            Ok(con) => con,
-                            Err(e) => {
-                                error!(
-                                    self.ctx.expect_logger(),
-                                    "unable to update observer: {}",
-                                    e.to_string()
-                                );
-                                continue;
-                            }
-                        };
-                        let last_block_height_update = tip.block.block_identifier.index;
-                        update_observer_progress(
-                            &data.chainhook.uuid,
-                            last_block_height_update,
-                            &observers_db_conn,
-                            &self.ctx,
-                        )
-                    }
                     if let Some(ref tx) = predicate_activity_relayer {
                         let _ = tx.send(data);
                     }
@@ -370,6 +475,83 @@ impl Service {
         Ok(())
     }
 
+    /// Evaluates the predicates streaming new blocks on `chain_event`. These predicates are registered
+    /// disabled in the observer: their payloads go through the filters, the sinks and the signed outbox
+    /// deliveries, like the payloads of scans.
+    fn process_streamed_chain_event(
+        &self,
+        chain_event: &BitcoinChainEvent,
+        observer_command_tx: &std::sync::mpsc::Sender<ObserverCommand>,
+    ) {
+        let chain_tip = match chain_event {
+            BitcoinChainEvent::ChainUpdatedWithBlocks(data) => data.new_blocks.last(),
+            BitcoinChainEvent::ChainUpdatedWithReorg(data) => data.blocks_to_apply.last(),
+        };
+        let Some(chain_tip) = chain_tip.map(|block| block.block_identifier.index) else {
+            return;
+        };
+        let observers_db_conn =
+            match open_readwrite_observers_db_conn(&self.config.expected_cache_path(), &self.ctx) {
+                Ok(con) => con,
+                Err(e) => {
+                    error!(
+                        self.ctx.expect_logger(),
+                        "unable to evaluate streamed blocks: {}",
+                        e.to_string()
+                    );
+                    return;
+                }
+            };
+        let predicates = find_all_observers(&observers_db_conn, &self.ctx)
+            .into_iter()
+            .filter_map(|(spec, report)| match spec {
+                ChainhookSpecification::Bitcoin(spec)
+                    if report.streaming_enabled && report.phase == ObserverPhase::Streaming =>
+                {
+                    Some(spec)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if predicates.is_empty() {
+            return;
+        }
+
+        let event_observer_config = self.config.get_event_observer_config();
+        let predicates_ref = predicates.iter().collect::<Vec<_>>();
+        let future = process_chain_event_with_predicates(
+            chain_event,
+            &predicates_ref,
+            "stream",
+            &event_observer_config,
+            &self.ctx,
+        );
+        if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+            error!(
+                self.ctx.expect_logger(),
+                "unable to evaluate predicates on block #{chain_tip}: {e}"
+            );
+        }
+
+        for predicate in predicates.iter() {
+            update_observer_progress(&predicate.uuid, chain_tip, &observers_db_conn, &self.ctx);
+            let expired = match (&predicate.blocks, predicate.end_block) {
+                (Some(blocks), _) => blocks.iter().all(|block| *block <= chain_tip),
+                (None, Some(end_block)) => end_block <= chain_tip,
+                (None, None) => false,
+            };
+            if expired {
+                info!(
+                    self.ctx.expect_logger(),
+                    "Predicate {} reached its last block, deregistering", predicate.uuid
+                );
+                let _ = observer_command_tx.send(ObserverCommand::DeregisterBitcoinPredicate(
+                    predicate.uuid.clone(),
+                ));
+            }
+        }
+    }
+
     pub fn set_up_observer_config(
         &self,
         predicates: Vec<BitcoinChainhookSpecification>,
@@ -493,16 +675,18 @@ impl Service {
                 blocks.into(),
                 first_inscription_height,
                 Some(&blocks_post_processor),
-                10_000,
                 &self.ctx,
             )
             .await?;
         }
 
+        if is_shutdown_requested() {
+            return Ok(0);
+        }
+
         // Start predicate processor
         let mut last_block_processed = 0;
-        while let Some((start_block, end_block, speed)) =
-            should_sync_ordhook_db(&self.config, &self.ctx)?
+        while let Some((start_block, end_block)) = should_sync_ordhook_db(&self.config, &self.ctx)?
         {
             if last_block_processed == end_block {
                 break;
@@ -528,11 +712,13 @@ impl Service {
                 blocks.into(),
                 first_inscription_height,
                 Some(&blocks_post_processor),
-                speed,
                 &self.ctx,
             )
             .await?;
 
+            if is_shutdown_requested() {
+                break;
+            }
             last_block_processed = end_block;
         }
 
@@ -555,7 +741,6 @@ impl Service {
             blocks,
             first_inscription_height,
             Some(&blocks_post_processor),
-            100,
             &self.ctx,
         )
         .await?;
@@ -564,8 +749,49 @@ impl Service {
     }
 }
 
-fn chainhook_sidecar_mutate_ordhook_db(command: HandleBlock, config: &Config, ctx: &Context) {
-    let (blocks_db_rw, inscriptions_db_conn_rw) =
+/// Keeps the chain tip reported by bitcoind up to date, for the lag of the index to be measured
+/// once blocks are streamed.
+fn start_chain_tip_monitoring(config: &Config, ctx: &Context) {
+    let config = config.clone();
+    let ctx = ctx.clone();
+    let _ = hiro_system_kit::thread_named("Chain tip monitoring").spawn(move || {
+        let mut bitcoin_rpc = None;
+        while !is_shutdown_requested() {
+            // The client is rebuilt after a failure, to pick up a cookie rotated by bitcoind
+            if bitcoin_rpc.is_none() {
+                match Client::new(&config.network.bitcoind_rpc_url, config.bitcoind_rpc_auth()) {
+                    Ok(con) => bitcoin_rpc = Some(con),
+                    Err(e) => {
+                        ctx.try_log(|logger| warn!(logger, "Bitcoin RPC error: {}", e.to_string()));
+                    }
+                }
+            }
+            if let Some(ref client) = bitcoin_rpc {
+                match client.get_blockchain_info() {
+                    Ok(result) => METRICS.set_chain_tip_block_height(result.blocks),
+                    Err(e) => {
+                        ctx.try_log(|logger| {
+                            warn!(
+                                logger,
+                                "unable to retrieve Bitcoin chain tip ({})",
+                                e.to_string()
+                            )
+                        });
+                        bitcoin_rpc = None;
+                    }
+                }
+            }
+            sleep(CHAIN_TIP_MONITORING_INTERVAL);
+        }
+    });
+}
+
+pub(crate) fn chainhook_sidecar_mutate_ordhook_db(
+    command: HandleBlock,
+    config: &Config,
+    ctx: &Context,
+) {
+    let (blocks_db_rw, mut inscriptions_db_conn_rw) =
         match open_readwrite_ordhook_dbs(&config.expected_cache_path(), &ctx) {
             Ok(dbs) => dbs,
             Err(e) => {
@@ -595,6 +821,15 @@ fn chainhook_sidecar_mutate_ordhook_db(command: HandleBlock, config: &Config, ct
                         "Unable to rollback bitcoin block {}: {e}", block.block_identifier
                     )
                 });
+                return;
+            }
+            for checkpoint in [Checkpoint::Archived, Checkpoint::Indexed] {
+                insert_checkpoint_in_blocks(
+                    checkpoint,
+                    block.block_identifier.index.saturating_sub(1),
+                    &blocks_db_rw,
+                    &ctx,
+                );
             }
         }
         HandleBlock::ApplyBlock(block) => {
@@ -621,11 +856,49 @@ fn chainhook_sidecar_mutate_ordhook_db(command: HandleBlock, config: &Config, ct
             );
             let _ = blocks_db_rw.flush();
 
-            update_inscriptions_with_block(&block, &inscriptions_db_conn_rw, &ctx);
+            // Apply the block atomically, an interruption must not leave it partially indexed
+            let inscriptions_db_tx = match inscriptions_db_conn_rw.transaction() {
+                Ok(tx) => tx,
+                Err(e) => {
+                    ctx.try_log(|logger| {
+                        error!(
+                            logger,
+                            "Unable to open sqlite transaction: {}",
+                            e.to_string()
+                        )
+                    });
+                    return;
+                }
+            };
 
-            update_locations_with_block(&block, &inscriptions_db_conn_rw, &ctx);
+            update_inscriptions_with_block(&block, &inscriptions_db_tx, &ctx);
 
-            update_sequence_metadata_with_block(&block, &inscriptions_db_conn_rw, &ctx);
+            update_locations_with_block(&block, &inscriptions_db_tx, &ctx);
+
+            update_sequence_metadata_with_block(&block, &inscriptions_db_tx, &ctx);
+
+            if let Err(e) = inscriptions_db_tx.commit() {
+                ctx.try_log(|logger| {
+                    error!(
+                        logger,
+                        "Unable to commit block #{}: {}",
+                        block.block_identifier.index,
+                        e.to_string()
+                    )
+                });
+                return;
+            }
+            for checkpoint in [Checkpoint::Archived, Checkpoint::Indexed] {
+                insert_checkpoint_in_blocks(
+                    checkpoint,
+                    block.block_identifier.index,
+                    &blocks_db_rw,
+                    &ctx,
+                );
+            }
+
+            METRICS.record_pipeline_blocks("streaming", 1);
+            METRICS.set_indexed_block_height(block.block_identifier.index);
         }
     }
 }
@@ -668,7 +941,7 @@ pub fn start_observer_forwarding(
 pub fn chainhook_sidecar_mutate_blocks(
     blocks_to_mutate: &mut Vec<BitcoinBlockDataCached>,
     blocks_ids_to_rollback: &Vec<BlockIdentifier>,
-    cache_l2: &Arc<DashMap<(u32, [u8; 8]), LazyBlockTransaction, BuildHasherDefault<FxHasher>>>,
+    cache_l2: &Arc<TraversalsCache>,
     config: &Config,
     ctx: &Context,
 ) {
@@ -686,19 +959,20 @@ pub fn chainhook_sidecar_mutate_blocks(
     let inscriptions_db_tx = inscriptions_db_conn_rw.transaction().unwrap();
 
     for block_id_to_rollback in blocks_ids_to_rollback.iter() {
-        if let Err(e) = delete_data_in_ordhook_db(
+        match delete_data_in_ordhook_db(
             block_id_to_rollback.index,
             block_id_to_rollback.index,
             &blocks_db_rw,
             &inscriptions_db_tx,
             &ctx,
         ) {
-            ctx.try_log(|logger| {
+            Ok(_) => LIVE_EVENTS.rollback_block(block_id_to_rollback),
+            Err(e) => ctx.try_log(|logger| {
                 error!(
                     logger,
                     "Unable to rollback bitcoin block {}: {e}", block_id_to_rollback.index
                 )
-            });
+            }),
         }
     }
 
@@ -771,6 +1045,7 @@ pub fn chainhook_sidecar_mutate_blocks(
             });
             cache.processed_by_sidecar = true;
         }
+        LIVE_EVENTS.apply_block(&cache.block);
     }
     let _ = inscriptions_db_tx.rollback();
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     path::PathBuf,
     sync::mpsc::{channel, Sender},
 };
@@ -22,7 +22,12 @@ use crate::{
         create_or_open_readwrite_db, open_existing_readonly_db, perform_query_one,
         perform_query_set,
     },
-    scan::bitcoin::process_block_with_predicates,
+    scan::{bitcoin::process_block_with_predicates, filters::InscriptionFilter},
+    service::outbox::{
+        open_readwrite_outbox_db_conn, remove_deliveries_for_predicate,
+        remove_signing_secrets_for_predicate,
+    },
+    sinks::{disconnect_sink, SinkConfig},
 };
 
 pub fn update_observer_progress(
@@ -40,6 +45,26 @@ pub fn update_observer_progress(
     }
 }
 
+/// Moves the progress of the observers back to `block_height`, for the blocks above to be evaluated again.
+pub fn rewind_observers_progress(
+    block_height: u64,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) -> usize {
+    loop {
+        match observers_db_conn.execute(
+            "UPDATE observers SET last_block_height_update = ?1 WHERE last_block_height_update > ?1",
+            rusqlite::params![block_height],
+        ) {
+            Ok(updated) => return updated,
+            Err(e) => {
+                ctx.try_log(|logger| warn!(logger, "unable to query observers.sqlite: {}", e.to_string()));
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+    }
+}
+
 pub fn update_observer_streaming_enabled(
     uuid: &str,
     streaming_enabled: bool,
@@ -69,6 +94,58 @@ pub fn insert_entry_in_observers(
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
+    update_observer_phase(
+        &spec.uuid(),
+        report.phase,
+        report.last_error.as_deref(),
+        observers_db_conn,
+        ctx,
+    );
+}
+
+/// Records the phase of an observer, and the error that made it fail, if any.
+pub fn update_observer_phase(
+    uuid: &str,
+    phase: ObserverPhase,
+    last_error: Option<&str>,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = observers_db_conn.execute(
+        "INSERT INTO observer_statuses (uuid, phase, blocks_remaining, last_error) VALUES (?1, ?2, 0, ?3)
+            ON CONFLICT(uuid) DO UPDATE SET phase = ?2, last_error = ?3,
+                blocks_remaining = CASE WHEN ?2 = 'streaming' THEN 0 ELSE blocks_remaining END",
+        rusqlite::params![&uuid, phase.as_str(), last_error],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query observers.sqlite: {}", e.to_string()));
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+/// Records the last block scanned by the observer `uuid` in `observers`, and sets its phase to
+/// `scanning` with `blocks_remaining` blocks left in `observer_statuses`. Rows are only updated,
+/// never inserted: predicates scanned outside of the service are left untouched.
+pub fn update_observer_scan_progress(
+    uuid: &str,
+    last_block_height_update: u64,
+    blocks_remaining: u64,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) {
+    update_observer_progress(uuid, last_block_height_update, observers_db_conn, ctx);
+    while let Err(e) = observers_db_conn.execute(
+        "UPDATE observer_statuses SET phase = ?1, blocks_remaining = ?2 WHERE uuid = ?3",
+        rusqlite::params![ObserverPhase::Scanning.as_str(), blocks_remaining, &uuid],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
 }
 
 pub fn get_default_observers_db_file_path(base_dir: &PathBuf) -> PathBuf {
@@ -127,13 +204,212 @@ pub fn initialize_observers_db(base_dir: &PathBuf, ctx: &Context) -> Connection
             )
         });
     }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS observer_statuses (
+            uuid TEXT NOT NULL PRIMARY KEY,
+            phase TEXT NOT NULL,
+            blocks_remaining INTEGER NOT NULL,
+            last_error TEXT
+        )",
+        [],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "Unable to create table observer_statuses: {}",
+                e.to_string()
+            )
+        });
+    }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS predicate_filters (
+            uuid TEXT NOT NULL PRIMARY KEY,
+            filter TEXT NOT NULL
+        )",
+        [],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "Unable to create table predicate_filters: {}",
+                e.to_string()
+            )
+        });
+    }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS predicate_sinks (
+            uuid TEXT NOT NULL PRIMARY KEY,
+            sink TEXT NOT NULL
+        )",
+        [],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "Unable to create table predicate_sinks: {}",
+                e.to_string()
+            )
+        });
+    }
     conn
 }
 
+pub fn insert_predicate_filter(
+    uuid: &str,
+    filter: &InscriptionFilter,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = observers_db_conn.execute(
+        "INSERT OR REPLACE INTO predicate_filters (uuid, filter) VALUES (?1, ?2)",
+        rusqlite::params![&uuid, json!(filter).to_string()],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+pub fn find_predicate_filter(
+    uuid: &str,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) -> Option<InscriptionFilter> {
+    let args: &[&dyn ToSql] = &[&uuid.to_sql().unwrap()];
+    let query = "SELECT filter FROM predicate_filters WHERE uuid = ?";
+    perform_query_one(query, args, observers_db_conn, ctx, |row| {
+        let encoded_filter: String = row.get(0).unwrap();
+        serde_json::from_str::<InscriptionFilter>(&encoded_filter).ok()
+    })
+    .flatten()
+}
+
+/// Filters of the predicates specified, when the observers db exists.
+pub fn find_predicate_filters(
+    uuids: &[&str],
+    base_dir: &PathBuf,
+    ctx: &Context,
+) -> HashMap<String, InscriptionFilter> {
+    let mut filters = HashMap::new();
+    if !get_default_observers_db_file_path(base_dir).exists() {
+        return filters;
+    }
+    let Ok(observers_db_conn) = open_readonly_observers_db_conn(base_dir, ctx) else {
+        return filters;
+    };
+    let has_filters_table = perform_query_one(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'predicate_filters'",
+        &[],
+        &observers_db_conn,
+        ctx,
+        |_| true,
+    )
+    .unwrap_or(false);
+    if !has_filters_table {
+        return filters;
+    }
+    for uuid in uuids.iter() {
+        if let Some(filter) = find_predicate_filter(uuid, &observers_db_conn, ctx) {
+            filters.insert(uuid.to_string(), filter);
+        }
+    }
+    filters
+}
+
+pub fn remove_predicate_filter(uuid: &str, observers_db_conn: &Connection, ctx: &Context) {
+    while let Err(e) = observers_db_conn.execute(
+        "DELETE FROM predicate_filters WHERE uuid = ?1",
+        rusqlite::params![&uuid],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObserverPhase {
+    /// Registered, waiting for a scan slot.
+    #[default]
+    Queued,
+    /// Catching up with the chain tip.
+    Scanning,
+    /// Evaluated on the blocks appended to the chain.
+    Streaming,
+    Paused,
+    /// Scan aborted, see `last_error`.
+    Failed,
+}
+
+impl ObserverPhase {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ObserverPhase::Queued => "queued",
+            ObserverPhase::Scanning => "scanning",
+            ObserverPhase::Streaming => "streaming",
+            ObserverPhase::Paused => "paused",
+            ObserverPhase::Failed => "failed",
+        }
+    }
+
+    fn from_str(phase: &str) -> Option<ObserverPhase> {
+        match phase {
+            "queued" => Some(ObserverPhase::Queued),
+            "scanning" => Some(ObserverPhase::Scanning),
+            "streaming" => Some(ObserverPhase::Streaming),
+            "paused" => Some(ObserverPhase::Paused),
+            "failed" => Some(ObserverPhase::Failed),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 pub struct ObserverReport {
     pub streaming_enabled: bool,
     pub last_block_height_update: u64,
+    #[serde(default)]
+    pub phase: ObserverPhase,
+    #[serde(default)]
+    pub blocks_remaining: u64,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+const OBSERVER_COLUMNS: &str = "o.spec, o.streaming_enabled, o.last_block_height_update, s.phase, s.blocks_remaining, s.last_error FROM observers o LEFT JOIN observer_statuses s ON s.uuid = o.uuid";
+
+fn parse_observer(row: &rusqlite::Row<'_>) -> (ChainhookSpecification, ObserverReport) {
+    let encoded_spec: String = row.get(0).unwrap();
+    let spec = ChainhookSpecification::deserialize_specification(&encoded_spec).unwrap();
+    let streaming_enabled: bool = row.get(1).unwrap();
+    let phase: Option<String> = row.get(3).unwrap();
+    // Observers registered before statuses were tracked
+    let phase = phase
+        .and_then(|phase| ObserverPhase::from_str(&phase))
+        .unwrap_or(match streaming_enabled {
+            true => ObserverPhase::Streaming,
+            false => ObserverPhase::Queued,
+        });
+    let blocks_remaining: Option<u64> = row.get(4).unwrap();
+    let report = ObserverReport {
+        streaming_enabled,
+        last_block_height_update: row.get(2).unwrap(),
+        phase,
+        blocks_remaining: blocks_remaining.unwrap_or(0),
+        last_error: row.get(5).unwrap(),
+    };
+    (spec, report)
 }
 
 pub fn find_observer_with_uuid(
@@ -142,17 +418,8 @@ pub fn find_observer_with_uuid(
     ctx: &Context,
 ) -> Option<(ChainhookSpecification, ObserverReport)> {
     let args: &[&dyn ToSql] = &[&uuid.to_sql().unwrap()];
-    let query =
-        "SELECT spec, streaming_enabled, last_block_height_update FROM observers WHERE uuid = ?";
-    perform_query_one(query, args, db_conn, ctx, |row| {
-        let encoded_spec: String = row.get(0).unwrap();
-        let spec = ChainhookSpecification::deserialize_specification(&encoded_spec).unwrap();
-        let report = ObserverReport {
-            streaming_enabled: row.get(1).unwrap(),
-            last_block_height_update: row.get(2).unwrap(),
-        };
-        (spec, report)
-    })
+    let query = format!("SELECT {OBSERVER_COLUMNS} WHERE o.uuid = ?");
+    perform_query_one(&query, args, db_conn, ctx, parse_observer)
 }
 
 pub fn find_all_observers(
@@ -160,16 +427,8 @@ pub fn find_all_observers(
     ctx: &Context,
 ) -> Vec<(ChainhookSpecification, ObserverReport)> {
     let args: &[&dyn ToSql] = &[];
-    let query = "SELECT spec, streaming_enabled, last_block_height_update FROM observers";
-    perform_query_set(query, args, db_conn, ctx, |row| {
-        let encoded_spec: String = row.get(0).unwrap();
-        let spec = ChainhookSpecification::deserialize_specification(&encoded_spec).unwrap();
-        let report = ObserverReport {
-            streaming_enabled: row.get(1).unwrap(),
-            last_block_height_update: row.get(2).unwrap(),
-        };
-        (spec, report)
-    })
+    let query = format!("SELECT {OBSERVER_COLUMNS}");
+    perform_query_set(&query, args, db_conn, ctx, parse_observer)
 }
 
 pub fn remove_entry_from_observers(uuid: &str, db_conn: &Connection, ctx: &Context) {
@@ -180,6 +439,19 @@ pub fn remove_entry_from_observers(uuid: &str, db_conn: &Connection, ctx: &Conte
         ctx.try_log(|logger| warn!(logger, "unable to query hord.sqlite: {}", e.to_string()));
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
+    while let Err(e) = db_conn.execute(
+        "DELETE FROM observer_statuses WHERE uuid = ?1",
+        rusqlite::params![&uuid],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
 }
 
 // Cases to cover:
@@ -189,6 +461,103 @@ pub fn remove_entry_from_observers(uuid: &str, db_conn: &Connection, ctx: &Conte
 //      - Blocks presents, inscription presents
 // - State up to date
 
+pub fn insert_predicate_sink(
+    uuid: &str,
+    sink: &SinkConfig,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = observers_db_conn.execute(
+        "INSERT OR REPLACE INTO predicate_sinks (uuid, sink) VALUES (?1, ?2)",
+        rusqlite::params![&uuid, json!(sink).to_string()],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+pub fn find_predicate_sink(
+    uuid: &str,
+    observers_db_conn: &Connection,
+    ctx: &Context,
+) -> Option<SinkConfig> {
+    let args: &[&dyn ToSql] = &[&uuid.to_sql().unwrap()];
+    let query = "SELECT sink FROM predicate_sinks WHERE uuid = ?";
+    perform_query_one(query, args, observers_db_conn, ctx, |row| {
+        let encoded_sink: String = row.get(0).unwrap();
+        serde_json::from_str::<SinkConfig>(&encoded_sink).ok()
+    })
+    .flatten()
+}
+
+/// Sinks of the predicates specified, when the observers db exists.
+pub fn find_predicate_sinks(
+    uuids: &[&str],
+    base_dir: &PathBuf,
+    ctx: &Context,
+) -> HashMap<String, SinkConfig> {
+    let mut sinks = HashMap::new();
+    if !get_default_observers_db_file_path(base_dir).exists() {
+        return sinks;
+    }
+    let Ok(observers_db_conn) = open_readonly_observers_db_conn(base_dir, ctx) else {
+        return sinks;
+    };
+    let has_sinks_table = perform_query_one(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'predicate_sinks'",
+        &[],
+        &observers_db_conn,
+        ctx,
+        |_| true,
+    )
+    .unwrap_or(false);
+    if !has_sinks_table {
+        return sinks;
+    }
+    for uuid in uuids.iter() {
+        if let Some(sink) = find_predicate_sink(uuid, &observers_db_conn, ctx) {
+            sinks.insert(uuid.to_string(), sink);
+        }
+    }
+    sinks
+}
+
+pub fn remove_predicate_sink(uuid: &str, observers_db_conn: &Connection, ctx: &Context) {
+    while let Err(e) = observers_db_conn.execute(
+        "DELETE FROM predicate_sinks WHERE uuid = ?1",
+        rusqlite::params![&uuid],
+    ) {
+        ctx.try_log(|logger| {
+            warn!(
+                logger,
+                "unable to query observers.sqlite: {}",
+                e.to_string()
+            )
+        });
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+/// Removes the observer `uuid`, along with its filter, its sink, its deliveries and its signing secrets.
+pub fn remove_predicate_data(uuid: &str, base_dir: &PathBuf, ctx: &Context) {
+    if let Ok(observers_db_conn) = open_readwrite_observers_db_conn(base_dir, ctx) {
+        remove_entry_from_observers(uuid, &observers_db_conn, ctx);
+        remove_predicate_filter(uuid, &observers_db_conn, ctx);
+        remove_predicate_sink(uuid, &observers_db_conn, ctx);
+    }
+    disconnect_sink(uuid);
+    if let Ok(outbox_db_conn) = open_readwrite_outbox_db_conn(base_dir, ctx) {
+        remove_deliveries_for_predicate(uuid, &outbox_db_conn, ctx);
+        remove_signing_secrets_for_predicate(uuid, &outbox_db_conn, ctx);
+    }
+}
+
 pub fn start_predicate_processor(
     event_observer_config: &EventObserverConfig,
     ctx: &Context,
@@ -271,6 +640,10 @@ pub fn create_and_consolidate_chainhook_config_with_predicates(
         let ChainhookSpecification::Bitcoin(spec) = spec else {
             continue;
         };
+        // Paused observers are registered again when resumed
+        if report.phase == ObserverPhase::Paused {
+            continue;
+        }
         // De-register outdated observers: was end_block (if specified) scanned?
         if let Some(expiration) = spec.end_block {
             if report.last_block_height_update >= expiration {
@@ -305,9 +678,10 @@ pub fn create_and_consolidate_chainhook_config_with_predicates(
         remove_entry_from_observers(outdated_observer, &observers_db_conn, ctx);
     }
 
-    // Registrations
+    // Registrations: the observer only tracks the predicates, their actions are triggered by
+    // ordhook on the blocks streamed (filters, sinks and signed outbox deliveries).
     for mut bitcoin_spec in observers_ready.into_iter() {
-        bitcoin_spec.enabled = true;
+        bitcoin_spec.enabled = false;
         let spec = ChainhookSpecification::Bitcoin(bitcoin_spec);
         chainhook_config.register_specification(spec)?;
     }
@@ -325,29 +699,8 @@ pub fn create_and_consolidate_chainhook_config_with_predicates(
     let mut full_specs = vec![];
 
     for (observer, report) in observers_to_catchup.into_iter() {
-        let mut networks = BTreeMap::new();
-        networks.insert(
-            config.network.bitcoin_network.clone(),
-            BitcoinChainhookNetworkSpecification {
-                start_block: Some(report.last_block_height_update + 1),
-                end_block: observer.end_block,
-                blocks: observer.blocks,
-                expire_after_occurrence: observer.expire_after_occurrence,
-                include_proof: Some(observer.include_proof),
-                include_inputs: Some(observer.include_inputs),
-                include_outputs: Some(observer.include_outputs),
-                include_witness: Some(observer.include_witness),
-                predicate: observer.predicate,
-                action: observer.action,
-            },
-        );
-        let full_spec = BitcoinChainhookFullSpecification {
-            uuid: observer.uuid,
-            owner_uuid: observer.owner_uuid,
-            name: observer.name,
-            version: observer.version,
-            networks,
-        };
+        let full_spec =
+            build_bitcoin_full_specification(observer, report.last_block_height_update + 1);
         info!(
             ctx.expect_logger(),
             "Observer '{}' to be caught-up (last block sent: {}, tip: {})",
@@ -360,3 +713,102 @@ pub fn create_and_consolidate_chainhook_config_with_predicates(
 
     Ok((chainhook_config, full_specs))
 }
+
+/// Specification to register for evaluating `observer` from `start_block`.
+pub fn build_bitcoin_full_specification(
+    observer: BitcoinChainhookSpecification,
+    start_block: u64,
+) -> BitcoinChainhookFullSpecification {
+    let blocks = observer.blocks.map(|blocks| {
+        blocks
+            .into_iter()
+            .filter(|block| *block >= start_block)
+            .collect::<Vec<_>>()
+    });
+    let mut networks = BTreeMap::new();
+    networks.insert(
+        observer.network.clone(),
+        BitcoinChainhookNetworkSpecification {
+            start_block: Some(start_block),
+            end_block: observer.end_block,
+            blocks,
+            expire_after_occurrence: observer.expire_after_occurrence,
+            include_proof: Some(observer.include_proof),
+            include_inputs: Some(observer.include_inputs),
+            include_outputs: Some(observer.include_outputs),
+            include_witness: Some(observer.include_witness),
+            predicate: observer.predicate,
+            action: observer.action,
+        },
+    );
+    BitcoinChainhookFullSpecification {
+        uuid: observer.uuid,
+        owner_uuid: observer.owner_uuid,
+        name: observer.name,
+        version: observer.version,
+        networks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chainhook_sdk::types::BitcoinNetwork;
+
+    use crate::utils::regtest_fixtures::{inscription_feed_predicate, TempDir};
+
+    use super::*;
+
+    fn build_observer(blocks: Option<Vec<u64>>) -> BitcoinChainhookSpecification {
+        BitcoinChainhookSpecification {
+            blocks,
+            start_block: Some(100),
+            ..inscription_feed_predicate("predicate")
+        }
+    }
+
+    #[test]
+    fn tracks_observer_phase_and_scan_progress() {
+        let ctx = Context::empty();
+        let base_dir = TempDir::new("ordhook-observers");
+        let conn = initialize_observers_db(base_dir.path(), &ctx);
+        let spec = ChainhookSpecification::Bitcoin(build_observer(None));
+        insert_entry_in_observers(&spec, &ObserverReport::default(), &conn, &ctx);
+        let (_, report) = find_observer_with_uuid("predicate", &conn, &ctx).unwrap();
+        assert_eq!(report.phase, ObserverPhase::Queued);
+
+        update_observer_scan_progress("predicate", 120, 30, &conn, &ctx);
+        let (_, report) = find_observer_with_uuid("predicate", &conn, &ctx).unwrap();
+        assert_eq!(report.phase, ObserverPhase::Scanning);
+        assert_eq!(
+            (report.last_block_height_update, report.blocks_remaining),
+            (120, 30)
+        );
+
+        update_observer_phase(
+            "predicate",
+            ObserverPhase::Failed,
+            Some("boom"),
+            &conn,
+            &ctx,
+        );
+        let (_, report) = find_observer_with_uuid("predicate", &conn, &ctx).unwrap();
+        assert_eq!(report.phase, ObserverPhase::Failed);
+        assert_eq!(report.last_error.as_deref(), Some("boom"));
+
+        update_observer_phase("predicate", ObserverPhase::Streaming, None, &conn, &ctx);
+        let (_, report) = find_observer_with_uuid("predicate", &conn, &ctx).unwrap();
+        assert_eq!((report.blocks_remaining, report.last_error), (0, None));
+
+        remove_entry_from_observers("predicate", &conn, &ctx);
+        update_observer_scan_progress("predicate", 121, 29, &conn, &ctx);
+        assert!(find_all_observers(&conn, &ctx).is_empty());
+    }
+
+    #[test]
+    fn resumes_specification_from_start_block() {
+        let full_spec = build_bitcoin_full_specification(build_observer(Some(vec![90, 110])), 101);
+        let network_spec = full_spec.networks.get(&BitcoinNetwork::Regtest).unwrap();
+        assert_eq!(network_spec.start_block, Some(101));
+        assert_eq!(network_spec.blocks, Some(vec![110]));
+    }
+}
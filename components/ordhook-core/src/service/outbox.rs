@@ -0,0 +1,1260 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    thread::sleep,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chainhook_sdk::utils::Context;
+use lazy_static::lazy_static;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Method, Request,
+};
+use rusqlite::{Connection, ToSql};
+
+use crate::{
+    config::Config,
+    db::{create_or_open_readwrite_db, perform_query_one, perform_query_set},
+    sinks::{publish_sink_events, SinkConfig, SinkEvent},
+    utils::{
+        monitoring::METRICS,
+        shutdown::is_shutdown_requested,
+        webhooks::{sign_webhook_payload, DELIVERY_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    },
+};
+
+/// Delay before the first retry of a failed delivery, doubled after each failure.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// Deliveries still failing after this number of attempts are moved to the dead letter queue.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 15;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(60);
+/// Delivered payloads are kept around for replays during this period.
+const DELIVERED_RETENTION: Duration = Duration::from_secs(24 * 3600);
+/// Scans stop evaluating blocks while the outbox of their predicate holds more pending payloads.
+pub const MAX_PENDING_DELIVERIES_PER_PREDICATE: u64 = 1_000;
+
+lazy_static! {
+    /// Predicates with a delivery in flight. Payloads of a predicate are delivered one at a time, in order.
+    static ref PREDICATES_IN_FLIGHT: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    /// Predicates with sink events being published, for the same reason.
+    static ref SINKS_IN_FLIGHT: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Entry of `PREDICATES_IN_FLIGHT` or `SINKS_IN_FLIGHT`, removed when dropped: deliveries
+/// cancelled or panicking don't leave their predicate marked as in flight.
+struct InFlightGuard {
+    predicates: &'static Mutex<HashSet<String>>,
+    predicate_uuid: String,
+}
+
+impl InFlightGuard {
+    /// `None` when the predicate is already in flight.
+    fn acquire(
+        predicates: &'static Mutex<HashSet<String>>,
+        predicate_uuid: &str,
+    ) -> Option<InFlightGuard> {
+        let inserted = predicates
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(predicate_uuid.to_string());
+        inserted.then(|| InFlightGuard {
+            predicates,
+            predicate_uuid: predicate_uuid.to_string(),
+        })
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.predicates
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.predicate_uuid);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Dead,
+}
+
+impl DeliveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+
+    fn from_str(status: &str) -> DeliveryStatus {
+        match status {
+            "delivered" => DeliveryStatus::Delivered,
+            "dead" => DeliveryStatus::Dead,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OutboxDelivery {
+    pub id: u64,
+    pub predicate_uuid: String,
+    pub block_height: u64,
+    pub url: String,
+    #[serde(skip)]
+    pub method: String,
+    #[serde(skip)]
+    pub headers: Vec<(String, String)>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub status: DeliveryStatus,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OutboxReport {
+    pub pending: u64,
+    pub delivered: u64,
+    pub dead: u64,
+    pub oldest_pending_block_height: Option<u64>,
+    pub next_attempt_at: Option<u64>,
+    /// Batches of sink events waiting for their broker to come back.
+    pub pending_sink_batches: u64,
+    /// Batches of sink events set aside because their row could not be read back.
+    pub dead_sink_batches: u64,
+}
+
+/// Sink events that could not be published, retried in order before any newer event.
+#[derive(Clone, Debug)]
+pub struct SinkBatch {
+    pub id: u64,
+    pub predicate_uuid: String,
+    pub block_height: u64,
+    pub sink: SinkConfig,
+    pub events: Vec<SinkEvent>,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+}
+
+pub fn get_default_outbox_db_file_path(base_dir: &PathBuf) -> PathBuf {
+    let mut destination_path = base_dir.clone();
+    destination_path.push("outbox.sqlite");
+    destination_path
+}
+
+pub fn open_readwrite_outbox_db_conn(
+    base_dir: &PathBuf,
+    ctx: &Context,
+) -> Result<Connection, String> {
+    let db_path = get_default_outbox_db_file_path(&base_dir);
+    let conn = create_or_open_readwrite_db(&db_path, ctx);
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            predicate_uuid TEXT NOT NULL,
+            block_height INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            method TEXT NOT NULL,
+            headers TEXT NOT NULL,
+            body BLOB NOT NULL,
+            attempts INTEGER NOT NULL,
+            next_attempt_at INTEGER NOT NULL,
+            last_error TEXT,
+            status TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    ) {
+        return Err(format!(
+            "unable to create table deliveries: {}",
+            e.to_string()
+        ));
+    }
+    if let Err(e) = conn.execute(
+        "CREATE INDEX IF NOT EXISTS deliveries_indexed_on_predicate_uuid_and_status ON deliveries(predicate_uuid, status);",
+        [],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+    }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS signing_secrets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            predicate_uuid TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER
+        )",
+        [],
+    ) {
+        return Err(format!(
+            "unable to create table signing_secrets: {}",
+            e.to_string()
+        ));
+    }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS sink_batches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            predicate_uuid TEXT NOT NULL,
+            block_height INTEGER NOT NULL,
+            sink TEXT NOT NULL,
+            events TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            next_attempt_at INTEGER NOT NULL,
+            last_error TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    ) {
+        return Err(format!(
+            "unable to create table sink_batches: {}",
+            e.to_string()
+        ));
+    }
+    if let Err(e) = conn.execute(
+        "CREATE TABLE IF NOT EXISTS dead_sink_batches (
+            id INTEGER PRIMARY KEY,
+            predicate_uuid TEXT NOT NULL,
+            block_height INTEGER NOT NULL,
+            sink TEXT NOT NULL,
+            events TEXT NOT NULL,
+            last_error TEXT,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    ) {
+        return Err(format!(
+            "unable to create table dead_sink_batches: {}",
+            e.to_string()
+        ));
+    }
+    Ok(conn)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn compute_backoff(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    INITIAL_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// Stores the payload of an HTTP action before any delivery attempt.
+pub fn enqueue_http_delivery(
+    predicate_uuid: &str,
+    block_height: u64,
+    request: &Request,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Result<(), String> {
+    let headers = request
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_string(), value.to_string()))
+        })
+        .collect::<Vec<_>>();
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .unwrap_or_default()
+        .to_vec();
+    let headers = serde_json::to_string(&headers)
+        .map_err(|e| format!("unable to serialize headers: {}", e.to_string()))?;
+    let now = now_ms();
+    while let Err(e) = outbox_db_conn.execute(
+        "INSERT INTO deliveries (predicate_uuid, block_height, url, method, headers, body, attempts, next_attempt_at, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7, ?8, ?7)",
+        rusqlite::params![
+            predicate_uuid,
+            block_height,
+            request.url().as_str(),
+            request.method().as_str(),
+            headers,
+            body,
+            now,
+            DeliveryStatus::Pending.as_str()
+        ],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+    Ok(())
+}
+
+fn parse_delivery(row: &rusqlite::Row<'_>) -> OutboxDelivery {
+    let encoded_headers: String = row.get(5).unwrap();
+    let status: String = row.get(10).unwrap();
+    OutboxDelivery {
+        id: row.get(0).unwrap(),
+        predicate_uuid: row.get(1).unwrap(),
+        block_height: row.get(2).unwrap(),
+        url: row.get(3).unwrap(),
+        method: row.get(4).unwrap(),
+        headers: serde_json::from_str(&encoded_headers).unwrap_or_default(),
+        body: row.get(6).unwrap(),
+        attempts: row.get(7).unwrap(),
+        next_attempt_at: row.get(8).unwrap(),
+        last_error: row.get(9).unwrap(),
+        status: DeliveryStatus::from_str(&status),
+        created_at: row.get(11).unwrap(),
+    }
+}
+
+const DELIVERY_COLUMNS: &str = "id, predicate_uuid, block_height, url, method, headers, body, attempts, next_attempt_at, last_error, status, created_at";
+
+pub fn find_deliveries_with_status(
+    predicate_uuid: &str,
+    status: DeliveryStatus,
+    limit: u64,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<OutboxDelivery> {
+    let args: &[&dyn ToSql] = &[
+        &predicate_uuid.to_sql().unwrap(),
+        &status.as_str().to_sql().unwrap(),
+        &limit.to_sql().unwrap(),
+    ];
+    let query = format!("SELECT {DELIVERY_COLUMNS} FROM deliveries WHERE predicate_uuid = ? AND status = ? ORDER BY id ASC LIMIT ?");
+    perform_query_set(&query, args, outbox_db_conn, ctx, parse_delivery)
+}
+
+pub fn find_predicates_with_pending_deliveries(
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<String> {
+    let args: &[&dyn ToSql] = &[&DeliveryStatus::Pending.as_str().to_sql().unwrap()];
+    let query = "SELECT predicate_uuid FROM deliveries WHERE status = ? UNION SELECT predicate_uuid FROM sink_batches";
+    perform_query_set(query, args, outbox_db_conn, ctx, |row| {
+        let predicate_uuid: String = row.get(0).unwrap();
+        predicate_uuid
+    })
+}
+
+pub fn count_pending_deliveries(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> u64 {
+    let args: &[&dyn ToSql] = &[
+        &predicate_uuid.to_sql().unwrap(),
+        &DeliveryStatus::Pending.as_str().to_sql().unwrap(),
+    ];
+    let query = "SELECT COUNT(*) FROM deliveries WHERE predicate_uuid = ? AND status = ?";
+    let pending_deliveries = perform_query_one(query, args, outbox_db_conn, ctx, |row| {
+        let count: u64 = row.get(0).unwrap();
+        count
+    })
+    .unwrap_or(0);
+    pending_deliveries + count_pending_sink_batches(predicate_uuid, outbox_db_conn, ctx)
+}
+
+fn count_pending_sink_batches(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> u64 {
+    let args: &[&dyn ToSql] = &[&predicate_uuid.to_sql().unwrap()];
+    let query = "SELECT COUNT(*) FROM sink_batches WHERE predicate_uuid = ?";
+    perform_query_one(query, args, outbox_db_conn, ctx, |row| {
+        let count: u64 = row.get(0).unwrap();
+        count
+    })
+    .unwrap_or(0)
+}
+
+pub fn get_outbox_report(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> OutboxReport {
+    let args: &[&dyn ToSql] = &[&predicate_uuid.to_sql().unwrap()];
+    let query = "SELECT status, COUNT(*), MIN(block_height), MIN(next_attempt_at) FROM deliveries WHERE predicate_uuid = ? GROUP BY status";
+    let entries = perform_query_set(query, args, outbox_db_conn, ctx, |row| {
+        let status: String = row.get(0).unwrap();
+        let count: u64 = row.get(1).unwrap();
+        let min_block_height: u64 = row.get(2).unwrap();
+        let min_next_attempt_at: u64 = row.get(3).unwrap();
+        (
+            DeliveryStatus::from_str(&status),
+            count,
+            min_block_height,
+            min_next_attempt_at,
+        )
+    });
+    let mut report = OutboxReport::default();
+    for (status, count, min_block_height, min_next_attempt_at) in entries.into_iter() {
+        match status {
+            DeliveryStatus::Pending => {
+                report.pending = count;
+                report.oldest_pending_block_height = Some(min_block_height);
+                report.next_attempt_at = Some(min_next_attempt_at);
+            }
+            DeliveryStatus::Delivered => report.delivered = count,
+            DeliveryStatus::Dead => report.dead = count,
+        }
+    }
+    report.pending_sink_batches = count_pending_sink_batches(predicate_uuid, outbox_db_conn, ctx);
+    let args: &[&dyn ToSql] = &[&predicate_uuid.to_sql().unwrap()];
+    let query = "SELECT COUNT(*) FROM dead_sink_batches WHERE predicate_uuid = ?";
+    report.dead_sink_batches = perform_query_one(query, args, outbox_db_conn, ctx, |row| {
+        let count: u64 = row.get(0).unwrap();
+        count
+    })
+    .unwrap_or(0);
+    report
+}
+
+fn update_delivery(
+    id: u64,
+    status: DeliveryStatus,
+    attempts: u32,
+    next_attempt_at: u64,
+    last_error: Option<&str>,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = outbox_db_conn.execute(
+        "UPDATE deliveries SET status = ?1, attempts = ?2, next_attempt_at = ?3, last_error = ?4 WHERE id = ?5",
+        rusqlite::params![status.as_str(), attempts, next_attempt_at, last_error, id],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+/// Records a failed attempt: the delivery is rescheduled with an exponential backoff,
+/// or moved to the dead letter queue once `MAX_DELIVERY_ATTEMPTS` is reached.
+pub fn record_delivery_failure(
+    delivery: &OutboxDelivery,
+    error: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> DeliveryStatus {
+    let attempts = delivery.attempts + 1;
+    let (status, next_attempt_at) = if attempts >= MAX_DELIVERY_ATTEMPTS {
+        (DeliveryStatus::Dead, delivery.next_attempt_at)
+    } else {
+        (
+            DeliveryStatus::Pending,
+            now_ms() + compute_backoff(attempts).as_millis() as u64,
+        )
+    };
+    update_delivery(
+        delivery.id,
+        status,
+        attempts,
+        next_attempt_at,
+        Some(error),
+        outbox_db_conn,
+        ctx,
+    );
+    status
+}
+
+pub fn record_delivery_success(
+    delivery: &OutboxDelivery,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    update_delivery(
+        delivery.id,
+        DeliveryStatus::Delivered,
+        delivery.attempts + 1,
+        now_ms(),
+        None,
+        outbox_db_conn,
+        ctx,
+    );
+}
+
+/// Marks a pending or dead delivery as delivered, without sending it.
+/// Returns false if the delivery is unknown.
+pub fn acknowledge_delivery(
+    predicate_uuid: &str,
+    id: u64,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> bool {
+    loop {
+        match outbox_db_conn.execute(
+            "UPDATE deliveries SET status = ?1, next_attempt_at = ?2 WHERE id = ?3 AND predicate_uuid = ?4",
+            rusqlite::params![DeliveryStatus::Delivered.as_str(), now_ms(), id, predicate_uuid],
+        ) {
+            Ok(updated) => return updated > 0,
+            Err(e) => {
+                ctx.try_log(|logger| {
+                    warn!(logger, "unable to query outbox.sqlite: {}", e.to_string())
+                });
+                sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
+
+/// Schedules deliveries for immediate (re)delivery: dead letters, and when `from_block_height` is
+/// specified, the payloads already delivered for blocks greater or equal to this height.
+/// Returns the number of deliveries rescheduled.
+pub fn replay_deliveries(
+    predicate_uuid: &str,
+    from_block_height: Option<u64>,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> usize {
+    loop {
+        let res = match from_block_height {
+            Some(block_height) => outbox_db_conn.execute(
+                "UPDATE deliveries SET status = ?1, attempts = 0, next_attempt_at = ?2 WHERE predicate_uuid = ?3 AND status != ?1 AND block_height >= ?4",
+                rusqlite::params![DeliveryStatus::Pending.as_str(), now_ms(), predicate_uuid, block_height],
+            ),
+            None => outbox_db_conn.execute(
+                "UPDATE deliveries SET status = ?1, attempts = 0, next_attempt_at = ?2 WHERE predicate_uuid = ?3 AND status = ?4",
+                rusqlite::params![DeliveryStatus::Pending.as_str(), now_ms(), predicate_uuid, DeliveryStatus::Dead.as_str()],
+            ),
+        };
+        match res {
+            Ok(updated) => return updated,
+            Err(e) => {
+                ctx.try_log(|logger| {
+                    warn!(logger, "unable to query outbox.sqlite: {}", e.to_string())
+                });
+                sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
+
+pub fn remove_deliveries_for_predicate(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    for query in [
+        "DELETE FROM deliveries WHERE predicate_uuid = ?1",
+        "DELETE FROM sink_batches WHERE predicate_uuid = ?1",
+        "DELETE FROM dead_sink_batches WHERE predicate_uuid = ?1",
+    ] {
+        while let Err(e) = outbox_db_conn.execute(query, rusqlite::params![predicate_uuid]) {
+            ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+            sleep(Duration::from_secs(1));
+        }
+    }
+}
+
+/// Starts signing the deliveries of `predicate_uuid` with `secret`. The secrets previously
+/// configured keep being used during `grace_period`, so that receivers can be updated.
+pub fn rotate_signing_secret(
+    predicate_uuid: &str,
+    secret: &str,
+    grace_period: Duration,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    let now = now_ms();
+    while let Err(e) = outbox_db_conn.execute(
+        "UPDATE signing_secrets SET expires_at = ?1 WHERE predicate_uuid = ?2 AND (expires_at IS NULL OR expires_at > ?1)",
+        rusqlite::params![now + grace_period.as_millis() as u64, predicate_uuid],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+    while let Err(e) = outbox_db_conn.execute(
+        "INSERT INTO signing_secrets (predicate_uuid, secret, created_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![predicate_uuid, secret, now],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+/// Secrets used for signing the deliveries of `predicate_uuid`, most recent first.
+pub fn find_active_signing_secrets(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<String> {
+    let args: &[&dyn ToSql] = &[
+        &predicate_uuid.to_sql().unwrap(),
+        &now_ms().to_sql().unwrap(),
+    ];
+    let query = "SELECT secret FROM signing_secrets WHERE predicate_uuid = ? AND (expires_at IS NULL OR expires_at > ?) ORDER BY id DESC";
+    perform_query_set(query, args, outbox_db_conn, ctx, |row| {
+        let secret: String = row.get(0).unwrap();
+        secret
+    })
+}
+
+pub fn remove_signing_secrets_for_predicate(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = outbox_db_conn.execute(
+        "DELETE FROM signing_secrets WHERE predicate_uuid = ?1",
+        rusqlite::params![predicate_uuid],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+pub fn purge_delivered_deliveries(outbox_db_conn: &Connection, ctx: &Context) {
+    let threshold = now_ms().saturating_sub(DELIVERED_RETENTION.as_millis() as u64);
+    while let Err(e) = outbox_db_conn.execute(
+        "DELETE FROM deliveries WHERE status = ?1 AND next_attempt_at < ?2",
+        rusqlite::params![DeliveryStatus::Delivered.as_str(), threshold],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+async fn send_delivery(
+    delivery: &OutboxDelivery,
+    signing_secrets: &[String],
+    http_client: &Client,
+) -> Result<(), String> {
+    let method = Method::from_bytes(delivery.method.as_bytes())
+        .map_err(|e| format!("invalid method: {}", e.to_string()))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in delivery.headers.iter() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    if !signing_secrets.is_empty() {
+        let delivery_id = delivery.id.to_string();
+        let timestamp = now_ms() / 1000;
+        let signature =
+            sign_webhook_payload(signing_secrets, &delivery_id, timestamp, &delivery.body);
+        for (name, value) in [
+            (DELIVERY_ID_HEADER, delivery_id),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (SIGNATURE_HEADER, signature),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+    let res = http_client
+        .request(method, &delivery.url)
+        .headers(headers)
+        .body(delivery.body.clone())
+        .timeout(DELIVERY_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("unable to send request: {}", e.to_string()))?;
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("request rejected with status {}", res.status()))
+    }
+}
+
+/// Delivers, in order, the pending payloads of `predicate_uuid` whose retry delay expired.
+/// Stops at the first failure, so that a payload is never delivered before the ones enqueued earlier.
+/// Returns the number of payloads delivered.
+pub async fn deliver_pending_deliveries(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    http_client: &Client,
+    ctx: &Context,
+) -> u64 {
+    let Some(_in_flight) = InFlightGuard::acquire(&PREDICATES_IN_FLIGHT, predicate_uuid) else {
+        return 0;
+    };
+
+    let mut delivered = 0;
+    'outer: loop {
+        let deliveries = find_deliveries_with_status(
+            predicate_uuid,
+            DeliveryStatus::Pending,
+            100,
+            outbox_db_conn,
+            ctx,
+        );
+        if deliveries.is_empty() {
+            break;
+        }
+        for delivery in deliveries.iter() {
+            if delivery.next_attempt_at > now_ms() || is_shutdown_requested() {
+                break 'outer;
+            }
+            let signing_secrets = find_active_signing_secrets(predicate_uuid, outbox_db_conn, ctx);
+            match send_delivery(delivery, &signing_secrets, http_client).await {
+                Ok(_) => {
+                    METRICS.record_predicate_delivery("outbox", true);
+                    record_delivery_success(delivery, outbox_db_conn, ctx);
+                    delivered += 1;
+                }
+                Err(e) => {
+                    METRICS.record_predicate_delivery("outbox", false);
+                    let status = record_delivery_failure(delivery, &e, outbox_db_conn, ctx);
+                    ctx.try_log(|logger| {
+                        if status == DeliveryStatus::Dead {
+                            error!(
+                                logger,
+                                "Delivery #{} of predicate {} (block #{}) moved to dead letter queue after {} attempts: {e}",
+                                delivery.id,
+                                predicate_uuid,
+                                delivery.block_height,
+                                delivery.attempts + 1
+                            )
+                        } else {
+                            warn!(
+                                logger,
+                                "Delivery #{} of predicate {} (block #{}) failed, will retry in {}s: {e}",
+                                delivery.id,
+                                predicate_uuid,
+                                delivery.block_height,
+                                compute_backoff(delivery.attempts + 1).as_secs()
+                            )
+                        }
+                    });
+                    // A dead letter no longer holds back the deliveries enqueued after it
+                    if status == DeliveryStatus::Dead {
+                        continue 'outer;
+                    }
+                    break 'outer;
+                }
+            }
+        }
+    }
+
+    delivered
+}
+
+fn enqueue_sink_batch(
+    predicate_uuid: &str,
+    block_height: u64,
+    sink: &SinkConfig,
+    events: &[SinkEvent],
+    error: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    let sink = serde_json::to_string(sink).unwrap_or_default();
+    let events = serde_json::to_string(events).unwrap_or_default();
+    let now = now_ms();
+    let next_attempt_at = now + compute_backoff(1).as_millis() as u64;
+    while let Err(e) = outbox_db_conn.execute(
+        "INSERT INTO sink_batches (predicate_uuid, block_height, sink, events, attempts, next_attempt_at, last_error, created_at)
+            VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6, ?7)",
+        rusqlite::params![predicate_uuid, block_height, &sink, &events, next_attempt_at, error, now],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+/// Sink batches of `predicate_uuid`, in order. Rows that can't be deserialized anymore are moved
+/// to `dead_sink_batches` instead of holding back the events queued after them.
+pub fn find_pending_sink_batches(
+    predicate_uuid: &str,
+    limit: u64,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Vec<SinkBatch> {
+    let args: &[&dyn ToSql] = &[&predicate_uuid.to_sql().unwrap(), &limit.to_sql().unwrap()];
+    let query = "SELECT id, predicate_uuid, block_height, sink, events, attempts, next_attempt_at FROM sink_batches WHERE predicate_uuid = ? ORDER BY id ASC LIMIT ?";
+    let rows = perform_query_set(query, args, outbox_db_conn, ctx, |row| {
+        let id: u64 = row.get(0).unwrap();
+        let sink: String = row.get(3).unwrap();
+        let events: String = row.get(4).unwrap();
+        let batch = serde_json::from_str(&sink)
+            .map_err(|e| format!("unable to deserialize sink: {e}"))
+            .and_then(|sink| {
+                serde_json::from_str(&events)
+                    .map_err(|e| format!("unable to deserialize sink events: {e}"))
+                    .map(|events| (sink, events))
+            })
+            .map(|(sink, events)| SinkBatch {
+                id,
+                predicate_uuid: row.get(1).unwrap(),
+                block_height: row.get(2).unwrap(),
+                sink,
+                events,
+                attempts: row.get(5).unwrap(),
+                next_attempt_at: row.get(6).unwrap(),
+            });
+        (id, batch)
+    });
+    let mut batches = vec![];
+    for (id, batch) in rows.into_iter() {
+        match batch {
+            Ok(batch) => batches.push(batch),
+            Err(error) => {
+                move_sink_batch_to_dead_letters(id, &error, outbox_db_conn, ctx);
+                ctx.try_log(|logger| {
+                    error!(
+                        logger,
+                        "Sink batch {id} of predicate {predicate_uuid} moved to the dead letters: {error}"
+                    )
+                });
+            }
+        }
+    }
+    batches
+}
+
+fn move_sink_batch_to_dead_letters(
+    id: u64,
+    error: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) {
+    while let Err(e) = outbox_db_conn.execute(
+        "INSERT OR REPLACE INTO dead_sink_batches (id, predicate_uuid, block_height, sink, events, last_error, created_at)
+            SELECT id, predicate_uuid, block_height, sink, events, ?1, created_at FROM sink_batches WHERE id = ?2",
+        rusqlite::params![error, id],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+    while let Err(e) = outbox_db_conn.execute(
+        "DELETE FROM sink_batches WHERE id = ?1",
+        rusqlite::params![id],
+    ) {
+        ctx.try_log(|logger| warn!(logger, "unable to query outbox.sqlite: {}", e.to_string()));
+        sleep(Duration::from_secs(1));
+    }
+}
+
+/// Publishes, in order, the sink batches of `predicate_uuid` whose retry delay expired. Unlike
+/// webhook payloads, sink batches are never dropped: consumers rely on getting every event.
+/// Expects the caller to hold the `SINKS_IN_FLIGHT` guard of the predicate.
+fn publish_pending_sink_batches(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> u64 {
+    let mut published = 0;
+    'outer: loop {
+        let batches = find_pending_sink_batches(predicate_uuid, 100, outbox_db_conn, ctx);
+        if batches.is_empty() {
+            break;
+        }
+        for batch in batches.iter() {
+            if batch.next_attempt_at > now_ms() || is_shutdown_requested() {
+                break 'outer;
+            }
+            match publish_sink_events(predicate_uuid, &batch.sink, &batch.events, ctx) {
+                Ok(_) => {
+                    METRICS.record_predicate_delivery("outbox", true);
+                    while let Err(e) = outbox_db_conn.execute(
+                        "DELETE FROM sink_batches WHERE id = ?1",
+                        rusqlite::params![batch.id],
+                    ) {
+                        ctx.try_log(|logger| {
+                            warn!(logger, "unable to query outbox.sqlite: {}", e.to_string())
+                        });
+                        sleep(Duration::from_secs(1));
+                    }
+                    published += 1;
+                }
+                Err(error) => {
+                    METRICS.record_predicate_delivery("outbox", false);
+                    let attempts = batch.attempts + 1;
+                    let next_attempt_at = now_ms() + compute_backoff(attempts).as_millis() as u64;
+                    while let Err(e) = outbox_db_conn.execute(
+                        "UPDATE sink_batches SET attempts = ?1, next_attempt_at = ?2, last_error = ?3 WHERE id = ?4",
+                        rusqlite::params![attempts, next_attempt_at, &error, batch.id],
+                    ) {
+                        ctx.try_log(|logger| {
+                            warn!(logger, "unable to query outbox.sqlite: {}", e.to_string())
+                        });
+                        sleep(Duration::from_secs(1));
+                    }
+                    ctx.try_log(|logger| {
+                        warn!(
+                            logger,
+                            "Sink events of predicate {} (block #{}) not published, will retry in {}s: {error}",
+                            predicate_uuid,
+                            batch.block_height,
+                            compute_backoff(attempts).as_secs()
+                        )
+                    });
+                    break 'outer;
+                }
+            }
+        }
+    }
+    published
+}
+
+/// Retries the sink batches left pending by `predicate_uuid`. Returns the number of batches published.
+pub fn deliver_pending_sink_batches(
+    predicate_uuid: &str,
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> u64 {
+    let Some(_in_flight) = InFlightGuard::acquire(&SINKS_IN_FLIGHT, predicate_uuid) else {
+        return 0;
+    };
+    publish_pending_sink_batches(predicate_uuid, outbox_db_conn, ctx)
+}
+
+/// Publishes `events` to the sink of `predicate_uuid`, after the batches left pending by earlier
+/// failures. Events that can't be published right away are persisted and retried by the outbox
+/// runloop, in order: the error returned only reports the delay.
+pub fn publish_or_enqueue_sink_events(
+    predicate_uuid: &str,
+    block_height: u64,
+    sink: &SinkConfig,
+    events: &[SinkEvent],
+    outbox_db_conn: &Connection,
+    ctx: &Context,
+) -> Result<(), String> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let res = match InFlightGuard::acquire(&SINKS_IN_FLIGHT, predicate_uuid) {
+        Some(_in_flight) => {
+            publish_pending_sink_batches(predicate_uuid, outbox_db_conn, ctx);
+            let pending_batches = count_pending_sink_batches(predicate_uuid, outbox_db_conn, ctx);
+            if pending_batches > 0 {
+                Err(format!("{pending_batches} earlier batches pending"))
+            } else {
+                publish_sink_events(predicate_uuid, sink, events, ctx)
+            }
+        }
+        None => Err(format!("earlier batches being published")),
+    };
+    if let Err(ref e) = res {
+        enqueue_sink_batch(
+            predicate_uuid,
+            block_height,
+            sink,
+            events,
+            e,
+            outbox_db_conn,
+            ctx,
+        );
+    }
+    res
+}
+
+/// Runs `publish_or_enqueue_sink_events` on the blocking thread pool, with its own connection to
+/// the outbox: the broker clients are synchronous, and would stall the tokio workers while waiting
+/// for acknowledgements.
+pub async fn spawn_sink_events_publishing(
+    predicate_uuid: String,
+    block_height: u64,
+    sink: SinkConfig,
+    events: Vec<SinkEvent>,
+    base_dir: PathBuf,
+    ctx: &Context,
+) -> Result<(), String> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let ctx = ctx.clone();
+    tokio::task::spawn_blocking(move || {
+        let outbox_db_conn = open_readwrite_outbox_db_conn(&base_dir, &ctx)?;
+        publish_or_enqueue_sink_events(
+            &predicate_uuid,
+            block_height,
+            &sink,
+            &events,
+            &outbox_db_conn,
+            &ctx,
+        )
+    })
+    .await
+    .map_err(|e| format!("sink publishing aborted: {e}"))?
+}
+
+/// Retries the pending deliveries of `predicate_uuid` until the outbox is drained, or until
+/// `max_wait` elapses. Returns the number of payloads still pending.
+pub async fn drain_pending_deliveries(
+    predicate_uuid: &str,
+    base_dir: &PathBuf,
+    max_wait: Duration,
+    ctx: &Context,
+) -> Result<u64, String> {
+    let outbox_db_conn = open_readwrite_outbox_db_conn(base_dir, ctx)?;
+    let http_client = Client::new();
+    let started_at = SystemTime::now();
+    loop {
+        deliver_pending_deliveries(predicate_uuid, &outbox_db_conn, &http_client, ctx).await;
+        // Broker clients are synchronous, see `spawn_sink_events_publishing`
+        let sink_batches = {
+            let predicate_uuid = predicate_uuid.to_string();
+            let base_dir = base_dir.clone();
+            let ctx = ctx.clone();
+            tokio::task::spawn_blocking(move || {
+                let outbox_db_conn = open_readwrite_outbox_db_conn(&base_dir, &ctx)?;
+                Ok::<_, String>(deliver_pending_sink_batches(
+                    &predicate_uuid,
+                    &outbox_db_conn,
+                    &ctx,
+                ))
+            })
+        };
+        sink_batches
+            .await
+            .map_err(|e| format!("sink publishing aborted: {e}"))??;
+        let pending_deliveries = count_pending_deliveries(predicate_uuid, &outbox_db_conn, ctx);
+        if pending_deliveries == 0
+            || is_shutdown_requested()
+            || started_at.elapsed().unwrap_or_default() >= max_wait
+        {
+            return Ok(pending_deliveries);
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Retries the pending deliveries of all the predicates, until shutdown.
+pub fn start_outbox_delivery_runloop(config: &Config, ctx: &Context) {
+    let base_dir = config.expected_cache_path();
+    let ctx = ctx.clone();
+    let _ = hiro_system_kit::thread_named("Outbox delivery runloop").spawn(move || {
+        let outbox_db_conn = match open_readwrite_outbox_db_conn(&base_dir, &ctx) {
+            Ok(conn) => conn,
+            Err(e) => {
+                ctx.try_log(|logger| error!(logger, "Unable to open outbox: {e}"));
+                return;
+            }
+        };
+        let http_client = Client::new();
+        let mut last_purge = SystemTime::UNIX_EPOCH;
+        while !is_shutdown_requested() {
+            for predicate_uuid in find_predicates_with_pending_deliveries(&outbox_db_conn, &ctx) {
+                let future = deliver_pending_deliveries(
+                    &predicate_uuid,
+                    &outbox_db_conn,
+                    &http_client,
+                    &ctx,
+                );
+                let _ = hiro_system_kit::nestable_block_on(future);
+                deliver_pending_sink_batches(&predicate_uuid, &outbox_db_conn, &ctx);
+            }
+            if last_purge.elapsed().unwrap_or_default() > Duration::from_secs(3600) {
+                purge_delivered_deliveries(&outbox_db_conn, &ctx);
+                last_purge = SystemTime::now();
+            }
+            sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::regtest_fixtures::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn moves_deliveries_to_dead_letter_queue_after_max_attempts() {
+        let ctx = Context::empty();
+        let base_dir = TempDir::new("ordhook-outbox");
+        let conn = open_readwrite_outbox_db_conn(base_dir.path(), &ctx).unwrap();
+        let request = Client::new()
+            .post("http://127.0.0.1:1/webhook")
+            .header("content-type", "application/json")
+            .body("{}")
+            .build()
+            .unwrap();
+        enqueue_http_delivery("predicate", 42, &request, &conn, &ctx).unwrap();
+
+        let mut delivery =
+            find_deliveries_with_status("predicate", DeliveryStatus::Pending, 10, &conn, &ctx)
+                .remove(0);
+        assert_eq!(delivery.body, b"{}".to_vec());
+        assert_eq!(
+            delivery.headers,
+            vec![("content-type".to_string(), "application/json".to_string())]
+        );
+        for _ in 0..MAX_DELIVERY_ATTEMPTS - 1 {
+            assert_eq!(
+                record_delivery_failure(&delivery, "unreachable", &conn, &ctx),
+                DeliveryStatus::Pending
+            );
+            delivery.attempts += 1;
+        }
+        assert_eq!(
+            record_delivery_failure(&delivery, "unreachable", &conn, &ctx),
+            DeliveryStatus::Dead
+        );
+        let report = get_outbox_report("predicate", &conn, &ctx);
+        assert_eq!((report.pending, report.dead), (0, 1));
+
+        assert_eq!(replay_deliveries("predicate", None, &conn, &ctx), 1);
+        assert_eq!(count_pending_deliveries("predicate", &conn, &ctx), 1);
+        assert!(acknowledge_delivery("predicate", delivery.id, &conn, &ctx));
+        assert_eq!(get_outbox_report("predicate", &conn, &ctx).delivered, 1);
+    }
+
+    #[test]
+    fn keeps_sink_events_until_published() {
+        use chainhook_sdk::types::{
+            OrdinalInscriptionTransferData, OrdinalInscriptionTransferDestination, OrdinalOperation,
+        };
+
+        use crate::sinks::SinkEventType;
+
+        let ctx = Context::empty();
+        let base_dir = TempDir::new("ordhook-outbox");
+        let conn = open_readwrite_outbox_db_conn(base_dir.path(), &ctx).unwrap();
+        // Unreachable broker (or sink not compiled in): the events are kept for the outbox runloop
+        let sink = SinkConfig::Kafka {
+            brokers: vec!["127.0.0.1:1".into()],
+            topic: "inscriptions".into(),
+        };
+        let event = |block_height: u64| SinkEvent {
+            event_type: SinkEventType::Apply,
+            predicate_uuid: "predicate".into(),
+            inscription_id: "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0"
+                .into(),
+            block_height,
+            block_hash: "0x00".into(),
+            tx_id: "0x01".into(),
+            tx_index: 1,
+            operation_index: 0,
+            operation: OrdinalOperation::InscriptionTransferred(OrdinalInscriptionTransferData {
+                inscription_id:
+                    "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0".into(),
+                destination: OrdinalInscriptionTransferDestination::SpentInFees,
+                tx_index: 1,
+                satpoint_pre_transfer: "".into(),
+                satpoint_post_transfer: "".into(),
+                post_transfer_output_value: None,
+            }),
+        };
+        for block_height in [42, 43] {
+            assert!(publish_or_enqueue_sink_events(
+                "predicate",
+                block_height,
+                &sink,
+                &[event(block_height)],
+                &conn,
+                &ctx
+            )
+            .is_err());
+        }
+        let batches = find_pending_sink_batches("predicate", 10, &conn, &ctx);
+        assert_eq!(
+            batches.iter().map(|b| b.block_height).collect::<Vec<_>>(),
+            vec![42, 43]
+        );
+        assert_eq!(batches[0].events, vec![event(42)]);
+        assert_eq!(batches[0].sink, sink);
+        assert_eq!(count_pending_deliveries("predicate", &conn, &ctx), 2);
+        assert_eq!(
+            get_outbox_report("predicate", &conn, &ctx).pending_sink_batches,
+            2
+        );
+        assert_eq!(
+            find_predicates_with_pending_deliveries(&conn, &ctx),
+            vec!["predicate".to_string()]
+        );
+
+        remove_deliveries_for_predicate("predicate", &conn, &ctx);
+        assert_eq!(count_pending_deliveries("predicate", &conn, &ctx), 0);
+    }
+
+    #[test]
+    fn moves_unreadable_sink_batches_to_dead_letters() {
+        let ctx = Context::empty();
+        let base_dir = TempDir::new("ordhook-outbox");
+        let conn = open_readwrite_outbox_db_conn(base_dir.path(), &ctx).unwrap();
+        let sink = SinkConfig::Nats {
+            url: "nats://127.0.0.1:1".into(),
+            subject: "inscriptions".into(),
+        };
+        enqueue_sink_batch("predicate", 42, &sink, &[], "unreachable", &conn, &ctx);
+        enqueue_sink_batch("predicate", 43, &sink, &[], "unreachable", &conn, &ctx);
+        conn.execute(
+            "UPDATE sink_batches SET events = '{' WHERE block_height = 42",
+            [],
+        )
+        .unwrap();
+
+        let batches = find_pending_sink_batches("predicate", 10, &conn, &ctx);
+        assert_eq!(
+            batches.iter().map(|b| b.block_height).collect::<Vec<_>>(),
+            vec![43]
+        );
+        let report = get_outbox_report("predicate", &conn, &ctx);
+        assert_eq!(
+            (report.pending_sink_batches, report.dead_sink_batches),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn keeps_previous_signing_secret_during_grace_period() {
+        let ctx = Context::empty();
+        let base_dir = TempDir::new("ordhook-outbox");
+        let conn = open_readwrite_outbox_db_conn(base_dir.path(), &ctx).unwrap();
+        rotate_signing_secret("predicate", "first", Duration::ZERO, &conn, &ctx);
+        rotate_signing_secret("predicate", "second", Duration::from_secs(60), &conn, &ctx);
+        assert_eq!(
+            find_active_signing_secrets("predicate", &conn, &ctx),
+            vec!["second", "first"]
+        );
+        rotate_signing_secret("predicate", "third", Duration::ZERO, &conn, &ctx);
+        assert_eq!(
+            find_active_signing_secrets("predicate", &conn, &ctx),
+            vec!["third"]
+        );
+        remove_signing_secrets_for_predicate("predicate", &conn, &ctx);
+        assert!(find_active_signing_secrets("predicate", &conn, &ctx).is_empty());
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        assert_eq!(compute_backoff(1), Duration::from_secs(1));
+        assert_eq!(compute_backoff(4), Duration::from_secs(8));
+        assert_eq!(compute_backoff(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn releases_predicates_in_flight_when_deliveries_stop() {
+        let guard = InFlightGuard::acquire(&PREDICATES_IN_FLIGHT, "in-flight").unwrap();
+        assert!(InFlightGuard::acquire(&PREDICATES_IN_FLIGHT, "in-flight").is_none());
+        drop(guard);
+
+        // Delivery panicking
+        let panicked = std::panic::catch_unwind(|| {
+            let _guard = InFlightGuard::acquire(&SINKS_IN_FLIGHT, "in-flight").unwrap();
+            panic!("delivery panicked");
+        });
+        assert!(panicked.is_err());
+        assert!(InFlightGuard::acquire(&SINKS_IN_FLIGHT, "in-flight").is_some());
+
+        // Delivery cancelled at an await point
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let delivery = async {
+                let _guard = InFlightGuard::acquire(&PREDICATES_IN_FLIGHT, "in-flight").unwrap();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            };
+            let _ = tokio::time::timeout(Duration::from_millis(10), delivery).await;
+        });
+        assert!(InFlightGuard::acquire(&PREDICATES_IN_FLIGHT, "in-flight").is_some());
+    }
+}
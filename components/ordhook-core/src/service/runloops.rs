@@ -1,67 +1,117 @@
-use std::sync::mpsc::Sender;
-
-use chainhook_sdk::{
-    chainhooks::types::{BitcoinChainhookSpecification, ChainhookSpecification},
-    observer::ObserverCommand,
-    utils::Context,
-};
-use threadpool::ThreadPool;
+use chainhook_sdk::{chainhooks::types::BitcoinChainhookSpecification, utils::Context};
 
 use crate::{
     config::Config,
-    scan::bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate,
+    scan::bitcoin::{scan_bitcoin_chainstate_via_rpc_using_predicate, SCAN_INTERRUPTED},
     service::{
-        observers::open_readwrite_observers_db_conn_or_panic, update_observer_streaming_enabled,
+        observers::{
+            find_observer_with_uuid, open_readwrite_observers_db_conn_or_panic,
+            update_observer_phase, ObserverPhase,
+        },
+        outbox::{open_readwrite_outbox_db_conn, remove_deliveries_for_predicate},
+        scan_queue::SCAN_QUEUE,
+        update_observer_streaming_enabled,
     },
+    utils::monitoring::METRICS,
 };
 
 pub fn start_bitcoin_scan_runloop(
     config: &Config,
     bitcoin_scan_op_rx: crossbeam_channel::Receiver<BitcoinChainhookSpecification>,
-    observer_command_tx: Sender<ObserverCommand>,
     ctx: &Context,
 ) {
-    let bitcoin_scan_pool = ThreadPool::new(config.limits.max_number_of_concurrent_bitcoin_scans);
-
-    while let Ok(predicate_spec) = bitcoin_scan_op_rx.recv() {
+    let mut workers = vec![];
+    for i in 0..config.limits.max_number_of_concurrent_bitcoin_scans {
         let moved_ctx = ctx.clone();
         let moved_config = config.clone();
-        let observer_command_tx = observer_command_tx.clone();
-        let db_base_dir = config.expected_cache_path();
-        bitcoin_scan_pool.execute(move || {
-            let op = scan_bitcoin_chainstate_via_rpc_using_predicate(
-                &predicate_spec,
-                &moved_config,
-                None,
-                &moved_ctx,
-            );
+        let handle = hiro_system_kit::thread_named(&format!("Bitcoin scan {i}"))
+            .spawn(move || {
+                while let Some(scan) = SCAN_QUEUE.pop() {
+                    let uuid = scan.spec.uuid.clone();
+                    run_bitcoin_scan(scan.spec, &moved_config, &moved_ctx);
+                    SCAN_QUEUE.finish(&uuid);
+                }
+            })
+            .expect("unable to spawn thread");
+        workers.push(handle);
+    }
+
+    while let Ok(predicate_spec) = bitcoin_scan_op_rx.recv() {
+        SCAN_QUEUE.push(predicate_spec);
+    }
+    SCAN_QUEUE.close();
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
 
-            match hiro_system_kit::nestable_block_on(op) {
-                Ok(_) => {}
-                Err(e) => {
-                    moved_ctx.try_log(|logger| {
-                        error!(
-                            logger,
-                            "Unable to evaluate predicate on Bitcoin chainstate: {e}",
-                        )
-                    });
+fn run_bitcoin_scan(predicate_spec: BitcoinChainhookSpecification, config: &Config, ctx: &Context) {
+    let db_base_dir = config.expected_cache_path();
+    // Predicates paused or deregistered while their scan was queued
+    {
+        let observers_db_conn = open_readwrite_observers_db_conn_or_panic(&db_base_dir, ctx);
+        match find_observer_with_uuid(&predicate_spec.uuid, &observers_db_conn, ctx) {
+            Some((_, report)) if report.phase != ObserverPhase::Paused => {}
+            _ => return,
+        }
+    }
+    METRICS.bitcoin_scans_running.inc();
+    let op = scan_bitcoin_chainstate_via_rpc_using_predicate(&predicate_spec, config, None, ctx);
 
-                    // Update predicate
-                    let mut observers_db_conn =
-                        open_readwrite_observers_db_conn_or_panic(&db_base_dir, &moved_ctx);
-                    update_observer_streaming_enabled(
-                        &predicate_spec.uuid,
-                        false,
-                        &mut observers_db_conn,
-                        &moved_ctx,
-                    );
-                    return;
+    let res = hiro_system_kit::nestable_block_on(op);
+    METRICS.bitcoin_scans_running.dec();
+    match res {
+        Ok(_) => {}
+        Err(e) if e == SCAN_INTERRUPTED => {
+            // Paused, re-scanned or superseded by a newer registration. Deregistered predicates
+            // may have enqueued the payloads of the last block scanned after their removal.
+            let observers_db_conn = open_readwrite_observers_db_conn_or_panic(&db_base_dir, ctx);
+            if find_observer_with_uuid(&predicate_spec.uuid, &observers_db_conn, ctx).is_none() {
+                if let Ok(outbox_db_conn) = open_readwrite_outbox_db_conn(&db_base_dir, ctx) {
+                    remove_deliveries_for_predicate(&predicate_spec.uuid, &outbox_db_conn, ctx);
                 }
-            };
-            let _ = observer_command_tx.send(ObserverCommand::EnablePredicate(
-                ChainhookSpecification::Bitcoin(predicate_spec),
-            ));
-        });
+            }
+            return;
+        }
+        Err(e) => {
+            ctx.try_log(|logger| {
+                error!(
+                    logger,
+                    "Unable to evaluate predicate on Bitcoin chainstate: {e}",
+                )
+            });
+
+            // Update predicate
+            let mut observers_db_conn =
+                open_readwrite_observers_db_conn_or_panic(&db_base_dir, ctx);
+            update_observer_streaming_enabled(
+                &predicate_spec.uuid,
+                false,
+                &mut observers_db_conn,
+                ctx,
+            );
+            update_observer_phase(
+                &predicate_spec.uuid,
+                ObserverPhase::Failed,
+                Some(&e),
+                &observers_db_conn,
+                ctx,
+            );
+            return;
+        }
+    };
+    // Streamed blocks are evaluated by the service, the predicate stays disabled in the observer
+    let observers_db_conn = open_readwrite_observers_db_conn_or_panic(&db_base_dir, ctx);
+    match find_observer_with_uuid(&predicate_spec.uuid, &observers_db_conn, ctx) {
+        Some((_, report)) if report.phase != ObserverPhase::Paused => {}
+        _ => return,
     }
-    let _ = bitcoin_scan_pool.join();
+    update_observer_streaming_enabled(&predicate_spec.uuid, true, &observers_db_conn, ctx);
+    update_observer_phase(
+        &predicate_spec.uuid,
+        ObserverPhase::Streaming,
+        None,
+        &observers_db_conn,
+        ctx,
+    );
 }
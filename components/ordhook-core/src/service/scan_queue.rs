@@ -0,0 +1,280 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+    sync::{Condvar, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chainhook_sdk::chainhooks::types::BitcoinChainhookSpecification;
+use lazy_static::lazy_static;
+
+use crate::{scan::bitcoin::interrupt_bitcoin_scan, utils::monitoring::METRICS};
+
+lazy_static! {
+    /// Scans of the predicates registered with the service, waiting for one of the
+    /// `max_number_of_concurrent_bitcoin_scans` slots.
+    pub static ref SCAN_QUEUE: ScanQueue = ScanQueue::new();
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for ScanPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScanPriority::Low => write!(f, "low"),
+            ScanPriority::Normal => write!(f, "normal"),
+            ScanPriority::High => write!(f, "high"),
+        }
+    }
+}
+
+impl FromStr for ScanPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(ScanPriority::Low),
+            "normal" => Ok(ScanPriority::Normal),
+            "high" => Ok(ScanPriority::High),
+            _ => Err(format!(
+                "invalid scan priority: {s} (expected low, normal or high)"
+            )),
+        }
+    }
+}
+
+pub struct QueuedScan {
+    pub spec: BitcoinChainhookSpecification,
+    pub priority: ScanPriority,
+    sequence: u64,
+    queued_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanQueueEntry {
+    pub uuid: String,
+    pub priority: ScanPriority,
+    pub running: bool,
+    /// Position in the queue, `None` for running scans.
+    pub position: Option<usize>,
+    pub queued_at: u64,
+}
+
+#[derive(Default)]
+struct ScanQueueState {
+    queued: Vec<QueuedScan>,
+    running: HashMap<String, (ScanPriority, u64)>,
+    /// Running scans cancelled before registering with `interrupt_bitcoin_scan`.
+    cancelled: HashSet<String>,
+    priorities: HashMap<String, ScanPriority>,
+    next_sequence: u64,
+    closed: bool,
+}
+
+impl ScanQueueState {
+    /// Scans ordered by priority, then by registration.
+    fn sort(&mut self) {
+        self.queued.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.sequence.cmp(&b.sequence))
+        });
+        METRICS.bitcoin_scans_queued.set(self.queued.len() as i64);
+    }
+}
+
+pub struct ScanQueue {
+    state: Mutex<ScanQueueState>,
+    scan_available: Condvar,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Could not get current time in ms")
+        .as_millis() as u64
+}
+
+impl ScanQueue {
+    pub fn new() -> ScanQueue {
+        ScanQueue {
+            state: Mutex::new(ScanQueueState::default()),
+            scan_available: Condvar::new(),
+        }
+    }
+
+    /// Queues the scan of `spec`, replacing the scan of this predicate still waiting in the queue, if any.
+    pub fn push(&self, spec: BitcoinChainhookSpecification) {
+        let mut state = self.state.lock().unwrap();
+        state.queued.retain(|scan| scan.spec.uuid != spec.uuid);
+        let priority = state
+            .priorities
+            .get(&spec.uuid)
+            .cloned()
+            .unwrap_or_default();
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.queued.push(QueuedScan {
+            spec,
+            priority,
+            sequence,
+            queued_at: now_ms(),
+        });
+        state.sort();
+        self.scan_available.notify_one();
+    }
+
+    /// Blocks until a scan is available, and marks it as running. Returns `None` once the queue is closed.
+    pub fn pop(&self) -> Option<QueuedScan> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if !state.queued.is_empty() {
+                let scan = state.queued.remove(0);
+                state.cancelled.remove(&scan.spec.uuid);
+                state
+                    .running
+                    .insert(scan.spec.uuid.clone(), (scan.priority, scan.queued_at));
+                METRICS.bitcoin_scans_queued.set(state.queued.len() as i64);
+                return Some(scan);
+            }
+            state = self.scan_available.wait(state).unwrap();
+        }
+    }
+
+    pub fn finish(&self, uuid: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.running.remove(uuid);
+        state.cancelled.remove(uuid);
+    }
+
+    /// Whether the running scan of the predicate `uuid` was cancelled. Checked by scans once registered,
+    /// for the cancellations received between `pop` and their registration not to be lost.
+    pub fn is_cancelled(&self, uuid: &str) -> bool {
+        self.state.lock().unwrap().cancelled.contains(uuid)
+    }
+
+    /// Sets the priority of the scans of the predicate `uuid`, including the scan waiting in the queue.
+    pub fn set_priority(&self, uuid: &str, priority: ScanPriority) {
+        let mut state = self.state.lock().unwrap();
+        state.priorities.insert(uuid.to_string(), priority);
+        for scan in state.queued.iter_mut() {
+            if scan.spec.uuid == uuid {
+                scan.priority = priority;
+            }
+        }
+        state.sort();
+    }
+
+    /// Removes the scan of the predicate `uuid` from the queue, and interrupts it if running.
+    /// Returns `false` if the predicate was not being scanned.
+    pub fn cancel(&self, uuid: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let queued = state.queued.len();
+        state.queued.retain(|scan| scan.spec.uuid != uuid);
+        let dequeued = state.queued.len() != queued;
+        METRICS.bitcoin_scans_queued.set(state.queued.len() as i64);
+        let running = state.running.contains_key(uuid);
+        if running {
+            state.cancelled.insert(uuid.to_string());
+        }
+        let interrupted = interrupt_bitcoin_scan(uuid);
+        dequeued || running || interrupted
+    }
+
+    /// Cancels the scan of a deregistered predicate, and forgets its priority.
+    pub fn forget(&self, uuid: &str) {
+        self.cancel(uuid);
+        self.state.lock().unwrap().priorities.remove(uuid);
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.scan_available.notify_all();
+    }
+
+    pub fn entries(&self) -> Vec<ScanQueueEntry> {
+        let state = self.state.lock().unwrap();
+        let mut running = state
+            .running
+            .iter()
+            .map(|(uuid, (priority, queued_at))| ScanQueueEntry {
+                uuid: uuid.clone(),
+                priority: *priority,
+                running: true,
+                position: None,
+                queued_at: *queued_at,
+            })
+            .collect::<Vec<_>>();
+        running.sort_by_key(|entry| entry.queued_at);
+        let queued = state
+            .queued
+            .iter()
+            .enumerate()
+            .map(|(position, scan)| ScanQueueEntry {
+                uuid: scan.spec.uuid.clone(),
+                priority: scan.priority,
+                running: false,
+                position: Some(position),
+                queued_at: scan.queued_at,
+            });
+        running.into_iter().chain(queued).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::regtest_fixtures::inscription_feed_predicate;
+
+    use super::*;
+
+    fn build_spec(uuid: &str) -> BitcoinChainhookSpecification {
+        BitcoinChainhookSpecification {
+            start_block: Some(0),
+            ..inscription_feed_predicate(uuid)
+        }
+    }
+
+    #[test]
+    fn pops_scans_by_priority_then_registration() {
+        let queue = ScanQueue::new();
+        queue.set_priority("urgent", ScanPriority::High);
+        queue.push(build_spec("first"));
+        queue.push(build_spec("second"));
+        queue.push(build_spec("urgent"));
+        queue.push(build_spec("first"));
+        queue.set_priority("second", ScanPriority::Low);
+        assert!(queue.cancel("urgent"));
+        queue.push(build_spec("urgent"));
+
+        let order = (0..3)
+            .map(|_| queue.pop().unwrap().spec.uuid)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["urgent", "first", "second"]);
+        assert_eq!(queue.entries().len(), 3);
+        queue.finish("urgent");
+        assert_eq!(queue.entries().len(), 2);
+        assert!(!queue.cancel("unknown"));
+
+        // Cancelled after being dequeued, before its scan registered
+        queue.push(build_spec("third"));
+        assert_eq!(queue.pop().unwrap().spec.uuid, "third");
+        assert!(!queue.is_cancelled("third"));
+        assert!(queue.cancel("third"));
+        assert!(queue.is_cancelled("third"));
+        queue.finish("third");
+        assert!(!queue.is_cancelled("third"));
+        queue.close();
+        assert!(queue.pop().is_none());
+    }
+}
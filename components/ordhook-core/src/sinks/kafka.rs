@@ -0,0 +1,68 @@
+use super::Sink;
+
+#[cfg(feature = "kafka")]
+mod producer {
+    use std::time::Duration;
+
+    use ::kafka::producer::{Producer, Record, RequiredAcks};
+
+    use super::super::{Sink, SinkMessage};
+
+    pub struct KafkaSink {
+        producer: Producer,
+        topic: String,
+    }
+
+    impl Sink for KafkaSink {
+        fn publish(&mut self, messages: &[SinkMessage]) -> Result<(), String> {
+            // Records are partitioned by key: the events of an inscription stay ordered.
+            let records = messages
+                .iter()
+                .map(|message| {
+                    Record::from_key_value(
+                        &self.topic,
+                        message.key.as_bytes(),
+                        &message.payload[..],
+                    )
+                })
+                .collect::<Vec<_>>();
+            let confirms = self
+                .producer
+                .send_all(&records)
+                .map_err(|e| e.to_string())?;
+            for confirm in confirms.iter() {
+                for partition_confirm in confirm.partition_confirms.iter() {
+                    if let Err(code) = partition_confirm.offset {
+                        return Err(format!(
+                            "partition {} of {} rejected the records: {:?}",
+                            partition_confirm.partition, confirm.topic, code
+                        ));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    pub fn connect(brokers: &[String], topic: &str) -> Result<Box<dyn Sink>, String> {
+        let producer = Producer::from_hosts(brokers.to_vec())
+            .with_ack_timeout(Duration::from_secs(10))
+            .with_required_acks(RequiredAcks::All)
+            .create()
+            .map_err(|e| format!("unable to connect to kafka: {}", e.to_string()))?;
+        Ok(Box::new(KafkaSink {
+            producer,
+            topic: topic.to_string(),
+        }))
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub fn connect(brokers: &[String], topic: &str) -> Result<Box<dyn Sink>, String> {
+    producer::connect(brokers, topic)
+}
+
+#[cfg(not(feature = "kafka"))]
+pub fn connect(_brokers: &[String], _topic: &str) -> Result<Box<dyn Sink>, String> {
+    Err(format!("ordhook was built without the `kafka` feature"))
+}
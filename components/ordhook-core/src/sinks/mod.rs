@@ -0,0 +1,338 @@
+mod kafka;
+mod nats;
+mod redis;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chainhook_sdk::{
+    chainhooks::bitcoin::BitcoinTriggerChainhook,
+    types::{BitcoinBlockData, BitcoinTransactionData, OrdinalOperation},
+    utils::Context,
+};
+use lazy_static::lazy_static;
+
+/// Message broker receiving the inscription events of a predicate, in addition to its action.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Events are keyed by inscription id, and spread over the partitions of the topic.
+    Kafka { brokers: Vec<String>, topic: String },
+    /// Events are published on `<subject>.<inscription id>`.
+    Nats { url: String, subject: String },
+    /// Events are appended to `<stream>`, or to `<stream>:<n>` when spread over several partitions.
+    RedisStreams {
+        url: String,
+        stream: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        partitions: Option<u32>,
+        /// Approximate maximum length of the streams (`XADD MAXLEN ~`).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_length: Option<u64>,
+    },
+}
+
+impl SinkConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let (feature, enabled) = match self {
+            SinkConfig::Kafka { .. } => ("kafka", cfg!(feature = "kafka")),
+            SinkConfig::Nats { .. } => ("nats", cfg!(feature = "nats")),
+            SinkConfig::RedisStreams { .. } => ("redis", cfg!(feature = "redis")),
+        };
+        if !enabled {
+            return Err(format!(
+                "{} sink unavailable: ordhook was built without the `{feature}` feature",
+                self.kind()
+            ));
+        }
+        match self {
+            SinkConfig::Kafka { brokers, topic } => {
+                if brokers.is_empty() || topic.is_empty() {
+                    return Err(format!("kafka sink requires brokers and a topic"));
+                }
+            }
+            SinkConfig::Nats { url, subject } => {
+                if url.is_empty() || subject.is_empty() {
+                    return Err(format!("nats sink requires an url and a subject"));
+                }
+            }
+            SinkConfig::RedisStreams {
+                url,
+                stream,
+                partitions,
+                ..
+            } => {
+                if url.is_empty() || stream.is_empty() {
+                    return Err(format!("redis_streams sink requires an url and a stream"));
+                }
+                if *partitions == Some(0) {
+                    return Err(format!("redis_streams sink requires at least 1 partition"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            SinkConfig::Kafka { .. } => "kafka",
+            SinkConfig::Nats { .. } => "nats",
+            SinkConfig::RedisStreams { .. } => "redis_streams",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkEventType {
+    Apply,
+    /// Compensating event, undoing an operation of a block orphaned by a re-org.
+    Rollback,
+}
+
+/// Event published for each ordinal operation. Events of a given inscription share the same key,
+/// and can be ordered with `(block_height, tx_index, operation_index)`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SinkEvent {
+    pub event_type: SinkEventType,
+    pub predicate_uuid: String,
+    pub inscription_id: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    pub tx_id: String,
+    pub tx_index: usize,
+    pub operation_index: usize,
+    pub operation: OrdinalOperation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SinkMessage {
+    pub key: String,
+    pub payload: Vec<u8>,
+}
+
+/// Connection to a broker, publishing messages in order.
+trait Sink: Send {
+    fn publish(&mut self, messages: &[SinkMessage]) -> Result<(), String>;
+}
+
+fn connect_sink(config: &SinkConfig) -> Result<Box<dyn Sink>, String> {
+    match config {
+        SinkConfig::Kafka { brokers, topic } => kafka::connect(brokers, topic),
+        SinkConfig::Nats { url, subject } => nats::connect(url, subject),
+        SinkConfig::RedisStreams {
+            url,
+            stream,
+            partitions,
+            max_length,
+        } => redis::connect(url, stream, partitions.unwrap_or(1), *max_length),
+    }
+}
+
+lazy_static! {
+    /// Broker connections, reused across blocks. Each connection has its own lock, held while
+    /// publishing, so that a slow broker does not hold back the sinks of the other predicates.
+    static ref SINKS: Mutex<HashMap<String, (SinkConfig, Arc<Mutex<Box<dyn Sink>>>)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Events of the operations included in `transactions`. Rollback events are listed in reverse order,
+/// so that consumers can undo the operations from the most recent one.
+pub fn build_sink_events(
+    predicate_uuid: &str,
+    event_type: SinkEventType,
+    block: &BitcoinBlockData,
+    transactions: &[&BitcoinTransactionData],
+) -> Vec<SinkEvent> {
+    let mut events = vec![];
+    for tx in transactions.iter() {
+        for (operation_index, operation) in tx.metadata.ordinal_operations.iter().enumerate() {
+            let (inscription_id, tx_index) = match operation {
+                OrdinalOperation::InscriptionRevealed(reveal) => {
+                    (reveal.inscription_id.clone(), reveal.tx_index)
+                }
+                OrdinalOperation::InscriptionTransferred(transfer) => {
+                    (transfer.inscription_id.clone(), transfer.tx_index)
+                }
+            };
+            events.push(SinkEvent {
+                event_type,
+                predicate_uuid: predicate_uuid.to_string(),
+                inscription_id,
+                block_height: block.block_identifier.index,
+                block_hash: block.block_identifier.hash.clone(),
+                tx_id: tx.transaction_identifier.hash.clone(),
+                tx_index,
+                operation_index,
+                operation: operation.clone(),
+            });
+        }
+    }
+    if event_type == SinkEventType::Rollback {
+        events.reverse();
+    }
+    events
+}
+
+/// Events of a triggered predicate: compensating events for the blocks rolled back (most recent
+/// first), followed by the events of the blocks applied.
+pub fn build_trigger_sink_events(
+    predicate_uuid: &str,
+    trigger: &BitcoinTriggerChainhook,
+) -> Vec<SinkEvent> {
+    let mut events = vec![];
+    let mut rollback = trigger.rollback.iter().collect::<Vec<_>>();
+    rollback.sort_by(|(_, a), (_, b)| b.block_identifier.index.cmp(&a.block_identifier.index));
+    for (transactions, block) in rollback.into_iter() {
+        events.append(&mut build_sink_events(
+            predicate_uuid,
+            SinkEventType::Rollback,
+            block,
+            transactions,
+        ));
+    }
+    for (transactions, block) in trigger.apply.iter() {
+        events.append(&mut build_sink_events(
+            predicate_uuid,
+            SinkEventType::Apply,
+            block,
+            transactions,
+        ));
+    }
+    events
+}
+
+/// Publishes `events` to the sink of the predicate `predicate_uuid`.
+pub fn publish_sink_events(
+    predicate_uuid: &str,
+    config: &SinkConfig,
+    events: &[SinkEvent],
+    ctx: &Context,
+) -> Result<(), String> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let messages = events
+        .iter()
+        .map(|event| SinkMessage {
+            key: event.inscription_id.clone(),
+            payload: serde_json::to_vec(event).unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+
+    let cached_sink = match SINKS.lock().unwrap().get(predicate_uuid) {
+        Some((cached, sink)) if cached == config => Some(sink.clone()),
+        _ => None,
+    };
+    let sink = match cached_sink {
+        Some(sink) => sink,
+        None => {
+            let sink = Arc::new(Mutex::new(connect_sink(config)?));
+            SINKS
+                .lock()
+                .unwrap()
+                .insert(predicate_uuid.to_string(), (config.clone(), sink.clone()));
+            sink
+        }
+    };
+    let res = sink.lock().unwrap().publish(&messages);
+    if let Err(e) = res {
+        // Reconnect on the next attempt, unless the connection was replaced in the meantime
+        let mut sinks = SINKS.lock().unwrap();
+        if matches!(sinks.get(predicate_uuid), Some((_, cached)) if Arc::ptr_eq(cached, &sink)) {
+            sinks.remove(predicate_uuid);
+        }
+        return Err(format!("unable to publish to sink: {e}"));
+    }
+    ctx.try_log(|logger| {
+        debug!(
+            logger,
+            "{} events of predicate {predicate_uuid} published to sink",
+            messages.len()
+        )
+    });
+    Ok(())
+}
+
+/// Drops the connection of a deregistered predicate.
+pub fn disconnect_sink(predicate_uuid: &str) {
+    SINKS.lock().unwrap().remove(predicate_uuid);
+}
+
+/// Stable partition of `key`, so that the events of an inscription land in the same partition.
+fn partition_for_key(key: &str, partitions: u32) -> u32 {
+    fxhash::hash32(key) % partitions.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sink_configs() {
+        let config: SinkConfig = serde_json::from_str(
+            r#"{"type": "redis_streams", "url": "redis://127.0.0.1:6379", "stream": "inscriptions", "partitions": 4}"#,
+        )
+        .unwrap();
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "redis"));
+        let config: SinkConfig = serde_json::from_str(
+            r#"{"type": "kafka", "brokers": ["127.0.0.1:9092"], "topic": "inscriptions"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "kafka"));
+        assert!(serde_json::from_str::<SinkConfig>(
+            r#"{"type": "nats", "url": "nats://127.0.0.1:4222", "topic": "inscriptions"}"#
+        )
+        .is_err());
+    }
+
+    /// Publishes to the brokers listed in the environment (see `dockerfiles/docker-compose.sinks.yml`).
+    #[test]
+    #[ignore = "requires the brokers of dockerfiles/docker-compose.sinks.yml"]
+    fn publishes_to_local_brokers() {
+        let mut configs = vec![];
+        if let Ok(brokers) = std::env::var("ORDHOOK_TEST_KAFKA_BROKERS") {
+            configs.push(SinkConfig::Kafka {
+                brokers: brokers.split(',').map(|b| b.to_string()).collect(),
+                topic: "ordhook-test".into(),
+            });
+        }
+        if let Ok(url) = std::env::var("ORDHOOK_TEST_NATS_URL") {
+            configs.push(SinkConfig::Nats {
+                url,
+                subject: "ordhook-test".into(),
+            });
+        }
+        if let Ok(url) = std::env::var("ORDHOOK_TEST_REDIS_URL") {
+            configs.push(SinkConfig::RedisStreams {
+                url,
+                stream: "ordhook-test".into(),
+                partitions: Some(2),
+                max_length: Some(1000),
+            });
+        }
+        assert!(
+            !configs.is_empty(),
+            "set ORDHOOK_TEST_KAFKA_BROKERS, ORDHOOK_TEST_NATS_URL or ORDHOOK_TEST_REDIS_URL"
+        );
+        let payload = r#"{"inscription_id":"6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0"}"#;
+        for config in configs.into_iter() {
+            let mut sink = connect_sink(&config).unwrap();
+            let messages = vec![SinkMessage {
+                key: "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0".into(),
+                payload: payload.as_bytes().to_vec(),
+            }];
+            assert_eq!(sink.publish(&messages), Ok(()), "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn partitions_are_stable() {
+        let id = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0";
+        assert_eq!(partition_for_key(id, 8), partition_for_key(id, 8));
+        assert!(partition_for_key(id, 8) < 8);
+        assert_eq!(partition_for_key(id, 1), 0);
+    }
+}
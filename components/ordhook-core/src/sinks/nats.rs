@@ -0,0 +1,43 @@
+use super::Sink;
+
+#[cfg(feature = "nats")]
+mod publisher {
+    use super::super::{Sink, SinkMessage};
+
+    pub struct NatsSink {
+        connection: ::nats::Connection,
+        subject: String,
+    }
+
+    impl Sink for NatsSink {
+        fn publish(&mut self, messages: &[SinkMessage]) -> Result<(), String> {
+            for message in messages.iter() {
+                // Inscription ids do not include `.`, and can be used as subject tokens.
+                let subject = format!("{}.{}", self.subject, message.key);
+                self.connection
+                    .publish(&subject, &message.payload)
+                    .map_err(|e| e.to_string())?;
+            }
+            self.connection.flush().map_err(|e| e.to_string())
+        }
+    }
+
+    pub fn connect(url: &str, subject: &str) -> Result<Box<dyn Sink>, String> {
+        let connection = ::nats::connect(url)
+            .map_err(|e| format!("unable to connect to nats: {}", e.to_string()))?;
+        Ok(Box::new(NatsSink {
+            connection,
+            subject: subject.to_string(),
+        }))
+    }
+}
+
+#[cfg(feature = "nats")]
+pub fn connect(url: &str, subject: &str) -> Result<Box<dyn Sink>, String> {
+    publisher::connect(url, subject)
+}
+
+#[cfg(not(feature = "nats"))]
+pub fn connect(_url: &str, _subject: &str) -> Result<Box<dyn Sink>, String> {
+    Err(format!("ordhook was built without the `nats` feature"))
+}
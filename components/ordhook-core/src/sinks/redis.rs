@@ -0,0 +1,82 @@
+use super::Sink;
+
+#[cfg(feature = "redis")]
+mod streams {
+    use super::super::{partition_for_key, Sink, SinkMessage};
+
+    pub struct RedisStreamsSink {
+        connection: ::redis::Connection,
+        stream: String,
+        partitions: u32,
+        max_length: Option<u64>,
+    }
+
+    impl RedisStreamsSink {
+        fn stream_for_key(&self, key: &str) -> String {
+            match self.partitions {
+                1 => self.stream.clone(),
+                partitions => format!("{}:{}", self.stream, partition_for_key(key, partitions)),
+            }
+        }
+    }
+
+    impl Sink for RedisStreamsSink {
+        fn publish(&mut self, messages: &[SinkMessage]) -> Result<(), String> {
+            let mut pipeline = ::redis::pipe();
+            pipeline.atomic();
+            for message in messages.iter() {
+                let command = pipeline.cmd("XADD").arg(self.stream_for_key(&message.key));
+                if let Some(max_length) = self.max_length {
+                    command.arg("MAXLEN").arg("~").arg(max_length);
+                }
+                command
+                    .arg("*")
+                    .arg("key")
+                    .arg(&message.key)
+                    .arg("payload")
+                    .arg(&message.payload)
+                    .ignore();
+            }
+            pipeline
+                .query::<()>(&mut self.connection)
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    pub fn connect(
+        url: &str,
+        stream: &str,
+        partitions: u32,
+        max_length: Option<u64>,
+    ) -> Result<Box<dyn Sink>, String> {
+        let connection = ::redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .map_err(|e| format!("unable to connect to redis: {}", e.to_string()))?;
+        Ok(Box::new(RedisStreamsSink {
+            connection,
+            stream: stream.to_string(),
+            partitions,
+            max_length,
+        }))
+    }
+}
+
+#[cfg(feature = "redis")]
+pub fn connect(
+    url: &str,
+    stream: &str,
+    partitions: u32,
+    max_length: Option<u64>,
+) -> Result<Box<dyn Sink>, String> {
+    streams::connect(url, stream, partitions, max_length)
+}
+
+#[cfg(not(feature = "redis"))]
+pub fn connect(
+    _url: &str,
+    _stream: &str,
+    _partitions: u32,
+    _max_length: Option<u64>,
+) -> Result<Box<dyn Sink>, String> {
+    Err(format!("ordhook was built without the `redis` feature"))
+}
@@ -0,0 +1,213 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use rocket::config::{self, Config as RocketConfig, LogLevel};
+use rocket::serde::json::{json, Json, Value as JsonValue};
+use rocket::{Shutdown, State};
+
+use crate::config::Config;
+
+const RPC_INVALID_PARAMETER: i64 = -8;
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+type MockChain = Arc<RwLock<Vec<JsonValue>>>;
+
+/// In-process stand-in for bitcoind, serving the RPC methods used by the indexer
+/// (`getblockhash`, `getblock`, `getblockchaininfo`, `getblockcount`, `getbestblockhash`)
+/// out of a list of blocks formatted as `getblock <hash> 3` responses.
+///
+/// The server is stopped when dropped.
+pub struct MockBitcoind {
+    port: u16,
+    chain: MockChain,
+    shutdown: Shutdown,
+}
+
+impl MockBitcoind {
+    pub fn start(blocks: Vec<JsonValue>) -> Result<MockBitcoind, String> {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .map(|addr| addr.port())
+            .map_err(|e| format!("unable to find an available port: {}", e.to_string()))?;
+
+        let mut shutdown_config = config::Shutdown::default();
+        shutdown_config.ctrlc = false;
+        shutdown_config.grace = 1;
+        shutdown_config.mercy = 1;
+
+        let rocket_config = RocketConfig {
+            port,
+            workers: 2,
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            keep_alive: 5,
+            temp_dir: std::env::temp_dir().into(),
+            log_level: LogLevel::Off,
+            cli_colors: false,
+            shutdown: shutdown_config,
+            ..RocketConfig::default()
+        };
+
+        let chain: MockChain = Arc::new(RwLock::new(blocks));
+        let ignite = hiro_system_kit::nestable_block_on(
+            rocket::custom(rocket_config)
+                .manage(chain.clone())
+                .mount("/", routes![handle_rpc_request])
+                .ignite(),
+        )
+        .map_err(|e| format!("unable to start mock bitcoind: {}", e.to_string()))?;
+        let shutdown = ignite.shutdown();
+
+        let _ = hiro_system_kit::thread_named("Mock bitcoind").spawn(move || {
+            let _ = hiro_system_kit::nestable_block_on(ignite.launch());
+        });
+
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let started_at = Instant::now();
+        while TcpStream::connect(addr).is_err() {
+            if started_at.elapsed() > Duration::from_secs(10) {
+                return Err(format!("mock bitcoind not listening on port {port}"));
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        Ok(MockBitcoind {
+            port,
+            chain,
+            shutdown,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// Default regtest configuration, pointing to this node and to a fresh working directory.
+    pub fn ordhook_config(&self) -> Config {
+        let mut config = Config::devnet_default();
+        config.network.bitcoind_rpc_url = self.url();
+        config.storage.working_dir = format!(
+            "{}",
+            std::env::temp_dir()
+                .join(format!("ordhook-regtest-{}", uuid::Uuid::new_v4()))
+                .display()
+        );
+        config.limits.max_number_of_processing_threads = 2;
+        config.limits.bitcoin_concurrent_http_requests_max = 4;
+        config.limits.max_caching_memory_size_mb = 32;
+        config
+    }
+
+    pub fn tip_height(&self) -> u64 {
+        self.chain.read().unwrap().len() as u64 - 1
+    }
+
+    /// Appends blocks to the chain served.
+    pub fn extend(&self, blocks: &[JsonValue]) {
+        self.chain.write().unwrap().extend_from_slice(blocks);
+    }
+
+    /// Replaces the chain served by `blocks`, as bitcoind would after switching to a heavier fork.
+    pub fn reorg(&self, blocks: Vec<JsonValue>) {
+        *self.chain.write().unwrap() = blocks;
+    }
+}
+
+impl Drop for MockBitcoind {
+    fn drop(&mut self) {
+        self.shutdown.clone().notify();
+    }
+}
+
+#[post("/", data = "<request>")]
+fn handle_rpc_request(request: Json<JsonValue>, chain: &State<MockChain>) -> Json<JsonValue> {
+    let chain = chain.read().unwrap();
+    let params = &request["params"];
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getblockhash" => params[0]
+            .as_u64()
+            .and_then(|height| chain.get(height as usize))
+            .map(|block| block["hash"].clone())
+            .ok_or((RPC_INVALID_PARAMETER, "Block height out of range")),
+        "getblock" => params[0]
+            .as_str()
+            .and_then(|hash| chain.iter().find(|block| block["hash"] == hash))
+            .map(|block| {
+                let mut block = block.clone();
+                let height = block["height"].as_u64().unwrap_or_default();
+                block["confirmations"] = json!(chain.len() as u64 - height);
+                if let Some(next_block) = chain.get(height as usize + 1) {
+                    block["nextblockhash"] = next_block["hash"].clone();
+                }
+                block
+            })
+            .ok_or((RPC_INVALID_ADDRESS_OR_KEY, "Block not found")),
+        "getblockcount" => Ok(json!(chain.len() - 1)),
+        "getbestblockhash" => Ok(chain.last().unwrap()["hash"].clone()),
+        "getblockchaininfo" => {
+            let tip = chain.last().unwrap();
+            Ok(json!({
+                "chain": "regtest",
+                "blocks": chain.len() - 1,
+                "headers": chain.len() - 1,
+                "bestblockhash": tip["hash"],
+                "difficulty": tip["difficulty"],
+                "time": tip["time"],
+                "mediantime": tip["mediantime"],
+                "verificationprogress": 1.0,
+                "initialblockdownload": false,
+                "chainwork": format!("{:064x}", chain.len() * 2),
+                "size_on_disk": 0,
+                "pruned": false,
+                "softforks": {},
+                "warnings": "",
+            }))
+        }
+        _ => Err((RPC_METHOD_NOT_FOUND, "Method not found")),
+    };
+
+    let mut response = match result {
+        Ok(result) => json!({ "result": result, "error": null, "id": request["id"] }),
+        Err((code, message)) => json!({
+            "result": null,
+            "error": { "code": code, "message": message },
+            "id": request["id"],
+        }),
+    };
+    if !request["jsonrpc"].is_null() {
+        response["jsonrpc"] = request["jsonrpc"].clone();
+    }
+    Json(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use chainhook_sdk::bitcoincore_rpc::{Auth, Client, RpcApi};
+
+    use super::MockBitcoind;
+    use crate::utils::regtest_fixtures::regtest_corpus;
+
+    #[test]
+    fn serves_chain_tip_and_reorgs() {
+        let corpus = regtest_corpus();
+        let bitcoind = MockBitcoind::start(corpus.chain.blocks().clone()).unwrap();
+        let rpc = Client::new(
+            &bitcoind.url(),
+            Auth::UserPass("devnet".into(), "devnet".into()),
+        )
+        .unwrap();
+
+        let info = rpc.get_blockchain_info().unwrap();
+        assert_eq!(info.blocks, 7);
+        let hash = rpc.get_block_hash(6).unwrap();
+        assert_eq!(hash.to_string(), corpus.chain.block_hash(6).unwrap());
+
+        let mut fork = corpus.chain.fork(5);
+        fork.mine_blocks(3);
+        bitcoind.reorg(fork.blocks().clone());
+        assert_eq!(rpc.get_blockchain_info().unwrap().blocks, 8);
+        let hash = rpc.get_block_hash(6).unwrap();
+        assert_eq!(hash.to_string(), fork.block_hash(6).unwrap());
+    }
+}
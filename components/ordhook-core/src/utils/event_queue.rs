@@ -0,0 +1,411 @@
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use chainhook_sdk::chainhooks::bitcoin::BitcoinTransactionPayload;
+
+/// How deliveries are retried when they are not acknowledged.
+#[derive(Clone, Debug)]
+pub struct DeliveryPolicy {
+    /// `0` retries forever.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Events not acknowledged in time are delivered again. Events wait for their acknowledgement
+    /// indefinitely when `None`.
+    pub ack_timeout: Option<Duration>,
+}
+
+impl DeliveryPolicy {
+    pub fn is_exhausted(&self, attempt: u32) -> bool {
+        self.max_attempts != 0 && attempt >= self.max_attempts
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        DeliveryPolicy {
+            max_attempts: 0,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            ack_timeout: None,
+        }
+    }
+}
+
+/// Block applied or rolled back, handed to the consumer of an SDK.
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexerEvent {
+    /// Identifier to acknowledge the event with.
+    pub id: i64,
+    /// `apply` or `rollback`
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    /// Starts at 1, and increases every time the event is delivered again.
+    pub attempt: u32,
+    pub block: serde_json::Value,
+}
+
+impl IndexerEvent {
+    pub fn new(
+        id: i64,
+        event_type: &str,
+        payload: &BitcoinTransactionPayload,
+    ) -> Result<IndexerEvent, String> {
+        Ok(IndexerEvent {
+            id,
+            event_type: event_type.to_string(),
+            block_height: payload.block.block_identifier.index,
+            block_hash: payload.block.block_identifier.hash.clone(),
+            attempt: 1,
+            block: serde_json::to_value(payload).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum NextEvent {
+    Event(IndexerEvent),
+    /// No event was ready in time
+    Pending,
+    Done,
+}
+
+#[derive(Default)]
+struct EventQueueState {
+    ready: Option<IndexerEvent>,
+    in_flight: Option<i64>,
+    acked: Option<i64>,
+    nacked: Option<i64>,
+    error: Option<String>,
+    closed: bool,
+}
+
+/// Hands the events of the indexer to the consumer of an SDK one at a time: an event is only
+/// followed by the next one once acknowledged, which holds the indexer back while the consumer
+/// is busy.
+#[derive(Default)]
+pub struct EventQueue {
+    state: Mutex<EventQueueState>,
+    updated: Condvar,
+}
+
+impl EventQueue {
+    /// Blocks until `event` is acknowledged, delivering it again when it is not.
+    pub fn deliver(&self, event: IndexerEvent, policy: &DeliveryPolicy) -> Result<(), String> {
+        let id = event.id;
+        let mut attempt = 1;
+        loop {
+            let mut state = self.state.lock().unwrap();
+            if state.closed {
+                return Err("the event stream was closed".to_string());
+            }
+            state.ready = Some(IndexerEvent {
+                attempt,
+                ..event.clone()
+            });
+            self.updated.notify_all();
+            let mut delivered_at = None;
+            loop {
+                if state.closed {
+                    return Err("the event stream was closed".to_string());
+                }
+                if state.acked == Some(id) {
+                    return Ok(());
+                }
+                if state.nacked == Some(id) {
+                    state.nacked = None;
+                    break;
+                }
+                if state.in_flight == Some(id) && delivered_at.is_none() {
+                    delivered_at = Some(Instant::now());
+                }
+                match (policy.ack_timeout, delivered_at) {
+                    (Some(ack_timeout), Some(delivered_at)) => {
+                        let elapsed = delivered_at.elapsed();
+                        if elapsed >= ack_timeout {
+                            state.in_flight = None;
+                            break;
+                        }
+                        state = self
+                            .updated
+                            .wait_timeout(state, ack_timeout - elapsed)
+                            .unwrap()
+                            .0;
+                    }
+                    _ => state = self.updated.wait(state).unwrap(),
+                }
+            }
+            drop(state);
+            if policy.is_exhausted(attempt) {
+                return Err(format!(
+                    "{} event of block #{} was not acknowledged after {attempt} attempts",
+                    event.event_type, event.block_height
+                ));
+            }
+            std::thread::sleep(policy.backoff(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// Waits for an event, up to `timeout` when set. The event previously returned is delivered
+    /// again when it was not acknowledged.
+    pub fn next(&self, timeout: Option<Duration>) -> Result<NextEvent, String> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.state.lock().unwrap();
+        if let Some(id) = state.in_flight.take() {
+            state.nacked = Some(id);
+            self.updated.notify_all();
+        }
+        loop {
+            if let Some(error) = state.error.take() {
+                state.closed = true;
+                self.updated.notify_all();
+                return Err(error);
+            }
+            if state.closed {
+                return Ok(NextEvent::Done);
+            }
+            if let Some(event) = state.ready.take() {
+                state.in_flight = Some(event.id);
+                self.updated.notify_all();
+                return Ok(NextEvent::Event(event));
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(NextEvent::Pending);
+                    }
+                    self.updated.wait_timeout(state, deadline - now).unwrap().0
+                }
+                None => self.updated.wait(state).unwrap(),
+            };
+        }
+    }
+
+    pub fn ack(&self, id: i64) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight != Some(id) {
+            return Err(format!("event {id} is not awaiting an acknowledgement"));
+        }
+        state.in_flight = None;
+        state.acked = Some(id);
+        self.updated.notify_all();
+        Ok(())
+    }
+
+    /// Ends the stream with `error`, returned by the pending `next()`.
+    pub fn fail(&self, error: String) {
+        let mut state = self.state.lock().unwrap();
+        state.error = Some(error);
+        self.updated.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        self.updated.notify_all();
+    }
+}
+
+/// Last block acknowledged, persisted in the working directory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub block_height: u64,
+    pub block_hash: String,
+}
+
+pub fn get_checkpoint_file_path(working_dir: &PathBuf) -> PathBuf {
+    working_dir.join("sdk-checkpoint.json")
+}
+
+pub fn read_checkpoint(working_dir: &PathBuf) -> Result<Option<Checkpoint>, String> {
+    let path = get_checkpoint_file_path(working_dir);
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| format!("unable to parse {}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("unable to read {}: {e}", path.display())),
+    }
+}
+
+/// Block left as the tip once `payload` is acknowledged: the block applied, or the parent of the
+/// block rolled back.
+pub fn checkpoint_after(event_type: &str, payload: &BitcoinTransactionPayload) -> Checkpoint {
+    let block_identifier = match event_type {
+        "rollback" => &payload.block.parent_block_identifier,
+        _ => &payload.block.block_identifier,
+    };
+    Checkpoint {
+        block_height: block_identifier.index,
+        block_hash: block_identifier.hash.clone(),
+    }
+}
+
+pub fn write_checkpoint(working_dir: &PathBuf, checkpoint: &Checkpoint) -> Result<(), String> {
+    let path = get_checkpoint_file_path(working_dir);
+    let tmp_path = path.with_extension("json.tmp");
+    let bytes = serde_json::to_vec(checkpoint).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(working_dir)
+        .and_then(|_| std::fs::write(&tmp_path, bytes))
+        .and_then(|_| std::fs::rename(&tmp_path, &path))
+        .map_err(|e| format!("unable to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+
+    use crate::utils::regtest_fixtures::TempDir;
+
+    use super::*;
+
+    fn test_event(id: i64) -> IndexerEvent {
+        IndexerEvent {
+            id,
+            event_type: "apply".to_string(),
+            block_height: 767430,
+            block_hash: "0x00".to_string(),
+            attempt: 1,
+            block: serde_json::Value::Null,
+        }
+    }
+
+    fn test_policy(max_attempts: u32, ack_timeout: Option<Duration>) -> DeliveryPolicy {
+        DeliveryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            ack_timeout,
+        }
+    }
+
+    fn start_delivery(
+        queue: &Arc<EventQueue>,
+        id: i64,
+        policy: DeliveryPolicy,
+    ) -> JoinHandle<Result<(), String>> {
+        let queue = queue.clone();
+        std::thread::spawn(move || queue.deliver(test_event(id), &policy))
+    }
+
+    fn next_event(queue: &EventQueue) -> IndexerEvent {
+        match queue.next(None) {
+            Ok(NextEvent::Event(event)) => event,
+            next => panic!("expected an event, got {next:?}"),
+        }
+    }
+
+    #[test]
+    fn delivers_until_acknowledged() {
+        let queue = Arc::new(EventQueue::default());
+        let delivery = start_delivery(&queue, 1, test_policy(0, None));
+        let event = next_event(&queue);
+        assert_eq!((event.id, event.attempt), (1, 1));
+        assert!(queue.ack(2).is_err());
+        queue.ack(1).unwrap();
+        assert_eq!(delivery.join().unwrap(), Ok(()));
+        assert!(queue.ack(1).is_err());
+    }
+
+    #[test]
+    fn delivers_again_events_not_acknowledged() {
+        let queue = Arc::new(EventQueue::default());
+        let delivery = start_delivery(&queue, 1, test_policy(0, None));
+        assert_eq!(next_event(&queue).attempt, 1);
+        // Requesting the next event without acknowledging the previous one
+        let event = next_event(&queue);
+        assert_eq!((event.id, event.attempt), (1, 2));
+        queue.ack(1).unwrap();
+        assert_eq!(delivery.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn delivers_again_events_not_acknowledged_in_time() {
+        let queue = Arc::new(EventQueue::default());
+        let policy = test_policy(0, Some(Duration::from_millis(20)));
+        let delivery = start_delivery(&queue, 1, policy);
+        assert_eq!(next_event(&queue).attempt, 1);
+        std::thread::sleep(Duration::from_millis(100));
+        // The acknowledgement came too late
+        assert!(queue.ack(1).is_err());
+        let event = next_event(&queue);
+        assert_eq!((event.id, event.attempt), (1, 2));
+        queue.ack(1).unwrap();
+        assert_eq!(delivery.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let queue = Arc::new(EventQueue::default());
+        let policy = test_policy(2, Some(Duration::from_millis(10)));
+        let delivery = start_delivery(&queue, 1, policy);
+        assert_eq!(next_event(&queue).attempt, 1);
+        assert_eq!(next_event(&queue).attempt, 2);
+        let error = delivery.join().unwrap().unwrap_err();
+        assert!(
+            error.contains("not acknowledged after 2 attempts"),
+            "{error}"
+        );
+
+        // The runloop ends the stream with the error
+        queue.fail(error.clone());
+        assert_eq!(queue.next(None).map(|_| ()), Err(error));
+        assert!(queue.is_closed());
+        assert!(matches!(queue.next(None), Ok(NextEvent::Done)));
+    }
+
+    #[test]
+    fn stops_delivering_once_closed() {
+        let queue = Arc::new(EventQueue::default());
+        let delivery = start_delivery(&queue, 1, test_policy(0, None));
+        assert_eq!(next_event(&queue).attempt, 1);
+        // Leaving the consumer loop
+        queue.close();
+        assert!(delivery.join().unwrap().is_err());
+        assert!(queue.is_closed());
+        assert!(matches!(queue.next(None), Ok(NextEvent::Done)));
+        let delivery = start_delivery(&queue, 2, test_policy(0, None));
+        assert!(delivery.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn waits_for_events_up_to_the_timeout() {
+        let queue = EventQueue::default();
+        let next = queue.next(Some(Duration::from_millis(10)));
+        assert!(matches!(next, Ok(NextEvent::Pending)));
+    }
+
+    #[test]
+    fn persists_checkpoints() {
+        let temp_dir = TempDir::new("ordhook-event-queue");
+        let working_dir = temp_dir.path();
+        assert!(read_checkpoint(working_dir).unwrap().is_none());
+        let checkpoint = Checkpoint {
+            block_height: 767430,
+            block_hash: "0x00".to_string(),
+        };
+        write_checkpoint(working_dir, &checkpoint).unwrap();
+        let checkpoint = read_checkpoint(working_dir).unwrap().unwrap();
+        assert_eq!(
+            (checkpoint.block_height, checkpoint.block_hash),
+            (767430, "0x00".to_string())
+        );
+    }
+}
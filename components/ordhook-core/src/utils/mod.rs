@@ -1,3 +1,12 @@
+#[cfg(any(test, feature = "fixtures"))]
+pub mod bitcoind_mock;
+pub mod event_queue;
+pub mod monitoring;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod regtest_fixtures;
+pub mod shutdown;
+pub mod webhooks;
+
 use std::{
     fs,
     io::{Read, Write},
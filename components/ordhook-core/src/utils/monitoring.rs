@@ -0,0 +1,246 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use rocket::config::{self, Config, LogLevel};
+use rocket::http::{ContentType, Status};
+use std::error::Error;
+
+lazy_static! {
+    /// Process wide metrics, exposed on `/metrics` using the Prometheus text format.
+    pub static ref METRICS: PrometheusMonitor = PrometheusMonitor::new();
+}
+
+pub struct PrometheusMonitor {
+    pub registry: Registry,
+    /// Height of the chain tip, as last reported by bitcoind.
+    pub chain_tip_block_height: IntGauge,
+    /// Height of the last block indexed in hord.sqlite.
+    pub indexed_block_height: IntGauge,
+    pub indexed_block_lag: IntGauge,
+    /// Blocks going through the pipeline, labelled by stage.
+    pub pipeline_blocks: IntCounterVec,
+    /// Satoshi traversals performed by `compute_satoshi_number`, labelled by outcome.
+    pub traversals: IntCounterVec,
+    pub traversal_hops: Histogram,
+    /// Traversals cache lookups, labelled by cache (l1, l2) and result (hit, miss).
+    pub cache_lookups: IntCounterVec,
+    /// Predicate payloads delivered, labelled by source (scan, stream) and outcome.
+    pub predicate_deliveries: IntCounterVec,
+    pub bitcoin_scans_running: IntGauge,
+    pub bitcoin_scans_queued: IntGauge,
+}
+
+impl PrometheusMonitor {
+    fn new() -> PrometheusMonitor {
+        let registry = Registry::new_custom(Some("ordhook".into()), None)
+            .expect("unable to create metrics registry");
+
+        let chain_tip_block_height = IntGauge::new(
+            "chain_tip_block_height",
+            "Height of the Bitcoin chain tip reported by bitcoind",
+        )
+        .expect("unable to create metric");
+        let indexed_block_height =
+            IntGauge::new("indexed_block_height", "Height of the last block indexed")
+                .expect("unable to create metric");
+        let indexed_block_lag = IntGauge::new(
+            "indexed_block_lag",
+            "Number of blocks between the chain tip and the last block indexed",
+        )
+        .expect("unable to create metric");
+        let pipeline_blocks = IntCounterVec::new(
+            Opts::new(
+                "pipeline_blocks_total",
+                "Blocks processed per pipeline stage",
+            ),
+            &["stage"],
+        )
+        .expect("unable to create metric");
+        let traversals = IntCounterVec::new(
+            Opts::new("traversals_total", "Satoshi traversals computed"),
+            &["outcome"],
+        )
+        .expect("unable to create metric");
+        let traversal_hops = Histogram::with_opts(
+            HistogramOpts::new(
+                "traversal_hops",
+                "Number of transactions walked back to reach the coinbase of a satoshi",
+            )
+            .buckets(vec![
+                1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0,
+            ]),
+        )
+        .expect("unable to create metric");
+        let cache_lookups = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Traversals cache lookups"),
+            &["cache", "result"],
+        )
+        .expect("unable to create metric");
+        let predicate_deliveries = IntCounterVec::new(
+            Opts::new("predicate_deliveries_total", "Predicate payloads delivered"),
+            &["source", "outcome"],
+        )
+        .expect("unable to create metric");
+        let bitcoin_scans_running = IntGauge::new(
+            "bitcoin_scans_running",
+            "Number of predicate scans currently running",
+        )
+        .expect("unable to create metric");
+        let bitcoin_scans_queued = IntGauge::new(
+            "bitcoin_scans_queued",
+            "Number of predicate scans waiting for a slot",
+        )
+        .expect("unable to create metric");
+
+        registry
+            .register(Box::new(chain_tip_block_height.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(indexed_block_height.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(indexed_block_lag.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(pipeline_blocks.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(traversals.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(traversal_hops.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(cache_lookups.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(predicate_deliveries.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(bitcoin_scans_running.clone()))
+            .expect("unable to register metric");
+        registry
+            .register(Box::new(bitcoin_scans_queued.clone()))
+            .expect("unable to register metric");
+
+        PrometheusMonitor {
+            registry,
+            chain_tip_block_height,
+            indexed_block_height,
+            indexed_block_lag,
+            pipeline_blocks,
+            traversals,
+            traversal_hops,
+            cache_lookups,
+            predicate_deliveries,
+            bitcoin_scans_running,
+            bitcoin_scans_queued,
+        }
+    }
+
+    pub fn set_chain_tip_block_height(&self, block_height: u64) {
+        self.chain_tip_block_height.set(block_height as i64);
+        self.update_indexed_block_lag();
+    }
+
+    pub fn set_indexed_block_height(&self, block_height: u64) {
+        self.indexed_block_height.set(block_height as i64);
+        if block_height as i64 > self.chain_tip_block_height.get() {
+            self.chain_tip_block_height.set(block_height as i64);
+        }
+        self.update_indexed_block_lag();
+    }
+
+    fn update_indexed_block_lag(&self) {
+        let lag = self.chain_tip_block_height.get() - self.indexed_block_height.get();
+        self.indexed_block_lag.set(lag.max(0));
+    }
+
+    pub fn record_pipeline_blocks(&self, stage: &str, count: u64) {
+        self.pipeline_blocks
+            .with_label_values(&[stage])
+            .inc_by(count);
+    }
+
+    pub fn record_traversal(&self, hops: Option<u32>) {
+        match hops {
+            Some(hops) => {
+                self.traversals.with_label_values(&["success"]).inc();
+                self.traversal_hops.observe(hops as f64);
+            }
+            None => {
+                self.traversals.with_label_values(&["failure"]).inc();
+            }
+        }
+    }
+
+    pub fn record_cache_lookups(&self, cache: &str, hits: u64, misses: u64) {
+        self.cache_lookups
+            .with_label_values(&[cache, "hit"])
+            .inc_by(hits);
+        self.cache_lookups
+            .with_label_values(&[cache, "miss"])
+            .inc_by(misses);
+    }
+
+    pub fn record_predicate_delivery(&self, source: &str, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.predicate_deliveries
+            .with_label_values(&[source, outcome])
+            .inc();
+    }
+
+    pub fn encode(&self) -> Result<String, String> {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| format!("unable to encode metrics: {}", e.to_string()))?;
+        String::from_utf8(buffer).map_err(|e| format!("unable to encode metrics: {}", e))
+    }
+}
+
+pub async fn start_serving_prometheus_metrics(port: u16) -> Result<(), Box<dyn Error>> {
+    let mut shutdown_config = config::Shutdown::default();
+    shutdown_config.ctrlc = false;
+    shutdown_config.grace = 1;
+    shutdown_config.mercy = 1;
+
+    let metrics_config = Config {
+        port,
+        workers: 1,
+        address: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+        keep_alive: 5,
+        temp_dir: std::env::temp_dir().into(),
+        log_level: LogLevel::Off,
+        cli_colors: false,
+        shutdown: shutdown_config,
+        ..Config::default()
+    };
+
+    let ignite = rocket::custom(metrics_config)
+        .mount("/", routes![handle_get_metrics])
+        .ignite()
+        .await?;
+
+    let _ = std::thread::spawn(move || {
+        let _ = hiro_system_kit::nestable_block_on(ignite.launch());
+    });
+    Ok(())
+}
+
+#[get("/metrics")]
+pub fn handle_get_metrics() -> (Status, (ContentType, String)) {
+    encode_metrics()
+}
+
+/// Metrics in the Prometheus text format.
+pub fn encode_metrics() -> (Status, (ContentType, String)) {
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    match METRICS.encode() {
+        Ok(metrics) => (Status::Ok, (content_type, metrics)),
+        Err(e) => (Status::InternalServerError, (ContentType::Plain, e)),
+    }
+}
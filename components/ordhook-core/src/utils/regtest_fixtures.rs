@@ -0,0 +1,514 @@
+use std::path::PathBuf;
+
+use chainhook_sdk::chainhooks::types::{
+    BitcoinChainhookSpecification, BitcoinPredicateType, HookAction, OrdinalOperations,
+};
+use chainhook_sdk::types::BitcoinNetwork;
+use serde_json::{json, Value as JsonValue};
+
+use crate::ord::height::Height;
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_IF: u8 = 0x63;
+const OP_ENDIF: u8 = 0x68;
+const OP_CHECKSIG: u8 = 0xac;
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+const SEQUENCE_FINAL: u32 = 0xffffffff;
+const SEQUENCE_RBF: u32 = 0xfffffffd;
+const BLOCK_TIME_START: u64 = 1_690_000_000;
+
+/// Output created by a fixture transaction, ready to be spent by a later transaction.
+#[derive(Clone, Debug)]
+pub struct FixtureOutpoint {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub block_height: u64,
+    pub generated: bool,
+}
+
+/// Inscription envelope, revealed through a taproot script path spend.
+#[derive(Clone, Debug, Default)]
+pub struct FixtureInscription {
+    pub content_type: Vec<u8>,
+    pub body: Vec<u8>,
+    /// Additional (tag, value) fields. Unrecognized even tags are curses.
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl FixtureInscription {
+    pub fn text(body: &str) -> FixtureInscription {
+        FixtureInscription {
+            content_type: b"text/plain;charset=utf-8".to_vec(),
+            body: body.as_bytes().to_vec(),
+            fields: vec![],
+        }
+    }
+
+    pub fn with_field(mut self, tag: &[u8], value: &[u8]) -> FixtureInscription {
+        self.fields.push((tag.to_vec(), value.to_vec()));
+        self
+    }
+
+    pub fn tapscript(&self) -> Vec<u8> {
+        let mut script = vec![];
+        push_bytes(&mut script, &[0x02; 32]);
+        script.push(OP_CHECKSIG);
+        script.push(OP_0);
+        script.push(OP_IF);
+        push_bytes(&mut script, b"ord");
+        push_bytes(&mut script, &[1]);
+        push_bytes(&mut script, &self.content_type);
+        for (tag, value) in self.fields.iter() {
+            push_bytes(&mut script, tag);
+            push_bytes(&mut script, value);
+        }
+        script.push(OP_0);
+        for chunk in self.body.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
+            push_bytes(&mut script, chunk);
+        }
+        script.push(OP_ENDIF);
+        script
+    }
+
+    /// Witness of a script path spend: signature, tapscript and control block.
+    pub fn witness(&self) -> Vec<Vec<u8>> {
+        let mut control_block = vec![0xc0];
+        control_block.extend_from_slice(&[0x03; 32]);
+        vec![vec![0x01; 64], self.tapscript(), control_block]
+    }
+}
+
+fn push_bytes(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        0 => script.push(OP_0),
+        len if len < OP_PUSHDATA1 as usize => script.push(len as u8),
+        len if len <= u8::MAX as usize => {
+            script.push(OP_PUSHDATA1);
+            script.push(len as u8);
+        }
+        len => {
+            script.push(OP_PUSHDATA2);
+            script.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+pub fn format_inscription_id(txid: &str, input_index: usize) -> String {
+    format!("{}i{}", txid, input_index)
+}
+
+/// Regtest `InscriptionFeed` predicate without action, to be adjusted with the struct update
+/// syntax.
+pub fn inscription_feed_predicate(uuid: &str) -> BitcoinChainhookSpecification {
+    BitcoinChainhookSpecification {
+        uuid: uuid.to_string(),
+        owner_uuid: None,
+        name: uuid.to_string(),
+        network: BitcoinNetwork::Regtest,
+        version: 1,
+        blocks: None,
+        start_block: None,
+        end_block: None,
+        expired_at: None,
+        expire_after_occurrence: None,
+        predicate: BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionFeed),
+        action: HookAction::Noop,
+        include_proof: false,
+        include_inputs: false,
+        include_outputs: false,
+        include_witness: false,
+        enabled: false,
+    }
+}
+
+/// Directory created under the system temporary directory, and removed when dropped, tests
+/// failing included.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(prefix: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("{prefix}-{}", uuid::Uuid::new_v4()));
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// In memory regtest chain, producing blocks in the format returned by `getblock <hash> 3`.
+///
+/// Transaction ids and block hashes are derived deterministically from the chain seed, so that
+/// a given sequence of calls always produces the same corpus. Transactions don't carry any
+/// valid signature: only the fields consumed by the indexer are being populated.
+#[derive(Clone, Debug)]
+pub struct RegtestChain {
+    seed: u64,
+    nonce: u64,
+    blocks: Vec<JsonValue>,
+    mempool: Vec<(JsonValue, u64)>,
+}
+
+impl Default for RegtestChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegtestChain {
+    pub fn new() -> RegtestChain {
+        let mut chain = RegtestChain {
+            seed: 0,
+            nonce: 0,
+            blocks: vec![],
+            mempool: vec![],
+        };
+        chain.mine_block();
+        chain
+    }
+
+    pub fn tip_height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    pub fn blocks(&self) -> &Vec<JsonValue> {
+        &self.blocks
+    }
+
+    pub fn block_hash(&self, block_height: u64) -> Option<String> {
+        self.blocks
+            .get(block_height as usize)
+            .and_then(|block| block["hash"].as_str())
+            .map(|hash| hash.to_string())
+    }
+
+    /// First output of the coinbase transaction mined at `block_height`.
+    pub fn coinbase(&self, block_height: u64) -> FixtureOutpoint {
+        let coinbase = &self.blocks[block_height as usize]["tx"][0];
+        FixtureOutpoint {
+            txid: coinbase["txid"].as_str().unwrap().to_string(),
+            vout: 0,
+            value: btc_to_sats(&coinbase["vout"][0]["value"]),
+            block_height,
+            generated: true,
+        }
+    }
+
+    /// Enqueues a transaction in the next block. Each input can reveal an inscription,
+    /// the difference between inputs and outputs is collected as a fee by the next coinbase.
+    pub fn add_transaction(
+        &mut self,
+        inputs: Vec<(FixtureOutpoint, Option<FixtureInscription>)>,
+        outputs: Vec<u64>,
+    ) -> Vec<FixtureOutpoint> {
+        let txid = self.next_hash();
+        let block_height = self.blocks.len() as u64;
+        let total_in: u64 = inputs.iter().map(|(outpoint, _)| outpoint.value).sum();
+        let total_out: u64 = outputs.iter().sum();
+        assert!(
+            total_in >= total_out,
+            "fixture transaction spending more than its inputs"
+        );
+
+        let vin = inputs
+            .iter()
+            .map(|(outpoint, inscription)| {
+                let witness = match inscription {
+                    Some(inscription) => inscription.witness(),
+                    None => vec![vec![0x01; 64]],
+                };
+                json!({
+                    "txid": outpoint.txid,
+                    "vout": outpoint.vout,
+                    "scriptSig": { "asm": "", "hex": "" },
+                    "txinwitness": witness.iter().map(hex::encode).collect::<Vec<_>>(),
+                    "prevout": {
+                        "generated": outpoint.generated,
+                        "height": outpoint.block_height,
+                        "value": sats_to_btc(outpoint.value),
+                        "scriptPubKey": p2tr_script_pubkey(outpoint.vout),
+                    },
+                    "sequence": SEQUENCE_RBF,
+                })
+            })
+            .collect::<Vec<_>>();
+        let vout = outputs
+            .iter()
+            .enumerate()
+            .map(|(n, value)| {
+                json!({
+                    "value": sats_to_btc(*value),
+                    "n": n,
+                    "scriptPubKey": p2tr_script_pubkey(n as u32),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.mempool.push((
+            json!({
+                "txid": txid,
+                "hash": txid,
+                "version": 2,
+                "locktime": 0,
+                "vin": vin,
+                "vout": vout,
+            }),
+            total_in - total_out,
+        ));
+
+        outputs
+            .into_iter()
+            .enumerate()
+            .map(|(vout, value)| FixtureOutpoint {
+                txid: txid.clone(),
+                vout: vout as u32,
+                value,
+                block_height,
+                generated: false,
+            })
+            .collect()
+    }
+
+    /// Reveals `inscription` on the first sat of `funding`, sent to a single output.
+    pub fn inscribe(
+        &mut self,
+        funding: &FixtureOutpoint,
+        inscription: &FixtureInscription,
+    ) -> FixtureOutpoint {
+        self.add_transaction(
+            vec![(funding.clone(), Some(inscription.clone()))],
+            vec![funding.value],
+        )
+        .remove(0)
+    }
+
+    /// Moves the sats of `outpoint` to a new output.
+    pub fn transfer(&mut self, outpoint: &FixtureOutpoint) -> FixtureOutpoint {
+        self.add_transaction(vec![(outpoint.clone(), None)], vec![outpoint.value])
+            .remove(0)
+    }
+
+    /// Mines the transactions enqueued, returns the height of the new block.
+    pub fn mine_block(&mut self) -> u64 {
+        let block_height = self.blocks.len() as u64;
+        let fees: u64 = self.mempool.iter().map(|(_, fee)| fee).sum();
+        let coinbase_txid = self.next_hash();
+        let coinbase = json!({
+            "txid": coinbase_txid,
+            "hash": coinbase_txid,
+            "version": 2,
+            "locktime": 0,
+            "vin": [{
+                "coinbase": hex::encode((block_height as u32).to_le_bytes()),
+                "sequence": SEQUENCE_FINAL,
+            }],
+            "vout": [{
+                "value": sats_to_btc(Height(block_height).subsidy() + fees),
+                "n": 0,
+                "scriptPubKey": p2tr_script_pubkey(0),
+            }],
+        });
+
+        let mut transactions = vec![coinbase];
+        transactions.extend(self.mempool.drain(..).map(|(tx, _)| tx));
+
+        let hash = self.next_hash();
+        let previous_block_hash = self.block_hash(block_height.saturating_sub(1));
+        let mut block = json!({
+            "hash": hash,
+            "height": block_height,
+            "version": 0x20000000,
+            "time": BLOCK_TIME_START + block_height * 600,
+            "mediantime": BLOCK_TIME_START + block_height * 600,
+            "nonce": block_height,
+            "bits": "207fffff",
+            "difficulty": 4.656542373906925e-10,
+            "nTx": transactions.len(),
+            "confirmations": 1,
+            "tx": transactions,
+        });
+        if block_height > 0 {
+            block["previousblockhash"] = json!(previous_block_hash);
+        }
+        self.blocks.push(block);
+        block_height
+    }
+
+    pub fn mine_blocks(&mut self, count: u64) -> u64 {
+        for _ in 0..count {
+            self.mine_block();
+        }
+        self.tip_height()
+    }
+
+    /// Competing chain sharing the blocks up to `common_ancestor_height`.
+    /// Blocks mined on the fork get hashes and txids distinct from the original chain.
+    pub fn fork(&self, common_ancestor_height: u64) -> RegtestChain {
+        RegtestChain {
+            seed: splitmix64(self.seed ^ self.nonce),
+            nonce: 0,
+            blocks: self.blocks[..=common_ancestor_height as usize].to_vec(),
+            mempool: vec![],
+        }
+    }
+
+    fn next_hash(&mut self) -> String {
+        let mut state = self.seed ^ splitmix64(self.nonce);
+        self.nonce += 1;
+        let mut bytes = Vec::with_capacity(32);
+        for _ in 0..4 {
+            state = splitmix64(state);
+            bytes.extend_from_slice(&state.to_be_bytes());
+        }
+        hex::encode(bytes)
+    }
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn p2tr_script_pubkey(seed: u32) -> JsonValue {
+    let mut script = vec![0x51, 0x20];
+    script.extend_from_slice(&[(seed % 255) as u8 + 1; 32]);
+    json!({
+        "asm": "",
+        "hex": hex::encode(script),
+    })
+}
+
+fn sats_to_btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn btc_to_sats(value: &JsonValue) -> u64 {
+    (value.as_f64().unwrap() * 100_000_000.0).round() as u64
+}
+
+/// Canonical corpus exercising the inscriptions life cycle:
+///
+/// - block #4: blessed inscription #0 (`inscription`) and an inscription cursed with an even tag (`tagged_curse`)
+/// - block #5: transfer of inscription #0 (`transfer_txid`) and an inscription revealed on the second input
+///   of a transaction (`batch_curse`)
+/// - block #6: reinscription of the sat holding inscription #0 (`reinscription`) and blessed inscription #1
+///   (`second_inscription`)
+pub struct RegtestCorpus {
+    pub chain: RegtestChain,
+    pub inscription: String,
+    pub tagged_curse: String,
+    pub transfer_txid: String,
+    pub batch_curse: String,
+    pub reinscription: String,
+    pub reinscription_txid: String,
+    pub second_inscription: String,
+}
+
+pub fn regtest_corpus() -> RegtestCorpus {
+    let mut chain = RegtestChain::new();
+    chain.mine_blocks(3);
+
+    let inscription = chain.inscribe(&chain.coinbase(1), &FixtureInscription::text("gm"));
+    let tagged_curse = chain.inscribe(
+        &chain.coinbase(2),
+        &FixtureInscription::text("tagged").with_field(&[2], b"unknown"),
+    );
+    chain.mine_block();
+
+    let transfer = chain.transfer(&inscription);
+    let batch = chain.add_transaction(
+        vec![
+            (chain.coinbase(3), None),
+            (chain.coinbase(4), Some(FixtureInscription::text("batch"))),
+        ],
+        vec![chain.coinbase(3).value, chain.coinbase(4).value],
+    );
+    chain.mine_block();
+
+    let reinscription = chain.inscribe(&transfer, &FixtureInscription::text("gm again"));
+    let second_inscription = chain.inscribe(&chain.coinbase(5), &FixtureInscription::text("gn"));
+    chain.mine_block();
+    chain.mine_block();
+
+    RegtestCorpus {
+        chain,
+        inscription: format_inscription_id(&inscription.txid, 0),
+        tagged_curse: format_inscription_id(&tagged_curse.txid, 0),
+        transfer_txid: transfer.txid,
+        batch_curse: format_inscription_id(&batch[0].txid, 1),
+        reinscription: format_inscription_id(&reinscription.txid, 0),
+        reinscription_txid: reinscription.txid,
+        second_inscription: format_inscription_id(&second_inscription.txid, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chainhook_sdk::indexer::bitcoin::BitcoinBlockFullBreakdown;
+    use chainhook_sdk::types::OrdinalInscriptionCurseType;
+    use chainhook_sdk::utils::Context;
+
+    use super::*;
+    use crate::core::protocol::inscription_parsing::parse_inscriptions_in_raw_tx;
+    use crate::db::LazyBlock;
+    use chainhook_sdk::types::OrdinalOperation;
+
+    #[test]
+    fn corpus_blocks_are_parsed_by_the_indexer() {
+        let ctx = Context::empty();
+        let corpus = regtest_corpus();
+        assert_eq!(corpus.chain.tip_height(), 7);
+
+        let mut reveals = vec![];
+        for block in corpus.chain.blocks() {
+            let block: BitcoinBlockFullBreakdown =
+                serde_json::from_value(block.clone()).expect("unable to parse fixture block");
+            LazyBlock::from_full_block(&block).expect("unable to compress fixture block");
+            for tx in block.tx.iter() {
+                for op in parse_inscriptions_in_raw_tx(tx, &ctx) {
+                    if let OrdinalOperation::InscriptionRevealed(reveal) = op {
+                        reveals.push((reveal.inscription_id, reveal.curse_type));
+                    }
+                }
+            }
+        }
+
+        assert_eq!(
+            reveals,
+            vec![
+                (corpus.inscription, None),
+                (
+                    corpus.tagged_curse,
+                    Some(OrdinalInscriptionCurseType::Tag(2))
+                ),
+                (corpus.batch_curse, Some(OrdinalInscriptionCurseType::Batch)),
+                (corpus.reinscription, None),
+                (corpus.second_inscription, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn forks_diverge_after_common_ancestor() {
+        let corpus = regtest_corpus();
+        let mut fork = corpus.chain.fork(5);
+        fork.mine_blocks(3);
+        assert_eq!(fork.block_hash(5), corpus.chain.block_hash(5));
+        assert_ne!(fork.block_hash(6), corpus.chain.block_hash(6));
+        assert_eq!(fork.tip_height(), 8);
+    }
+}
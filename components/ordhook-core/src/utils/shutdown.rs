@@ -0,0 +1,15 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Flags the process as shutting down (SIGINT / SIGTERM).
+///
+/// Pipelines stop downloading new blocks and drain the blocks in flight, processors commit
+/// the block being processed, flush their databases and record a checkpoint before terminating.
+pub fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,156 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Identifier of the delivery, stable across retries.
+pub const DELIVERY_ID_HEADER: &str = "ordhook-delivery-id";
+/// Unix timestamp (in seconds) of the delivery attempt.
+pub const TIMESTAMP_HEADER: &str = "ordhook-timestamp";
+/// Comma separated list of `v1=<hex encoded HMAC-SHA256>`, one per active secret.
+pub const SIGNATURE_HEADER: &str = "ordhook-signature";
+const SIGNATURE_SCHEME: &str = "v1";
+
+/// Deliveries older than this are rejected by `verify_webhook_signature`, to prevent replays.
+pub const DEFAULT_SIGNATURE_TOLERANCE_SECS: u64 = 300;
+
+pub fn generate_signing_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+fn build_mac(secret: &str, delivery_id: &str, timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{timestamp}.{delivery_id}.").as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// Signs `{timestamp}.{delivery_id}.{body}` with each of the secrets, and formats the
+/// result as the value of the `ordhook-signature` header.
+pub fn sign_webhook_payload(
+    secrets: &[String],
+    delivery_id: &str,
+    timestamp: u64,
+    body: &[u8],
+) -> String {
+    secrets
+        .iter()
+        .map(|secret| {
+            let signature = build_mac(secret, delivery_id, timestamp, body)
+                .finalize()
+                .into_bytes();
+            format!("{SIGNATURE_SCHEME}={}", hex::encode(signature))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Checks that one of the signatures listed in `signature_header` was produced with `secret`,
+/// and that the delivery was sent less than `tolerance_secs` before `now`.
+pub fn verify_webhook_signature(
+    secret: &str,
+    delivery_id: &str,
+    timestamp: u64,
+    body: &[u8],
+    signature_header: &str,
+    now: u64,
+    tolerance_secs: u64,
+) -> Result<(), String> {
+    if now.abs_diff(timestamp) > tolerance_secs {
+        return Err(format!(
+            "timestamp {timestamp} outside of the tolerance window ({tolerance_secs}s)"
+        ));
+    }
+    for entry in signature_header.split(',') {
+        let Some((scheme, signature)) = entry.trim().split_once('=') else {
+            continue;
+        };
+        if scheme != SIGNATURE_SCHEME {
+            continue;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            continue;
+        };
+        if build_mac(secret, delivery_id, timestamp, body)
+            .verify_slice(&signature)
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+    Err(format!("no valid signature found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_signatures_of_rotated_secrets() {
+        let (previous, current) = (generate_signing_secret(), generate_signing_secret());
+        let header = sign_webhook_payload(
+            &vec![current.clone(), previous.clone()],
+            "42",
+            1_700_000_000,
+            b"{}",
+        );
+        for secret in [&previous, &current] {
+            assert_eq!(
+                verify_webhook_signature(
+                    secret,
+                    "42",
+                    1_700_000_000,
+                    b"{}",
+                    &header,
+                    1_700_000_010,
+                    300
+                ),
+                Ok(())
+            );
+        }
+        assert!(verify_webhook_signature(
+            &current,
+            "42",
+            1_700_000_000,
+            b"{ }",
+            &header,
+            1_700_000_010,
+            300
+        )
+        .is_err());
+        assert!(verify_webhook_signature(
+            &current,
+            "43",
+            1_700_000_000,
+            b"{}",
+            &header,
+            1_700_000_010,
+            300
+        )
+        .is_err());
+        assert!(verify_webhook_signature(
+            &current,
+            "42",
+            1_700_000_000,
+            b"{}",
+            &header,
+            1_700_001_000,
+            300
+        )
+        .is_err());
+        assert!(verify_webhook_signature(
+            "other",
+            "42",
+            1_700_000_000,
+            b"{}",
+            &header,
+            1_700_000_010,
+            300
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,43 @@
+-- Fixture of the ordhook-sdk-js tests, generated with `sqlite3 hord.sqlite < hord.sql`.
+-- Inscription #0 is revealed in block 767430 and transferred in block 767431, where inscription #1
+-- is revealed. Indexed before reveal details and transfer destinations were recorded.
+PRAGMA journal_mode = WAL;
+PRAGMA user_version = 1;
+
+CREATE TABLE inscriptions (
+    inscription_id TEXT NOT NULL PRIMARY KEY,
+    block_height INTEGER NOT NULL,
+    ordinal_number INTEGER NOT NULL,
+    inscription_number INTEGER NOT NULL
+);
+CREATE INDEX index_inscriptions_on_ordinal_number ON inscriptions(ordinal_number);
+CREATE INDEX index_inscriptions_on_inscription_number ON inscriptions(inscription_number);
+CREATE INDEX index_inscriptions_on_block_height ON inscriptions(block_height);
+
+CREATE TABLE locations (
+    inscription_id TEXT NOT NULL,
+    block_height INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    outpoint_to_watch BLOB NOT NULL,
+    offset INTEGER NOT NULL
+);
+CREATE INDEX locations_indexed_on_block_height ON locations(block_height);
+CREATE INDEX locations_indexed_on_outpoint_to_watch ON locations(outpoint_to_watch);
+CREATE INDEX locations_indexed_on_inscription_id ON locations(inscription_id);
+
+CREATE TABLE sequence_metadata (
+    block_height INTEGER NOT NULL,
+    latest_cursed_inscription_number INTEGER NOT NULL,
+    latest_inscription_number INTEGER NOT NULL
+);
+CREATE INDEX sequence_metadata_indexed_on_block_height ON sequence_metadata(block_height);
+
+INSERT INTO inscriptions VALUES ('419a636ccc2aa55c7347c79971a738c3103b34254bd79c1a3d767df62a788b85i0', 767430, 1252201400000000, 0);
+INSERT INTO inscriptions VALUES ('26b9790188386c5c48d0f0b00d4b9f4f8c72c2a63c6a250ad9e46a35ddf49a9bi0', 767431, 1252201500000000, 1);
+INSERT INTO sequence_metadata VALUES (767430, 0, 0);
+INSERT INTO sequence_metadata VALUES (767431, 0, 1);
+
+-- Outpoints are consensus-encoded: txid bytes in internal order, then vout (u32, little endian)
+INSERT INTO locations VALUES ('419a636ccc2aa55c7347c79971a738c3103b34254bd79c1a3d767df62a788b85i0', 767430, 1, X'858b782af67d763d1a9cd74b25343b10c338a77199c747735ca52acc6c639a4100000000', 0);
+INSERT INTO locations VALUES ('419a636ccc2aa55c7347c79971a738c3103b34254bd79c1a3d767df62a788b85i0', 767431, 2, X'292ebffe7fa9d5314c6cf906778726da1461f694d08bbe44ed63b2fbca76f52700000000', 0);
+INSERT INTO locations VALUES ('26b9790188386c5c48d0f0b00d4b9f4f8c72c2a63c6a250ad9e46a35ddf49a9bi0', 767431, 3, X'9b9af4dd356ae4d90a256a3ca6c2728c4f9f4b0db0f0d0485c6c38880179b92600000000', 0);
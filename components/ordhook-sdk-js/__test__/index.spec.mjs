@@ -1,4 +1,8 @@
 import test from 'ava'
+import { copyFileSync, mkdtempSync } from 'fs';
+import { tmpdir } from 'os';
+import { join } from 'path';
+import { fileURLToPath } from 'url';
 
 import { OrdinalsIndexer } from "../index.js";
 
@@ -19,3 +23,83 @@ indexer.onBlockRollBack(block => {
 });
 
 indexer.replayBlocks([767430, 767431]);
+
+// Index described in fixtures/hord.sql: inscription #0 revealed in block 767430 and transferred in
+// block 767431, inscription #1 revealed in block 767431, indexed before reveal details, destinations
+// and values were recorded.
+const REVEAL_TXID = '419a636ccc2aa55c7347c79971a738c3103b34254bd79c1a3d767df62a788b85';
+const TRANSFER_TXID = '27f576cafbb263ed44be8bd094f66114da26877706f96c4c31d5a97ffebf2e29';
+const SECOND_REVEAL_TXID = '26b9790188386c5c48d0f0b00d4b9f4f8c72c2a63c6a250ad9e46a35ddf49a9b';
+const UNKNOWN_INSCRIPTION_ID = `${'0'.repeat(64)}i0`;
+
+const fixtureIndexer = () => {
+    const workingDir = mkdtempSync(join(tmpdir(), 'ordhook-sdk-js-'));
+    copyFileSync(
+        fileURLToPath(new URL('./fixtures/hord.sqlite', import.meta.url)),
+        join(workingDir, 'hord.sqlite')
+    );
+    return new OrdinalsIndexer({ workingDir, logsEnabled: false });
+};
+
+test('getTip resolves to the last block indexed', async t => {
+    const tip = await fixtureIndexer().getTip();
+    t.is(tip.blockHeight, 767431);
+});
+
+test('getInscription looks up inscriptions by id and by number', async t => {
+    const indexer = fixtureIndexer();
+    const inscription = await indexer.getInscription(`${REVEAL_TXID}i0`);
+    t.is(inscription.inscriptionNumber, 0);
+    t.is(inscription.blockHeight, 767430);
+    t.is(inscription.txIndex, 1);
+    t.is(inscription.genesisSatpoint, `${REVEAL_TXID}:0:0`);
+    t.is(inscription.currentSatpoint, `${TRANSFER_TXID}:0:0`);
+    t.is(inscription.transfersCount, 1);
+    t.deepEqual(inscription.blocksWithoutDetails, [767430, 767431]);
+    t.falsy(inscription.contentType);
+
+    const second = await indexer.getInscription(1);
+    t.is(second.inscriptionId, `${SECOND_REVEAL_TXID}i0`);
+});
+
+test('getInscription resolves to null for unknown inscriptions', async t => {
+    const indexer = fixtureIndexer();
+    t.is(await indexer.getInscription(UNKNOWN_INSCRIPTION_ID), null);
+    t.is(await indexer.getInscription(42), null);
+    await t.throwsAsync(indexer.getInscription('not-an-inscription-id'));
+});
+
+test('getInscriptionsInBlock lists the inscriptions revealed in a block', async t => {
+    const indexer = fixtureIndexer();
+    const inscriptions = await indexer.getInscriptionsInBlock(767431);
+    t.deepEqual(inscriptions.map(inscription => inscription.inscriptionId), [`${SECOND_REVEAL_TXID}i0`]);
+    t.deepEqual(await indexer.getInscriptionsInBlock(800000), []);
+});
+
+test('getTransfers lists the transfers of an inscription', async t => {
+    const indexer = fixtureIndexer();
+    const transfers = await indexer.getTransfers(`${REVEAL_TXID}i0`);
+    t.is(transfers.length, 1);
+    t.is(transfers[0].blockHeight, 767431);
+    t.is(transfers[0].txId, TRANSFER_TXID);
+    t.is(transfers[0].satpointPreTransfer, `${REVEAL_TXID}:0:0`);
+    t.is(transfers[0].satpointPostTransfer, `${TRANSFER_TXID}:0:0`);
+    // Destinations are only known for blocks indexed since they are recorded
+    t.falsy(transfers[0].destinationType);
+
+    t.deepEqual(await indexer.getTransfers(`${SECOND_REVEAL_TXID}i0`), []);
+    t.deepEqual(await indexer.getTransfers(UNKNOWN_INSCRIPTION_ID), []);
+});
+
+test('getInscriptionsAtOutpoint lists the inscriptions currently on an outpoint', async t => {
+    const indexer = fixtureIndexer();
+    const located = await indexer.getInscriptionsAtOutpoint(`${TRANSFER_TXID}:0`);
+    t.deepEqual(located, [{
+        inscriptionId: `${REVEAL_TXID}i0`,
+        satpoint: `${TRANSFER_TXID}:0:0`,
+        offset: 0,
+    }]);
+    // Inscriptions moved away are no longer listed on their former outpoint
+    t.deepEqual(await indexer.getInscriptionsAtOutpoint(`${REVEAL_TXID}:0`), []);
+    await t.throwsAsync(indexer.getInscriptionsAtOutpoint('not-an-outpoint'));
+});
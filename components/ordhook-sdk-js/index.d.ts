@@ -3,19 +3,142 @@
 
 /* auto-generated by NAPI-RS */
 
+/** Event delivered by `OrdinalsIndexer.events()`. */
+export interface IndexerEvent {
+  /** Identifier to acknowledge the event with. */
+  id: number
+  /** `apply` or `rollback` */
+  type: string
+  blockHeight: number
+  blockHash: string
+  /** Starts at 1, and increases every time the event is delivered again. */
+  attempt: number
+  block: any
+}
+export interface IndexerEventResult {
+  done: boolean
+  value?: IndexerEvent
+}
+/** Last block acknowledged, persisted in the working directory. */
+export interface Checkpoint {
+  blockHeight: number
+  blockHash: string
+}
 export interface OrdinalsIndexerConfig {
   bitcoinRpcUrl?: string
   bitcoinRpcUsername?: string
   bitcoinRpcPassword?: string
   workingDir?: string
   logsEnabled?: boolean
+  /** Deliveries of a block before giving up and emitting an error, `0` (default) retries forever. */
+  maxDeliveryAttempts?: number
+  /** Delay before the first retry of a block, doubled on every retry (default 1000). */
+  retryBackoffMs?: number
+  maxRetryBackoffMs?: number
+  /** Events of `events()` not acknowledged in time are delivered again (default: no timeout). */
+  ackTimeoutMs?: number
+}
+/** Inscription, as recorded in the local index. */
+export interface Inscription {
+  inscriptionId: string
+  inscriptionNumber: number
+  ordinalNumber: number
+  blockHeight: number
+  genesisSatpoint: string
+  currentSatpoint: string
+  transfersCount: number
+  txIndex: number
+  /**
+   * Blocks of the inscription indexed before reveal details, destinations and values were
+   * recorded. The reveal details are `null` when the reveal block is one of them.
+   */
+  blocksWithoutDetails: Array<number>
+  contentType?: string
+  contentLength?: number
+  inscriberAddress?: string
+  inscriptionFee?: number
+  inscriptionOutputValue?: number
+  curseType?: any
+}
+/** Transfer of an inscription, as recorded in the local index. */
+export interface InscriptionTransfer {
+  blockHeight: number
+  txId: string
+  txIndex: number
+  satpointPreTransfer: string
+  satpointPostTransfer: string
+  /** `transferred`, `spent_in_fees` or `burnt`, `null` when the block was indexed before destinations were recorded. */
+  destinationType?: string
+  address?: string
+  script?: string
+  postTransferOutputValue?: number
+}
+/** Inscription currently located on an outpoint. */
+export interface InscriptionLocation {
+  inscriptionId: string
+  satpoint: string
+  offset: number
+}
+export interface IndexTip {
+  /** Last block with ordinal activity indexed, `null` when the index is empty. */
+  blockHeight?: number
+}
+/** Values of the `ordhook-delivery-id`, `ordhook-timestamp` and `ordhook-signature` headers. */
+export interface WebhookSignatureHeaders {
+  deliveryId: string
+  timestamp: string
+  signature: string
+}
+/**
+ * Checks that a delivery was signed with `secret` and sent recently (300 seconds by default).
+ * `body` must be the raw request body, as received.
+ */
+export function verifyWebhookSignature(secret: string, body: string | Buffer, headers: WebhookSignatureHeaders, toleranceSeconds?: number | undefined | null): boolean
+/** Events of the indexer, consumed with `for await (const event of indexer.events())`. */
+export class IndexerEventStream {
+  /**
+   * Resolves with the next event, once the previous one was acknowledged. An event not
+   * acknowledged when `next` is called again is delivered again.
+   */
+  next(): Promise<IteratorResult<IndexerEvent, undefined>>
+  ack(eventId: number): void
+  /** Stops the delivery of events, when leaving a `for await` loop. */
+  return(): IndexerEventResult
 }
 export class OrdinalsIndexer {
   constructor(configOverrides?: OrdinalsIndexerConfig | undefined | null)
-  onBlock(callback: (block: any) => boolean): void
-  onBlockRollBack(callback: (block: any) => boolean): void
+  /**
+   * Blocks are delivered again, following the retry policy, until the callback returns `true`,
+   * or a promise resolving to `true`. Exceptions thrown and promises rejected count as failed
+   * deliveries.
+   */
+  onBlock(callback: (block: any) => boolean | Promise<boolean>): void
+  onBlockRollBack(callback: (block: any) => boolean | Promise<boolean>): void
+  /**
+   * Called once when the indexer could not start, or when a block could not be delivered after
+   * `maxDeliveryAttempts`: the indexer is then stopped, and a new indexer must be created to
+   * resume from the checkpoint.
+   */
+  onError(callback: (error: Error) => void): void
+  /**
+   * Events of the indexer, delivered one at a time: each event has to be acknowledged with
+   * `ack(event.id)` before the next one is delivered. Replaces the `onBlock` and
+   * `onBlockRollBack` callbacks.
+   */
+  events(): IndexerEventStream & AsyncIterable<IndexerEvent>
+  /** Last block acknowledged, `null` until a block is acknowledged. */
+  getCheckpoint(): Checkpoint | null
   streamBlocks(): void
   replayBlocks(blocks: Array<number>): void
   replayBlockRange(startBlock: number, endBlock: number): void
   terminate(): void
+  /** Looks up an inscription by id or by number, `null` when it is not indexed. */
+  getInscription(inscription: string | number): Promise<Inscription | null>
+  /** Inscriptions revealed in a block. */
+  getInscriptionsInBlock(blockHeight: number): Promise<Array<Inscription>>
+  /** Transfers of an inscription, oldest first (empty when it is not indexed). */
+  getTransfers(inscriptionId: string): Promise<Array<InscriptionTransfer>>
+  /** Inscriptions currently located on an outpoint (`<txid>:<vout>`). */
+  getInscriptionsAtOutpoint(outpoint: string): Promise<Array<InscriptionLocation>>
+  getTip(): Promise<IndexTip>
 }
@@ -252,6 +252,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { OrdinalsIndexer } = nativeBinding
+const { verifyWebhookSignature, OrdinalsIndexer } = nativeBinding
 
+module.exports.verifyWebhookSignature = verifyWebhookSignature
 module.exports.OrdinalsIndexer = OrdinalsIndexer
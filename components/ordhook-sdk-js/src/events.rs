@@ -0,0 +1,135 @@
+use napi::bindgen_prelude::*;
+use napi::{sys, JsObject, JsSymbol, NapiValue};
+use ordhook::utils::event_queue::{self, EventQueue, NextEvent};
+use std::sync::Arc;
+
+/// Event delivered by `OrdinalsIndexer.events()`.
+#[napi(object)]
+#[derive(Clone)]
+pub struct IndexerEvent {
+  /// Identifier to acknowledge the event with.
+  pub id: i64,
+  /// `apply` or `rollback`
+  #[napi(js_name = "type")]
+  pub event_type: String,
+  pub block_height: i64,
+  pub block_hash: String,
+  /// Starts at 1, and increases every time the event is delivered again.
+  pub attempt: u32,
+  pub block: serde_json::Value,
+}
+
+impl From<event_queue::IndexerEvent> for IndexerEvent {
+  fn from(event: event_queue::IndexerEvent) -> Self {
+    IndexerEvent {
+      id: event.id,
+      event_type: event.event_type,
+      block_height: event.block_height as i64,
+      block_hash: event.block_hash,
+      attempt: event.attempt,
+      block: event.block,
+    }
+  }
+}
+
+#[napi(object)]
+pub struct IndexerEventResult {
+  pub done: bool,
+  pub value: Option<IndexerEvent>,
+}
+
+/// Events of the indexer, consumed with `for await (const event of indexer.events())`.
+#[napi]
+pub struct IndexerEventStream {
+  queue: Arc<EventQueue>,
+}
+
+impl IndexerEventStream {
+  pub fn new(queue: Arc<EventQueue>) -> Self {
+    IndexerEventStream { queue }
+  }
+}
+
+#[napi]
+impl IndexerEventStream {
+  /// Resolves with the next event, once the previous one was acknowledged. An event not
+  /// acknowledged when `next` is called again is delivered again.
+  #[napi(ts_return_type = "Promise<IteratorResult<IndexerEvent, undefined>>")]
+  pub async fn next(&self) -> Result<IndexerEventResult> {
+    let queue = self.queue.clone();
+    let event = napi::tokio::task::spawn_blocking(move || queue.next(None))
+      .await
+      .map_err(|e| Error::from_reason(e.to_string()))?
+      .map_err(Error::from_reason)?;
+    let value = match event {
+      NextEvent::Event(event) => Some(IndexerEvent::from(event)),
+      NextEvent::Pending | NextEvent::Done => None,
+    };
+    Ok(IndexerEventResult {
+      done: value.is_none(),
+      value,
+    })
+  }
+
+  #[napi]
+  pub fn ack(&self, event_id: i64) -> Result<()> {
+    self.queue.ack(event_id).map_err(Error::from_reason)
+  }
+
+  /// Stops the delivery of events, when leaving a `for await` loop.
+  #[napi(js_name = "return")]
+  pub fn close(&self) -> IndexerEventResult {
+    self.queue.close();
+    IndexerEventResult {
+      done: true,
+      value: None,
+    }
+  }
+}
+
+unsafe extern "C" fn return_this(
+  env: sys::napi_env,
+  info: sys::napi_callback_info,
+) -> sys::napi_value {
+  let mut this = std::ptr::null_mut();
+  let mut argc = 0;
+  sys::napi_get_cb_info(
+    env,
+    info,
+    &mut argc,
+    std::ptr::null_mut(),
+    &mut this,
+    std::ptr::null_mut(),
+  );
+  this
+}
+
+/// Sets `[Symbol.asyncIterator]` on the stream, for `for await` loops.
+pub fn into_async_iterable(env: Env, stream: IndexerEventStream) -> Result<JsObject> {
+  let instance = stream.into_instance(env)?;
+  let mut object = unsafe {
+    let raw = ClassInstance::to_napi_value(env.raw(), instance)?;
+    JsObject::from_raw_unchecked(env.raw(), raw)
+  };
+  let symbol: JsObject = env.get_global()?.get_named_property("Symbol")?;
+  let async_iterator: JsSymbol = symbol.get_named_property("asyncIterator")?;
+  let iterator = env.create_function("asyncIterator", Some(return_this))?;
+  object.set_property(async_iterator, iterator)?;
+  Ok(object)
+}
+
+/// Last block acknowledged, persisted in the working directory.
+#[napi(object)]
+pub struct Checkpoint {
+  pub block_height: i64,
+  pub block_hash: String,
+}
+
+impl From<event_queue::Checkpoint> for Checkpoint {
+  fn from(checkpoint: event_queue::Checkpoint) -> Self {
+    Checkpoint {
+      block_height: checkpoint.block_height as i64,
+      block_hash: checkpoint.block_hash,
+    }
+  }
+}
@@ -3,4 +3,10 @@
 #[macro_use]
 extern crate napi_derive;
 
+#[macro_use]
+extern crate hiro_system_kit;
+
+mod events;
 mod ordinals_indexer;
+mod queries;
+mod webhooks;
@@ -1,4 +1,3 @@
-use core::panic;
 use crossbeam_channel::Sender;
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{
@@ -9,13 +8,26 @@ use ordhook::chainhook_sdk::chainhooks::types::{
   BitcoinChainhookFullSpecification, BitcoinChainhookNetworkSpecification, BitcoinPredicateType,
   HookAction, OrdinalOperations,
 };
-use ordhook::chainhook_sdk::observer::DataHandlerEvent;
+use ordhook::chainhook_sdk::observer::{DataHandlerEvent, ObserverCommand};
 use ordhook::chainhook_sdk::utils::{BlockHeights, Context as OrdhookContext};
 use ordhook::config::Config;
 use ordhook::scan::bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate;
 use ordhook::service::Service;
+use ordhook::utils::event_queue::{
+  checkpoint_after, read_checkpoint, write_checkpoint, DeliveryPolicy, EventQueue, IndexerEvent,
+};
 use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender as ObserverCommandSender;
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
+
+use crate::events::{into_async_iterable, Checkpoint, IndexerEventStream};
+use crate::queries::{
+  find_inscription, find_inscriptions_at_outpoint, find_inscriptions_in_block, find_tip,
+  find_transfers, run_query, IndexTip, Inscription, InscriptionLocation, InscriptionTransfer,
+};
 
 enum IndexerCommand {
   StreamBlocks,
@@ -28,20 +40,100 @@ enum IndexerCommand {
 
 type BlockJsHandler = ThreadsafeFunction<BitcoinTransactionPayload, ErrorStrategy::Fatal>;
 
+type ErrorJsHandler = ThreadsafeFunction<String, ErrorStrategy::Fatal>;
+
 #[allow(dead_code)]
 enum CustomIndexerCommand {
   UpdateApplyCallback(BlockJsHandler),
   UpdateUndoCallback(BlockJsHandler),
+  UpdateErrorCallback(ErrorJsHandler),
+  EnableEventStream,
+  /// The indexer could not start, or stopped
+  Fail(String),
   Terminate,
 }
 
+/// Wraps the block callbacks, so that exceptions are reported to the runloop instead of being
+/// rethrown as uncaught exceptions. Async callbacks are awaited: the wrapper always returns a
+/// promise.
+const CATCHING_CALLBACK_WRAPPER: &str = "(callback) => async (block) => { \
+  try { return { ok: (await callback(block)) === true }; } \
+  catch (error) { return { error: String((error && error.stack) || error) }; } }";
+
+fn create_block_handler(env: Env, callback: JsFunction) -> Result<BlockJsHandler> {
+  let wrapper: JsFunction = env.run_script(CATCHING_CALLBACK_WRAPPER)?;
+  let wrapped: JsFunction = unsafe { wrapper.call(None, &[callback])?.cast() };
+  wrapped.create_threadsafe_function(
+    0,
+    |ctx: ThreadSafeCallContext<BitcoinTransactionPayload>| {
+      ctx.env.to_js_value(&ctx.value).map(|v| vec![v])
+    },
+  )
+}
+
+/// Calls `callback` until it returns, or resolves to, `true`, following `policy`.
+fn deliver_to_callback(
+  callback: &BlockJsHandler,
+  event_type: &str,
+  payload: &BitcoinTransactionPayload,
+  policy: &DeliveryPolicy,
+) -> std::result::Result<(), String> {
+  let mut attempt = 1;
+  loop {
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    callback.call_with_return_value::<Promise<serde_json::Value>, _>(
+      payload.clone(),
+      ThreadsafeFunctionCallMode::Blocking,
+      move |outcome| {
+        let _ = tx.send(outcome);
+        Ok(())
+      },
+    );
+    let failure = match rx.recv().map(hiro_system_kit::nestable_block_on) {
+      Ok(Ok(outcome)) => match outcome.get("error") {
+        Some(error) => error.as_str().unwrap_or("callback failed").to_string(),
+        None if outcome.get("ok") == Some(&serde_json::Value::Bool(true)) => return Ok(()),
+        None => "callback did not return true".to_string(),
+      },
+      Ok(Err(e)) => format!("callback failed: {e}"),
+      Err(_) => "callback did not complete".to_string(),
+    };
+    if policy.is_exhausted(attempt) {
+      return Err(format!(
+        "{event_type} callback of block #{} failed after {attempt} attempts: {failure}",
+        payload.block.block_identifier.index
+      ));
+    }
+    thread::sleep(policy.backoff(attempt));
+    attempt += 1;
+  }
+}
+
+/// Hands `error` to the `onError` callback, and to the pending `next()` of the event stream.
+fn report_error(
+  error: String,
+  error_callback: Option<&ErrorJsHandler>,
+  event_queue: &EventQueue,
+  ctx: &OrdhookContext,
+) {
+  ctx.try_log(|logger| error!(logger, "{error}"));
+  if let Some(callback) = error_callback {
+    callback.call(error.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+  }
+  event_queue.fail(error);
+}
+
 struct OrdinalsIndexingRunloop {
   pub command_tx: Sender<IndexerCommand>,
   pub custom_indexer_command_tx: Sender<CustomIndexerCommand>,
 }
 
 impl OrdinalsIndexingRunloop {
-  pub fn new(ordhook_config: Config) -> Self {
+  pub fn new(
+    ordhook_config: Config,
+    delivery_policy: DeliveryPolicy,
+    event_queue: Arc<EventQueue>,
+  ) -> std::result::Result<Self, String> {
     let (command_tx, command_rx) = crossbeam_channel::unbounded();
     let (custom_indexer_command_tx, custom_indexer_command_rx) = crossbeam_channel::unbounded();
 
@@ -52,26 +144,36 @@ impl OrdinalsIndexingRunloop {
       tracer: false,
     };
 
+    let working_dir = ordhook_config.expected_cache_path();
+    let indexer_ctx = ctx.clone();
     let mut service: Service = Service::new(ordhook_config, ctx);
+    // Set once blocks are streamed, for the indexing thread to stop the observer
+    let observer_command_tx: Arc<Mutex<Option<ObserverCommandSender<ObserverCommand>>>> =
+      Arc::new(Mutex::new(None));
+    let moved_observer_command_tx = observer_command_tx.clone();
 
     // Set-up the observer sidecar - used for augmenting the bitcoin blocks with
     // ordinals informations
-    let observer_sidecar = service
-      .set_up_observer_sidecar_runloop()
-      .expect("unable to setup indexer");
+    let observer_sidecar = service.set_up_observer_sidecar_runloop()?;
     // Prepare internal predicate
-    let (observer_config, payload_rx) = service
-      .set_up_observer_config(vec![], true)
-      .expect("unable to setup indexer");
+    let (observer_config, payload_rx) = service.set_up_observer_config(vec![], true)?;
 
     // Indexing thread
     thread::spawn(move || {
       let payload_rx = payload_rx.unwrap();
+      let observer_command_tx = moved_observer_command_tx;
+      let ctx = indexer_ctx;
 
       let mut apply_callback: Option<BlockJsHandler> = None;
 
       let mut undo_callback: Option<BlockJsHandler> = None;
 
+      let mut error_callback: Option<ErrorJsHandler> = None;
+
+      let mut event_stream_enabled = false;
+
+      let mut next_event_id = 0;
+
       loop {
         let mut sel = crossbeam_channel::Select::new();
         let payload_rx_sel = sel.recv(&payload_rx);
@@ -82,53 +184,58 @@ impl OrdinalsIndexingRunloop {
         match oper.index() {
           i if i == payload_rx_sel => match oper.recv(&payload_rx) {
             Ok(DataHandlerEvent::Process(payload)) => {
-              if let Some(callback) = undo_callback.clone() {
-                for to_rollback in payload.rollback.into_iter() {
-                  loop {
-                    let (tx, rx) = crossbeam_channel::bounded(1);
-                    callback.call_with_return_value::<bool, _>(
-                      to_rollback.clone(),
-                      ThreadsafeFunctionCallMode::Blocking,
-                      move |p| {
-                        let _ = tx.send(p);
-                        Ok(())
-                      },
-                    );
-                    match rx.recv() {
-                      Ok(true) => break,
-                      Ok(false) => continue,
-                      _ => panic!(),
+              let blocks = payload
+                .rollback
+                .iter()
+                .map(|block| ("rollback", block))
+                .chain(payload.apply.iter().map(|block| ("apply", block)));
+              let mut delivery = Ok(());
+              for (event_type, block) in blocks {
+                delivery = if event_stream_enabled {
+                  next_event_id += 1;
+                  IndexerEvent::new(next_event_id, event_type, block)
+                    .and_then(|event| event_queue.deliver(event, &delivery_policy))
+                } else {
+                  let callback = match event_type {
+                    "rollback" => undo_callback.as_ref(),
+                    _ => apply_callback.as_ref(),
+                  };
+                  match callback {
+                    Some(callback) => {
+                      deliver_to_callback(callback, event_type, block, &delivery_policy)
                     }
+                    None => continue,
                   }
+                };
+                if delivery.is_err() {
+                  break;
+                }
+                let checkpoint = checkpoint_after(event_type, block);
+                if let Err(e) = write_checkpoint(&working_dir, &checkpoint) {
+                  ctx.try_log(|logger| warn!(logger, "Unable to persist checkpoint: {e}"));
                 }
               }
-
-              if let Some(ref callback) = apply_callback.clone() {
-                for to_apply in payload.apply.into_iter() {
-                  loop {
-                    let (tx, rx) = crossbeam_channel::bounded(1);
-                    callback.call_with_return_value::<bool, _>(
-                      to_apply.clone(),
-                      ThreadsafeFunctionCallMode::Blocking,
-                      move |p| {
-                        let _ = tx.send(p);
-                        Ok(())
-                      },
-                    );
-                    match rx.recv() {
-                      Ok(true) => break,
-                      Ok(false) => continue,
-                      _ => panic!(),
-                    }
-                  }
+              if let Err(e) = delivery {
+                // Leaving a `for await` loop closes the stream, and is not an error
+                if !event_queue.is_closed() {
+                  report_error(e, error_callback.as_ref(), &event_queue, &ctx);
                 }
+                // Blocks are no longer delivered: the observer is stopped, and replays stop once
+                // they attempt to deliver a block. A new indexer resumes from the checkpoint.
+                if let Some(tx) = observer_command_tx.lock().unwrap().take() {
+                  let _ = tx.send(ObserverCommand::Terminate);
+                }
+                break;
               }
             }
             Ok(DataHandlerEvent::Terminate) => {
+              event_queue.close();
               break;
             }
             Err(e) => {
-              println!("Error {}", e.to_string());
+              ctx.try_log(|logger| error!(logger, "{}", e.to_string()));
+              event_queue.close();
+              break;
             }
           },
           i if i == custom_indexer_command_rx_sel => match oper.recv(&custom_indexer_command_rx) {
@@ -138,6 +245,16 @@ impl OrdinalsIndexingRunloop {
             Ok(CustomIndexerCommand::UpdateUndoCallback(callback)) => {
               undo_callback = Some(callback);
             }
+            Ok(CustomIndexerCommand::UpdateErrorCallback(callback)) => {
+              error_callback = Some(callback);
+            }
+            Ok(CustomIndexerCommand::EnableEventStream) => {
+              event_stream_enabled = true;
+            }
+            Ok(CustomIndexerCommand::Fail(e)) => {
+              report_error(e, error_callback.as_ref(), &event_queue, &ctx);
+              break;
+            }
             Ok(CustomIndexerCommand::Terminate) => break,
             _ => {}
           },
@@ -147,23 +264,36 @@ impl OrdinalsIndexingRunloop {
     });
 
     // Processing thread
+    let failure_tx = custom_indexer_command_tx.clone();
     thread::spawn(move || {
       loop {
         let cmd = match command_rx.recv() {
           Ok(cmd) => cmd,
-          Err(e) => {
-            panic!("Runloop error: {}", e.to_string());
-          }
+          // The indexer was dropped
+          Err(_) => break,
         };
 
         match cmd {
           IndexerCommand::StreamBlocks => {
             // We start the service as soon as the start() method is being called.
             let future = service.catch_up_with_chain_tip(false);
-            let _ = hiro_system_kit::nestable_block_on(future).expect("unable to start indexer");
+            if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+              let _ = failure_tx.send(CustomIndexerCommand::Fail(format!(
+                "unable to start indexer: {e}"
+              )));
+              break;
+            }
             let future = service.start_event_observer(observer_sidecar);
-            let (command_tx, event_rx) =
-              hiro_system_kit::nestable_block_on(future).expect("unable to start indexer");
+            let (command_tx, event_rx) = match hiro_system_kit::nestable_block_on(future) {
+              Ok(observer) => observer,
+              Err(e) => {
+                let _ = failure_tx.send(CustomIndexerCommand::Fail(format!(
+                  "unable to start indexer: {e}"
+                )));
+                break;
+              }
+            };
+            *observer_command_tx.lock().unwrap() = Some(command_tx.clone());
             // Blocking call
             let _ = service.start_main_runloop(&command_tx, event_rx, None);
             break;
@@ -205,7 +335,10 @@ impl OrdinalsIndexingRunloop {
               Some(&observer_config),
               &service.ctx,
             );
-            let _ = hiro_system_kit::nestable_block_on(future).expect("unable to start indexer");
+            // Ignored once the indexing thread stopped after a delivery failure
+            if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+              let _ = failure_tx.send(CustomIndexerCommand::Fail(e));
+            }
 
             if let Some(tx) = observer_config.data_handler_tx {
               let _ = tx.send(DataHandlerEvent::Terminate);
@@ -213,13 +346,19 @@ impl OrdinalsIndexingRunloop {
             break;
           }
           IndexerCommand::DropBlocks(blocks) => {
-            println!("Will drop blocks {:?}", blocks);
+            service
+              .ctx
+              .try_log(|logger| info!(logger, "Will drop blocks {:?}", blocks));
           }
           IndexerCommand::RewriteBlocks(blocks) => {
-            println!("Will rewrite blocks {:?}", blocks);
+            service
+              .ctx
+              .try_log(|logger| info!(logger, "Will rewrite blocks {:?}", blocks));
           }
           IndexerCommand::SyncBlocks => {
-            println!("Will sync blocks");
+            service
+              .ctx
+              .try_log(|logger| info!(logger, "Will sync blocks"));
           }
           IndexerCommand::Terminate => {
             if let Some(tx) = observer_config.data_handler_tx {
@@ -231,10 +370,10 @@ impl OrdinalsIndexingRunloop {
       }
     });
 
-    Self {
+    Ok(Self {
       command_tx,
       custom_indexer_command_tx,
-    }
+    })
   }
 }
 
@@ -245,6 +384,13 @@ pub struct OrdinalsIndexerConfig {
   pub bitcoin_rpc_password: Option<String>,
   pub working_dir: Option<String>,
   pub logs_enabled: Option<bool>,
+  /// Deliveries of a block before giving up and emitting an error, `0` (default) retries forever.
+  pub max_delivery_attempts: Option<u32>,
+  /// Delay before the first retry of a block, doubled on every retry (default 1000).
+  pub retry_backoff_ms: Option<u32>,
+  pub max_retry_backoff_ms: Option<u32>,
+  /// Events of `events()` not acknowledged in time are delivered again (default: no timeout).
+  pub ack_timeout_ms: Option<u32>,
 }
 
 impl OrdinalsIndexerConfig {
@@ -255,6 +401,10 @@ impl OrdinalsIndexerConfig {
       bitcoin_rpc_password: Some("devnet".to_string()),
       working_dir: Some("/tmp/ordinals".to_string()),
       logs_enabled: Some(true),
+      max_delivery_attempts: Some(0),
+      retry_backoff_ms: Some(1000),
+      max_retry_backoff_ms: Some(60000),
+      ack_timeout_ms: None,
     }
   }
 }
@@ -262,13 +412,16 @@ impl OrdinalsIndexerConfig {
 #[napi(js_name = "OrdinalsIndexer")]
 pub struct OrdinalsIndexer {
   runloop: OrdinalsIndexingRunloop,
+  working_dir: PathBuf,
+  event_queue: Arc<EventQueue>,
 }
 
 #[napi]
 impl OrdinalsIndexer {
   #[napi(constructor)]
-  pub fn new(config_overrides: Option<OrdinalsIndexerConfig>) -> Self {
+  pub fn new(config_overrides: Option<OrdinalsIndexerConfig>) -> Result<Self> {
     let mut config = Config::mainnet_default();
+    let mut delivery_policy = DeliveryPolicy::default();
 
     if let Some(config_overrides) = config_overrides {
       if let Some(bitcoin_rpc_url) = config_overrides.bitcoin_rpc_url {
@@ -289,44 +442,97 @@ impl OrdinalsIndexer {
       if let Some(logs_enabled) = config_overrides.logs_enabled {
         config.logs.ordinals_internals = logs_enabled;
       }
+      if let Some(max_delivery_attempts) = config_overrides.max_delivery_attempts {
+        delivery_policy.max_attempts = max_delivery_attempts;
+      }
+      if let Some(retry_backoff_ms) = config_overrides.retry_backoff_ms {
+        delivery_policy.initial_backoff = Duration::from_millis(retry_backoff_ms.into());
+      }
+      if let Some(max_retry_backoff_ms) = config_overrides.max_retry_backoff_ms {
+        delivery_policy.max_backoff = Duration::from_millis(max_retry_backoff_ms.into());
+      }
+      if let Some(ack_timeout_ms) = config_overrides.ack_timeout_ms {
+        delivery_policy.ack_timeout = Some(Duration::from_millis(ack_timeout_ms.into()));
+      }
     }
 
-    let runloop = OrdinalsIndexingRunloop::new(config);
+    let working_dir = config.expected_cache_path();
+    let event_queue = Arc::new(EventQueue::default());
+    let runloop = OrdinalsIndexingRunloop::new(config, delivery_policy, event_queue.clone())
+      .map_err(Error::from_reason)?;
 
-    OrdinalsIndexer { runloop }
+    Ok(OrdinalsIndexer {
+      runloop,
+      working_dir,
+      event_queue,
+    })
   }
 
+  /// Blocks are delivered again, following the retry policy, until the callback returns `true`,
+  /// or a promise resolving to `true`. Exceptions thrown and promises rejected count as failed
+  /// deliveries.
   #[napi(
     js_name = "onBlock",
-    ts_args_type = "callback: (block: any) => boolean"
+    ts_args_type = "callback: (block: any) => boolean | Promise<boolean>"
   )]
-  pub fn update_apply_block_callback(&self, apply_block_cb: JsFunction) {
-    let tsfn: ThreadsafeFunction<BitcoinTransactionPayload, ErrorStrategy::Fatal> = apply_block_cb
-      .create_threadsafe_function(0, |ctx| ctx.env.to_js_value(&ctx.value).map(|v| vec![v]))
-      .unwrap();
+  pub fn update_apply_block_callback(&self, env: Env, apply_block_cb: JsFunction) -> Result<()> {
+    let tsfn = create_block_handler(env, apply_block_cb)?;
     let _ = self
       .runloop
       .custom_indexer_command_tx
       .send(CustomIndexerCommand::UpdateApplyCallback(tsfn));
+    Ok(())
   }
 
   #[napi(
     js_name = "onBlockRollBack",
-    ts_args_type = "callback: (block: any) => boolean"
+    ts_args_type = "callback: (block: any) => boolean | Promise<boolean>"
   )]
-  pub fn update_undo_block_callback(&self, undo_block_cb: JsFunction) {
-    let tsfn: ThreadsafeFunction<BitcoinTransactionPayload, ErrorStrategy::Fatal> = undo_block_cb
-      .create_threadsafe_function(
-        0,
-        |ctx: ThreadSafeCallContext<BitcoinTransactionPayload>| {
-          ctx.env.to_js_value(&ctx.value).map(|v| vec![v])
-        },
-      )
-      .unwrap();
+  pub fn update_undo_block_callback(&self, env: Env, undo_block_cb: JsFunction) -> Result<()> {
+    let tsfn = create_block_handler(env, undo_block_cb)?;
     let _ = self
       .runloop
       .custom_indexer_command_tx
       .send(CustomIndexerCommand::UpdateUndoCallback(tsfn));
+    Ok(())
+  }
+
+  /// Called once when the indexer could not start, or when a block could not be delivered after
+  /// `maxDeliveryAttempts`: the indexer is then stopped, and a new indexer must be created to
+  /// resume from the checkpoint.
+  #[napi(js_name = "onError", ts_args_type = "callback: (error: Error) => void")]
+  pub fn update_error_callback(&self, error_cb: JsFunction) -> Result<()> {
+    let tsfn: ErrorJsHandler =
+      error_cb.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<String>| {
+        ctx
+          .env
+          .create_error(Error::from_reason(ctx.value))
+          .map(|v| vec![v])
+      })?;
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::UpdateErrorCallback(tsfn));
+    Ok(())
+  }
+
+  /// Events of the indexer, delivered one at a time: each event has to be acknowledged with
+  /// `ack(event.id)` before the next one is delivered. Replaces the `onBlock` and
+  /// `onBlockRollBack` callbacks.
+  #[napi(ts_return_type = "IndexerEventStream & AsyncIterable<IndexerEvent>")]
+  pub fn events(&self, env: Env) -> Result<JsObject> {
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::EnableEventStream);
+    into_async_iterable(env, IndexerEventStream::new(self.event_queue.clone()))
+  }
+
+  /// Last block acknowledged, `null` until a block is acknowledged.
+  #[napi]
+  pub fn get_checkpoint(&self) -> Result<Option<Checkpoint>> {
+    let checkpoint = read_checkpoint(&self.working_dir).map_err(Error::from_reason)?;
+    Ok(checkpoint.map(Checkpoint::from))
   }
 
   #[napi]
@@ -360,4 +566,51 @@ impl OrdinalsIndexer {
   pub fn terminate(&self) {
     let _ = self.runloop.command_tx.send(IndexerCommand::Terminate);
   }
+
+  /// Looks up an inscription by id or by number, `null` when it is not indexed.
+  #[napi]
+  pub async fn get_inscription(
+    &self,
+    inscription: Either<String, i64>,
+  ) -> Result<Option<Inscription>> {
+    run_query(self.working_dir.clone(), move |conn, ctx| {
+      find_inscription(inscription, conn, ctx)
+    })
+    .await
+  }
+
+  /// Inscriptions revealed in a block.
+  #[napi]
+  pub async fn get_inscriptions_in_block(&self, block_height: i64) -> Result<Vec<Inscription>> {
+    run_query(self.working_dir.clone(), move |conn, ctx| {
+      find_inscriptions_in_block(block_height as u64, conn, ctx)
+    })
+    .await
+  }
+
+  /// Transfers of an inscription, oldest first (empty when it is not indexed).
+  #[napi]
+  pub async fn get_transfers(&self, inscription_id: String) -> Result<Vec<InscriptionTransfer>> {
+    run_query(self.working_dir.clone(), move |conn, ctx| {
+      find_transfers(&inscription_id, conn, ctx)
+    })
+    .await
+  }
+
+  /// Inscriptions currently located on an outpoint (`<txid>:<vout>`).
+  #[napi]
+  pub async fn get_inscriptions_at_outpoint(
+    &self,
+    outpoint: String,
+  ) -> Result<Vec<InscriptionLocation>> {
+    run_query(self.working_dir.clone(), move |conn, ctx| {
+      find_inscriptions_at_outpoint(&outpoint, conn, ctx)
+    })
+    .await
+  }
+
+  #[napi]
+  pub async fn get_tip(&self) -> Result<IndexTip> {
+    run_query(self.working_dir.clone(), find_tip).await
+  }
 }
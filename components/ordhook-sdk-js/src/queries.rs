@@ -0,0 +1,223 @@
+use napi::bindgen_prelude::*;
+use ordhook::chainhook_sdk::bitcoincore_rpc::bitcoin::OutPoint;
+use ordhook::chainhook_sdk::types::OrdinalInscriptionTransferDestination;
+use ordhook::chainhook_sdk::utils::Context as OrdhookContext;
+use ordhook::db::{
+  find_all_inscriptions_in_block_range, find_inscription_details,
+  find_inscription_id_with_inscription_number, find_inscriptions_at_wached_outpoint,
+  find_latest_inscription_block_height, find_latest_inscription_transfer_data,
+  find_latest_transfers_block_height, get_default_ordhook_db_file_path,
+  open_readonly_ordhook_db_conn, InscriptionDetails, InscriptionTransferRecord,
+};
+use rusqlite::Connection;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Inscription, as recorded in the local index.
+#[napi(object)]
+pub struct Inscription {
+  pub inscription_id: String,
+  pub inscription_number: i64,
+  pub ordinal_number: i64,
+  pub block_height: i64,
+  pub genesis_satpoint: String,
+  pub current_satpoint: String,
+  pub transfers_count: i64,
+  pub tx_index: i64,
+  /// Blocks of the inscription indexed before reveal details, destinations and values were
+  /// recorded. The reveal details are `null` when the reveal block is one of them.
+  pub blocks_without_details: Vec<i64>,
+  pub content_type: Option<String>,
+  pub content_length: Option<i64>,
+  pub inscriber_address: Option<String>,
+  pub inscription_fee: Option<i64>,
+  pub inscription_output_value: Option<i64>,
+  pub curse_type: Option<serde_json::Value>,
+}
+
+/// Transfer of an inscription, as recorded in the local index.
+#[napi(object)]
+pub struct InscriptionTransfer {
+  pub block_height: i64,
+  pub tx_id: String,
+  pub tx_index: i64,
+  pub satpoint_pre_transfer: String,
+  pub satpoint_post_transfer: String,
+  /// `transferred`, `spent_in_fees` or `burnt`, `null` when the block was indexed before destinations were recorded.
+  pub destination_type: Option<String>,
+  pub address: Option<String>,
+  pub script: Option<String>,
+  pub post_transfer_output_value: Option<i64>,
+}
+
+/// Inscription currently located on an outpoint.
+#[napi(object)]
+pub struct InscriptionLocation {
+  pub inscription_id: String,
+  pub satpoint: String,
+  pub offset: i64,
+}
+
+#[napi(object)]
+pub struct IndexTip {
+  /// Last block with ordinal activity indexed, `null` when the index is empty.
+  pub block_height: Option<i64>,
+}
+
+impl From<InscriptionTransferRecord> for InscriptionTransfer {
+  fn from(record: InscriptionTransferRecord) -> Self {
+    let (destination_type, address, script) = match record.destination {
+      Some(OrdinalInscriptionTransferDestination::Transferred(address)) => {
+        (Some("transferred"), Some(address), None)
+      }
+      Some(OrdinalInscriptionTransferDestination::SpentInFees) => {
+        (Some("spent_in_fees"), None, None)
+      }
+      Some(OrdinalInscriptionTransferDestination::Burnt(script)) => {
+        (Some("burnt"), None, Some(script))
+      }
+      None => (None, None, None),
+    };
+    InscriptionTransfer {
+      block_height: record.block_height as i64,
+      tx_id: record.tx_id,
+      tx_index: record.tx_index as i64,
+      satpoint_pre_transfer: record.satpoint_pre_transfer,
+      satpoint_post_transfer: record.satpoint_post_transfer,
+      destination_type: destination_type.map(|d| d.to_string()),
+      address,
+      script,
+      post_transfer_output_value: record.post_transfer_output_value.map(|v| v as i64),
+    }
+  }
+}
+
+impl From<InscriptionDetails> for Inscription {
+  fn from(details: InscriptionDetails) -> Self {
+    let reveal = details.reveal;
+    Inscription {
+      inscription_id: details.inscription_id,
+      inscription_number: details.inscription_number,
+      ordinal_number: details.ordinal_number as i64,
+      block_height: details.block_height as i64,
+      genesis_satpoint: details.genesis_satpoint,
+      current_satpoint: details.current_satpoint,
+      transfers_count: details.transfers.len() as i64,
+      tx_index: details.tx_index as i64,
+      blocks_without_details: details
+        .blocks_without_details
+        .iter()
+        .map(|block_height| *block_height as i64)
+        .collect(),
+      content_type: reveal.as_ref().map(|r| r.content_type.clone()),
+      content_length: reveal.as_ref().map(|r| r.content_length as i64),
+      inscriber_address: reveal.as_ref().and_then(|r| r.inscriber_address.clone()),
+      inscription_fee: reveal.as_ref().map(|r| r.inscription_fee as i64),
+      inscription_output_value: reveal.as_ref().map(|r| r.inscription_output_value as i64),
+      curse_type: reveal
+        .as_ref()
+        .and_then(|r| r.curse_type.as_ref())
+        .and_then(|curse_type| serde_json::to_value(curse_type).ok()),
+    }
+  }
+}
+
+/// Runs `query` against a read-only connection to the index of `working_dir`, off the JS thread.
+pub async fn run_query<T, F>(working_dir: PathBuf, query: F) -> Result<T>
+where
+  F: FnOnce(&Connection, &OrdhookContext) -> std::result::Result<T, String> + Send + 'static,
+  T: Send + 'static,
+{
+  napi::tokio::task::spawn_blocking(move || {
+    if !get_default_ordhook_db_file_path(&working_dir).exists() {
+      return Err(Error::from_reason(format!(
+        "no ordhook index found in {}",
+        working_dir.display()
+      )));
+    }
+    let ctx = OrdhookContext::empty();
+    let conn = open_readonly_ordhook_db_conn(&working_dir, &ctx).map_err(Error::from_reason)?;
+    query(&conn, &ctx).map_err(Error::from_reason)
+  })
+  .await
+  .map_err(|e| Error::from_reason(e.to_string()))?
+}
+
+pub fn find_inscription(
+  inscription: Either<String, i64>,
+  conn: &Connection,
+  ctx: &OrdhookContext,
+) -> std::result::Result<Option<Inscription>, String> {
+  let inscription_id = match inscription {
+    Either::A(inscription_id) => inscription_id,
+    Either::B(inscription_number) => {
+      match find_inscription_id_with_inscription_number(inscription_number, conn, ctx) {
+        Some(inscription_id) => inscription_id,
+        None => return Ok(None),
+      }
+    }
+  };
+  Ok(find_inscription_details(&inscription_id, conn, ctx)?.map(Inscription::from))
+}
+
+pub fn find_inscriptions_in_block(
+  block_height: u64,
+  conn: &Connection,
+  ctx: &OrdhookContext,
+) -> std::result::Result<Vec<Inscription>, String> {
+  let mut inscriptions = vec![];
+  for (_, inscription_id, _, _) in
+    find_all_inscriptions_in_block_range(block_height, block_height, conn, ctx)
+  {
+    if let Some(details) = find_inscription_details(&inscription_id, conn, ctx)? {
+      inscriptions.push(details.into());
+    }
+  }
+  Ok(inscriptions)
+}
+
+pub fn find_transfers(
+  inscription_id: &str,
+  conn: &Connection,
+  ctx: &OrdhookContext,
+) -> std::result::Result<Vec<InscriptionTransfer>, String> {
+  Ok(
+    find_inscription_details(inscription_id, conn, ctx)?
+      .map(|details| details.transfers.into_iter().map(|t| t.into()).collect())
+      .unwrap_or_default(),
+  )
+}
+
+pub fn find_inscriptions_at_outpoint(
+  outpoint: &str,
+  conn: &Connection,
+  ctx: &OrdhookContext,
+) -> std::result::Result<Vec<InscriptionLocation>, String> {
+  let outpoint = OutPoint::from_str(outpoint)
+    .map_err(|e| format!("invalid outpoint {outpoint}, expected <txid>:<vout> ({e})"))?;
+  let mut locations = vec![];
+  for watched in find_inscriptions_at_wached_outpoint(&outpoint, conn, ctx) {
+    // Locations are kept once inscriptions moved: only the latest one is current
+    let Some(latest) = find_latest_inscription_transfer_data(&watched.inscription_id, conn, ctx)?
+    else {
+      continue;
+    };
+    if latest.satpoint.outpoint != outpoint || latest.satpoint.offset != watched.offset {
+      continue;
+    }
+    locations.push(InscriptionLocation {
+      inscription_id: watched.inscription_id,
+      satpoint: latest.satpoint.to_string(),
+      offset: watched.offset as i64,
+    });
+  }
+  Ok(locations)
+}
+
+pub fn find_tip(conn: &Connection, ctx: &OrdhookContext) -> std::result::Result<IndexTip, String> {
+  let block_height = find_latest_inscription_block_height(conn, ctx)?
+    .max(find_latest_transfers_block_height(conn, ctx));
+  Ok(IndexTip {
+    block_height: block_height.map(|h| h as i64),
+  })
+}
@@ -0,0 +1,45 @@
+use napi::bindgen_prelude::*;
+use ordhook::utils::webhooks::{self, DEFAULT_SIGNATURE_TOLERANCE_SECS};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Values of the `ordhook-delivery-id`, `ordhook-timestamp` and `ordhook-signature` headers.
+#[napi(object)]
+pub struct WebhookSignatureHeaders {
+  pub delivery_id: String,
+  pub timestamp: String,
+  pub signature: String,
+}
+
+/// Checks that a delivery was signed with `secret` and sent recently (300 seconds by default).
+/// `body` must be the raw request body, as received.
+#[napi]
+pub fn verify_webhook_signature(
+  secret: String,
+  body: Either<String, Buffer>,
+  headers: WebhookSignatureHeaders,
+  tolerance_seconds: Option<u32>,
+) -> bool {
+  let Ok(timestamp) = headers.timestamp.parse::<u64>() else {
+    return false;
+  };
+  let body = match body {
+    Either::A(ref body) => body.as_bytes(),
+    Either::B(ref body) => body.as_ref(),
+  };
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0);
+  webhooks::verify_webhook_signature(
+    &secret,
+    &headers.delivery_id,
+    timestamp,
+    body,
+    &headers.signature,
+    now,
+    tolerance_seconds
+      .map(|t| t as u64)
+      .unwrap_or(DEFAULT_SIGNATURE_TOLERANCE_SECS),
+  )
+  .is_ok()
+}
@@ -0,0 +1,84 @@
+from typing import Any, Dict, Iterator, List, Optional, Union
+
+def parse_inscriptions_in_raw_tx(tx: Union[Dict[str, Any], str]) -> List[Dict[str, Any]]:
+    """Inscriptions revealed by a transaction. `tx` is the output of bitcoind's
+    `getrawtransaction` with verbosity 1 or 2, as a dict or as its JSON encoding."""
+
+class Sat:
+    """Sat, in any of the notations of ordinal theory."""
+
+    def __init__(self, notation: Union[int, str]) -> None: ...
+    @property
+    def n(self) -> int: ...
+    @property
+    def decimal(self) -> str: ...
+    @property
+    def degree(self) -> str: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def percentile(self) -> str: ...
+    @property
+    def rarity(self) -> str: ...
+    @property
+    def block_height(self) -> int:
+        """Height of the block mining the sat."""
+    @property
+    def cycle(self) -> int: ...
+    @property
+    def epoch(self) -> int: ...
+    @property
+    def offset(self) -> int:
+        """Position of the sat in its block."""
+    def to_dict(self) -> Dict[str, Any]: ...
+    def __int__(self) -> int: ...
+
+class Index:
+    """Read-only queries over the index (`hord.sqlite`) of a working directory."""
+
+    def __init__(self, working_dir: str) -> None: ...
+    def get_inscription(self, inscription: Union[str, int]) -> Optional[Dict[str, Any]]:
+        """Looks up an inscription by id or by number."""
+    def get_inscriptions_in_block(self, block_height: int) -> List[Dict[str, Any]]: ...
+    def get_inscriptions_on_sat(self, sat: Union[Sat, int, str]) -> List[Dict[str, Any]]: ...
+    def get_transfers(self, inscription_id: str) -> List[Dict[str, Any]]:
+        """Transfers of an inscription, oldest first."""
+    def get_inscriptions_at_outpoint(self, outpoint: str) -> List[Dict[str, Any]]:
+        """Inscriptions currently located on an outpoint (`<txid>:<vout>`)."""
+    def get_tip(self) -> Optional[int]:
+        """Last block with ordinal activity indexed, `None` when the index is empty."""
+
+class IndexerEventStream(Iterator[Dict[str, Any]]):
+    """Events of the indexer, each with the keys `id`, `type` (`apply` or `rollback`),
+    `block_height`, `block_hash`, `attempt` and `block`."""
+
+    def __iter__(self) -> "IndexerEventStream": ...
+    def __next__(self) -> Dict[str, Any]: ...
+    def ack(self, event_id: int) -> None: ...
+    def close(self) -> None: ...
+
+class OrdinalsIndexer:
+    def __init__(
+        self,
+        bitcoin_rpc_url: Optional[str] = None,
+        bitcoin_rpc_username: Optional[str] = None,
+        bitcoin_rpc_password: Optional[str] = None,
+        working_dir: Optional[str] = None,
+        logs_enabled: Optional[bool] = None,
+        max_delivery_attempts: Optional[int] = None,
+        retry_backoff_ms: Optional[int] = None,
+        max_retry_backoff_ms: Optional[int] = None,
+        ack_timeout_ms: Optional[int] = None,
+    ) -> None: ...
+    def on_block(self, callback: Any) -> None: ...
+    def on_block_rollback(self, callback: Any) -> None: ...
+    def on_error(self, callback: Any) -> None: ...
+    def events(self) -> IndexerEventStream: ...
+    def get_checkpoint(self) -> Optional[Dict[str, Any]]: ...
+    @property
+    def index(self) -> Index: ...
+    def stream_blocks(self) -> None: ...
+    def replay_blocks(self, blocks: List[int]) -> None: ...
+    def replay_block_range(self, start_block: int, end_block: int) -> None: ...
+    def terminate(self) -> None: ...
+    def wait(self) -> None: ...
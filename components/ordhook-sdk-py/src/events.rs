@@ -0,0 +1,51 @@
+use ordhook::utils::event_queue::{EventQueue, NextEvent};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often threads blocked on the indexer check for `KeyboardInterrupt`.
+pub const SIGNALS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Events of the indexer, consumed with `for event in indexer.events()`.
+#[pyclass(module = "ordhook_sdk")]
+pub struct IndexerEventStream {
+  queue: Arc<EventQueue>,
+}
+
+impl IndexerEventStream {
+  pub fn new(queue: Arc<EventQueue>) -> Self {
+    IndexerEventStream { queue }
+  }
+}
+
+#[pymethods]
+impl IndexerEventStream {
+  fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+    slf
+  }
+
+  /// Blocks until the next event, once the previous one was acknowledged. An event not
+  /// acknowledged when the next one is requested is delivered again.
+  fn __next__(&self, py: Python) -> PyResult<Option<PyObject>> {
+    loop {
+      let queue = self.queue.clone();
+      match py.allow_threads(move || queue.next(Some(SIGNALS_POLL_INTERVAL))) {
+        Ok(NextEvent::Event(event)) => return Ok(Some(pythonize(py, &event)?)),
+        Ok(NextEvent::Done) => return Ok(None),
+        Ok(NextEvent::Pending) => py.check_signals()?,
+        Err(e) => return Err(PyRuntimeError::new_err(e)),
+      }
+    }
+  }
+
+  fn ack(&self, event_id: i64) -> PyResult<()> {
+    self.queue.ack(event_id).map_err(PyRuntimeError::new_err)
+  }
+
+  /// Stops the delivery of events.
+  fn close(&self) {
+    self.queue.close();
+  }
+}
@@ -0,0 +1,477 @@
+use crossbeam_channel::{Receiver, Sender};
+use ordhook::chainhook_sdk::chainhooks::bitcoin::BitcoinTransactionPayload;
+use ordhook::chainhook_sdk::chainhooks::types::{
+  BitcoinChainhookFullSpecification, BitcoinChainhookNetworkSpecification, BitcoinPredicateType,
+  HookAction, OrdinalOperations,
+};
+use ordhook::chainhook_sdk::observer::DataHandlerEvent;
+use ordhook::chainhook_sdk::utils::{BlockHeights, Context as OrdhookContext};
+use ordhook::config::Config;
+use ordhook::scan::bitcoin::scan_bitcoin_chainstate_via_rpc_using_predicate;
+use ordhook::service::Service;
+use ordhook::utils::event_queue::{
+  checkpoint_after, read_checkpoint, write_checkpoint, DeliveryPolicy, EventQueue, IndexerEvent,
+};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::events::{IndexerEventStream, SIGNALS_POLL_INTERVAL};
+use crate::queries::Index;
+
+enum IndexerCommand {
+  StreamBlocks,
+  ReplayBlocks(Vec<u64>),
+  Terminate,
+}
+
+enum CustomIndexerCommand {
+  UpdateApplyCallback(PyObject),
+  UpdateUndoCallback(PyObject),
+  UpdateErrorCallback(PyObject),
+  EnableEventStream,
+  /// The indexer could not start, or stopped
+  Fail(String),
+}
+
+/// Calls `callback` until it neither raises nor returns `False`, following `policy`.
+fn deliver_to_callback(
+  callback: &PyObject,
+  event_type: &str,
+  payload: &BitcoinTransactionPayload,
+  policy: &DeliveryPolicy,
+) -> Result<(), String> {
+  let mut attempt = 1;
+  loop {
+    let failure = Python::with_gil(|py| {
+      let block = pythonize(py, payload).map_err(|e| e.to_string())?;
+      match callback.call1(py, (block,)) {
+        Ok(outcome) if outcome.extract::<bool>(py).ok() == Some(false) => {
+          Err("callback returned False".to_string())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+      }
+    });
+    let Err(failure) = failure else {
+      return Ok(());
+    };
+    if policy.is_exhausted(attempt) {
+      return Err(format!(
+        "{event_type} callback of block #{} failed after {attempt} attempts: {failure}",
+        payload.block.block_identifier.index
+      ));
+    }
+    thread::sleep(policy.backoff(attempt));
+    attempt += 1;
+  }
+}
+
+/// Hands `error`, as a `RuntimeError`, to the `on_error` callback and to the pending `next()` of
+/// the event stream.
+fn report_error(error: String, error_callback: Option<&PyObject>, event_queue: &EventQueue) {
+  println!("Error {error}");
+  if let Some(callback) = error_callback {
+    Python::with_gil(|py| {
+      let exception = PyRuntimeError::new_err(error.clone());
+      if let Err(e) = callback.call1(py, (exception,)) {
+        e.print(py);
+      }
+    });
+  }
+  event_queue.fail(error);
+}
+
+struct OrdinalsIndexingRunloop {
+  pub command_tx: Sender<IndexerCommand>,
+  pub custom_indexer_command_tx: Sender<CustomIndexerCommand>,
+  /// Disconnected once the indexing thread stopped.
+  pub done_rx: Receiver<()>,
+}
+
+impl OrdinalsIndexingRunloop {
+  pub fn new(
+    ordhook_config: Config,
+    delivery_policy: DeliveryPolicy,
+    event_queue: Arc<EventQueue>,
+  ) -> Result<Self, String> {
+    let (command_tx, command_rx) = crossbeam_channel::unbounded();
+    let (custom_indexer_command_tx, custom_indexer_command_rx) = crossbeam_channel::unbounded();
+    let (done_tx, done_rx) = crossbeam_channel::bounded::<()>(0);
+
+    let logger = hiro_system_kit::log::setup_logger();
+    let _guard = hiro_system_kit::log::setup_global_logger(logger.clone());
+    let ctx = OrdhookContext {
+      logger: Some(logger),
+      tracer: false,
+    };
+
+    let working_dir = ordhook_config.expected_cache_path();
+    let mut service: Service = Service::new(ordhook_config, ctx);
+
+    // Set-up the observer sidecar - used for augmenting the bitcoin blocks with
+    // ordinals informations
+    let observer_sidecar = service.set_up_observer_sidecar_runloop()?;
+    // Prepare internal predicate
+    let (observer_config, payload_rx) = service.set_up_observer_config(vec![], true)?;
+
+    // Indexing thread
+    thread::spawn(move || {
+      // Dropped when leaving the thread
+      let _done_tx = done_tx;
+      let payload_rx = payload_rx.unwrap();
+
+      let mut apply_callback: Option<PyObject> = None;
+
+      let mut undo_callback: Option<PyObject> = None;
+
+      let mut error_callback: Option<PyObject> = None;
+
+      let mut event_stream_enabled = false;
+
+      let mut next_event_id = 0;
+
+      loop {
+        let mut sel = crossbeam_channel::Select::new();
+        let payload_rx_sel = sel.recv(&payload_rx);
+        let custom_indexer_command_rx_sel = sel.recv(&custom_indexer_command_rx);
+
+        let oper = sel.select();
+        match oper.index() {
+          i if i == payload_rx_sel => match oper.recv(&payload_rx) {
+            Ok(DataHandlerEvent::Process(payload)) => {
+              let blocks = payload
+                .rollback
+                .iter()
+                .map(|block| ("rollback", block))
+                .chain(payload.apply.iter().map(|block| ("apply", block)));
+              let mut delivery = Ok(());
+              for (event_type, block) in blocks {
+                delivery = if event_stream_enabled {
+                  next_event_id += 1;
+                  IndexerEvent::new(next_event_id, event_type, block)
+                    .and_then(|event| event_queue.deliver(event, &delivery_policy))
+                } else {
+                  let callback = match event_type {
+                    "rollback" => undo_callback.as_ref(),
+                    _ => apply_callback.as_ref(),
+                  };
+                  match callback {
+                    Some(callback) => {
+                      deliver_to_callback(callback, event_type, block, &delivery_policy)
+                    }
+                    None => continue,
+                  }
+                };
+                if delivery.is_err() {
+                  break;
+                }
+                let checkpoint = checkpoint_after(event_type, block);
+                if let Err(e) = write_checkpoint(&working_dir, &checkpoint) {
+                  println!("Unable to persist checkpoint: {e}");
+                }
+              }
+              if let Err(e) = delivery {
+                // Closing the stream is not an error
+                if !event_queue.is_closed() {
+                  report_error(e, error_callback.as_ref(), &event_queue);
+                }
+                break;
+              }
+            }
+            Ok(DataHandlerEvent::Terminate) => {
+              event_queue.close();
+              break;
+            }
+            Err(e) => {
+              println!("Error {}", e.to_string());
+              event_queue.close();
+              break;
+            }
+          },
+          i if i == custom_indexer_command_rx_sel => match oper.recv(&custom_indexer_command_rx) {
+            Ok(CustomIndexerCommand::UpdateApplyCallback(callback)) => {
+              apply_callback = Some(callback);
+            }
+            Ok(CustomIndexerCommand::UpdateUndoCallback(callback)) => {
+              undo_callback = Some(callback);
+            }
+            Ok(CustomIndexerCommand::UpdateErrorCallback(callback)) => {
+              error_callback = Some(callback);
+            }
+            Ok(CustomIndexerCommand::EnableEventStream) => {
+              event_stream_enabled = true;
+            }
+            Ok(CustomIndexerCommand::Fail(e)) => {
+              report_error(e, error_callback.as_ref(), &event_queue);
+              break;
+            }
+            // The indexer was dropped
+            Err(_) => break,
+          },
+          _ => unreachable!(),
+        };
+      }
+    });
+
+    // Processing thread
+    let failure_tx = custom_indexer_command_tx.clone();
+    thread::spawn(move || {
+      let cmd = match command_rx.recv() {
+        Ok(cmd) => cmd,
+        // The indexer was dropped
+        Err(_) => return,
+      };
+
+      match cmd {
+        IndexerCommand::StreamBlocks => {
+          // We start the service as soon as the stream_blocks() method is being called.
+          let future = service.catch_up_with_chain_tip(false);
+          if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+            let _ = failure_tx.send(CustomIndexerCommand::Fail(format!(
+              "unable to start indexer: {e}"
+            )));
+            return;
+          }
+          let future = service.start_event_observer(observer_sidecar);
+          let (command_tx, event_rx) = match hiro_system_kit::nestable_block_on(future) {
+            Ok(observer) => observer,
+            Err(e) => {
+              let _ = failure_tx.send(CustomIndexerCommand::Fail(format!(
+                "unable to start indexer: {e}"
+              )));
+              return;
+            }
+          };
+          // Blocking call
+          let _ = service.start_main_runloop(&command_tx, event_rx, None);
+        }
+        IndexerCommand::ReplayBlocks(blocks) => {
+          let network = &service.config.network.bitcoin_network;
+          let mut networks = BTreeMap::new();
+          networks.insert(
+            network.clone(),
+            BitcoinChainhookNetworkSpecification {
+              start_block: None,
+              end_block: None,
+              blocks: Some(blocks),
+              expire_after_occurrence: None,
+              include_proof: None,
+              include_inputs: None,
+              include_outputs: None,
+              include_witness: None,
+              predicate: BitcoinPredicateType::OrdinalsProtocol(OrdinalOperations::InscriptionFeed),
+              action: HookAction::Noop,
+            },
+          );
+          let predicate_spec = BitcoinChainhookFullSpecification {
+            uuid: "replay".to_string(),
+            owner_uuid: None,
+            name: "replay".to_string(),
+            version: 1,
+            networks,
+          }
+          .into_selected_network_specification(&network)
+          .unwrap();
+
+          let future = scan_bitcoin_chainstate_via_rpc_using_predicate(
+            &predicate_spec,
+            &service.config,
+            Some(&observer_config),
+            &service.ctx,
+          );
+          // Ignored once the indexing thread stopped after a delivery failure
+          if let Err(e) = hiro_system_kit::nestable_block_on(future) {
+            let _ = failure_tx.send(CustomIndexerCommand::Fail(e));
+          }
+
+          if let Some(tx) = observer_config.data_handler_tx {
+            let _ = tx.send(DataHandlerEvent::Terminate);
+          }
+        }
+        IndexerCommand::Terminate => {
+          if let Some(tx) = observer_config.data_handler_tx {
+            let _ = tx.send(DataHandlerEvent::Terminate);
+          }
+        }
+      }
+    });
+
+    Ok(Self {
+      command_tx,
+      custom_indexer_command_tx,
+      done_rx,
+    })
+  }
+}
+
+/// Indexes the inscriptions of a bitcoind node, and delivers the blocks with ordinal activity to
+/// callbacks (`on_block`, `on_block_rollback`) or to a generator (`events()`).
+#[pyclass(module = "ordhook_sdk")]
+pub struct OrdinalsIndexer {
+  runloop: OrdinalsIndexingRunloop,
+  working_dir: PathBuf,
+  event_queue: Arc<EventQueue>,
+}
+
+#[pymethods]
+impl OrdinalsIndexer {
+  #[new]
+  #[pyo3(signature = (
+    bitcoin_rpc_url = None,
+    bitcoin_rpc_username = None,
+    bitcoin_rpc_password = None,
+    working_dir = None,
+    logs_enabled = None,
+    max_delivery_attempts = None,
+    retry_backoff_ms = None,
+    max_retry_backoff_ms = None,
+    ack_timeout_ms = None,
+  ))]
+  #[allow(clippy::too_many_arguments)]
+  fn new(
+    bitcoin_rpc_url: Option<String>,
+    bitcoin_rpc_username: Option<String>,
+    bitcoin_rpc_password: Option<String>,
+    working_dir: Option<String>,
+    logs_enabled: Option<bool>,
+    max_delivery_attempts: Option<u32>,
+    retry_backoff_ms: Option<u64>,
+    max_retry_backoff_ms: Option<u64>,
+    ack_timeout_ms: Option<u64>,
+  ) -> PyResult<Self> {
+    let mut config = Config::mainnet_default();
+    let mut delivery_policy = DeliveryPolicy::default();
+
+    if let Some(bitcoin_rpc_url) = bitcoin_rpc_url {
+      config.network.bitcoind_rpc_url = bitcoin_rpc_url;
+    }
+    if let Some(bitcoin_rpc_username) = bitcoin_rpc_username {
+      config.network.bitcoind_rpc_username = bitcoin_rpc_username;
+    }
+    if let Some(bitcoin_rpc_password) = bitcoin_rpc_password {
+      config.network.bitcoind_rpc_password = bitcoin_rpc_password;
+    }
+    if let Some(working_dir) = working_dir {
+      config.storage.working_dir = working_dir;
+    }
+    if let Some(logs_enabled) = logs_enabled {
+      config.logs.chainhook_internals = logs_enabled;
+      config.logs.ordinals_internals = logs_enabled;
+    }
+    if let Some(max_delivery_attempts) = max_delivery_attempts {
+      delivery_policy.max_attempts = max_delivery_attempts;
+    }
+    if let Some(retry_backoff_ms) = retry_backoff_ms {
+      delivery_policy.initial_backoff = Duration::from_millis(retry_backoff_ms);
+    }
+    if let Some(max_retry_backoff_ms) = max_retry_backoff_ms {
+      delivery_policy.max_backoff = Duration::from_millis(max_retry_backoff_ms);
+    }
+    if let Some(ack_timeout_ms) = ack_timeout_ms {
+      delivery_policy.ack_timeout = Some(Duration::from_millis(ack_timeout_ms));
+    }
+
+    let working_dir = config.expected_cache_path();
+    let event_queue = Arc::new(EventQueue::default());
+    let runloop = OrdinalsIndexingRunloop::new(config, delivery_policy, event_queue.clone())
+      .map_err(PyRuntimeError::new_err)?;
+
+    Ok(OrdinalsIndexer {
+      runloop,
+      working_dir,
+      event_queue,
+    })
+  }
+
+  /// Blocks are delivered again, following the retry policy, while the callback raises or returns
+  /// `False`.
+  fn on_block(&self, callback: PyObject) {
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::UpdateApplyCallback(callback));
+  }
+
+  fn on_block_rollback(&self, callback: PyObject) {
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::UpdateUndoCallback(callback));
+  }
+
+  /// Called once, with a `RuntimeError`, when the indexer could not start, or when a block could
+  /// not be delivered after `max_delivery_attempts`: the indexer then stops delivering blocks.
+  fn on_error(&self, callback: PyObject) {
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::UpdateErrorCallback(callback));
+  }
+
+  /// Events of the indexer, delivered one at a time: each event has to be acknowledged with
+  /// `ack(event["id"])` before the next one is delivered. Replaces the `on_block` and
+  /// `on_block_rollback` callbacks.
+  fn events(&self) -> IndexerEventStream {
+    let _ = self
+      .runloop
+      .custom_indexer_command_tx
+      .send(CustomIndexerCommand::EnableEventStream);
+    IndexerEventStream::new(self.event_queue.clone())
+  }
+
+  /// Last block acknowledged, `None` until a block is acknowledged.
+  fn get_checkpoint(&self, py: Python) -> PyResult<PyObject> {
+    let checkpoint = read_checkpoint(&self.working_dir).map_err(PyRuntimeError::new_err)?;
+    Ok(pythonize(py, &checkpoint)?)
+  }
+
+  /// Queries over the index of the working directory.
+  #[getter]
+  fn index(&self) -> Index {
+    Index {
+      working_dir: self.working_dir.clone(),
+    }
+  }
+
+  fn stream_blocks(&self) {
+    let _ = self.runloop.command_tx.send(IndexerCommand::StreamBlocks);
+  }
+
+  fn replay_blocks(&self, blocks: Vec<u64>) {
+    let _ = self
+      .runloop
+      .command_tx
+      .send(IndexerCommand::ReplayBlocks(blocks));
+  }
+
+  fn replay_block_range(&self, start_block: u64, end_block: u64) {
+    let range = BlockHeights::BlockRange(start_block, end_block);
+    let blocks = range.get_sorted_entries().into_iter().collect();
+    let _ = self
+      .runloop
+      .command_tx
+      .send(IndexerCommand::ReplayBlocks(blocks));
+  }
+
+  fn terminate(&self) {
+    let _ = self.runloop.command_tx.send(IndexerCommand::Terminate);
+  }
+
+  /// Blocks until the indexer stops delivering blocks: at the end of a replay, after
+  /// `terminate()`, or after a delivery error.
+  fn wait(&self, py: Python) -> PyResult<()> {
+    loop {
+      let done_rx = self.runloop.done_rx.clone();
+      match py.allow_threads(move || done_rx.recv_timeout(SIGNALS_POLL_INTERVAL)) {
+        Err(crossbeam_channel::RecvTimeoutError::Timeout) => py.check_signals()?,
+        _ => return Ok(()),
+      }
+    }
+  }
+}
@@ -0,0 +1,17 @@
+use pyo3::prelude::*;
+
+mod events;
+mod indexer;
+mod parsing;
+mod queries;
+mod sat;
+
+#[pymodule]
+fn ordhook_sdk(_py: Python, m: &PyModule) -> PyResult<()> {
+  m.add_function(wrap_pyfunction!(parsing::parse_inscriptions_in_raw_tx, m)?)?;
+  m.add_class::<sat::Sat>()?;
+  m.add_class::<queries::Index>()?;
+  m.add_class::<events::IndexerEventStream>()?;
+  m.add_class::<indexer::OrdinalsIndexer>()?;
+  Ok(())
+}
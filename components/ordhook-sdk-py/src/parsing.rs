@@ -0,0 +1,21 @@
+use ordhook::chainhook_sdk::indexer::bitcoin::BitcoinTransactionFullBreakdown;
+use ordhook::chainhook_sdk::utils::Context as OrdhookContext;
+use ordhook::core::protocol::inscription_parsing;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pythonize::{depythonize, pythonize};
+
+/// Inscriptions revealed by a transaction. `tx` is the output of bitcoind's `getrawtransaction`
+/// with verbosity 1 or 2, as a dict or as its JSON encoding.
+#[pyfunction]
+pub fn parse_inscriptions_in_raw_tx(py: Python, tx: &PyAny) -> PyResult<PyObject> {
+  let tx: BitcoinTransactionFullBreakdown = match tx.extract::<&str>() {
+    Ok(json) => serde_json::from_str(json)
+      .map_err(|e| PyValueError::new_err(format!("invalid transaction: {e}")))?,
+    Err(_) => {
+      depythonize(tx).map_err(|e| PyValueError::new_err(format!("invalid transaction: {e}")))?
+    }
+  };
+  let operations = inscription_parsing::parse_inscriptions_in_raw_tx(&tx, &OrdhookContext::empty());
+  Ok(pythonize(py, &operations)?)
+}
@@ -0,0 +1,225 @@
+use ordhook::chainhook_sdk::bitcoincore_rpc::bitcoin::OutPoint;
+use ordhook::chainhook_sdk::types::OrdinalInscriptionTransferDestination;
+use ordhook::chainhook_sdk::utils::Context as OrdhookContext;
+use ordhook::db::{
+  find_all_inscription_ids_with_ordinal_number, find_all_inscriptions_in_block_range,
+  find_inscription_details, find_inscription_id_with_inscription_number,
+  find_inscriptions_at_wached_outpoint, find_latest_inscription_block_height,
+  find_latest_inscription_transfer_data, find_latest_transfers_block_height,
+  get_default_ordhook_db_file_path, open_readonly_ordhook_db_conn, InscriptionDetails,
+  InscriptionTransferRecord,
+};
+use pyo3::exceptions::{PyFileNotFoundError, PyRuntimeError};
+use pyo3::prelude::*;
+use pythonize::pythonize;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::sat::Sat;
+
+fn transfer_to_value(record: InscriptionTransferRecord) -> Value {
+  let (destination_type, address, script) = match record.destination {
+    Some(OrdinalInscriptionTransferDestination::Transferred(address)) => {
+      (Some("transferred"), Some(address), None)
+    }
+    Some(OrdinalInscriptionTransferDestination::SpentInFees) => (Some("spent_in_fees"), None, None),
+    Some(OrdinalInscriptionTransferDestination::Burnt(script)) => {
+      (Some("burnt"), None, Some(script))
+    }
+    None => (None, None, None),
+  };
+  json!({
+    "block_height": record.block_height,
+    "tx_id": record.tx_id,
+    "tx_index": record.tx_index,
+    "satpoint_pre_transfer": record.satpoint_pre_transfer,
+    "satpoint_post_transfer": record.satpoint_post_transfer,
+    "destination_type": destination_type,
+    "address": address,
+    "script": script,
+    "post_transfer_output_value": record.post_transfer_output_value,
+  })
+}
+
+/// Same fields as the `Inscription` objects of the Node SDK. The reveal details are `None` when the
+/// reveal block was indexed before they were recorded.
+fn inscription_to_value(details: InscriptionDetails) -> Value {
+  let reveal = details.reveal;
+  json!({
+    "inscription_id": details.inscription_id,
+    "inscription_number": details.inscription_number,
+    "ordinal_number": details.ordinal_number,
+    "block_height": details.block_height,
+    "genesis_satpoint": details.genesis_satpoint,
+    "current_satpoint": details.current_satpoint,
+    "transfers_count": details.transfers.len(),
+    "tx_index": details.tx_index,
+    "blocks_without_details": details.blocks_without_details,
+    "content_type": reveal.as_ref().map(|r| r.content_type.clone()),
+    "content_length": reveal.as_ref().map(|r| r.content_length),
+    "inscriber_address": reveal.as_ref().and_then(|r| r.inscriber_address.clone()),
+    "inscription_fee": reveal.as_ref().map(|r| r.inscription_fee),
+    "inscription_output_value": reveal.as_ref().map(|r| r.inscription_output_value),
+    "curse_type": reveal.as_ref().and_then(|r| r.curse_type.as_ref()),
+  })
+}
+
+fn find_inscriptions_with_ids(
+  inscription_ids: Vec<String>,
+  conn: &Connection,
+  ctx: &OrdhookContext,
+) -> Result<Value, String> {
+  let mut inscriptions = vec![];
+  for inscription_id in inscription_ids.into_iter() {
+    if let Some(details) = find_inscription_details(&inscription_id, conn, ctx)? {
+      inscriptions.push(inscription_to_value(details));
+    }
+  }
+  Ok(Value::Array(inscriptions))
+}
+
+/// Read-only queries over the index (`hord.sqlite`) of a working directory. Each query opens its
+/// own connection, so that the index can be queried while an indexer writes to it.
+#[pyclass(module = "ordhook_sdk")]
+#[derive(Clone)]
+pub struct Index {
+  pub working_dir: PathBuf,
+}
+
+impl Index {
+  /// Runs `query` against a read-only connection, without holding the GIL.
+  fn run_query<F>(&self, py: Python, query: F) -> PyResult<PyObject>
+  where
+    F: FnOnce(&Connection, &OrdhookContext) -> Result<Value, String> + Send,
+  {
+    let working_dir = self.working_dir.clone();
+    let value = py.allow_threads(move || {
+      if !get_default_ordhook_db_file_path(&working_dir).exists() {
+        return Err(PyFileNotFoundError::new_err(format!(
+          "no ordhook index found in {}",
+          working_dir.display()
+        )));
+      }
+      let ctx = OrdhookContext::empty();
+      let conn =
+        open_readonly_ordhook_db_conn(&working_dir, &ctx).map_err(PyRuntimeError::new_err)?;
+      query(&conn, &ctx).map_err(PyRuntimeError::new_err)
+    })?;
+    Ok(pythonize(py, &value)?)
+  }
+}
+
+#[pymethods]
+impl Index {
+  #[new]
+  fn new(working_dir: PathBuf) -> Self {
+    Index { working_dir }
+  }
+
+  /// Looks up an inscription by id or by number, `None` when unknown.
+  fn get_inscription(&self, py: Python, inscription: &PyAny) -> PyResult<PyObject> {
+    let inscription_number = inscription.extract::<i64>().ok();
+    let inscription_id = match inscription_number {
+      Some(_) => None,
+      None => Some(inscription.extract::<String>()?),
+    };
+    self.run_query(py, move |conn, ctx| {
+      let inscription_id = match (inscription_id, inscription_number) {
+        (Some(inscription_id), _) => inscription_id,
+        (None, Some(inscription_number)) => {
+          match find_inscription_id_with_inscription_number(inscription_number, conn, ctx) {
+            Some(inscription_id) => inscription_id,
+            None => return Ok(Value::Null),
+          }
+        }
+        (None, None) => unreachable!(),
+      };
+      Ok(
+        find_inscription_details(&inscription_id, conn, ctx)?
+          .map(inscription_to_value)
+          .unwrap_or(Value::Null),
+      )
+    })
+  }
+
+  /// Inscriptions revealed in a block.
+  fn get_inscriptions_in_block(&self, py: Python, block_height: u64) -> PyResult<PyObject> {
+    self.run_query(py, move |conn, ctx| {
+      let inscription_ids =
+        find_all_inscriptions_in_block_range(block_height, block_height, conn, ctx)
+          .into_iter()
+          .map(|(_, inscription_id, _, _)| inscription_id)
+          .collect();
+      find_inscriptions_with_ids(inscription_ids, conn, ctx)
+    })
+  }
+
+  /// Inscriptions located on a sat, given as a `Sat` or in any notation accepted by `Sat`.
+  fn get_inscriptions_on_sat(&self, py: Python, sat: &PyAny) -> PyResult<PyObject> {
+    let sat = match sat.extract::<Sat>() {
+      Ok(sat) => sat,
+      Err(_) => Sat::new(sat)?,
+    };
+    self.run_query(py, move |conn, ctx| {
+      let inscription_ids = find_all_inscription_ids_with_ordinal_number(sat.0.n(), conn, ctx);
+      find_inscriptions_with_ids(inscription_ids, conn, ctx)
+    })
+  }
+
+  /// Transfers of an inscription, oldest first.
+  fn get_transfers(&self, py: Python, inscription_id: String) -> PyResult<PyObject> {
+    self.run_query(py, move |conn, ctx| {
+      let transfers = find_inscription_details(&inscription_id, conn, ctx)?
+        .map(|details| {
+          details
+            .transfers
+            .into_iter()
+            .map(transfer_to_value)
+            .collect()
+        })
+        .unwrap_or_default();
+      Ok(Value::Array(transfers))
+    })
+  }
+
+  /// Inscriptions currently located on an outpoint (`<txid>:<vout>`).
+  fn get_inscriptions_at_outpoint(&self, py: Python, outpoint: String) -> PyResult<PyObject> {
+    self.run_query(py, move |conn, ctx| {
+      let outpoint = OutPoint::from_str(&outpoint)
+        .map_err(|e| format!("invalid outpoint {outpoint}, expected <txid>:<vout> ({e})"))?;
+      let mut locations = vec![];
+      for watched in find_inscriptions_at_wached_outpoint(&outpoint, conn, ctx) {
+        // Locations are kept once inscriptions moved: only the latest one is current
+        let Some(latest) =
+          find_latest_inscription_transfer_data(&watched.inscription_id, conn, ctx)?
+        else {
+          continue;
+        };
+        if latest.satpoint.outpoint != outpoint || latest.satpoint.offset != watched.offset {
+          continue;
+        }
+        locations.push(json!({
+          "inscription_id": watched.inscription_id,
+          "satpoint": latest.satpoint.to_string(),
+          "offset": watched.offset,
+        }));
+      }
+      Ok(Value::Array(locations))
+    })
+  }
+
+  /// Last block with ordinal activity indexed, `None` when the index is empty.
+  fn get_tip(&self, py: Python) -> PyResult<PyObject> {
+    self.run_query(py, |conn, ctx| {
+      let block_height = find_latest_inscription_block_height(conn, ctx)?
+        .max(find_latest_transfers_block_height(conn, ctx));
+      Ok(json!(block_height))
+    })
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Index({:?})", self.working_dir)
+  }
+}
@@ -0,0 +1,107 @@
+use ordhook::ord::sat::Sat as OrdSat;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Sat, in any of the notations of ordinal theory.
+#[pyclass(module = "ordhook_sdk", frozen)]
+#[derive(Clone, Copy)]
+pub struct Sat(pub OrdSat);
+
+#[pymethods]
+impl Sat {
+  /// Accepts an integer, or a string in integer (`2099994106992659`), decimal (`3891094.16797`),
+  /// degree (`3°111094′214″16797‴`), name (`satoshi`) or percentile (`99.99971949060254%`)
+  /// notation.
+  #[new]
+  pub fn new(notation: &PyAny) -> PyResult<Self> {
+    let notation = match notation.extract::<u64>() {
+      Ok(n) => n.to_string(),
+      Err(_) => notation.extract::<String>()?,
+    };
+    notation
+      .parse::<OrdSat>()
+      .map(Sat)
+      .map_err(|e| PyValueError::new_err(format!("unable to parse sat {notation}: {e}")))
+  }
+
+  #[getter]
+  fn n(&self) -> u64 {
+    self.0.n()
+  }
+
+  #[getter]
+  fn decimal(&self) -> String {
+    self.0.decimal().to_string()
+  }
+
+  #[getter]
+  fn degree(&self) -> String {
+    self.0.degree().to_string()
+  }
+
+  #[getter]
+  fn name(&self) -> String {
+    self.0.name()
+  }
+
+  #[getter]
+  fn percentile(&self) -> String {
+    self.0.percentile()
+  }
+
+  #[getter]
+  fn rarity(&self) -> String {
+    self.0.rarity().to_string()
+  }
+
+  /// Height of the block mining the sat.
+  #[getter]
+  fn block_height(&self) -> u64 {
+    self.0.height().n()
+  }
+
+  #[getter]
+  fn cycle(&self) -> u64 {
+    self.0.cycle()
+  }
+
+  #[getter]
+  fn epoch(&self) -> u64 {
+    self.0.epoch().0
+  }
+
+  /// Position of the sat in its block.
+  #[getter]
+  fn offset(&self) -> u64 {
+    self.0.third()
+  }
+
+  /// The notations of the sat, with the keys of `ordhook sat --format json`.
+  fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("number", self.n())?;
+    dict.set_item("decimal", self.decimal())?;
+    dict.set_item("degree", self.degree())?;
+    dict.set_item("name", self.name())?;
+    dict.set_item("percentile", self.percentile())?;
+    dict.set_item("rarity", self.rarity())?;
+    dict.set_item("block_height", self.block_height())?;
+    dict.set_item("cycle", self.cycle())?;
+    dict.set_item("epoch", self.epoch())?;
+    dict.set_item("offset", self.offset())?;
+    Ok(dict)
+  }
+
+  fn __int__(&self) -> u64 {
+    self.0.n()
+  }
+
+  fn __str__(&self) -> String {
+    self.0.to_string()
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Sat({})", self.0.n())
+  }
+}
@@ -0,0 +1,63 @@
+import sqlite3
+import struct
+
+import pytest
+
+INSCRIPTION_0_TXID = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799"
+INSCRIPTION_1_TXID = "26482871f33f1051f450f2da9af275794c0b5f1c61ebf35e4467fb42c2813403"
+TRANSFER_1_TXID = "2c8a11858825ae2056be90c3e49938d271671ac4245b452cd88b1475cbea8971"
+TRANSFER_2_TXID = "bc4c30829a9564c0d58e6287195622b53ced54a25711d1b86be7cd3a70ef61ed"
+
+
+def encode_outpoint(txid, vout):
+    """Outpoints are stored with their consensus encoding: txid in internal byte order, then vout."""
+    return bytes.fromhex(txid)[::-1] + struct.pack("<I", vout)
+
+
+@pytest.fixture
+def index_dir(tmp_path):
+    """Working directory with a `hord.sqlite` holding the first two inscriptions, the first one
+    transferred twice. Indexed before reveal details were recorded: they are not available."""
+    conn = sqlite3.connect(tmp_path / "hord.sqlite")
+    conn.executescript(
+        """
+        CREATE TABLE inscriptions (
+            inscription_id TEXT NOT NULL PRIMARY KEY,
+            block_height INTEGER NOT NULL,
+            ordinal_number INTEGER NOT NULL,
+            inscription_number INTEGER NOT NULL
+        );
+        CREATE TABLE locations (
+            inscription_id TEXT NOT NULL,
+            block_height INTEGER NOT NULL,
+            tx_index INTEGER NOT NULL,
+            outpoint_to_watch BLOB NOT NULL,
+            offset INTEGER NOT NULL
+        );
+        CREATE TABLE sequence_metadata (
+            block_height INTEGER NOT NULL,
+            latest_cursed_inscription_number INTEGER NOT NULL,
+            latest_inscription_number INTEGER NOT NULL
+        );
+        PRAGMA user_version = 1;
+        """
+    )
+    conn.executemany(
+        "INSERT INTO inscriptions VALUES (?, ?, ?, ?)",
+        [
+            (f"{INSCRIPTION_0_TXID}i0", 767430, 1252201400444387, 0),
+            (f"{INSCRIPTION_1_TXID}i0", 767753, 727624168684699, 1),
+        ],
+    )
+    conn.executemany(
+        "INSERT INTO locations VALUES (?, ?, ?, ?, ?)",
+        [
+            (f"{INSCRIPTION_0_TXID}i0", 767430, 1, encode_outpoint(INSCRIPTION_0_TXID, 0), 0),
+            (f"{INSCRIPTION_1_TXID}i0", 767753, 2, encode_outpoint(INSCRIPTION_1_TXID, 0), 0),
+            (f"{INSCRIPTION_0_TXID}i0", 785391, 5, encode_outpoint(TRANSFER_1_TXID, 0), 0),
+            (f"{INSCRIPTION_0_TXID}i0", 785396, 3, encode_outpoint(TRANSFER_2_TXID, 0), 0),
+        ],
+    )
+    conn.commit()
+    conn.close()
+    return tmp_path
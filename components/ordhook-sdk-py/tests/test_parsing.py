@@ -0,0 +1,67 @@
+import json
+
+from ordhook_sdk import parse_inscriptions_in_raw_tx
+
+TXID = "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799"
+
+# Tapscript revealing a text/plain inscription
+INSCRIPTION_SCRIPT = (
+    "208737bc46923c3e64c7e6768c0346879468bf3aba795a5f5f56efca288f50ed2aac0063036f7264010118746578"
+    "742f706c61696e3b636861727365743d7574662d38004c9948656c6c6f2030303030303030303030303030303030"
+    "303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030"
+    "303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030303030"
+    "303030303030303030303030303030303030303030303030303030303030303030303030303030303030300a68"
+)
+
+
+def raw_tx(witness):
+    """Transaction as returned by `getrawtransaction <txid> 1`."""
+    return {
+        "txid": TXID,
+        "hash": TXID,
+        "version": 2,
+        "size": 0,
+        "vsize": 0,
+        "weight": 0,
+        "locktime": 0,
+        "vin": [
+            {
+                "txid": "00" * 32,
+                "vout": 0,
+                "scriptSig": {"asm": "", "hex": ""},
+                "txinwitness": witness,
+                "sequence": 4294967293,
+            }
+        ],
+        "vout": [
+            {
+                "value": 0.0001,
+                "n": 0,
+                "scriptPubKey": {
+                    "asm": "",
+                    "hex": "5120" + "00" * 32,
+                    "type": "witness_v1_taproot",
+                },
+            }
+        ],
+    }
+
+
+def test_parse_inscription_reveal():
+    tx = raw_tx(["01" * 64, INSCRIPTION_SCRIPT, "c1" + "00" * 32])
+    operations = parse_inscriptions_in_raw_tx(tx)
+    assert len(operations) == 1
+    (reveal,) = operations[0].values()
+    assert reveal["inscription_id"] == f"{TXID}i0"
+    assert reveal["content_type"] == "text/plain;charset=utf-8"
+    assert reveal["content_length"] == 153
+    assert bytes.fromhex(reveal["content_bytes"][2:]).startswith(b"Hello 000")
+
+
+def test_parse_json_encoded_tx():
+    tx = raw_tx(["01" * 64, INSCRIPTION_SCRIPT, "c1" + "00" * 32])
+    assert parse_inscriptions_in_raw_tx(json.dumps(tx)) == parse_inscriptions_in_raw_tx(tx)
+
+
+def test_parse_tx_without_inscription():
+    assert parse_inscriptions_in_raw_tx(raw_tx(["01" * 64])) == []
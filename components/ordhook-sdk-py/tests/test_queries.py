@@ -0,0 +1,87 @@
+import pytest
+
+from ordhook_sdk import Index, Sat
+
+from conftest import (
+    INSCRIPTION_0_TXID,
+    INSCRIPTION_1_TXID,
+    TRANSFER_1_TXID,
+    TRANSFER_2_TXID,
+)
+
+
+def test_get_inscription_by_number_and_id(index_dir):
+    index = Index(str(index_dir))
+    by_number = index.get_inscription(0)
+    by_id = index.get_inscription(f"{INSCRIPTION_0_TXID}i0")
+    assert by_number == by_id
+    assert by_number["inscription_number"] == 0
+    assert by_number["ordinal_number"] == 1252201400444387
+    assert by_number["block_height"] == 767430
+    assert by_number["genesis_satpoint"] == f"{INSCRIPTION_0_TXID}:0:0"
+    assert by_number["current_satpoint"] == f"{TRANSFER_2_TXID}:0:0"
+    assert by_number["transfers_count"] == 2
+    # The fixture is indexed before reveal details were recorded
+    assert by_number["content_type"] is None
+    assert by_number["blocks_without_details"] == [767430, 785391, 785396]
+
+
+def test_get_unknown_inscription(index_dir):
+    index = Index(str(index_dir))
+    assert index.get_inscription(42) is None
+    assert index.get_inscription(f"{TRANSFER_1_TXID}i0") is None
+
+
+def test_get_inscriptions_in_block(index_dir):
+    index = Index(str(index_dir))
+    inscriptions = index.get_inscriptions_in_block(767753)
+    assert [i["inscription_id"] for i in inscriptions] == [f"{INSCRIPTION_1_TXID}i0"]
+    assert index.get_inscriptions_in_block(767431) == []
+
+
+def test_get_inscriptions_on_sat(index_dir):
+    index = Index(str(index_dir))
+    by_number = index.get_inscriptions_on_sat(1252201400444387)
+    by_sat = index.get_inscriptions_on_sat(Sat(1252201400444387))
+    assert by_number == by_sat
+    assert [i["inscription_id"] for i in by_number] == [f"{INSCRIPTION_0_TXID}i0"]
+
+
+def test_get_transfers(index_dir):
+    index = Index(str(index_dir))
+    transfers = index.get_transfers(f"{INSCRIPTION_0_TXID}i0")
+    assert [(t["block_height"], t["tx_id"]) for t in transfers] == [
+        (785391, TRANSFER_1_TXID),
+        (785396, TRANSFER_2_TXID),
+    ]
+    assert transfers[1]["satpoint_post_transfer"] == f"{TRANSFER_2_TXID}:0:0"
+    assert transfers[1]["destination_type"] is None
+    assert index.get_transfers(f"{INSCRIPTION_1_TXID}i0") == []
+
+
+def test_get_inscriptions_at_outpoint(index_dir):
+    index = Index(str(index_dir))
+    located = index.get_inscriptions_at_outpoint(f"{TRANSFER_2_TXID}:0")
+    assert located == [
+        {
+            "inscription_id": f"{INSCRIPTION_0_TXID}i0",
+            "satpoint": f"{TRANSFER_2_TXID}:0:0",
+            "offset": 0,
+        }
+    ]
+    # The inscription moved away from its genesis outpoint
+    assert index.get_inscriptions_at_outpoint(f"{INSCRIPTION_0_TXID}:0") == []
+
+
+def test_get_inscriptions_at_invalid_outpoint(index_dir):
+    with pytest.raises(RuntimeError, match="invalid outpoint"):
+        Index(str(index_dir)).get_inscriptions_at_outpoint("not-an-outpoint")
+
+
+def test_get_tip(index_dir):
+    assert Index(str(index_dir)).get_tip() == 785396
+
+
+def test_missing_index(tmp_path):
+    with pytest.raises(FileNotFoundError):
+        Index(str(tmp_path)).get_tip()
@@ -0,0 +1,46 @@
+import pytest
+
+from ordhook_sdk import Sat
+
+
+@pytest.mark.parametrize(
+    "notation",
+    [
+        2099994106992659,
+        "2099994106992659",
+        "3891094.16797",
+        "3°111094′214″16797‴",
+        "satoshi",
+        "99.99971949060254%",
+    ],
+)
+def test_notations(notation):
+    assert Sat(notation).n == 2099994106992659
+
+
+def test_properties():
+    sat = Sat("satoshi")
+    assert sat.to_dict() == {
+        "number": 2099994106992659,
+        "decimal": "3891094.16797",
+        "degree": "3°111094′214″16797‴",
+        "name": "satoshi",
+        "percentile": "99.99971949060254%",
+        "rarity": "common",
+        "block_height": 3891094,
+        "cycle": 3,
+        "epoch": 18,
+        "offset": 16797,
+    }
+    assert int(sat) == 2099994106992659
+
+
+def test_rarity():
+    assert Sat(0).rarity == "mythic"
+    assert Sat(5000000000).rarity == "uncommon"
+
+
+@pytest.mark.parametrize("notation", ["", "not a sat!", "2099999997690000", "-1"])
+def test_invalid_notations(notation):
+    with pytest.raises(ValueError):
+        Sat(notation)
@@ -0,0 +1,25 @@
+mod parsing;
+mod sat;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+pub use parsing::*;
+pub use sat::*;
+
+/// Converts `value` into a plain JS object.
+fn to_js_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+  value
+    .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+    .map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn decode_witness(witness: Vec<String>) -> Result<Vec<Vec<u8>>, JsError> {
+  witness
+    .iter()
+    .map(|w| {
+      hex::decode(w.trim_start_matches("0x"))
+        .map_err(|e| JsError::new(&format!("invalid witness element {w}: {e}")))
+    })
+    .collect()
+}
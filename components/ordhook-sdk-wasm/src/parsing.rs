@@ -0,0 +1,149 @@
+use ordhook::bitcoin::{hashes::hex::FromHex, Txid, Witness};
+use ordhook::core::protocol::inscription_parsing::{
+  parse_inscriptions_from_witness, InscriptionParser,
+};
+use ordhook::core::{compute_next_satpoint_data, SatPosition};
+use ordhook::ord::{inscription_id::InscriptionId, sat_point::SatPoint};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{decode_witness, to_js_value};
+
+/// Inscription revealed by the witness of the input `inputIndex` of the transaction `txid`, with
+/// the shape of the `inscription_revealed` operations of ordhook. Fields depending on the state of
+/// the index (inscription and ordinal numbers, fees, ...) are left to `0`. Returns `null` when the
+/// witness does not hold an inscription.
+#[wasm_bindgen(js_name = parseInscriptionsFromWitness)]
+pub fn parse_inscriptions_from_witness_js(
+  witness: Vec<String>,
+  txid: &str,
+  input_index: usize,
+) -> Result<JsValue, JsError> {
+  Txid::from_hex(txid).map_err(|e| JsError::new(&format!("invalid txid {txid}: {e}")))?;
+  let operation = parse_inscriptions_from_witness(input_index, decode_witness(witness)?, txid);
+  to_js_value(&operation)
+}
+
+/// Envelope of an inscription, as written in a witness.
+#[wasm_bindgen]
+pub struct InscriptionEnvelope {
+  body: Option<Vec<u8>>,
+  content_type: Option<String>,
+  parent: Option<String>,
+  curse: JsValue,
+}
+
+#[wasm_bindgen]
+impl InscriptionEnvelope {
+  #[wasm_bindgen(getter)]
+  pub fn body(&self) -> Option<Vec<u8>> {
+    self.body.clone()
+  }
+
+  #[wasm_bindgen(getter, js_name = contentType)]
+  pub fn content_type(&self) -> Option<String> {
+    self.content_type.clone()
+  }
+
+  /// Id of the parent inscription, if any.
+  #[wasm_bindgen(getter)]
+  pub fn parent(&self) -> Option<String> {
+    self.parent.clone()
+  }
+
+  /// Reason the inscription is cursed, `null` when it is not.
+  #[wasm_bindgen(getter)]
+  pub fn curse(&self) -> JsValue {
+    self.curse.clone()
+  }
+}
+
+/// Parses the inscription envelope of a witness, throwing when it does not hold one.
+#[wasm_bindgen(js_name = parseInscriptionEnvelope)]
+pub fn parse_inscription_envelope(witness: Vec<String>) -> Result<InscriptionEnvelope, JsError> {
+  let witness = Witness::from_vec(decode_witness(witness)?);
+  let inscription = InscriptionParser::parse(&witness)
+    .map_err(|e| JsError::new(&format!("unable to parse inscription: {e:?}")))?;
+  Ok(InscriptionEnvelope {
+    content_type: inscription
+      .content_type
+      .map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+    body: inscription.body,
+    parent: inscription.parent.map(|parent| parent.to_string()),
+    curse: to_js_value(&inscription.curse)?,
+  })
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum NextSatpoint {
+  Output { output_index: usize, offset: u64 },
+  Fee { offset: u64 },
+}
+
+/// Where the sat at `offset` in the input `inputIndex` lands once the transaction is mined:
+/// `{ type: "output", outputIndex, offset }`, or `{ type: "fee", offset }` when it is spent in
+/// fees, `offset` then being its position in the fees of the transaction.
+#[wasm_bindgen(js_name = computeNextSatpoint)]
+pub fn compute_next_satpoint(
+  input_index: usize,
+  offset: u64,
+  inputs: Vec<u64>,
+  outputs: Vec<u64>,
+) -> Result<JsValue, JsError> {
+  if input_index >= inputs.len() {
+    return Err(JsError::new(&format!(
+      "input {input_index} out of bounds ({} inputs)",
+      inputs.len()
+    )));
+  }
+  if outputs.is_empty() {
+    return Err(JsError::new("transaction without outputs"));
+  }
+  let next_satpoint = match compute_next_satpoint_data(input_index, offset, &inputs, &outputs) {
+    SatPosition::Output((output_index, offset)) => NextSatpoint::Output {
+      output_index,
+      offset,
+    },
+    SatPosition::Fee(offset) => NextSatpoint::Fee { offset },
+  };
+  to_js_value(&next_satpoint)
+}
+
+#[derive(Serialize)]
+struct SatPointComponents {
+  txid: String,
+  vout: u32,
+  offset: u64,
+}
+
+/// Splits a satpoint (`txid:vout:offset`) into `{ txid, vout, offset }`.
+#[wasm_bindgen(js_name = parseSatpoint)]
+pub fn parse_satpoint(satpoint: &str) -> Result<JsValue, JsError> {
+  let satpoint = satpoint
+    .parse::<SatPoint>()
+    .map_err(|e| JsError::new(&format!("invalid satpoint {satpoint}: {e}")))?;
+  to_js_value(&SatPointComponents {
+    txid: satpoint.outpoint.txid.to_string(),
+    vout: satpoint.outpoint.vout,
+    offset: satpoint.offset,
+  })
+}
+
+#[derive(Serialize)]
+struct InscriptionIdComponents {
+  txid: String,
+  index: u32,
+}
+
+/// Splits an inscription id (`<txid>i<index>`) into `{ txid, index }`.
+#[wasm_bindgen(js_name = parseInscriptionId)]
+pub fn parse_inscription_id(inscription_id: &str) -> Result<JsValue, JsError> {
+  let inscription_id = inscription_id
+    .parse::<InscriptionId>()
+    .map_err(|e| JsError::new(&format!("invalid inscription id {inscription_id}: {e}")))?;
+  to_js_value(&InscriptionIdComponents {
+    txid: inscription_id.txid.to_string(),
+    index: inscription_id.index,
+  })
+}
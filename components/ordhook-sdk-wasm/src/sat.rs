@@ -0,0 +1,82 @@
+use ordhook::ord::sat::Sat as OrdSat;
+use wasm_bindgen::prelude::*;
+
+/// Sat, in any of the notations of ordinal theory.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct Sat(OrdSat);
+
+#[wasm_bindgen]
+impl Sat {
+  /// Accepts the integer (`2099994106992659`), decimal (`3891094.16797`), degree
+  /// (`3°111094′214″16797‴`), name (`satoshi`) or percentile (`99.99971949060254%`) notation.
+  #[wasm_bindgen(constructor)]
+  pub fn new(notation: &str) -> Result<Sat, JsError> {
+    notation
+      .parse::<OrdSat>()
+      .map(Sat)
+      .map_err(|e| JsError::new(&format!("unable to parse sat {notation}: {e}")))
+  }
+
+  #[wasm_bindgen(js_name = fromNumber)]
+  pub fn from_number(n: u64) -> Result<Sat, JsError> {
+    Sat::new(&n.to_string())
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn n(&self) -> u64 {
+    self.0.n()
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn decimal(&self) -> String {
+    self.0.decimal().to_string()
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn degree(&self) -> String {
+    self.0.degree().to_string()
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn name(&self) -> String {
+    self.0.name()
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn percentile(&self) -> String {
+    self.0.percentile()
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn rarity(&self) -> String {
+    self.0.rarity().to_string()
+  }
+
+  /// Height of the block mining the sat.
+  #[wasm_bindgen(getter, js_name = blockHeight)]
+  pub fn block_height(&self) -> f64 {
+    self.0.height().n() as f64
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn cycle(&self) -> f64 {
+    self.0.cycle() as f64
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn epoch(&self) -> f64 {
+    self.0.epoch().0 as f64
+  }
+
+  /// Position of the sat in its block.
+  #[wasm_bindgen(getter)]
+  pub fn offset(&self) -> f64 {
+    self.0.third() as f64
+  }
+
+  #[wasm_bindgen(js_name = toString)]
+  pub fn to_string_js(&self) -> String {
+    self.0.to_string()
+  }
+}
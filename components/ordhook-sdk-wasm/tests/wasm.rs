@@ -0,0 +1,42 @@
+//! Run with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+
+use ordhook_sdk_wasm::{compute_next_satpoint, parse_inscription_id, parse_satpoint, Sat};
+use serde_json::json;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+fn to_json(value: JsValue) -> serde_json::Value {
+  serde_wasm_bindgen::from_value(value).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn sat_notations() {
+  let sat = Sat::new("satoshi").unwrap();
+  assert_eq!(sat.n(), 2099994106992659);
+  assert_eq!(sat.rarity(), "common");
+  assert_eq!(Sat::from_number(0).unwrap().rarity(), "mythic");
+  assert!(Sat::new("not a sat").is_err());
+}
+
+#[wasm_bindgen_test]
+fn next_satpoint() {
+  let output = compute_next_satpoint(1, 10, vec![100, 50], vec![120, 20]).unwrap();
+  assert_eq!(
+    to_json(output),
+    json!({ "type": "output", "outputIndex": 0, "offset": 110 })
+  );
+  let fee = compute_next_satpoint(1, 40, vec![100, 50], vec![120, 20]).unwrap();
+  assert_eq!(to_json(fee), json!({ "type": "fee", "offset": 0 }));
+  assert!(compute_next_satpoint(2, 0, vec![100, 50], vec![120]).is_err());
+  assert!(compute_next_satpoint(0, 0, vec![100], vec![]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn identifiers() {
+  let txid = "1111111111111111111111111111111111111111111111111111111111111111";
+  assert!(parse_satpoint(&format!("{txid}:1:1")).is_ok());
+  assert!(parse_satpoint(txid).is_err());
+  assert!(parse_inscription_id(&format!("{txid}i0")).is_ok());
+  assert!(parse_inscription_id(txid).is_err());
+}